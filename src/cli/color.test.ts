@@ -0,0 +1,21 @@
+import { describe, expect, it } from "bun:test";
+import { createPalette, shouldColor } from "./color";
+
+describe("shouldColor", () => {
+  it("colors only interactive terminals", () => {
+    expect(shouldColor({ isTTY: true }, {})).toBe(true);
+    expect(shouldColor({ isTTY: false }, {})).toBe(false);
+    expect(shouldColor({}, {})).toBe(false);
+  });
+
+  it("honors NO_COLOR and dumb terminals", () => {
+    expect(shouldColor({ isTTY: true }, { NO_COLOR: "1" })).toBe(false);
+    expect(shouldColor({ isTTY: true }, { NO_COLOR: "" })).toBe(true);
+    expect(shouldColor({ isTTY: true }, { TERM: "dumb" })).toBe(false);
+  });
+
+  it("returns text untouched when disabled", () => {
+    expect(createPalette(false).green("+added")).toBe("+added");
+    expect(createPalette(true).green("+added")).toBe("\x1b[32m+added\x1b[0m");
+  });
+});
@@ -0,0 +1,31 @@
+// ANSI styling for terminal output. Every command that colors output goes
+// through shouldColor() so NO_COLOR (https://no-color.org) and pipes are honored.
+
+interface ColorStream {
+  isTTY?: boolean;
+}
+
+export function shouldColor(
+  stream: ColorStream = process.stdout,
+  env: Record<string, string | undefined> = process.env
+): boolean {
+  if (env["NO_COLOR"]) return false;
+  if (env["TERM"] === "dumb") return false;
+  return Boolean(stream.isTTY);
+}
+
+export interface Palette {
+  red(text: string): string;
+  green(text: string): string;
+  cyan(text: string): string;
+  bold(text: string): string;
+}
+
+const sgr = (code: number) => (text: string) => `\x1b[${code}m${text}\x1b[0m`;
+const plain = (text: string) => text;
+
+export function createPalette(enabled: boolean = shouldColor()): Palette {
+  return enabled
+    ? { red: sgr(31), green: sgr(32), cyan: sgr(36), bold: sgr(1) }
+    : { red: plain, green: plain, cyan: plain, bold: plain };
+}
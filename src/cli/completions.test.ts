@@ -0,0 +1,41 @@
+import { describe, expect, it } from "bun:test";
+import { Command } from "commander";
+import { generateCompletion } from "./completions";
+
+function sampleProgram(): Command {
+  const program = new Command("tool").option("--global <x>", "Global option");
+  program.command("start").option("-p, --port <n>", "Port");
+  const repo = program.command("repo").description("Manage repos");
+  repo.command("add").description("Add a repo").option("--name <n>", "Name");
+  return program;
+}
+
+describe("generateCompletion", () => {
+  it("bash completes subcommands and nested options", () => {
+    const script = generateCompletion("bash", sampleProgram());
+    expect(script).toContain("complete -o default -F _tool tool");
+    expect(script).toContain(`"") words="start repo --global --help" ;;`);
+    expect(script).toContain(`"start") words="--port -p --help" ;;`);
+    expect(script).toContain(`"repo add") words="--name --help" ;;`);
+  });
+
+  it("zsh wraps the bash completer", () => {
+    const script = generateCompletion("zsh", sampleProgram());
+    expect(script.startsWith("autoload -U +X bashcompinit && bashcompinit")).toBe(true);
+  });
+
+  it("fish scopes options to their subcommand", () => {
+    const script = generateCompletion("fish", sampleProgram());
+    expect(script).toContain("complete -c tool -n '__fish_use_subcommand' -a repo -d 'Manage repos'");
+    expect(script).toContain(
+      "complete -c tool -n '__fish_seen_subcommand_from repo; and not __fish_seen_subcommand_from add' -a add -d 'Add a repo'"
+    );
+    expect(script).toContain("complete -c tool -n '__fish_seen_subcommand_from start' -s p -d 'Port'");
+  });
+
+  it("powershell lists known command paths", () => {
+    const script = generateCompletion("powershell", sampleProgram());
+    expect(script).toContain("Register-ArgumentCompleter -Native -CommandName tool");
+    expect(script).toContain("$known = @('start', 'repo', 'repo add')");
+  });
+});
@@ -0,0 +1,131 @@
+import type { Command } from "commander";
+
+export const COMPLETION_SHELLS = ["bash", "zsh", "fish", "powershell"] as const;
+export type CompletionShell = (typeof COMPLETION_SHELLS)[number];
+
+// One completable position: the words after `cerebro <path>`
+interface CompletionNode {
+  path: string; // "" for the root, "repo add" for nested commands
+  subcommands: Array<{ name: string; description: string }>;
+  options: Array<{ flag: string; description: string }>;
+}
+
+// Flatten the commander tree so the scripts always match the registered commands
+function collectNodes(command: Command, path = ""): CompletionNode[] {
+  const node: CompletionNode = {
+    path,
+    subcommands: command.commands.map((sub) => ({ name: sub.name(), description: sub.description() })),
+    options: [
+      ...command.options.flatMap((option) =>
+        [option.long, option.short]
+          .filter((flag): flag is string => Boolean(flag))
+          .map((flag) => ({ flag, description: option.description }))
+      ),
+      { flag: "--help", description: "Display help" },
+    ],
+  };
+
+  return [
+    node,
+    ...command.commands.flatMap((sub) => collectNodes(sub, path ? `${path} ${sub.name()}` : sub.name())),
+  ];
+}
+
+function words(node: CompletionNode): string[] {
+  return [...node.subcommands.map((sub) => sub.name), ...node.options.map((option) => option.flag)];
+}
+
+function bashScript(name: string, nodes: CompletionNode[]): string {
+  const paths = nodes.filter((node) => node.path).map((node) => `"${node.path}"`);
+  const cases = nodes.map((node) => `    "${node.path}") words="${words(node).join(" ")}" ;;`);
+
+  return `_${name}() {
+  local cur="\${COMP_WORDS[COMP_CWORD]}" path="" candidate words i
+  for ((i = 1; i < COMP_CWORD; i++)); do
+    candidate="\${path:+$path }\${COMP_WORDS[i]}"
+    case "$candidate" in
+      ${paths.join("|")}) path="$candidate" ;;
+    esac
+  done
+  case "$path" in
+${cases.join("\n")}
+  esac
+  COMPREPLY=($(compgen -W "$words" -- "$cur"))
+}
+complete -o default -F _${name} ${name}
+`;
+}
+
+function zshScript(name: string, nodes: CompletionNode[]): string {
+  // zsh runs the bash completer through bashcompinit
+  return `autoload -U +X bashcompinit && bashcompinit\n${bashScript(name, nodes)}`;
+}
+
+function fishEscape(text: string): string {
+  return text.replace(/\\/g, "\\\\").replace(/'/g, "\\'");
+}
+
+function fishScript(name: string, nodes: CompletionNode[]): string {
+  const lines = [`complete -c ${name} -f`];
+
+  for (const node of nodes) {
+    const seen = node.path.split(" ").filter(Boolean).map((part) => `__fish_seen_subcommand_from ${part}`);
+    const optCondition = seen.length ? seen : ["__fish_use_subcommand"];
+    // Offer a group's subcommands only until one of them has been typed
+    const subCondition = seen.length
+      ? [...seen, `not __fish_seen_subcommand_from ${node.subcommands.map((sub) => sub.name).join(" ")}`]
+      : optCondition;
+
+    for (const sub of node.subcommands) {
+      lines.push(
+        `complete -c ${name} -n '${subCondition.join("; and ")}' -a ${sub.name} -d '${fishEscape(sub.description)}'`
+      );
+    }
+    for (const option of node.options) {
+      const flag = option.flag.startsWith("--") ? `-l ${option.flag.slice(2)}` : `-s ${option.flag.slice(1)}`;
+      lines.push(`complete -c ${name} -n '${optCondition.join("; and ")}' ${flag} -d '${fishEscape(option.description)}'`);
+    }
+  }
+
+  return lines.join("\n") + "\n";
+}
+
+function powershellScript(name: string, nodes: CompletionNode[]): string {
+  const known = nodes.filter((node) => node.path).map((node) => `'${node.path}'`);
+  const cases = nodes.map((node) => `    '${node.path}' { @(${words(node).map((word) => `'${word}'`).join(", ")}) }`);
+
+  return `Register-ArgumentCompleter -Native -CommandName ${name} -ScriptBlock {
+  param($wordToComplete, $commandAst, $cursorPosition)
+  $known = @(${known.join(", ")})
+  $path = ''
+  foreach ($element in $commandAst.CommandElements | Select-Object -Skip 1) {
+    $word = $element.ToString()
+    if ($word -eq $wordToComplete) { break }
+    $candidate = ($path + ' ' + $word).Trim()
+    if ($known -contains $candidate) { $path = $candidate }
+  }
+  $words = switch ($path) {
+${cases.join("\n")}
+  }
+  $words | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+  }
+}
+`;
+}
+
+export function generateCompletion(shell: CompletionShell, program: Command): string {
+  const name = program.name();
+  const nodes = collectNodes(program);
+
+  switch (shell) {
+    case "bash":
+      return bashScript(name, nodes);
+    case "zsh":
+      return zshScript(name, nodes);
+    case "fish":
+      return fishScript(name, nodes);
+    case "powershell":
+      return powershellScript(name, nodes);
+  }
+}
@@ -1,4 +1,4 @@
-import { mkdtempSync, rmSync, mkdirSync } from "fs";
+import { existsSync, mkdtempSync, rmSync, mkdirSync, statSync } from "fs";
 import { tmpdir } from "os";
 import { join } from "path";
 import { beforeAll, afterAll, beforeEach, describe, expect, it } from "bun:test";
@@ -319,3 +319,99 @@ describe("notes CLI", () => {
     expect(result.stdout).toContain("suggestion");
   });
 });
+
+describe("state CLI", () => {
+  it("exports and re-imports viewed state", async () => {
+    const repo = await state.addRepo("/tmp/state-repo", "state-repo", "main");
+    await state.setFileViewed(repo.id, "main", "abc123", "src/a.ts", true, "alice");
+    const out = join(tempHome, "state.json");
+
+    const exported = await runCli(["state", "export", "--out", out, "--repo", repo.id]);
+    expect(exported.exitCode).toBe(0);
+
+    await state.setFileViewed(repo.id, "main", "abc123", "src/a.ts", false);
+    const imported = await runCli(["state", "import", "--in", out, "--repo", repo.id]);
+    expect(imported.exitCode).toBe(0);
+    expect(imported.stdout).toContain("Imported 1 viewed file(s)");
+
+    const viewedBy = await state.getViewedBy(repo.id, "main", "abc123");
+    expect(viewedBy["src/a.ts"]).toBe("alice");
+  });
+
+  it("merges several state files into one", async () => {
+    const fileA = join(tempHome, "a.json");
+    const fileB = join(tempHome, "b.json");
+    const out = join(tempHome, "merged.json");
+    const viewed = (file_path: string) => [{ branch: "main", commit: "abc", file_path, viewed_at: 1 }];
+    await Bun.write(fileA, JSON.stringify({ version: 1, exported_at: 1, repos: [{ path: "/r", name: "r", viewed: viewed("a.ts") }] }));
+    await Bun.write(fileB, JSON.stringify({ version: 1, exported_at: 2, repos: [{ path: "/r", name: "r", viewed: viewed("b.ts") }] }));
+
+    const result = await runCli(["state", "merge", fileA, fileB, "--out", out]);
+    expect(result.exitCode).toBe(0);
+
+    const merged = JSON.parse(await Bun.file(out).text());
+    expect(merged.repos[0].viewed.length).toBe(2);
+  });
+
+  it("rejects state files with an unknown version", async () => {
+    const repo = await state.addRepo("/tmp/state-repo", "state-repo", "main");
+    const file = join(tempHome, "bad-state.json");
+    await Bun.write(file, JSON.stringify({ version: 99, exported_at: 0, repos: [] }));
+
+    const result = await runCli(["state", "import", "--in", file, "--repo", repo.id]);
+    expect(result.exitCode).toBe(1);
+    expect(result.stderr).toContain("Unsupported state file version");
+  });
+});
+
+describe("doctor CLI", () => {
+  it("reports the repo and state location without creating state", async () => {
+    const { stdout, exitCode } = await runCli(["doctor"]);
+    expect(exitCode).toBe(0);
+    expect(stdout).toContain("Git repository:");
+    expect(stdout).toContain(join(configDir, "cerebro.db"));
+    expect(stdout).toContain("(not created yet)");
+    expect(existsSync(join(configDir, "cerebro.db"))).toBe(false);
+  });
+
+  it("reads the tracked repo read-only and reports the configured port", async () => {
+    const repo = await state.addRepo(originalCwd, "doctor-repo", "develop");
+    await state.saveConfig({ defaultPort: 4040 });
+    state.closeDb();
+    const dbPath = join(configDir, "cerebro.db");
+    const modified = statSync(dbPath).mtimeMs;
+
+    const { stdout, exitCode } = await runCli(["doctor"]);
+    expect(exitCode).toBe(0);
+    expect(stdout).toContain(`doctor-repo (${repo.id})`);
+    expect(stdout).toContain("develop (stored");
+    expect(stdout).toContain("4040 (config port");
+    expect(statSync(dbPath).mtimeMs).toBe(modified);
+  });
+});
+
+describe("start CLI", () => {
+  it("rejects a non-integer --collapse-context or --tab-width before starting", async () => {
+    const collapse = await runCli(["start", "--collapse-context", "abc"]);
+    expect(collapse.exitCode).toBe(1);
+    expect(collapse.stderr).toContain("--collapse-context must be a non-negative integer");
+
+    const tabs = await runCli(["start", "--tab-width=1.5"]);
+    expect(tabs.exitCode).toBe(1);
+    expect(tabs.stderr).toContain("--tab-width must be a non-negative integer");
+  });
+});
+
+describe("completions CLI", () => {
+  it("prints a bash completion script covering registered flags", async () => {
+    const result = await runCli(["completions", "bash"]);
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout).toContain("complete -o default -F _cerebro cerebro");
+    expect(result.stdout).toContain("--rate-limit");
+  });
+
+  it("rejects unknown shells", async () => {
+    const result = await runCli(["completions", "tcsh"]);
+    expect(result.exitCode).toBe(1);
+  });
+});
@@ -1,9 +1,29 @@
 import { program } from "commander";
-import { relative, resolve } from "path";
-import { startServer, stopServer } from "../server";
+import { existsSync, readFileSync, statSync, writeFileSync } from "fs";
+import { join, relative, resolve } from "path";
+import { COPY_SOURCES, DEFAULT_MAX_FILES, MAX_REQUEST_TIMEOUT, startServer, stopServer, type CopySources } from "../server";
+import { LOG_FORMATS, type LogFormat } from "../server/logger";
+import { createIgnoreMatcher, IGNORE_MODES, type IgnoreMode } from "../server/ignore";
+import { parseStatusList } from "../server/completion";
 import * as state from "../state";
-import { getGitManager, isGitRepo, getRepoName } from "../git";
-import type { Repository } from "../types";
+import {
+  DEFAULT_MAX_BLOB_MB,
+  diffDirectories,
+  getGitManager,
+  isGitRepo,
+  getRepoName,
+  NO_UPSTREAM_MESSAGE,
+  parseDiffOverride,
+  UPSTREAM_SPEC,
+} from "../git";
+import { DEFAULT_PATCH_CACHE_MB } from "../git/patchCache";
+import { watchDirectories } from "../git/watch";
+import { PRE_REVIEW_HOOK } from "../git/hooks";
+import { REVIEW_STATE_VERSION, ReviewStateExportSchema } from "../schemas";
+import { createPalette, shouldColor } from "./color";
+import { COMPLETION_SHELLS, generateCompletion, type CompletionShell } from "./completions";
+import { colorPatchLine, runTui, runTuiSource } from "./tui";
+import type { DiffMode, ExportedRepoState, Repository, ReviewStateExport } from "../types";
 
 const VERSION = "0.1.0";
 
@@ -28,20 +48,181 @@ export async function resolveRepo(repoOption?: string): Promise<Repository> {
   throw new Error("No repository found. Use 'cerebro repo add <path>' first or pass --repo.");
 }
 
+interface StartOptions {
+  port?: string;
+  open?: boolean;
+  base?: string;
+  corsOrigin: string[];
+  allowedBase: string[];
+  rateLimit?: string;
+  readOnly?: boolean;
+  autoMarkOnOpen?: boolean;
+  allowOpen?: boolean;
+  editor?: string;
+  collapseContext?: string;
+  tabWidth?: string;
+  reviewer?: string;
+  gitNotes?: boolean;
+  logFormat: string;
+  ignore: string[];
+  ignoreMode?: string;
+  stateKey: string;
+  patchCache?: string;
+  maxFiles?: string;
+  maxBlobSize?: string;
+  requestTimeout?: string;
+  idleTimeout?: string;
+  precompute?: boolean;
+  detectCopies?: string | boolean;
+  ignoreEolWhitespace?: boolean;
+  minimal?: boolean;
+  preReviewHook?: string | true;
+  ignoreHookFailure?: boolean;
+}
+
+// An @{upstream} base only works on a checkout that tracks something; fail before serving
+async function requireUpstream(repo: Repository): Promise<void> {
+  if (UPSTREAM_SPEC.test(repo.baseBranch) && !(await getGitManager(repo.path).getUpstream())) {
+    console.error(`Error: ${NO_UPSTREAM_MESSAGE}`);
+    process.exit(1);
+  }
+}
+
+// Add (or fetch) the repo being started; an explicit --base overrides the stored/detected one
+async function trackRepoForStart(repoPath: string, base?: string): Promise<Repository> {
+  const git = getGitManager(repoPath);
+  const baseBranch = base ?? (await git.getDefaultBranch());
+  const repo = await state.addRepo(repoPath, getRepoName(repoPath), baseBranch);
+  if (base && repo.baseBranch !== base) {
+    await state.updateRepo(repo.id, { baseBranch: base });
+    return { ...repo, baseBranch: base };
+  }
+  return repo;
+}
+
+// Accumulate a repeatable option into an array
+function collect(value: string, previous: string[]): string[] {
+  return [...previous, value];
+}
+
+// An integer flag's value, or undefined when it wasn't given; a value out of range exits
+function parseIntFlag(name: string, value: string | undefined, range: { min: 0 | 1; max?: number }): number | undefined {
+  if (value === undefined) return undefined;
+  const { min, max } = range;
+  const parsed = Number(value);
+  if (!Number.isInteger(parsed) || parsed < min || (max !== undefined && parsed > max)) {
+    const expected =
+      max !== undefined ? `an integer from ${min} to ${max}` : min === 0 ? "a non-negative integer" : "a positive integer";
+    console.error(`Error: ${name} must be ${expected}`);
+    process.exit(1);
+  }
+  return parsed;
+}
+
 program
   .name("cerebro")
   .description("Git diff review tool with web interface")
-  .version(VERSION);
+  .version(VERSION)
+  .option("--state-file <path>", "Review state database location (or set CEREBRO_STATE_FILE)")
+  .hook("preAction", () => {
+    const { stateFile } = program.opts<{ stateFile?: string }>();
+    if (stateFile) {
+      process.env["CEREBRO_STATE_FILE"] = resolve(stateFile);
+    }
+  });
 
 // Start command
 program
   .command("start")
   .description("Start the Cerebro server")
   .argument("[path]", "Repository path (optional, use repo picker if not provided)")
-  .option("-p, --port <number>", "Port to run on", "3030")
+  .option("-p, --port <number>", "Port to run on (default: config port, 3030)")
   .option("-o, --open", "Open browser after starting")
-  .action(async (path: string | undefined, options: { port: string; open?: boolean }) => {
-    const port = parseInt(options.port, 10);
+  .option(
+    "-b, --base <branch>",
+    "Base branch to diff against, or @{upstream} for the checkout's tracking branch (default: detected from origin/HEAD, then main/master/trunk)"
+  )
+  .option("--cors-origin <origin>", "Allow cross-origin API access from this origin (repeatable)", collect, [])
+  .option(
+    "--allowed-base <glob>",
+    "Only diff refs matching this glob, e.g. 'release/*' (repeatable). Bases, range ends and shown commits outside the list return 403",
+    collect,
+    []
+  )
+  .option("--rate-limit <n>", "Limit git-heavy requests (diffs, stats, blame, file reads) per second per client IP")
+  .option(
+    "--read-only",
+    "Serve diffs without allowing changes: marking files viewed, comments, notes, staging, commits and repo edits all return 403. Existing viewed state is still shown"
+  )
+  .option("--allow-open", "Allow the UI to open files in your editor on this machine")
+  .option("--auto-mark-on-open", "Have the UI mark files viewed when you open them")
+  .option("--editor <command>", "Editor for --allow-open (default: $EDITOR), e.g. \"code -g\" or vim")
+  .option("--collapse-context <n>", "Collapse runs of more than N unchanged lines in hunk/split views")
+  .option("--git-notes", "Also store viewed marks in git notes (refs/notes/cerebro-review) so they can be pushed and shared")
+  .option("--reviewer <name>", "Name recorded on files you mark viewed (default: git config user.name)")
+  .option("--ignore <glob>", "Keep matching tracked files out of review, e.g. 'generated/' (repeatable, adds to config)", collect, [])
+  .option("--ignore-mode <mode>", `What to do with ignored files: ${IGNORE_MODES.join(", ")} (default: collapse)`)
+  .option("--log-format <format>", `Log format: ${LOG_FORMATS.join(", ")} (json logs every API request)`, "pretty")
+  .option("--tab-width <n>", "Expand tabs to N spaces in hunk/split views (default: keep tabs)")
+  .option(
+    "--state-key <key>",
+    `Match viewed marks by: ${state.STATE_KEYS.join(", ")} (content keeps files viewed wherever their content is unchanged)`,
+    "commit"
+  )
+  .option("--ignore-eol-whitespace", "Hide changes that only add or remove trailing whitespace (indentation changes still show)")
+  .option("--minimal", "Compute the smallest possible diff (slower on large changes)")
+  .option(
+    "--detect-copies [sources]",
+    `Report copied files with their source; sources: ${COPY_SOURCES.join(", ")} (all also checks unmodified files, slower)`
+  )
+  .option("--patch-cache <mb>", `Disk space for cached file patches in MB, 0 to disable (default: ${DEFAULT_PATCH_CACHE_MB})`)
+  .option(
+    "--pre-review-hook [script]",
+    `Run a script from the repo root before serving (default: the ${PRE_REVIEW_HOOK} hook in core.hooksPath/.git/hooks). This runs arbitrary code from the repository; only use it on repos you trust`
+  )
+  .option("--ignore-hook-failure", "Start even if the pre-review hook exits non-zero")
+  .option("--precompute", "Compute the default diff at startup and log a summary, so the first page load is fast")
+  .option("--max-files <n>", `Refuse unpaged diffs with more files than this, asking for a path filter; 0 to disable (default: ${DEFAULT_MAX_FILES})`)
+  .option("--max-blob-size <mb>", `List files larger than this many MB as too large instead of diffing them; 0 to disable (default: ${DEFAULT_MAX_BLOB_MB})`)
+  .option("--request-timeout <seconds>", `Answer API requests still running after this long (at most ${MAX_REQUEST_TIMEOUT}) with 503; git processes silent that long are killed (default: no limit)`)
+  .option("--idle-timeout <seconds>", "Shut down after this many seconds without a request, e.g. when launched from an editor (default: never)")
+  .action(async (path: string | undefined, options: StartOptions) => {
+    const port = options.port !== undefined ? parseInt(options.port, 10) : (await state.getConfig()).defaultPort;
+
+    const rateLimit = parseIntFlag("--rate-limit", options.rateLimit, { min: 1 });
+    const patchCacheMb = parseIntFlag("--patch-cache", options.patchCache, { min: 0 });
+    const maxFiles = parseIntFlag("--max-files", options.maxFiles, { min: 0 });
+    const collapseContext = parseIntFlag("--collapse-context", options.collapseContext, { min: 0 });
+    const tabWidth = parseIntFlag("--tab-width", options.tabWidth, { min: 0 });
+    const maxBlobMb = parseIntFlag("--max-blob-size", options.maxBlobSize, { min: 0 });
+    const requestTimeout = parseIntFlag("--request-timeout", options.requestTimeout, { min: 1, max: MAX_REQUEST_TIMEOUT });
+    const idleTimeout = parseIntFlag("--idle-timeout", options.idleTimeout, { min: 1 });
+
+    // Bare --detect-copies looks for sources among the changed files only
+    const detectCopies = options.detectCopies === true ? "changed" : options.detectCopies;
+    if (detectCopies !== undefined && !COPY_SOURCES.includes(detectCopies as CopySources)) {
+      console.error(`Error: --detect-copies must be one of: ${COPY_SOURCES.join(", ")}`);
+      process.exit(1);
+    }
+
+    if (!LOG_FORMATS.includes(options.logFormat as LogFormat)) {
+      console.error(`Error: --log-format must be one of: ${LOG_FORMATS.join(", ")}`);
+      process.exit(1);
+    }
+
+    if (!state.STATE_KEYS.includes(options.stateKey as state.StateKey)) {
+      console.error(`Error: --state-key must be one of: ${state.STATE_KEYS.join(", ")}`);
+      process.exit(1);
+    }
+
+    // Ignore patterns from config.json plus any given on the command line
+    const config = await state.getConfig();
+    const ignoreMode = options.ignoreMode ?? config.ignoreMode ?? "collapse";
+    if (!IGNORE_MODES.includes(ignoreMode as IgnoreMode)) {
+      console.error(`Error: --ignore-mode must be one of: ${IGNORE_MODES.join(", ")}`);
+      process.exit(1);
+    }
+    const ignore = [...(config.ignorePatterns ?? []), ...options.ignore];
 
     // If path provided, validate and set as current repo
     if (path) {
@@ -53,26 +234,22 @@ program
       }
 
       // Add/get repo
-      const git = getGitManager(repoPath);
-      const baseBranch = await git.getDefaultBranch();
-      const name = getRepoName(repoPath);
-      const repo = await state.addRepo(repoPath, name, baseBranch);
+      const repo = await trackRepoForStart(repoPath, options.base);
+      await requireUpstream(repo);
 
       // Set as current
       await state.setCurrentRepo(repo.id);
 
-      console.log(`Starting Cerebro for ${name} (${repoPath})`);
-      console.log(`Base branch: ${baseBranch}`);
+      console.log(`Starting Cerebro for ${repo.name} (${repoPath})`);
+      console.log(`Base branch: ${repo.baseBranch}`);
     } else {
       // No path - check if cwd is a git repo
       const cwd = process.cwd();
       if (await isGitRepo(cwd)) {
-        const git = getGitManager(cwd);
-        const baseBranch = await git.getDefaultBranch();
-        const name = getRepoName(cwd);
-        const repo = await state.addRepo(cwd, name, baseBranch);
+        const repo = await trackRepoForStart(cwd, options.base);
+        await requireUpstream(repo);
         await state.setCurrentRepo(repo.id);
-        console.log(`Starting Cerebro for ${name} (${cwd})`);
+        console.log(`Starting Cerebro for ${repo.name} (${cwd})`);
       } else {
         // Start without a repo - UI will show repo picker
         console.log("Starting Cerebro (no repository selected)");
@@ -81,7 +258,44 @@ program
     }
 
     // Start server
-    await startServer({ port });
+    try {
+      await startServer({
+        port,
+        corsOrigins: options.corsOrigin,
+        allowedBases: options.allowedBase,
+        rateLimit,
+        readOnly: options.readOnly,
+        autoMarkOnOpen: options.autoMarkOnOpen,
+        allowOpen: options.allowOpen,
+        editor: options.editor,
+        reviewer: options.reviewer,
+        gitNotes: options.gitNotes,
+        logFormat: options.logFormat as LogFormat,
+        ignore,
+        ignoreMode: ignoreMode as IgnoreMode,
+        requireReviewFor: config.requireReviewFor,
+        diffOverrides: config.diffOverrides,
+        stateKey: options.stateKey as state.StateKey,
+        patchCacheMb,
+        maxFiles,
+        maxBlobMb,
+        requestTimeout,
+        idleTimeout,
+        onIdleShutdown: () => process.exit(0),
+        precompute: options.precompute,
+        detectCopies: detectCopies as CopySources | undefined,
+        ignoreEolWhitespace: options.ignoreEolWhitespace,
+        minimal: options.minimal,
+        collapseContext,
+        tabWidth,
+        preReviewHook: options.preReviewHook,
+        ignoreHookFailure: options.ignoreHookFailure,
+      });
+    } catch (error) {
+      // A failing --pre-review-hook stops startup
+      console.error(`Error: ${error instanceof Error ? error.message : String(error)}`);
+      process.exit(1);
+    }
 
     if (options.open) {
       const url = `http://localhost:${port}`;
@@ -102,6 +316,119 @@ program
     });
   });
 
+// Terminal review (no server)
+program
+  .command("tui")
+  .description("Review changes in the terminal instead of the browser")
+  .option("-r, --repo <idOrPath>", "Repository ID or path (defaults to current directory)")
+  .option("-m, --mode <mode>", "Diff mode: branch, working, unstaged (index vs worktree), or full (committed plus uncommitted)", "branch")
+  .option("-b, --base <branch>", "Compare against this branch (default: the repo's base branch)")
+  .option("-w, --watch", "Reload when HEAD, the index or the worktree changes")
+  .option("--reviewer <name>", "Name recorded on files you mark viewed (default: git config user.name)")
+  .option("--state-key <key>", `Match viewed marks by: ${state.STATE_KEYS.join(", ")} (as for start)`, "commit")
+  .action(async (options: { repo?: string; mode: string; base?: string; watch?: boolean; reviewer?: string; stateKey: string }) => {
+    if (!["branch", "working", "unstaged", "full"].includes(options.mode)) {
+      console.error("Error: --mode must be one of: branch, working, unstaged, full");
+      process.exit(1);
+    }
+    if (!state.STATE_KEYS.includes(options.stateKey as state.StateKey)) {
+      console.error(`Error: --state-key must be one of: ${state.STATE_KEYS.join(", ")}`);
+      process.exit(1);
+    }
+
+    let repo: Repository;
+    try {
+      // Like `start`, an untracked repo in the current directory is picked up automatically
+      repo =
+        !options.repo && (await isGitRepo(process.cwd()))
+          ? await trackRepoForStart(resolve(process.cwd()))
+          : await resolveRepo(options.repo);
+    } catch (err) {
+      console.error((err as Error).message);
+      process.exit(1);
+      return;
+    }
+
+    try {
+      await runTui(repo, {
+        mode: options.mode as DiffMode,
+        compare: options.base ?? repo.baseBranch,
+        watch: options.watch,
+        reviewer: options.reviewer,
+        stateKey: options.stateKey as state.StateKey,
+      });
+    } catch (err) {
+      console.error(`Error: ${(err as Error).message}`);
+      process.exit(1);
+    }
+  });
+
+// Two directories with no shared history (generated output vs a reference, unrelated checkouts)
+program
+  .command("diff-dirs")
+  .description("Diff two directories that aren't related by git history")
+  .argument("<old>", "Directory on the old side")
+  .argument("<new>", "Directory on the new side")
+  .option("--exclude <glob>", "Skip paths matching a glob, relative to each directory (repeatable; dir/ covers everything beneath)", collect, [])
+  .option("--tui", "Review the differences in the terminal UI instead of printing patches")
+  .option("-w, --watch", "Re-run whenever either directory changes (Ctrl-C to stop)")
+  .action(async (oldDir: string, newDir: string, options: { exclude: string[]; tui?: boolean; watch?: boolean }) => {
+    for (const dir of [oldDir, newDir]) {
+      if (!statSync(dir, { throwIfNoEntry: false })?.isDirectory()) {
+        console.error(`Error: not a directory: ${dir}`);
+        process.exit(1);
+      }
+    }
+
+    const load = () => diffDirectories(resolve(oldDir), resolve(newDir), createIgnoreMatcher(options.exclude));
+    const watchDirs = (onChange: () => void) => watchDirectories([{ dir: oldDir }, { dir: newDir }], onChange);
+    try {
+      if (options.tui) {
+        // Viewed marks only last as long as the session: there's no repo to key them on
+        const viewed: Record<string, boolean> = {};
+        await runTuiSource(
+          {
+            load: async () => ({ title: `cerebro  ${oldDir} vs ${newDir}`, files: await load(), viewed: { ...viewed } }),
+            loadPatch: async (file) => file.patch,
+            setViewed: async (file, isViewed) => {
+              viewed[file.path] = isViewed;
+            },
+            watch: async (onChange) => watchDirs(onChange),
+          },
+          { watch: options.watch }
+        );
+        return;
+      }
+
+      const palette = createPalette(shouldColor(process.stdout));
+      const print = async () => {
+        const files = await load();
+        for (const file of files) {
+          console.log(palette.bold(`${file.status} ${file.path} (+${file.additions} -${file.deletions})`));
+        }
+        for (const file of files) {
+          console.log(file.patch.trimEnd().split("\n").map((line) => colorPatchLine(line, palette)).join("\n"));
+        }
+        if (files.length === 0) console.log("No differences");
+      };
+      await print();
+      if (!options.watch) return;
+
+      // Clear the screen and reprint after each burst of changes until Ctrl-C
+      const stop = watchDirs(() => {
+        if (process.stdout.isTTY) process.stdout.write("\x1b[H\x1b[2J");
+        print().catch((err) => console.error(`Error: ${(err as Error).message}`));
+      });
+      process.once("SIGINT", () => {
+        stop();
+        process.exit(0);
+      });
+    } catch (err) {
+      console.error(`Error: ${(err as Error).message}`);
+      process.exit(1);
+    }
+  });
+
 // Repo commands
 const repoCmd = program.command("repo").description("Manage repositories");
 
@@ -191,6 +518,16 @@ configCmd
 
     console.log("Configuration:\n");
     console.log(`  Default port: ${config.defaultPort}`);
+    if (config.ignorePatterns?.length) {
+      console.log(`  Ignore: ${config.ignorePatterns.join(", ")} (${config.ignoreMode ?? "collapse"})`);
+    }
+    if (config.requireReviewFor) {
+      console.log(`  Require review for: ${config.requireReviewFor.join(", ") || "(none)"}`);
+    }
+    for (const override of config.diffOverrides ?? []) {
+      const flags = [...(override.ignoreWhitespace ? ["ignore-whitespace"] : []), ...(override.context !== undefined ? [`context=${override.context}`] : [])];
+      console.log(`  Diff override: ${override.path} (${flags.join(", ")})`);
+    }
     if (currentRepo) {
       console.log(`  Current repo: ${currentRepo.name} (${currentRepo.path})`);
       console.log(`  Base branch: ${currentRepo.baseBranch}`);
@@ -200,7 +537,7 @@ configCmd
 configCmd
   .command("set")
   .description("Set a configuration value")
-  .argument("<key>", "Configuration key (e.g., base-branch, port)")
+  .argument("<key>", "Configuration key (e.g., base-branch, port, ignore, ignore-mode, require-review-for, diff-override)")
   .argument("<value>", "Configuration value")
   .action(async (key: string, value: string) => {
     if (key === "base-branch") {
@@ -216,9 +553,53 @@ configCmd
       config.defaultPort = parseInt(value, 10);
       await state.saveConfig(config);
       console.log(`Set default port to: ${value}`);
+    } else if (key === "ignore") {
+      // Comma-separated globs; an empty value clears the list
+      const config = await state.getConfig();
+      config.ignorePatterns = value.split(",").map((p) => p.trim()).filter(Boolean);
+      await state.saveConfig(config);
+      console.log(`Set ignore patterns to: ${config.ignorePatterns.join(", ") || "(none)"}`);
+    } else if (key === "ignore-mode") {
+      if (!IGNORE_MODES.includes(value as IgnoreMode)) {
+        console.error(`Error: ignore-mode must be one of: ${IGNORE_MODES.join(", ")}`);
+        process.exit(1);
+      }
+      const config = await state.getConfig();
+      config.ignoreMode = value as IgnoreMode;
+      await state.saveConfig(config);
+      console.log(`Set ignore mode to: ${value}`);
+    } else if (key === "require-review-for") {
+      // Comma-separated statuses; "all" goes back to requiring every file
+      const config = await state.getConfig();
+      if (value === "all") {
+        delete config.requireReviewFor;
+      } else {
+        const statuses = parseStatusList(value);
+        if (typeof statuses === "string") {
+          console.error(`Error: ${statuses}`);
+          process.exit(1);
+        }
+        config.requireReviewFor = statuses;
+      }
+      await state.saveConfig(config);
+      console.log(`Require review for: ${config.requireReviewFor?.join(", ") ?? "all files"}`);
+    } else if (key === "diff-override") {
+      // <glob>:<options> replaces that glob's override; no options removes it
+      const override = parseDiffOverride(value);
+      if (typeof override === "string") {
+        console.error(`Error: ${override}`);
+        process.exit(1);
+      }
+      const config = await state.getConfig();
+      const others = (config.diffOverrides ?? []).filter((o) => o.path !== override.path);
+      const remove = override.ignoreWhitespace === undefined && override.context === undefined;
+      config.diffOverrides = remove ? others : [...others, override];
+      if (config.diffOverrides.length === 0) delete config.diffOverrides;
+      await state.saveConfig(config);
+      console.log(remove ? `Removed diff override for: ${override.path}` : `Set diff override for: ${override.path}`);
     } else {
       console.error(`Unknown config key: ${key}`);
-      console.log("Available keys: base-branch, port");
+      console.log("Available keys: base-branch, port, ignore, ignore-mode, require-review-for, diff-override");
       process.exit(1);
     }
   });
@@ -264,7 +645,7 @@ commentsCmd
         .filter(Boolean)
         .join(" | ");
 
-      console.log(`[${comment.id}] ${location} (${metaParts})`);
+      console.log(`[${comment.id}] ${location} (${metaParts})${comment.author ? ` by ${comment.author}` : ""}`);
       console.log(`  ${comment.text}`);
       if (comment.parent_id) {
         console.log(`  ↳ reply to ${comment.parent_id}`);
@@ -282,7 +663,8 @@ commentsCmd
   .option("-l, --line <number>", "Line number")
   .option("-b, --branch <branch>", "Branch name")
   .option("-c, --commit <hash>", "Commit hash")
-  .action(async (text: string, options: { repo?: string; file?: string; line?: string; branch?: string; commit?: string }) => {
+  .option("-a, --author <name>", "Comment author (default: git config user.name)")
+  .action(async (text: string, options: { repo?: string; file?: string; line?: string; branch?: string; commit?: string; author?: string }) => {
     if (!options.file) {
       console.error("Error: --file is required");
       process.exit(1);
@@ -297,13 +679,9 @@ commentsCmd
       return;
     }
 
-    let branch = options.branch;
-    let commit = options.commit;
-    if (!branch || !commit) {
-      const git = getGitManager(repo.path);
-      branch = branch || (await git.getCurrentBranch());
-      commit = commit || (await git.getCurrentCommit());
-    }
+    const git = getGitManager(repo.path);
+    const branch = options.branch || (await git.getCurrentBranch());
+    const commit = options.commit || (await git.getCurrentCommit());
     const filePath = resolve(repo.path, options.file);
 
     const comment = await state.addComment(repo.id, {
@@ -312,6 +690,7 @@ commentsCmd
       text,
       branch,
       commit,
+      author: options.author ?? (await git.getUserName()),
     });
 
     console.log(`Added comment: ${comment.id}`);
@@ -347,6 +726,7 @@ commentsCmd
       return;
     }
 
+    const repo = await state.getRepo(parent.repo_id);
     const reply = await state.addComment(parent.repo_id, {
       file_path: parent.file_path,
       line_number: parent.line_number,
@@ -354,6 +734,7 @@ commentsCmd
       branch: parent.branch,
       commit: parent.commit,
       parent_id: parent.id,
+      author: repo ? await getGitManager(repo.path).getUserName() : undefined,
     });
 
     console.log(`Added reply: ${reply.id}`);
@@ -483,6 +864,220 @@ notesCmd
     }
   });
 
+// Review state commands
+const stateCmd = program.command("state").description("Export, import, merge and clear review state");
+
+// Read and validate a state file written by `cerebro state export`
+function readStateFile(path: string): ReviewStateExport {
+  let raw: unknown;
+  try {
+    raw = JSON.parse(readFileSync(resolve(path), "utf-8"));
+  } catch (err) {
+    throw new Error(`Could not read state file ${path}: ${(err as Error).message}`);
+  }
+
+  const result = ReviewStateExportSchema.safeParse(raw);
+  if (!result.success) {
+    const issue = result.error.issues[0];
+    throw new Error(`Invalid state file ${path}: ${issue?.path.join(".") || "root"}: ${issue?.message}`);
+  }
+  return result.data;
+}
+
+// Pick the exported repo matching the target: same path, else same name, else the only one
+function findExportedRepo(data: ReviewStateExport, repo: Repository): ExportedRepoState | undefined {
+  return (
+    data.repos.find((r) => r.path === repo.path) ??
+    data.repos.find((r) => r.name === repo.name) ??
+    (data.repos.length === 1 ? data.repos[0] : undefined)
+  );
+}
+
+stateCmd
+  .command("export")
+  .description("Write viewed-file state to a JSON file")
+  .requiredOption("--out <file>", "Output file")
+  .option("-r, --repo <idOrPath>", "Repository ID or path (defaults to current directory)")
+  .option("--all", "Export every tracked repository")
+  .action(async (options: { out: string; repo?: string; all?: boolean }) => {
+    let repos: Repository[];
+    try {
+      repos = options.all ? await state.getRepos() : [await resolveRepo(options.repo)];
+    } catch (err) {
+      console.error((err as Error).message);
+      process.exit(1);
+      return;
+    }
+
+    const data: ReviewStateExport = {
+      version: REVIEW_STATE_VERSION,
+      exported_at: Date.now(),
+      repos: await Promise.all(
+        repos.map(async (repo) => ({
+          path: repo.path,
+          name: repo.name,
+          viewed: await state.getViewedEntries(repo.id),
+        }))
+      ),
+    };
+
+    writeFileSync(resolve(options.out), JSON.stringify(data, null, 2) + "\n");
+    const count = data.repos.reduce((sum, r) => sum + r.viewed.length, 0);
+    console.log(`Exported ${count} viewed file(s) from ${repos.length} repo(s) to ${options.out}`);
+  });
+
+stateCmd
+  .command("import")
+  .description("Merge viewed-file state from a JSON file into a repository")
+  .requiredOption("--in <file>", "State file written by 'cerebro state export'")
+  .option("-r, --repo <idOrPath>", "Repository ID or path (defaults to current directory)")
+  .action(async (options: { in: string; repo?: string }) => {
+    let repo: Repository;
+    let data: ReviewStateExport;
+    try {
+      repo = await resolveRepo(options.repo);
+      data = readStateFile(options.in);
+    } catch (err) {
+      console.error((err as Error).message);
+      process.exit(1);
+      return;
+    }
+
+    const source = findExportedRepo(data, repo);
+    if (!source) {
+      console.error(`No state for ${repo.name} (${repo.path}) in ${options.in}`);
+      process.exit(1);
+      return;
+    }
+
+    const written = await state.mergeViewedEntries(repo.id, source.viewed);
+    console.log(`Imported ${written} viewed file(s) into ${repo.name}`);
+  });
+
+stateCmd
+  .command("merge")
+  .description("Merge one or more state files into review state, or into a combined file with --out")
+  .argument("<files...>", "State files written by 'cerebro state export'")
+  .option("--out <file>", "Write the merged state to a file instead of the database")
+  .action(async (files: string[], options: { out?: string }) => {
+    let merged: ReviewStateExport;
+    try {
+      merged = files.map(readStateFile).reduce((acc, data) => state.mergeReviewStates(acc, data));
+    } catch (err) {
+      console.error((err as Error).message);
+      process.exit(1);
+      return;
+    }
+
+    if (options.out) {
+      writeFileSync(resolve(options.out), JSON.stringify(merged, null, 2) + "\n");
+      console.log(`Merged ${files.length} file(s) into ${options.out}`);
+      return;
+    }
+
+    for (const exported of merged.repos) {
+      const repo = await state.getRepoByPath(exported.path);
+      if (!repo) {
+        console.log(`Skipped ${exported.name} (${exported.path}): not tracked here`);
+        continue;
+      }
+      const written = await state.mergeViewedEntries(repo.id, exported.viewed);
+      console.log(`Merged ${written} viewed file(s) into ${repo.name}`);
+    }
+  });
+
+stateCmd
+  .command("clear")
+  .description("Remove viewed and collapsed marks and the saved review position for a repository")
+  .option("-r, --repo <idOrPath>", "Repository ID or path (defaults to current directory)")
+  .option("--branch <name>", "Only clear this branch")
+  .option("--keep-position", "Leave the saved review position so the UI still reopens where you were")
+  .action(async (options: { repo?: string; branch?: string; keepPosition?: boolean }) => {
+    let repo: Repository;
+    try {
+      repo = await resolveRepo(options.repo);
+    } catch (err) {
+      console.error((err as Error).message);
+      process.exit(1);
+      return;
+    }
+
+    const removed = await state.clearReviewState(repo.id, { branch: options.branch, keepPosition: options.keepPosition });
+    const scope = options.branch ? ` on ${options.branch}` : "";
+    console.log(`Cleared ${removed} viewed file(s) from ${repo.name}${scope}${options.keepPosition ? " (position kept)" : ""}`);
+  });
+
+// Read-only diagnostics: what `start` would resolve from here, without touching state
+program
+  .command("doctor")
+  .description("Print the resolved configuration, state location and repository detection for this directory")
+  .action(async () => {
+    const cwd = resolve(process.cwd());
+    const dbPath = state.getDbPath();
+    // Opening the database through state would create or migrate it; it's only read, read-only
+    const dbExists = existsSync(dbPath);
+    const location = state.probeStateLocation();
+    const row = (label: string, value: string) => console.log(`  ${`${label}:`.padEnd(20)}${value}`);
+
+    console.log("Cerebro doctor\n");
+    row("Version", `${VERSION} (Bun ${Bun.version})`);
+    row("Working directory", cwd);
+    row("State file", `${dbPath}${process.env["CEREBRO_STATE_FILE"] ? " (CEREBRO_STATE_FILE)" : ""}${dbExists ? "" : " (not created yet)"}`);
+    if (location.mode !== "configured") {
+      // The configured location can't be created; start would fall back
+      row("State fallback", location.path ? `${location.path} (${location.mode})` : "memory only (nothing persists)");
+    }
+    row("Config file", join(state.getConfigDir(), "config.json"));
+    const config = await state.getConfig();
+    row("Port", `${config.defaultPort} (config port; -p overrides)`);
+    // Bun.serve binds every interface unless given a hostname
+    row("Host", `all interfaces (opened as http://localhost:${config.defaultPort})`);
+
+    if (!(await isGitRepo(cwd))) {
+      row("Git repository", "no");
+      return;
+    }
+
+    const git = getGitManager(cwd);
+    const [bare, linked, defaultBranch, identity, upstream] = await Promise.all([
+      git.isBare(),
+      git.isLinkedWorktree(),
+      git.getDefaultBranch(),
+      git.getUserIdentity(),
+      git.getUpstream(),
+    ]);
+    row("Git repository", bare ? "yes (bare)" : linked ? "yes (linked worktree)" : "yes (worktree)");
+    if (!bare) row("Current branch", (await git.getCurrentBranch()) || "(detached)");
+    row("Upstream", upstream ?? "none");
+    row("Default branch", `${defaultBranch} (detected)`);
+
+    const { tracked, current: currentRepo } = dbExists ? state.inspectRepos(dbPath, cwd) : {};
+    if (tracked) {
+      row("Tracked repo", `${tracked.name} (${tracked.id})`);
+      row("Base branch", `${tracked.baseBranch} (stored; start --base overrides)`);
+    } else {
+      row("Tracked repo", "no (start would add it)");
+      row("Base branch", `${defaultBranch} (detected; start --base overrides)`);
+    }
+    if (currentRepo) row("Current repo", `${currentRepo.name} (${currentRepo.path})`);
+
+    const user = [identity.name, identity.email && `<${identity.email}>`].filter(Boolean).join(" ");
+    row("Git user", user || "not set (viewed marks are recorded as anonymous)");
+  });
+
+// Shell completions (generated from the registered commands)
+program
+  .command("completions")
+  .description("Print a shell completion script")
+  .argument("<shell>", `Shell: ${COMPLETION_SHELLS.join(", ")}`)
+  .action((shell: string) => {
+    if (!COMPLETION_SHELLS.includes(shell as CompletionShell)) {
+      console.error(`Error: unsupported shell '${shell}' (expected ${COMPLETION_SHELLS.join(", ")})`);
+      process.exit(1);
+    }
+    process.stdout.write(generateCompletion(shell as CompletionShell, program));
+  });
+
 // Version command with more detail
 program
   .command("version")
@@ -0,0 +1,118 @@
+import { mkdirSync, mkdtempSync, rmSync, writeFileSync } from "fs";
+import { tmpdir } from "os";
+import { join } from "path";
+import { afterAll, beforeAll, describe, expect, it } from "bun:test";
+import { applyAction, keyToAction, renderTui, repoTuiSource, type TuiView } from "./tui";
+import { createPalette } from "./color";
+import * as state from "../state";
+import type { FileDiff } from "../types";
+
+function file(path: string): FileDiff {
+  return { path, status: "modified", additions: 1, deletions: 1, patch: "", viewed: false };
+}
+
+function sampleView(overrides: Partial<TuiView> = {}): TuiView {
+  return {
+    title: "cerebro  repo  feature vs main (branch)",
+    files: [file("src/a.ts"), file("src/b.ts"), file("src/c.ts")],
+    viewed: { "src/b.ts": true },
+    selected: 0,
+    scroll: 0,
+    patch: Array.from({ length: 30 }, (_, i) => `+line ${i}`),
+    ...overrides,
+  };
+}
+
+// Strip ANSI escapes so assertions read like the screen
+function plain(lines: string[]): string[] {
+  return lines.map((line) =>
+    line
+      .split("\x1b[")
+      .map((part, i) => (i === 0 ? part : part.replace(/^[0-9;?]*[A-Za-z]/, "")))
+      .join("")
+  );
+}
+
+describe("tui", () => {
+  it("maps vim keys and arrows to actions", () => {
+    expect(keyToAction("j")).toBe("next");
+    expect(keyToAction("\x1b[A")).toBe("prev");
+    expect(keyToAction("v")).toBe("toggle_viewed");
+    expect(keyToAction("z")).toBeNull();
+  });
+
+  it("clamps selection and scrolling", () => {
+    const view = sampleView({ selected: 2 });
+    expect(applyAction(view, "next", 10)).toEqual({ selected: 2, scroll: 0 });
+    expect(applyAction(sampleView({ scroll: 15 }), "page_down", 10).scroll).toBe(20);
+    expect(applyAction(sampleView({ scroll: 3 }), "page_up", 10).scroll).toBe(0);
+    // Moving to another file starts its patch from the top
+    expect(applyAction(sampleView({ scroll: 5 }), "next", 10)).toEqual({ selected: 1, scroll: 0 });
+  });
+
+  it("renders the header, file list with viewed marks, patch and footer", () => {
+    const lines = plain(renderTui(sampleView({ selected: 1, scroll: 2 }), 80, 6));
+    expect(lines).toHaveLength(6);
+    expect(lines[0]).toStartWith("cerebro  repo  feature vs main (branch)  1/3 viewed");
+    expect(lines[1]).toStartWith("[ ] src/a.ts");
+    expect(lines[2]).toStartWith("[x] src/b.ts");
+    expect(lines[1]).toContain("│+line 2");
+    expect(lines[5]).toContain("q quit");
+    expect(lines.every((line) => line.length <= 80)).toBe(true);
+  });
+
+  it("leaves patch lines uncolored when color is off", () => {
+    const colored = renderTui(sampleView(), 80, 4, createPalette(true))[1]!;
+    const uncolored = renderTui(sampleView(), 80, 4, createPalette(false))[1]!;
+    expect(colored).toContain("\x1b[32m+line 0");
+    expect(uncolored).not.toContain("\x1b[32m");
+  });
+});
+
+describe("repoTuiSource", () => {
+  let tempHome: string;
+  let repoPath: string;
+
+  beforeAll(async () => {
+    tempHome = mkdtempSync(join(tmpdir(), "cerebro-tui-test-"));
+    const configDir = join(tempHome, "config");
+    mkdirSync(configDir, { recursive: true });
+    process.env["CEREBRO_CONFIG_DIR"] = configDir;
+
+    repoPath = join(tempHome, "repo");
+    mkdirSync(repoPath);
+    await Bun.$`git -C ${repoPath} init -q -b main`.quiet();
+    await Bun.$`git -C ${repoPath} config user.email test@example.com`.quiet();
+    await Bun.$`git -C ${repoPath} config user.name Test`.quiet();
+    writeFileSync(join(repoPath, "README.md"), "# Repo\n");
+    await Bun.$`git -C ${repoPath} add .`.quiet();
+    await Bun.$`git -C ${repoPath} commit -q -m Base`.quiet();
+  });
+
+  afterAll(() => {
+    state.closeDb();
+    rmSync(tempHome, { recursive: true, force: true });
+    delete process.env["CEREBRO_CONFIG_DIR"];
+  });
+
+  it("carries marks on untouched files across an amend", async () => {
+    await Bun.$`git -C ${repoPath} checkout -q -b feature`.quiet();
+    writeFileSync(join(repoPath, "a.ts"), "a\n");
+    writeFileSync(join(repoPath, "b.ts"), "b\n");
+    await Bun.$`git -C ${repoPath} add .`.quiet();
+    await Bun.$`git -C ${repoPath} commit -q -m Feature`.quiet();
+
+    const repo = await state.addRepo(repoPath, "tui-repo", "main");
+    const source = repoTuiSource(repo, { mode: "branch", compare: "main", reviewer: "alice" });
+    const first = await source.load();
+    await source.setViewed(first.files.find((f) => f.path === "a.ts")!, true);
+
+    writeFileSync(join(repoPath, "b.ts"), "b changed\n");
+    await Bun.$`git -C ${repoPath} commit -q -a --amend --no-edit`.quiet();
+
+    const amended = await source.load();
+    expect(amended.viewed["a.ts"]).toBe(true);
+    expect(amended.viewed["b.ts"]).toBeUndefined();
+    expect(amended.message).toStartWith("Carried 1 viewed mark(s) from ");
+  });
+});
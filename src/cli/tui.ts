@@ -0,0 +1,326 @@
+/**
+ * Terminal review mode: a file list beside the selected file's patch, with
+ * viewed marks stored in the same state database as the web UI.
+ */
+import { getGitManager } from "../git";
+import { watchRepository } from "../git/watch";
+import { createPalette, shouldColor, type Palette } from "./color";
+import { requiresReview } from "../server/completion";
+import { createIgnoreMatcher } from "../server/ignore";
+import * as state from "../state";
+import { carryForwardViewed, reviewBlobOids, type ReviewKey } from "../state/carry";
+import type { DiffMode, FileDiff, Repository } from "../types";
+
+export interface TuiOptions {
+  mode: DiffMode;
+  compare: string;
+  // Reload whenever HEAD, the index or the worktree changes
+  watch?: boolean;
+  // As for start: the name recorded on marks, and whether marks also match by file content
+  reviewer?: string;
+  stateKey?: state.StateKey;
+}
+
+export interface TuiView {
+  title: string;
+  files: FileDiff[];
+  viewed: Record<string, boolean>;
+  selected: number;
+  // First visible patch line
+  scroll: number;
+  patch: string[];
+  message?: string;
+}
+
+export type TuiAction =
+  | "next"
+  | "prev"
+  | "first"
+  | "last"
+  | "line_down"
+  | "line_up"
+  | "page_down"
+  | "page_up"
+  | "toggle_viewed"
+  | "refresh"
+  | "quit";
+
+const KEY_ACTIONS: Record<string, TuiAction> = {
+  j: "next",
+  "\x1b[B": "next",
+  k: "prev",
+  "\x1b[A": "prev",
+  g: "first",
+  G: "last",
+  J: "line_down",
+  K: "line_up",
+  " ": "page_down",
+  "\x1b[6~": "page_down",
+  b: "page_up",
+  "\x1b[5~": "page_up",
+  v: "toggle_viewed",
+  r: "refresh",
+  q: "quit",
+  "\x03": "quit", // Ctrl+C
+};
+
+export function keyToAction(key: string): TuiAction | null {
+  return KEY_ACTIONS[key] ?? null;
+}
+
+const HELP = "j/k file  J/K scroll  space/b page  v viewed  r refresh  q quit";
+
+// Cursor control and reverse/dim video are layout, not color, so they stay on under NO_COLOR
+const ESC = "\x1b[";
+const RESET = `${ESC}0m`;
+const REVERSE = `${ESC}7m`;
+const DIM = `${ESC}2m`;
+
+export function colorPatchLine(line: string, palette: Palette): string {
+  if (line.startsWith("+++") || line.startsWith("---")) return palette.bold(line);
+  if (line.startsWith("+")) return palette.green(line);
+  if (line.startsWith("-")) return palette.red(line);
+  if (line.startsWith("@@")) return palette.cyan(line);
+  return line;
+}
+
+// Pad or cut to exactly `width` columns (tabs expanded so widths stay predictable)
+function fit(text: string, width: number): string {
+  const plain = text.replace(/\t/g, "    ");
+  return plain.length > width ? plain.slice(0, width) : plain.padEnd(width);
+}
+
+// Full-screen frame, one string per terminal row (the caller positions the cursor)
+export function renderTui(view: TuiView, width: number, height: number, palette: Palette = createPalette(true)): string[] {
+  const bodyHeight = Math.max(height - 2, 1);
+  const listWidth = Math.min(40, Math.max(Math.floor(width * 0.35), 12));
+  const patchWidth = Math.max(width - listWidth - 1, 1);
+
+  // Files outside require-review-for (or ignored) don't count toward completion
+  const required = view.files.filter((f) => f.review_required !== false);
+  const viewedCount = required.filter((f) => view.viewed[f.path]).length;
+  const header = `${view.title}  ${viewedCount}/${required.length} viewed`;
+
+  // Keep the selected file inside the visible part of the list
+  const listStart = Math.max(0, Math.min(view.selected - Math.floor(bodyHeight / 2), view.files.length - bodyHeight));
+
+  const lines = [`${REVERSE}${fit(header, width)}${RESET}`];
+  for (let row = 0; row < bodyHeight; row++) {
+    const file = view.files[listStart + row];
+    let left = " ".repeat(listWidth);
+    if (file) {
+      const entry = fit(`${view.viewed[file.path] ? "[x]" : "[ ]"} ${file.path}`, listWidth);
+      left = listStart + row === view.selected ? `${REVERSE}${entry}${RESET}` : entry;
+    }
+
+    const patchLine = view.patch[view.scroll + row];
+    const right = patchLine !== undefined ? colorPatchLine(fit(patchLine, patchWidth), palette) : "";
+    lines.push(`${left}${DIM}│${RESET}${right}`);
+  }
+  lines.push(`${DIM}${fit(view.message ?? HELP, width)}${RESET}`);
+  return lines;
+}
+
+// Apply a navigation action; returns the new selection/scroll (patch loading is the caller's job)
+export function applyAction(view: TuiView, action: TuiAction, pageSize: number): Pick<TuiView, "selected" | "scroll"> {
+  const lastFile = Math.max(view.files.length - 1, 0);
+  const maxScroll = Math.max(view.patch.length - pageSize, 0);
+  const clampScroll = (n: number) => Math.min(Math.max(n, 0), maxScroll);
+
+  switch (action) {
+    case "next":
+      return { selected: Math.min(view.selected + 1, lastFile), scroll: 0 };
+    case "prev":
+      return { selected: Math.max(view.selected - 1, 0), scroll: 0 };
+    case "first":
+      return { selected: 0, scroll: 0 };
+    case "last":
+      return { selected: lastFile, scroll: 0 };
+    case "line_down":
+      return { selected: view.selected, scroll: clampScroll(view.scroll + 1) };
+    case "line_up":
+      return { selected: view.selected, scroll: clampScroll(view.scroll - 1) };
+    case "page_down":
+      return { selected: view.selected, scroll: clampScroll(view.scroll + pageSize) };
+    case "page_up":
+      return { selected: view.selected, scroll: clampScroll(view.scroll - pageSize) };
+    default:
+      return { selected: view.selected, scroll: view.scroll };
+  }
+}
+
+// Where the terminal review gets its files and keeps viewed marks: a repo's diff, or two directories
+export interface TuiSource {
+  // `message` is shown in the status line, e.g. marks carried over from a previous HEAD
+  load(): Promise<{ title: string; files: FileDiff[]; viewed: Record<string, boolean>; message?: string }>;
+  // Patch for a file listed without one
+  loadPatch(file: FileDiff): Promise<string | undefined>;
+  setViewed(file: FileDiff, viewed: boolean): Promise<void>;
+  // Start calling `onChange` when the files may have changed; returns a function that stops
+  watch?(onChange: () => void): Promise<() => void>;
+}
+
+export function repoTuiSource(repo: Repository, options: TuiOptions): TuiSource {
+  const git = getGitManager(repo.path);
+  let key: ReviewKey = { branch: "", commit: "" };
+  const blobOids = (paths: string[]) => reviewBlobOids(git, key, paths);
+  return {
+    load: async () => {
+      const [diff, config] = await Promise.all([git.getDiff({ baseBranch: options.compare, mode: options.mode }), state.getConfig()]);
+      key = { branch: diff.branch, commit: diff.commit, ...(options.mode !== "branch" && { worktree: true as const }) };
+      const { branch, commit } = key;
+      // Same as the web UI: marks on files an amend or rebase didn't touch move to the new HEAD
+      const carry = await carryForwardViewed(repo.id, git, key);
+      const isIgnored = createIgnoreMatcher(config.ignorePatterns ?? []);
+      const files = diff.files.map((f) =>
+        requiresReview({ status: f.status, ignored: isIgnored(f.path) }, config.requireReviewFor)
+          ? f
+          : { ...f, review_required: false as const }
+      );
+      const viewed = await state.getViewedFiles(repo.id, branch, commit);
+      if (options.stateKey === "content") {
+        const matches = await state.getViewedByContent(repo.id, await blobOids(files.map((f) => f.path)));
+        for (const filePath of Object.keys(matches)) viewed[filePath] = true;
+      }
+      return {
+        title: `cerebro  ${repo.name}  ${branch} vs ${options.compare} (${options.mode})`,
+        files,
+        viewed,
+        ...(carry && carry.carried > 0 && { message: `Carried ${carry.carried} viewed mark(s) from ${carry.from.slice(0, 7)}` }),
+      };
+    },
+    // Branch mode lists files without patches; fetch the selected one on demand
+    loadPatch: async (file) => (await git.getFileDiff({ baseBranch: options.compare, mode: options.mode, filePath: file.path }))?.patch,
+    setViewed: async (file, viewed) => {
+      const reviewer = options.reviewer ?? (await git.getUserName()) ?? "anonymous";
+      const blob = (await blobOids([file.path]))[file.path];
+      await state.setFileViewed(repo.id, key.branch, key.commit, file.path, viewed, reviewer, blob);
+      // Otherwise a mark on the same content elsewhere would keep it viewed
+      if (!viewed && options.stateKey === "content" && blob) {
+        await state.clearViewedContent(repo.id, key.branch, file.path, blob);
+      }
+    },
+    watch: async (onChange) => watchRepository(repo.path, await git.getGitDir(), onChange),
+  };
+}
+
+export async function runTui(repo: Repository, options: TuiOptions): Promise<void> {
+  await runTuiSource(repoTuiSource(repo, options), { watch: options.watch });
+}
+
+export async function runTuiSource(source: TuiSource, options: { watch?: boolean } = {}): Promise<void> {
+  const stdin = process.stdin;
+  const stdout = process.stdout;
+  if (!stdin.isTTY || !stdout.isTTY) {
+    throw new Error("cerebro tui needs an interactive terminal");
+  }
+
+  const palette = createPalette(shouldColor(stdout));
+  const view: TuiView = { title: "", files: [], viewed: {}, selected: 0, scroll: 0, patch: [] };
+
+  const loadPatch = async () => {
+    const file = view.files[view.selected];
+    if (!file) {
+      view.patch = [];
+      return;
+    }
+    const patch = file.patch || (await source.loadPatch(file));
+    view.patch = patch ? patch.split("\n") : ["(no textual changes)"];
+  };
+
+  const refresh = async () => {
+    const loaded = await source.load();
+    view.title = loaded.title;
+    view.files = loaded.files;
+    view.viewed = loaded.viewed;
+    if (loaded.message) view.message = loaded.message;
+    view.selected = Math.min(view.selected, Math.max(loaded.files.length - 1, 0));
+    view.scroll = 0;
+    await loadPatch();
+  };
+
+  const draw = () => {
+    const frame = renderTui(view, stdout.columns || 80, stdout.rows || 24, palette);
+    stdout.write(`${ESC}H${frame.join(`${ESC}K\r\n`)}${ESC}K`);
+    view.message = undefined;
+  };
+
+  const pageSize = () => Math.max((stdout.rows || 24) - 2, 1);
+
+  await refresh();
+  // Alternate screen, hidden cursor
+  stdout.write(`${ESC}?1049h${ESC}?25l`);
+  stdin.setRawMode(true);
+  stdin.resume();
+  draw();
+
+  // Keys and watch reloads run one at a time, so a slow patch load, mark or reload can't
+  // interleave with the next; a failure shows in the status line instead of ending the session
+  let closed = false;
+  let pending = Promise.resolve();
+  const enqueue = (task: () => Promise<void>) => {
+    pending = pending
+      .then(() => (closed ? undefined : task()))
+      .catch((error) => {
+        view.message = `Error: ${error instanceof Error ? error.message : String(error)}`;
+        draw();
+      });
+  };
+
+  // Picks up commits, staging and edits made in another terminal
+  const stopWatching =
+    options.watch && source.watch
+      ? await source.watch(() =>
+          enqueue(async () => {
+            await refresh();
+            view.message ??= `Updated: ${view.files.length} file(s)`;
+            draw();
+          })
+        )
+      : undefined;
+
+  await new Promise<void>((done) => {
+    const quit = () => {
+      closed = true;
+      stopWatching?.();
+      stdin.off("data", onKey);
+      stdout.off("resize", draw);
+      stdin.setRawMode(false);
+      stdin.pause();
+      stdout.write(`${ESC}?25h${ESC}?1049l`);
+      done();
+    };
+
+    const onKey = (data: Buffer) => enqueue(() => handleKey(data));
+
+    const handleKey = async (data: Buffer) => {
+      const action = keyToAction(data.toString());
+      if (!action) return;
+
+      if (action === "quit") {
+        quit();
+        return;
+      }
+
+      if (action === "refresh") {
+        await refresh();
+        view.message = `Refreshed: ${view.files.length} file(s)`;
+      } else if (action === "toggle_viewed") {
+        const file = view.files[view.selected];
+        if (file) {
+          const viewed = !view.viewed[file.path];
+          await source.setViewed(file, viewed);
+          view.viewed = { ...view.viewed, [file.path]: viewed };
+        }
+      } else {
+        const previous = view.selected;
+        Object.assign(view, applyAction(view, action, pageSize()));
+        if (view.selected !== previous) await loadPatch();
+      }
+      draw();
+    };
+
+    stdin.on("data", onKey);
+    stdout.on("resize", draw);
+  });
+}
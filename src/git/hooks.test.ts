@@ -0,0 +1,62 @@
+import { chmodSync, mkdirSync, mkdtempSync, rmSync, writeFileSync } from "fs";
+import { tmpdir } from "os";
+import { basename, join } from "path";
+import { $ } from "bun";
+import { afterAll, beforeAll, describe, expect, it } from "bun:test";
+import { getHooksDir, runPreReviewHook } from "./hooks";
+
+const repoPath = mkdtempSync(join(tmpdir(), "cerebro-hooks-test-"));
+
+function writeHook(path: string, body: string): void {
+  writeFileSync(path, `#!/bin/sh\n${body}\n`);
+  chmodSync(path, 0o755);
+}
+
+beforeAll(async () => {
+  await $`git init -q`.cwd(repoPath);
+});
+
+afterAll(() => {
+  rmSync(repoPath, { recursive: true, force: true });
+});
+
+describe("pre-review hook", () => {
+  it("is skipped when the repo has none", async () => {
+    expect(await runPreReviewHook(repoPath, "main")).toBeNull();
+  });
+
+  it("runs from the repo root and captures its output", async () => {
+    writeHook(join(repoPath, ".git", "hooks", "pre-review"), 'echo "base=$CEREBRO_BASE_BRANCH"\necho oops >&2\nexit 3');
+    const result = await runPreReviewHook(repoPath, "main");
+    expect(result?.exitCode).toBe(3);
+    expect(result?.output).toBe("base=main\noops\n");
+  });
+
+  it("honors core.hooksPath", async () => {
+    mkdirSync(join(repoPath, "tools", "hooks"), { recursive: true });
+    writeHook(join(repoPath, "tools", "hooks", "pre-review"), "pwd");
+    await $`git config core.hooksPath tools/hooks`.cwd(repoPath);
+    try {
+      expect(await getHooksDir(repoPath)).toBe(join(repoPath, "tools", "hooks"));
+      const result = await runPreReviewHook(repoPath, "main");
+      expect(result?.exitCode).toBe(0);
+      expect(result?.output.trim()).toEndWith(basename(repoPath));
+    } finally {
+      await $`git config --unset core.hooksPath`.cwd(repoPath);
+    }
+  });
+
+  it("runs an explicit script relative to the repo root", async () => {
+    writeHook(join(repoPath, "regen.sh"), "echo regenerated");
+    const result = await runPreReviewHook(repoPath, "main", "regen.sh");
+    expect(result).toEqual({ path: join(repoPath, "regen.sh"), exitCode: 0, output: "regenerated\n" });
+  });
+
+  it("kills a hook that runs past the timeout", async () => {
+    writeHook(join(repoPath, "hang.sh"), "sleep 10");
+    const started = performance.now();
+    const result = await runPreReviewHook(repoPath, "main", "hang.sh", 200);
+    expect(result?.timedOut).toBe(true);
+    expect(performance.now() - started).toBeLessThan(5000);
+  });
+});
@@ -0,0 +1,71 @@
+/**
+ * The opt-in pre-review hook (start --pre-review-hook): a script run in the repository before
+ * the server computes its first diff, e.g. to regenerate snapshots or lockfiles. It is arbitrary
+ * code from the repo (or the path given), run with the user's permissions.
+ */
+import { existsSync } from "fs";
+import { join, resolve } from "path";
+
+export const PRE_REVIEW_HOOK = "pre-review";
+
+// A hook still running after this long is killed, so a hung script can't block startup forever
+export const PRE_REVIEW_HOOK_TIMEOUT_MS = 5 * 60 * 1000;
+
+export interface HookResult {
+  path: string;
+  exitCode: number;
+  // All of stdout followed by all of stderr (not interleaved); empty when the hook timed out
+  output: string;
+  timedOut?: true;
+}
+
+// The repo's hooks directory, honoring core.hooksPath (relative values are from the worktree root)
+export async function getHooksDir(repoPath: string): Promise<string> {
+  const result = await Bun.$`git rev-parse --git-path hooks`.cwd(repoPath).quiet().nothrow();
+  if (result.exitCode !== 0) {
+    throw new Error(`Not a git repository: ${repoPath}`);
+  }
+  return resolve(repoPath, result.stdout.toString().trim());
+}
+
+// An explicit script is resolved from the repo root; otherwise <hooks dir>/pre-review
+export async function resolvePreReviewHook(repoPath: string, script?: string): Promise<string> {
+  return script ? resolve(repoPath, script) : join(await getHooksDir(repoPath), PRE_REVIEW_HOOK);
+}
+
+// Runs the hook from the repo root with CEREBRO_BASE_BRANCH set; null when it doesn't exist
+export async function runPreReviewHook(
+  repoPath: string,
+  baseBranch: string,
+  script?: string,
+  timeoutMs = PRE_REVIEW_HOOK_TIMEOUT_MS
+): Promise<HookResult | null> {
+  const path = await resolvePreReviewHook(repoPath, script);
+  if (!existsSync(path)) return null;
+
+  let proc;
+  try {
+    proc = Bun.spawn([path], {
+      cwd: repoPath,
+      env: { ...process.env, CEREBRO_BASE_BRANCH: baseBranch },
+      stdout: "pipe",
+      stderr: "pipe",
+    });
+  } catch (error) {
+    // Typically a hook that isn't executable (chmod +x it)
+    throw new Error(`Could not run ${path}: ${error instanceof Error ? error.message : String(error)}`);
+  }
+  const hook = proc;
+  let timedOut = false;
+  const timer = setTimeout(() => {
+    timedOut = true;
+    hook.kill();
+  }, timeoutMs);
+  const output = Promise.all([new Response(hook.stdout).text(), new Response(hook.stderr).text()]);
+  const exitCode = await hook.exited;
+  clearTimeout(timer);
+  // Children the hook started may still hold its pipes open, so don't wait on them after a kill
+  if (timedOut) return { path, exitCode, output: "", timedOut: true };
+  const [stdout, stderr] = await output;
+  return { path, exitCode, output: stdout + stderr };
+}
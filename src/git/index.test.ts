@@ -1,8 +1,37 @@
-import { mkdtempSync, rmSync, writeFileSync } from "fs";
+import { chmodSync, mkdtempSync, rmSync, writeFileSync } from "fs";
 import { tmpdir } from "os";
 import { join } from "path";
 import { beforeAll, afterAll, describe, expect, it } from "bun:test";
-import { getGitManager, isGitRepo, getRepoName, type GitManager } from "./index";
+import {
+  assertRefAllowed,
+  countConflictMarkers,
+  diffDirectories,
+  diffOverrideFor,
+  getGitManager,
+  isGitRepo,
+  getRepoName,
+  numstatPath,
+  parseBlamePorcelain,
+  parseDiffOverride,
+  parseLsTree,
+  parseNameStatusLine,
+  parseRange,
+  parseRawDiff,
+  parseReflog,
+  parseReviewNote,
+  RefNotAllowedError,
+  REVIEW_NOTES_REF,
+  setAllowedRefs,
+  setDiffContentOptions,
+  setDiffFindOptions,
+  setDiffOverrides,
+  setMaxBlobSize,
+  setPatchCache,
+  setRemoteFallbackHandler,
+  sumNumstat,
+  withDiffContentOptions,
+  type GitManager,
+} from "./index";
 
 let tempDir: string;
 let testRepoPath: string;
@@ -52,11 +81,71 @@ describe("branch and commit info", () => {
     expect(/^[a-f0-9]+$/.test(commit)).toBe(true);
   });
 
+  it("getUserIdentity reads user.name and user.email from git config", async () => {
+    expect(await git.getUserIdentity()).toEqual({ name: "Test", email: "test@test.com" });
+  });
+
   it("getDefaultBranch returns main for this repo", async () => {
     const defaultBranch = await git.getDefaultBranch();
     expect(defaultBranch).toBe("main");
   });
 
+  it("getDefaultBranch detects master-based repos", async () => {
+    const repoPath = join(tempDir, "master-repo");
+    await Bun.$`mkdir -p ${repoPath}`.quiet();
+    await Bun.$`git -C ${repoPath} init`.quiet();
+    await Bun.$`git -C ${repoPath} config user.email "test@test.com"`.quiet();
+    await Bun.$`git -C ${repoPath} config user.name "Test"`.quiet();
+    await Bun.$`git -C ${repoPath} checkout -b master`.quiet();
+    writeFileSync(join(repoPath, "README.md"), "# Master\n");
+    await Bun.$`git -C ${repoPath} add .`.quiet();
+    await Bun.$`git -C ${repoPath} commit -m "Initial"`.quiet();
+    await Bun.$`git -C ${repoPath} checkout -b feature`.quiet();
+
+    expect(await getGitManager(repoPath).getDefaultBranch()).toBe("master");
+  });
+
+  it("getDefaultBranch prefers origin/HEAD over common names", async () => {
+    const clonePath = join(tempDir, "release-clone");
+    await Bun.$`git -C ${testRepoPath} branch release`.quiet();
+    await Bun.$`git clone -q ${testRepoPath} ${clonePath}`.quiet();
+    await Bun.$`git -C ${clonePath} remote set-head origin release`.quiet();
+
+    expect(await getGitManager(clonePath).getDefaultBranch()).toBe("release");
+
+    await Bun.$`git -C ${testRepoPath} branch -D release`.quiet();
+  });
+
+  it("handles an unborn branch with no commits yet", async () => {
+    const repoPath = join(tempDir, "unborn-repo");
+    await Bun.$`mkdir -p ${repoPath}`.quiet();
+    await Bun.$`git -C ${repoPath} init -q -b trunk`.quiet();
+    writeFileSync(join(repoPath, "draft.txt"), "not committed\n");
+    const unborn = getGitManager(repoPath);
+
+    expect(await unborn.getCurrentBranch()).toBe("trunk");
+    expect(await unborn.getCurrentCommit()).toBe("");
+    const diff = await unborn.getDiff({ baseBranch: "main", mode: "working" });
+    expect(diff.files).toEqual([]);
+    expect(diff.branch).toBe("trunk");
+  });
+
+  it("getCurrentCommit throws when a detached HEAD doesn't resolve", async () => {
+    const repoPath = join(tempDir, "broken-head-repo");
+    await Bun.$`mkdir -p ${repoPath}`.quiet();
+    await Bun.$`git -C ${repoPath} init -q`.quiet();
+    writeFileSync(join(repoPath, ".git", "HEAD"), `${"1".repeat(40)}\n`);
+
+    await expect(getGitManager(repoPath).getCurrentCommit()).rejects.toThrow("Cannot resolve HEAD");
+  });
+
+  it("isDirty reflects uncommitted changes", async () => {
+    expect(await git.isDirty()).toBe(false);
+    writeFileSync(join(testRepoPath, "untracked.txt"), "new\n");
+    expect(await git.isDirty()).toBe(true);
+    rmSync(join(testRepoPath, "untracked.txt"));
+  });
+
   it("getRemoteUrl returns undefined without remote", async () => {
     const remoteUrl = await git.getRemoteUrl();
     expect(remoteUrl).toBeUndefined();
@@ -126,6 +215,99 @@ describe("diff operations", () => {
       // Cleanup
       await Bun.$`rm ${join(testRepoPath, "untracked.txt")}`.quiet();
     });
+
+    it("marks a missing final newline in untracked file patches", async () => {
+      writeFileSync(join(testRepoPath, "no-eol.txt"), "one\ntwo");
+      writeFileSync(join(testRepoPath, "eol.txt"), "one\n");
+
+      const diff = await git.getDiff({ baseBranch: "main", mode: "working" });
+      const noEol = diff.files.find((f) => f.path === "no-eol.txt");
+      const eol = diff.files.find((f) => f.path === "eol.txt");
+      expect(noEol?.patch).toEndWith("@@ -0,0 +1,2 @@\n+one\n+two\n\\ No newline at end of file\n");
+      expect(eol?.patch).toEndWith("@@ -0,0 +1,1 @@\n+one\n");
+
+      // Applying the patch recreates the file byte for byte
+      await Bun.$`rm ${join(testRepoPath, "no-eol.txt")}`.quiet();
+      await Bun.$`git -C ${testRepoPath} apply < ${Buffer.from(noEol!.patch)}`.quiet();
+      expect(await Bun.file(join(testRepoPath, "no-eol.txt")).text()).toBe("one\ntwo");
+
+      await Bun.$`rm ${join(testRepoPath, "no-eol.txt")} ${join(testRepoPath, "eol.txt")}`.quiet();
+    });
+
+    it("estimateDiff totals tracked changes and counts untracked files", async () => {
+      writeFileSync(join(testRepoPath, "README.md"), "# Modified\nmore\n");
+      writeFileSync(join(testRepoPath, "untracked.txt"), "untracked content\n");
+
+      expect(await git.estimateDiff({ baseBranch: "main", mode: "working" })).toEqual({
+        files: 2,
+        additions: 2,
+        deletions: 1,
+      });
+
+      await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+      await Bun.$`rm ${join(testRepoPath, "untracked.txt")}`.quiet();
+    });
+  });
+
+  describe("full mode", () => {
+    it("diffs the merge base against the working tree and labels where changes are", async () => {
+      await Bun.$`git -C ${testRepoPath} checkout -b full-test`.quiet();
+      writeFileSync(join(testRepoPath, "committed.txt"), "one\n");
+      writeFileSync(join(testRepoPath, "both.txt"), "one\n");
+      await Bun.$`git -C ${testRepoPath} add .`.quiet();
+      await Bun.$`git -C ${testRepoPath} commit -m "Committed work"`.quiet();
+      writeFileSync(join(testRepoPath, "both.txt"), "one\ntwo\n");
+      writeFileSync(join(testRepoPath, "README.md"), "# Local edit\n");
+      writeFileSync(join(testRepoPath, "untracked.txt"), "new\n");
+
+      const diff = await git.getDiff({ baseBranch: "main", mode: "full" });
+      expect(diff.mode).toBe("full");
+      expect(diff.files.map((f) => [f.path, f.status, f.additions, f.changed_in]).sort()).toEqual([
+        ["README.md", "modified", 1, "uncommitted"],
+        ["both.txt", "added", 2, "both"],
+        ["committed.txt", "added", 1, "committed"],
+        ["untracked.txt", "untracked", 2, "uncommitted"],
+      ]);
+
+      const both = await git.getFileDiff({ baseBranch: "main", mode: "full", filePath: "both.txt" });
+      expect(both?.patch).toContain("+two");
+      expect(both?.changed_in).toBe("both");
+      expect(both?.new_file?.contents).toBe("one\ntwo\n");
+      expect(await git.estimateDiff({ baseBranch: "main", mode: "full" })).toEqual({ files: 4, additions: 4, deletions: 1 });
+
+      await Bun.$`git -C ${testRepoPath} checkout -- README.md both.txt`.quiet();
+      await Bun.$`rm ${join(testRepoPath, "untracked.txt")}`.quiet();
+      await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+      await Bun.$`git -C ${testRepoPath} branch -D full-test`.quiet();
+    });
+  });
+
+  describe("pathspec scoping", () => {
+    it("getDiff only includes paths matching the pathspec", async () => {
+      writeFileSync(join(testRepoPath, "README.md"), "# Scoped\n");
+      writeFileSync(join(testRepoPath, "src/index.ts"), "// scoped\n");
+
+      const diff = await git.getDiff({ baseBranch: "main", mode: "working", paths: ["src"] });
+      expect(diff.files.map((f) => f.path)).toEqual(["src/index.ts"]);
+
+      // Restore
+      await Bun.$`git -C ${testRepoPath} checkout -- README.md src/index.ts`.quiet();
+    });
+
+    it("getDiff scopes branch mode with globs", async () => {
+      await Bun.$`git -C ${testRepoPath} checkout -b pathspec-test`.quiet();
+      writeFileSync(join(testRepoPath, "one.ts"), "1\n");
+      writeFileSync(join(testRepoPath, "two.md"), "2\n");
+      await Bun.$`git -C ${testRepoPath} add .`.quiet();
+      await Bun.$`git -C ${testRepoPath} commit -m "Two files"`.quiet();
+
+      const diff = await git.getDiff({ baseBranch: "main", mode: "branch", paths: ["*.md"] });
+      expect(diff.files.map((f) => f.path)).toEqual(["two.md"]);
+
+      // Cleanup
+      await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+      await Bun.$`git -C ${testRepoPath} branch -D pathspec-test`.quiet();
+    });
   });
 
   describe("staged files in working mode", () => {
@@ -156,6 +338,71 @@ describe("diff operations", () => {
       await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
     });
   });
+
+  describe("unstaged mode", () => {
+    it("diffs the index against the working tree, leaving staged changes out", async () => {
+      writeFileSync(join(testRepoPath, "staged.txt"), "staged content\n");
+      writeFileSync(join(testRepoPath, "README.md"), "# Staged line\n");
+      await Bun.$`git -C ${testRepoPath} add staged.txt README.md`.quiet();
+      writeFileSync(join(testRepoPath, "README.md"), "# Staged line\nUnstaged line\n");
+      writeFileSync(join(testRepoPath, "loose.txt"), "not added\n");
+
+      const diff = await git.getDiff({ baseBranch: "main", mode: "unstaged" });
+      expect(diff.files.map((f) => [f.path, f.status, f.additions, f.deletions, f.staged])).toEqual([
+        ["README.md", "modified", 1, 0, false],
+        ["loose.txt", "untracked", 2, 0, false],
+      ]);
+
+      const readme = await git.getFileDiff({ baseBranch: "main", mode: "unstaged", filePath: "README.md" });
+      expect(readme?.patch).toContain("+Unstaged line");
+      expect(readme?.patch).not.toContain("-# Test");
+      expect(readme?.old_file?.contents).toBe("# Staged line\n");
+      expect(await git.getFileDiff({ baseBranch: "main", mode: "unstaged", filePath: "staged.txt" })).toBeNull();
+      expect((await git.estimateDiff({ baseBranch: "main", mode: "unstaged" })).files).toBe(2);
+
+      await Bun.$`git -C ${testRepoPath} reset -q HEAD staged.txt README.md`.quiet();
+      await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+      rmSync(join(testRepoPath, "staged.txt"));
+      rmSync(join(testRepoPath, "loose.txt"));
+    });
+  });
+
+  describe("conflicts", () => {
+    it("lists unmerged paths and flags them in working-tree diffs", async () => {
+      await Bun.$`git -C ${testRepoPath} checkout -q -b conflict-theirs`.quiet();
+      writeFileSync(join(testRepoPath, "README.md"), "# Theirs\n");
+      await Bun.$`git -C ${testRepoPath} rm -q src/index.ts`.quiet();
+      await Bun.$`git -C ${testRepoPath} commit -qam "Theirs"`.quiet();
+      await Bun.$`git -C ${testRepoPath} checkout -q -b conflict-ours main`.quiet();
+      writeFileSync(join(testRepoPath, "README.md"), "# Ours\n");
+      writeFileSync(join(testRepoPath, "src/index.ts"), "console.log('ours');\n");
+      await Bun.$`git -C ${testRepoPath} commit -qam "Ours"`.quiet();
+      await Bun.$`git -C ${testRepoPath} merge conflict-theirs`.quiet().nothrow();
+
+      expect(await git.getConflicts()).toEqual([
+        { path: "README.md", base: true, ours: true, theirs: true, markers: 1 },
+        { path: "src/index.ts", base: true, ours: true, theirs: false, markers: 0 },
+      ]);
+
+      const working = await git.getDiff({ baseBranch: "main", mode: "working" });
+      expect(working.files.map((f) => [f.path, f.conflicted])).toEqual([["README.md", true]]);
+      const unstaged = await git.getDiff({ baseBranch: "main", mode: "unstaged" });
+      expect(unstaged.files.map((f) => [f.path, f.conflicted])).toEqual([
+        ["README.md", true],
+        ["src/index.ts", true],
+      ]);
+      expect((await git.getFileDiff({ baseBranch: "main", mode: "working", filePath: "README.md" }))?.conflicted).toBe(true);
+
+      await Bun.$`git -C ${testRepoPath} merge --abort`.quiet();
+      await Bun.$`git -C ${testRepoPath} checkout -q main`.quiet();
+      await Bun.$`git -C ${testRepoPath} branch -qD conflict-ours conflict-theirs`.quiet();
+    });
+
+    it("counts conflict blocks", () => {
+      expect(countConflictMarkers("a\n<<<<<<< HEAD\nb\n=======\nc\n>>>>>>> x\n<<<<<<<\r\n")).toBe(2);
+      expect(countConflictMarkers("<<<<<<<< not a marker\n  <<<<<<< indented\n")).toBe(0);
+    });
+  });
 });
 
 // =============================================================================
@@ -203,6 +450,809 @@ describe("getFileDiff", () => {
   });
 });
 
+// =============================================================================
+// Submodules
+// =============================================================================
+
+describe("submodule changes", () => {
+  it("sumNumstat counts binary files without lines", () => {
+    expect(sumNumstat("3\t1\ta.ts\n-\t-\tlogo.png\n")).toEqual({ files: 2, additions: 3, deletions: 1 });
+    expect(sumNumstat("")).toEqual({ files: 0, additions: 0, deletions: 0 });
+  });
+
+  it("parseRawDiff reads modes and gitlink commits", () => {
+    const oldSha = "1".repeat(40);
+    const newSha = "2".repeat(40);
+    const raw = [
+      `:160000 160000 ${oldSha} ${newSha} M\tvendor/lib`,
+      `:100644 100644 ${oldSha} ${newSha} M\tsrc/index.ts`,
+      `:000000 160000 ${"0".repeat(40)} ${newSha} A\tvendor/new`,
+    ].join("\n");
+
+    const entries = parseRawDiff(raw);
+    expect(entries.size).toBe(3);
+    expect(entries.get("vendor/lib")).toEqual({ oldMode: "160000", newMode: "160000", oldOid: oldSha, newOid: newSha });
+    expect(entries.get("vendor/new")?.oldOid).toBeUndefined();
+    expect(entries.get("src/index.ts")?.newMode).toBe("100644");
+  });
+
+  it("getDiff reports submodule pointer updates in branch mode", async () => {
+    const head = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    await Bun.$`git -C ${testRepoPath} checkout -b submodule-test`.quiet();
+    await Bun.$`git -C ${testRepoPath} update-index --add --cacheinfo 160000,${head},vendor/lib`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add submodule"`.quiet();
+
+    const diff = await git.getDiff({ baseBranch: "main", mode: "branch" });
+    const submodule = diff.files.find((f) => f.path === "vendor/lib");
+    expect(submodule?.submodule).toBe(true);
+    expect(submodule?.submodule_old_commit).toBeUndefined();
+    expect(submodule?.submodule_new_commit).toBe(head);
+
+    // Cleanup
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D submodule-test`.quiet();
+  });
+});
+
+// =============================================================================
+// Symlinks
+// =============================================================================
+
+describe("symlink changes", () => {
+  it("getFileDiff reports old and new targets when a symlink changes", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b symlink-test`.quiet();
+    await Bun.$`ln -s README.md ${join(testRepoPath, "link")}`.quiet();
+    await Bun.$`git -C ${testRepoPath} add link`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add link"`.quiet();
+
+    // Retarget the link in the working tree
+    await Bun.$`ln -sfn src/index.ts ${join(testRepoPath, "link")}`.quiet();
+
+    const fileDiff = await git.getFileDiff({ baseBranch: "main", mode: "working", filePath: "link" });
+    expect(fileDiff?.symlink).toBe(true);
+    expect(fileDiff?.symlink_old_target).toBe("README.md");
+    expect(fileDiff?.symlink_new_target).toBe("src/index.ts");
+    expect(fileDiff?.new_file).toBeUndefined();
+
+    const diff = await git.getDiff({ baseBranch: "main", mode: "working" });
+    expect(diff.files.find((f) => f.path === "link")?.symlink_new_target).toBe("src/index.ts");
+
+    // Cleanup
+    await Bun.$`git -C ${testRepoPath} checkout -- link`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D symlink-test`.quiet();
+  });
+});
+
+// =============================================================================
+// Blame
+// =============================================================================
+
+describe("getBlame", () => {
+  it("returns per-line blame for the base version", async () => {
+    const blame = await git.getBlame({ baseBranch: "main", filePath: "README.md" });
+    expect(blame).not.toBeNull();
+    expect(blame?.length).toBe(1);
+    expect(blame?.[0]?.line).toBe(1);
+    expect(blame?.[0]?.author).toBe("Test");
+    expect(blame?.[0]?.summary).toBe("Initial commit");
+    expect(blame?.[0]?.commit.length).toBe(40);
+  });
+
+  it("returns null for files missing from the base", async () => {
+    const blame = await git.getBlame({ baseBranch: "main", filePath: "does-not-exist.ts" });
+    expect(blame).toBeNull();
+  });
+
+  it("doesn't cache a blame against a base that didn't resolve", async () => {
+    expect(await git.getBlame({ baseBranch: "late-base", filePath: "README.md" })).toBeNull();
+    await Bun.$`git -C ${testRepoPath} branch late-base`.quiet();
+    expect(await git.getBlame({ baseBranch: "late-base", filePath: "README.md" })).toHaveLength(1);
+    await Bun.$`git -C ${testRepoPath} branch -D late-base`.quiet();
+  });
+
+  it("follows files renamed on the branch back to their base name", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b blame-rename`.quiet();
+    await Bun.$`git -C ${testRepoPath} mv README.md GUIDE.md`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Rename readme"`.quiet();
+
+    const blame = await git.getBlame({ baseBranch: "main", filePath: "GUIDE.md" });
+    expect(blame?.[0]?.summary).toBe("Initial commit");
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D blame-rename`.quiet();
+  });
+
+  it("parseBlamePorcelain reuses commit info for repeated commits", () => {
+    const sha = "a".repeat(40);
+    const output = [
+      `${sha} 1 1 2`,
+      "author Alice",
+      "summary First",
+      "\tline one",
+      `${sha} 2 2`,
+      "\tline two",
+    ].join("\n");
+    const lines = parseBlamePorcelain(output);
+    expect(lines).toEqual([
+      { line: 1, commit: sha, author: "Alice", summary: "First" },
+      { line: 2, commit: sha, author: "Alice", summary: "First" },
+    ]);
+  });
+});
+
+// =============================================================================
+// Single Commit Diffs
+// =============================================================================
+
+describe("getCommitDiff", () => {
+  it("diffs a commit against its first parent", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b show-test`.quiet();
+    writeFileSync(join(testRepoPath, "first.txt"), "1\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "First"`.quiet();
+    const first = (await Bun.$`git -C ${testRepoPath} rev-parse --short HEAD`.text()).trim();
+    writeFileSync(join(testRepoPath, "second.txt"), "2\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Second"`.quiet();
+
+    const oid = await git.resolveCommit(first);
+    expect(oid?.length).toBe(40);
+    const diff = await git.getCommitDiff(oid!);
+    expect(diff.files.map((f) => f.path)).toEqual(["first.txt"]);
+    expect(diff.is_merge).toBeUndefined();
+    expect((await git.getCommitFileDiff(oid!, "first.txt"))?.additions).toBe(1);
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D show-test`.quiet();
+  });
+
+  it("treats root commits as additions against the empty tree", async () => {
+    const root = (await Bun.$`git -C ${testRepoPath} rev-list --max-parents=0 HEAD`.text()).trim();
+    const diff = await git.getCommitDiff(root);
+    expect(diff.files.every((f) => f.status === "added")).toBe(true);
+  });
+
+  it("flags merge commits and diffs against the chosen parent", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b merge-ours`.quiet();
+    writeFileSync(join(testRepoPath, "ours.txt"), "ours\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Ours"`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout -b merge-theirs main`.quiet();
+    writeFileSync(join(testRepoPath, "theirs.txt"), "theirs\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Theirs"`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout merge-ours`.quiet();
+    await Bun.$`git -C ${testRepoPath} merge --no-ff merge-theirs -m "Merge"`.quiet();
+    const merge = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+
+    expect(await git.getParentCount(merge)).toBe(2);
+    const first = await git.getCommitDiff(merge);
+    expect(first.is_merge).toBe(true);
+    expect(first.parent).toBe(1);
+    expect(first.files.map((f) => f.path)).toEqual(["theirs.txt"]);
+
+    const second = await git.getCommitDiff(merge, undefined, 2);
+    expect(second.files.map((f) => f.path)).toEqual(["ours.txt"]);
+    expect(second.base_branch).toBe(`${merge.slice(0, 7)}^2`);
+    expect((await git.getCommitFileDiff(merge, "ours.txt", 2))?.additions).toBe(1);
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D merge-ours merge-theirs`.quiet();
+  });
+
+  it("combined diffs keep only the lines a merge added itself", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b evil-ours`.quiet();
+    writeFileSync(join(testRepoPath, "ours.txt"), "ours\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Ours"`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout -b evil-theirs main`.quiet();
+    writeFileSync(join(testRepoPath, "theirs.txt"), "theirs\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Theirs"`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout evil-ours`.quiet();
+    await Bun.$`git -C ${testRepoPath} merge --no-ff --no-commit evil-theirs`.quiet();
+    writeFileSync(join(testRepoPath, "ours.txt"), "ours\nevil\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Evil merge"`.quiet();
+    const merge = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+
+    const combined = await git.getCombinedCommitDiff(merge);
+    expect(combined.combined).toBe(true);
+    // theirs.txt matches the second parent, so only the hand-edited file is left
+    expect(combined.files.map((f) => [f.path, f.resolution_lines])).toEqual([["ours.txt", [2]]]);
+    expect(combined.files[0]?.patch).toContain("++evil");
+
+    // Non-merges fall back to the ordinary diff
+    expect((await git.getCombinedCommitDiff(`${merge}^2`)).combined).toBeUndefined();
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D evil-ours evil-theirs`.quiet();
+  });
+
+  it("resolveCommit returns null for unknown revisions", async () => {
+    expect(await git.resolveCommit("not-a-commit")).toBeNull();
+  });
+});
+
+describe("renames", () => {
+  it("reports renames under their new path", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b rename-test`.quiet();
+    await Bun.$`git -C ${testRepoPath} mv src/index.ts src/main.ts`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Rename"`.quiet();
+
+    const diff = await git.getDiff({ baseBranch: "main", mode: "branch" });
+    expect(diff.files).toHaveLength(1);
+    expect(diff.files[0]).toMatchObject({ path: "src/main.ts", old_path: "src/index.ts", status: "renamed", similarity: 100 });
+
+    // Loaded on its own, the file is still a rename rather than an add
+    const file = await git.getFileDiff({ baseBranch: "main", mode: "branch", filePath: "src/main.ts" });
+    expect(file).toMatchObject({ path: "src/main.ts", old_path: "src/index.ts", status: "renamed", additions: 0, deletions: 0 });
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D rename-test`.quiet();
+  });
+
+  it("reports copies with their source when copy detection is on", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b copy-test`.quiet();
+    await Bun.$`cp ${join(testRepoPath, "src/index.ts")} ${join(testRepoPath, "src/copy.ts")}`.quiet();
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Copy"`.quiet();
+
+    const plain = await git.getDiff({ baseBranch: "main", mode: "branch" });
+    expect(plain.files.map((f) => [f.path, f.status])).toEqual([["src/copy.ts", "added"]]);
+
+    // The source is unchanged, so only the exhaustive search finds it
+    setDiffFindOptions({ copies: true, copiesFromUnmodified: true });
+    const diff = await git.getDiff({ baseBranch: "main", mode: "branch" });
+    setDiffFindOptions({ copies: false, copiesFromUnmodified: false });
+    expect(diff.files).toHaveLength(1);
+    expect(diff.files[0]).toMatchObject({ path: "src/copy.ts", old_path: "src/index.ts", status: "copied", similarity: 100 });
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D copy-test`.quiet();
+  });
+
+  it("parseNameStatusLine and numstatPath read rename notation", () => {
+    expect(parseNameStatusLine("R087\told.ts\tnew.ts")).toEqual({
+      path: "new.ts",
+      status: "renamed",
+      oldPath: "old.ts",
+      similarity: 87,
+    });
+    expect(parseNameStatusLine("C100\tsrc.ts\tdup.ts")?.status).toBe("copied");
+    expect(parseNameStatusLine("M\tsrc/a.ts")).toEqual({ path: "src/a.ts", status: "modified" });
+    expect(numstatPath("src/{a => b}/x.ts")).toBe("src/b/x.ts");
+    expect(numstatPath("src/{a => }/x.ts")).toBe("src/x.ts");
+    expect(numstatPath("old.ts => new.ts")).toBe("new.ts");
+  });
+});
+
+describe("trailing whitespace", () => {
+  it("ignoreEolWhitespace hides trailing-space churn but keeps indentation changes", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b eol-test`.quiet();
+    writeFileSync(join(testRepoPath, "eol.txt"), "one\ntwo\n");
+    writeFileSync(join(testRepoPath, "indent.txt"), "one\ntwo\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Base"`.quiet();
+    writeFileSync(join(testRepoPath, "eol.txt"), "one  \ntwo\t\n");
+    writeFileSync(join(testRepoPath, "indent.txt"), "  one\ntwo \n");
+    writeFileSync(join(testRepoPath, "mode.sh"), "echo\n");
+    await Bun.$`git -C ${testRepoPath} add mode.sh && git -C ${testRepoPath} commit -qm "Script"`.quiet();
+    // A mode-only change has no line changes to hide and stays in the list
+    chmodSync(join(testRepoPath, "mode.sh"), 0o755);
+    await Bun.$`git -C ${testRepoPath} commit -am "Whitespace"`.quiet();
+    const head = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+
+    const plain = await git.getCommitDiff(head);
+    expect(plain.files.map((f) => [f.path, f.additions, f.deletions])).toEqual([
+      ["eol.txt", 2, 2],
+      ["indent.txt", 2, 2],
+      ["mode.sh", 0, 0],
+    ]);
+
+    setDiffContentOptions({ ignoreEolWhitespace: true });
+    const diff = await git.getCommitDiff(head);
+    const patch = (await git.getCommitFileDiff(head, "indent.txt"))?.patch;
+    setDiffContentOptions({ ignoreEolWhitespace: false });
+    expect(diff.files.map((f) => [f.path, f.additions, f.deletions])).toEqual([
+      ["indent.txt", 1, 1],
+      ["mode.sh", 0, 0],
+    ]);
+    expect(patch).toContain("+  one");
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D eol-test`.quiet();
+  });
+});
+
+describe("minimal diffs", () => {
+  // Seeded pseudo-random lines over a tiny alphabet: a case where myers' heuristics give up
+  // a few lines that --minimal recovers
+  const lines = (seed: number) => {
+    let x = seed;
+    return Array.from({ length: 800 }, () => {
+      x = (x * 48271) % 2147483647;
+      return String(x % 4);
+    }).join("\n") + "\n";
+  };
+
+  it("minimal finds fewer changed lines, from startup options or per call", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b minimal-test`.quiet();
+    writeFileSync(join(testRepoPath, "noise.txt"), lines(1));
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Base"`.quiet();
+    writeFileSync(join(testRepoPath, "noise.txt"), lines(1001));
+    await Bun.$`git -C ${testRepoPath} commit -am "Rewrite"`.quiet();
+    const head = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    const changed = async () => {
+      const [file] = (await git.getCommitDiff(head)).files;
+      return file!.additions + file!.deletions;
+    };
+
+    const plain = await changed();
+    setDiffContentOptions({ minimal: true });
+    const minimal = await changed();
+    setDiffContentOptions({ minimal: false });
+    // Exact counts depend on git's heuristics; only the ordering is stable across versions
+    expect(minimal).toBeLessThan(plain);
+    expect(await withDiffContentOptions({ minimal: true }, changed)).toBe(minimal);
+    expect(await changed()).toBe(plain);
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D minimal-test`.quiet();
+  });
+});
+
+describe("diff overrides", () => {
+  it("parses <glob>:<options>", () => {
+    expect(parseDiffOverride("*.min.js:ignore-whitespace,context=0")).toEqual({ path: "*.min.js", ignoreWhitespace: true, context: 0 });
+    expect(parseDiffOverride("vendor/**:")).toEqual({ path: "vendor/**" });
+    expect(parseDiffOverride("*.js:context=x")).toContain("Unknown diff override option");
+    expect(parseDiffOverride(":context=1")).toBe("Expected <glob>:<options>");
+  });
+
+  it("matches file names without a slash and merges later entries over earlier ones", () => {
+    setDiffOverrides([
+      { path: "*.min.js", ignoreWhitespace: true, context: 3 },
+      { path: "dist/**", context: 0 },
+    ]);
+    expect(diffOverrideFor("web/app.min.js")).toEqual({ ignoreWhitespace: true, context: 3 });
+    expect(diffOverrideFor("dist/app.min.js")).toEqual({ ignoreWhitespace: true, context: 0 });
+    expect(diffOverrideFor("src/app.js")).toEqual({});
+    setDiffOverrides([]);
+  });
+
+  it("applies to the matching file's patch only", async () => {
+    const original = "a\nb\nc\nd\ne\nf\ng\n";
+    const edited = "A\nb\nc\n  d\ne\nf\ng\n";
+    await Bun.$`git -C ${testRepoPath} checkout -b overrides-test`.quiet();
+    writeFileSync(join(testRepoPath, "app.min.js"), original);
+    writeFileSync(join(testRepoPath, "app.js"), original);
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Bundles"`.quiet();
+    writeFileSync(join(testRepoPath, "app.min.js"), edited);
+    writeFileSync(join(testRepoPath, "app.js"), edited);
+
+    setDiffOverrides([{ path: "*.min.js", ignoreWhitespace: true, context: 0 }]);
+    const minified = await git.getFileDiff({ baseBranch: "main", mode: "working", filePath: "app.min.js" });
+    const plain = await git.getFileDiff({ baseBranch: "main", mode: "working", filePath: "app.js" });
+    setDiffOverrides([]);
+
+    expect(minified?.additions).toBe(1);
+    expect(minified?.patch).toContain("@@ -1 +1 @@");
+    expect(minified?.patch).not.toContain(" b\n");
+    expect(plain?.additions).toBe(2);
+
+    await Bun.$`git -C ${testRepoPath} checkout -- .`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D overrides-test`.quiet();
+  });
+
+  it("applies to the file list and estimate, leaving out files with only ignored changes", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b overrides-list-test`.quiet();
+    writeFileSync(join(testRepoPath, "indent.min.js"), "a\n  b\n");
+    writeFileSync(join(testRepoPath, "edit.min.js"), "A\n  b\n");
+    writeFileSync(join(testRepoPath, "indent.js"), "a\n  b\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Bundles"`.quiet();
+    writeFileSync(join(testRepoPath, "indent.min.js"), "a\nb\n");
+    writeFileSync(join(testRepoPath, "edit.min.js"), "B\nb\n");
+    writeFileSync(join(testRepoPath, "indent.js"), "a\nb\n");
+    await Bun.$`git -C ${testRepoPath} commit -am "Reformat"`.quiet();
+
+    setDiffOverrides([{ path: "*.min.js", ignoreWhitespace: true }]);
+    const diff = await git.getDiff({ baseBranch: "HEAD~1", mode: "branch" });
+    const estimate = await git.estimateDiff({ baseBranch: "HEAD~1", mode: "branch" });
+    setDiffOverrides([]);
+
+    expect(diff.files.map((f) => [f.path, f.additions, f.deletions]).sort()).toEqual([
+      ["edit.min.js", 1, 1],
+      ["indent.js", 1, 1],
+    ]);
+    expect(estimate).toEqual({ files: 2, additions: 2, deletions: 2 });
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D overrides-list-test`.quiet();
+  });
+});
+
+describe("patch cache", () => {
+  it("serves repeated file diffs from the cache", async () => {
+    const store = new Map<string, string>();
+    setPatchCache({
+      get: async (key) => store.get(key) ?? null,
+      set: async (key, patch) => void store.set(key, patch),
+    });
+
+    const root = (await Bun.$`git -C ${testRepoPath} rev-list --max-parents=0 HEAD`.text()).trim();
+    const first = await git.getCommitFileDiff(root, "README.md");
+    expect(store.size).toBe(1);
+
+    // A planted entry proves the second read skips git
+    const [key] = [...store.keys()];
+    store.set(key!, first!.patch.replace("# Test Repo", "# Cached"));
+    expect((await git.getCommitFileDiff(root, "README.md"))?.patch).toContain("# Cached");
+
+    setPatchCache(null);
+    expect((await git.getCommitFileDiff(root, "README.md"))?.patch).toContain("# Test Repo");
+  });
+});
+
+describe("blob size limit", () => {
+  it("reports files over the limit as too large without diffing them", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b blob-size-test`.quiet();
+    writeFileSync(join(testRepoPath, "big.txt"), "line\n".repeat(400));
+    writeFileSync(join(testRepoPath, "small.txt"), "line\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Big file"`.quiet();
+    const head = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+
+    setMaxBlobSize(1000);
+    const limited = getGitManager(testRepoPath);
+    const diff = await limited.getCommitDiff(head);
+    const big = await limited.getCommitFileDiff(head, "big.txt");
+    const small = await limited.getCommitFileDiff(head, "small.txt");
+    setMaxBlobSize(null);
+
+    expect(diff.files.find((f) => f.path === "big.txt")).toMatchObject({ too_large: true, blob_size: 2000 });
+    expect(diff.files.find((f) => f.path === "small.txt")?.too_large).toBeUndefined();
+    expect(big).toMatchObject({ status: "added", too_large: true, blob_size: 2000, patch: "" });
+    expect(big?.new_file).toBeUndefined();
+    expect(small?.patch).toContain("+line");
+
+    expect((await getGitManager(testRepoPath).getCommitFileDiff(head, "big.txt"))?.too_large).toBeUndefined();
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D blob-size-test`.quiet();
+  });
+
+  it("flags too-large files in a path-filtered working diff", async () => {
+    writeFileSync(join(testRepoPath, "big.txt"), "line\n".repeat(400));
+    await Bun.$`git -C ${testRepoPath} add big.txt`.quiet();
+
+    setMaxBlobSize(1000);
+    try {
+      const diff = await getGitManager(testRepoPath).getDiff({ baseBranch: "main", mode: "working", paths: ["big.txt"] });
+      expect(diff.files).toHaveLength(1);
+      expect(diff.files[0]).toMatchObject({ path: "big.txt", too_large: true, blob_size: 2000 });
+    } finally {
+      setMaxBlobSize(null);
+      await Bun.$`git -C ${testRepoPath} rm -q --cached big.txt`.quiet();
+      rmSync(join(testRepoPath, "big.txt"));
+    }
+  });
+});
+
+describe("remotes", () => {
+  it("resolves <remote>/<branch> bases from refs/remotes with two remotes", async () => {
+    const main = (await Bun.$`git -C ${testRepoPath} rev-parse main`.text()).trim();
+    await Bun.$`git -C ${testRepoPath} checkout -b fork-test`.quiet();
+    writeFileSync(join(testRepoPath, "upstream.txt"), "upstream\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Upstream work"`.quiet();
+    const upstreamHead = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    writeFileSync(join(testRepoPath, "fork.txt"), "fork\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Fork work"`.quiet();
+
+    await Bun.$`git -C ${testRepoPath} remote add origin https://example.com/fork.git`.quiet();
+    await Bun.$`git -C ${testRepoPath} remote add upstream https://example.com/upstream.git`.quiet();
+    await Bun.$`git -C ${testRepoPath} update-ref refs/remotes/origin/main ${main}`.quiet();
+    await Bun.$`git -C ${testRepoPath} update-ref refs/remotes/upstream/main ${upstreamHead}`.quiet();
+    // A tag with the same name must not shadow the remote-tracking branch
+    await Bun.$`git -C ${testRepoPath} tag upstream/main ${main}`.quiet();
+
+    try {
+      const remotes = await git.getRemotes();
+      expect(remotes.map((r) => r.name).sort()).toEqual(["origin", "upstream"]);
+      expect(remotes.find((r) => r.name === "upstream")?.fetch_url).toBe("https://example.com/upstream.git");
+
+      const upstreamDiff = await git.getDiff({ baseBranch: "upstream/main", mode: "branch" });
+      expect(upstreamDiff.files.map((f) => f.path)).toEqual(["fork.txt"]);
+      const originDiff = await git.getDiff({ baseBranch: "origin/main", mode: "branch" });
+      expect(originDiff.files.map((f) => f.path).sort()).toEqual(["fork.txt", "upstream.txt"]);
+    } finally {
+      await Bun.$`git -C ${testRepoPath} tag -d upstream/main`.quiet();
+      await Bun.$`git -C ${testRepoPath} remote remove upstream`.quiet();
+      await Bun.$`git -C ${testRepoPath} remote remove origin`.quiet();
+      await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+      await Bun.$`git -C ${testRepoPath} branch -D fork-test`.quiet();
+    }
+  });
+});
+
+describe("binary sizes", () => {
+  it("reports both sides' sizes for binary changes", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b binary-size-test`.quiet();
+    writeFileSync(join(testRepoPath, "image.bin"), Buffer.alloc(10));
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add binary"`.quiet();
+    writeFileSync(join(testRepoPath, "image.bin"), Buffer.alloc(40));
+    await Bun.$`git -C ${testRepoPath} commit -am "Grow binary"`.quiet();
+    const head = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+
+    const committed = (await git.getCommitDiff(head)).files.find((f) => f.path === "image.bin");
+    expect(committed).toMatchObject({ binary: true, old_size: 10, new_size: 40 });
+
+    writeFileSync(join(testRepoPath, "image.bin"), Buffer.alloc(100));
+    const working = (await git.getDiff({ baseBranch: "main", mode: "working" })).files.find((f) => f.path === "image.bin");
+    expect(working).toMatchObject({ binary: true, old_size: 40, new_size: 100 });
+
+    expect((await git.getCommitDiff(head)).files.find((f) => f.path === "README.md")?.binary).toBeUndefined();
+
+    await Bun.$`git -C ${testRepoPath} checkout -- image.bin`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D binary-size-test`.quiet();
+  });
+});
+
+// =============================================================================
+// Ref Resolution
+// =============================================================================
+
+describe("resolveCommit spec forms", () => {
+  it("resolves branches, tags, oids, ancestry, remote branches and stashes", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b resolve-test`.quiet();
+    writeFileSync(join(testRepoPath, "resolve.txt"), "1\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Resolve"`.quiet();
+    const head = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    const main = (await Bun.$`git -C ${testRepoPath} rev-parse main`.text()).trim();
+    await Bun.$`git -C ${testRepoPath} tag resolve-tag`.quiet();
+    await Bun.$`git -C ${testRepoPath} update-ref refs/remotes/origin/resolve-test HEAD`.quiet();
+
+    writeFileSync(join(testRepoPath, "resolve.txt"), "stashed\n");
+    await Bun.$`git -C ${testRepoPath} stash`.quiet();
+    const stash = (await Bun.$`git -C ${testRepoPath} rev-parse stash@{0}`.text()).trim();
+
+    expect(await git.resolveCommit("main")).toBe(main);
+    expect(await git.resolveCommit("resolve-tag")).toBe(head);
+    expect(await git.resolveCommit(head.slice(0, 7))).toBe(head);
+    expect(await git.resolveCommit("HEAD~1")).toBe(main);
+    expect(await git.resolveCommit("origin/resolve-test")).toBe(head);
+    expect(await git.resolveCommit("stash@{0}")).toBe(stash);
+    expect(await git.resolveCommit("stash@{5}")).toBeNull();
+
+    await Bun.$`git -C ${testRepoPath} stash drop`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D resolve-test`.quiet();
+    await Bun.$`git -C ${testRepoPath} tag -d resolve-tag`.quiet();
+    await Bun.$`git -C ${testRepoPath} update-ref -d refs/remotes/origin/resolve-test`.quiet();
+  });
+
+  it("compares against a stash snapshot instead of its merge base", async () => {
+    writeFileSync(join(testRepoPath, "README.md"), "# Stashed\n");
+    await Bun.$`git -C ${testRepoPath} stash`.quiet();
+
+    const diff = await git.getDiff({ baseBranch: "stash@{0}", mode: "branch" });
+    expect(diff.files.map((f) => f.path)).toEqual(["README.md"]);
+
+    await Bun.$`git -C ${testRepoPath} stash drop`.quiet();
+  });
+});
+
+// =============================================================================
+// Revision Ranges
+// =============================================================================
+
+describe("upstream base", () => {
+  it("resolves @{u} to the current branch's tracking branch", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b upstream-test`.quiet();
+    writeFileSync(join(testRepoPath, "tracked.txt"), "tracked\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Tracked"`.quiet();
+
+    await expect(git.getDiff({ baseBranch: "@{upstream}", mode: "branch" })).rejects.toThrow("no upstream configured");
+
+    await Bun.$`git -C ${testRepoPath} branch --set-upstream-to=main`.quiet();
+    expect(await git.getUpstream()).toBe("main");
+    const diff = await git.getDiff({ baseBranch: "@{u}", mode: "branch" });
+    expect(diff.files.map((f) => f.path)).toEqual(["tracked.txt"]);
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D upstream-test`.quiet();
+  });
+});
+
+describe("remote-only base branches", () => {
+  it("falls back to the remote-tracking branch on a fresh clone", async () => {
+    const clonePath = join(tempDir, "remote-only-clone");
+    await Bun.$`git clone --quiet ${testRepoPath} ${clonePath}`.quiet();
+    await Bun.$`git -C ${clonePath} checkout -b feature`.quiet();
+    await Bun.$`git -C ${clonePath} branch -D main`.quiet();
+    writeFileSync(join(clonePath, "feature.txt"), "feature\n");
+    await Bun.$`git -C ${clonePath} add feature.txt`.quiet();
+    await Bun.$`git -C ${clonePath} -c user.email=test@test.com -c user.name=Test commit -m "Feature"`.quiet();
+
+    const fallbacks: Array<[string, string]> = [];
+    setRemoteFallbackHandler((requested, used) => fallbacks.push([requested, used]));
+    const clone = getGitManager(clonePath);
+    const originMain = (await Bun.$`git -C ${clonePath} rev-parse origin/main`.text()).trim();
+
+    expect(await clone.resolveCommit("main")).toBe(originMain);
+    const diff = await clone.getDiff({ baseBranch: "main", mode: "branch" });
+    expect(diff.files.map((f) => f.path)).toEqual(["feature.txt"]);
+    expect(fallbacks[0]).toEqual(["main", "origin/main"]);
+    expect(await clone.resolveCommit("no-such-branch")).toBeNull();
+
+    setRemoteFallbackHandler(null);
+  });
+});
+
+describe("revision ranges", () => {
+  it("parseRange reads two- and three-dot ranges", () => {
+    expect(parseRange("main...feature")).toEqual({ base: "main", head: "feature", threeDot: true });
+    expect(parseRange("v1.2..v1.3")).toEqual({ base: "v1.2", head: "v1.3", threeDot: false });
+    expect(parseRange("main..")).toEqual({ base: "main", head: "HEAD", threeDot: false });
+  });
+
+  it("parseRange rejects malformed ranges", () => {
+    for (const spec of ["main", "..", "a....b", "a..b..c"]) {
+      expect(parseRange(spec)).toBeNull();
+    }
+  });
+
+  it("resolveRange diffs from the merge base only for three dots", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b range-side`.quiet();
+    writeFileSync(join(testRepoPath, "side.txt"), "side\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Side"`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout -b range-main`.quiet();
+    writeFileSync(join(testRepoPath, "mainline.txt"), "main\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Mainline"`.quiet();
+
+    const threeDot = await git.resolveRange(parseRange("range-main...range-side")!);
+    const twoDot = await git.resolveRange(parseRange("range-main..range-side")!);
+    const threeDotFiles = (await git.getRevisionDiff(threeDot!.from, threeDot!.to)).files.map((f) => f.path);
+    const twoDotFiles = (await git.getRevisionDiff(twoDot!.from, twoDot!.to)).files.map((f) => f.path).sort();
+    expect(threeDotFiles).toEqual(["side.txt"]);
+    expect(twoDotFiles).toEqual(["mainline.txt", "side.txt"]);
+    expect(await git.resolveRange(parseRange("main..no-such-branch")!)).toBeNull();
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D range-side range-main`.quiet();
+  });
+});
+
+// =============================================================================
+// Range Info
+// =============================================================================
+
+describe("getRangeInfo", () => {
+  it("counts commits since the merge base and reports both ends", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -b range-test`.quiet();
+    for (const n of [1, 2, 3]) {
+      writeFileSync(join(testRepoPath, `range-${n}.txt`), `${n}\n`);
+      await Bun.$`git -C ${testRepoPath} add .`.quiet();
+      await Bun.$`git -C ${testRepoPath} commit -m ${`Range commit ${n}`}`.quiet();
+    }
+
+    const info = await git.getRangeInfo("main");
+    expect(info.commit_count).toBe(3);
+    expect(info.oldest?.summary).toBe("Range commit 1");
+    expect(info.newest?.summary).toBe("Range commit 3");
+    expect(info.newest?.author).toBe("Test");
+    expect(info.merge_base.length).toBe(40);
+    expect(info.truncated).toBeUndefined();
+
+    const capped = await git.getRangeInfo("main", 2);
+    expect(capped.truncated).toBe(true);
+    expect(capped.commit_count).toBe(2);
+    expect(capped.newest?.summary).toBe("Range commit 3");
+    expect(capped.oldest).toBeUndefined();
+
+    await Bun.$`git -C ${testRepoPath} checkout main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D range-test`.quiet();
+  });
+
+  it("reports an empty range on the base branch", async () => {
+    const info = await git.getRangeInfo("main");
+    expect(info.commit_count).toBe(0);
+    expect(info.oldest).toBeUndefined();
+  });
+});
+
+describe("findRewrite", () => {
+  it("finds the tip before the last amend or rebase", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -q -b rewrite-test`.quiet();
+    writeFileSync(join(testRepoPath, "rewrite.txt"), "first\n");
+    await Bun.$`git -C ${testRepoPath} add rewrite.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -qm "Rewrite me"`.quiet();
+    expect(await git.findRewrite()).toBeNull();
+
+    const before = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    writeFileSync(join(testRepoPath, "rewrite.txt"), "amended\n");
+    await Bun.$`git -C ${testRepoPath} commit -qa --amend -m "Rewritten"`.quiet();
+    writeFileSync(join(testRepoPath, "after.txt"), "on top\n");
+    await Bun.$`git -C ${testRepoPath} add after.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -qm "After the rewrite"`.quiet();
+    const tip = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+
+    const rewrite = await git.findRewrite();
+    expect(rewrite).toMatchObject({ ref: "refs/heads/rewrite-test", old_tip: before, new_tip: tip, available: true });
+    expect(rewrite?.reason).toStartWith("commit (amend)");
+    expect(await git.findRewrite("rewrite-test")).toEqual(rewrite);
+
+    const diff = await git.getRevisionDiff(before, tip);
+    expect(diff.files.map((f) => f.path).sort()).toEqual(["after.txt", "rewrite.txt"]);
+
+    await Bun.$`git -C ${testRepoPath} checkout -q main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -qD rewrite-test`.quiet();
+  });
+
+  it("reports a pruned old tip as unavailable", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -q -b pruned-test`.quiet();
+    const tip = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    const gone = "1".repeat(40);
+    // As if `git gc` pruned the commit the branch was force-pushed away from
+    const log = join(testRepoPath, ".git", "logs", "refs", "heads", "pruned-test");
+    writeFileSync(log, `${gone} ${tip} Test <test@test.com> 1700000000 +0000\tpush: forced-update\n`);
+
+    expect(await git.findRewrite()).toMatchObject({ old_tip: gone, new_tip: tip, timestamp: 1700000000, available: false });
+
+    await Bun.$`git -C ${testRepoPath} checkout -q main`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -qD pruned-test`.quiet();
+  });
+
+  it("refuses a detached HEAD", async () => {
+    await Bun.$`git -C ${testRepoPath} checkout -q --detach`.quiet();
+    await expect(git.findRewrite()).rejects.toThrow("not a branch");
+    await Bun.$`git -C ${testRepoPath} checkout -q main`.quiet();
+  });
+
+  it("parses reflog lines", () => {
+    const oid = "a".repeat(40);
+    expect(parseReflog(`${"0".repeat(40)} ${oid} A U Thor <a@example.com> 1700000000 -0700\tbranch: Created from HEAD\n\n`)).toEqual([
+      { oldOid: "0".repeat(40), newOid: oid, timestamp: 1700000000, message: "branch: Created from HEAD" },
+    ]);
+  });
+});
+
+// =============================================================================
+// Review Notes
+// =============================================================================
+
+describe("review notes", () => {
+  it("round-trips a review note on a commit", async () => {
+    const commit = await git.getCurrentCommit();
+    expect(await git.getReviewNote(commit)).toEqual({ viewed: {} });
+
+    const note = { viewed: { main: { "README.md": { viewed_at: 1, viewed_by: "Test" } } } };
+    await git.setReviewNote(commit, note);
+    expect(await git.getReviewNote(commit)).toEqual(note);
+
+    await Bun.$`git -C ${testRepoPath} update-ref -d ${REVIEW_NOTES_REF}`.quiet();
+  });
+
+  it("parseReviewNote ignores malformed notes", () => {
+    expect(parseReviewNote("not json")).toEqual({ viewed: {} });
+    expect(parseReviewNote('{"viewed":null}')).toEqual({ viewed: {} });
+  });
+});
+
 // =============================================================================
 // Git Operations
 // =============================================================================
@@ -286,6 +1336,31 @@ describe("git operations", () => {
 // =============================================================================
 
 describe("utility functions", () => {
+  it("diffDirectories compares plain directories with repo-style paths", async () => {
+    const oldDir = join(tempDir, "dirs-old");
+    const newDir = join(tempDir, "dirs-new");
+    await Bun.$`mkdir -p ${oldDir}/sub ${newDir}/sub ${newDir}/.git`.quiet();
+    writeFileSync(join(oldDir, "same.txt"), "same\n");
+    writeFileSync(join(newDir, "same.txt"), "same\n");
+    writeFileSync(join(oldDir, "sub/changed.txt"), "one\n");
+    writeFileSync(join(newDir, "sub/changed.txt"), "two\n");
+    writeFileSync(join(oldDir, "gone.txt"), "gone\n");
+    writeFileSync(join(newDir, "added.txt"), "added\n");
+    writeFileSync(join(newDir, "skip.map"), "{}\n");
+    writeFileSync(join(newDir, ".git/HEAD"), "ref\n");
+
+    const files = await diffDirectories(oldDir, newDir, (path) => path.endsWith(".map"));
+    expect(files.map((f) => [f.path, f.status])).toEqual([
+      ["added.txt", "added"],
+      ["gone.txt", "deleted"],
+      ["sub/changed.txt", "modified"],
+    ]);
+    const changed = files.find((f) => f.path === "sub/changed.txt");
+    expect(changed).toMatchObject({ additions: 1, deletions: 1 });
+    expect(changed?.patch).toContain("--- a/sub/changed.txt\n+++ b/sub/changed.txt");
+    expect(files.find((f) => f.path === "added.txt")?.patch).toContain("--- /dev/null\n+++ b/added.txt");
+  });
+
   it("isGitRepo returns true for git directory", async () => {
     const result = await isGitRepo(testRepoPath);
     expect(result).toBe(true);
@@ -299,11 +1374,52 @@ describe("utility functions", () => {
     expect(result).toBe(false);
   });
 
+  it("isGitRepo and isBare handle bare repositories", async () => {
+    const barePath = join(tempDir, "bare.git");
+    await Bun.$`git clone --bare ${testRepoPath} ${barePath}`.quiet();
+
+    expect(await isGitRepo(barePath)).toBe(true);
+    const bare = getGitManager(barePath);
+    expect(await bare.isBare()).toBe(true);
+    expect(await git.isBare()).toBe(false);
+
+    // Committed diffs still work without a worktree
+    const diff = await bare.getDiff({ baseBranch: "main", mode: "branch" });
+    expect(diff.files).toEqual([]);
+  });
+
   it("isGitRepo returns false for non-existent path", async () => {
     const result = await isGitRepo("/nonexistent/path");
     expect(result).toBe(false);
   });
 
+  it("parseLsTree maps blob paths to oids and skips submodules", () => {
+    const output = [
+      "100644 blob 1111111111111111111111111111111111111111\tsrc/a b.ts",
+      "160000 commit 2222222222222222222222222222222222222222\tvendor/lib",
+      "",
+    ].join("\0");
+    expect(parseLsTree(output)).toEqual({ "src/a b.ts": "1111111111111111111111111111111111111111" });
+  });
+
+  it("getBlobOids reads blobs at a revision", async () => {
+    const blobs = await git.getBlobOids("HEAD", ["README.md", "missing.txt"]);
+    expect(Object.keys(blobs)).toEqual(["README.md"]);
+    expect(blobs["README.md"]?.length).toBe(40);
+  });
+
+  it("getWorkingBlobOids hashes the working-tree content", async () => {
+    writeFileSync(join(testRepoPath, "README.md"), "edited\n");
+    try {
+      const blobs = await git.getWorkingBlobOids(["README.md", "missing.txt"]);
+      const expected = (await Bun.$`git -C ${testRepoPath} hash-object README.md`.text()).trim();
+      expect(blobs).toEqual({ "README.md": expected });
+      expect(expected).not.toBe((await git.getBlobOids("HEAD", ["README.md"]))["README.md"]);
+    } finally {
+      await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+    }
+  });
+
   it("getRepoName extracts basename from path", () => {
     expect(getRepoName("/path/to/my-repo")).toBe("my-repo");
     expect(getRepoName("/another/project")).toBe("project");
@@ -361,3 +1477,25 @@ describe("edge cases", () => {
     expect(diff.base_branch).toBe("main");
   });
 });
+
+describe("allowed refs", () => {
+  it("checks request refs against the allowlist", async () => {
+    setAllowedRefs(["main", "release/*"]);
+    try {
+      expect(() => assertRefAllowed("main")).not.toThrow();
+      expect(() => assertRefAllowed("release/1.0")).not.toThrow();
+      // `*` stops at slashes, so nested names need their own glob
+      expect(() => assertRefAllowed("release/1.0/hotfix")).toThrow(RefNotAllowedError);
+      expect(() => assertRefAllowed("dev")).toThrow(RefNotAllowedError);
+      // The checked-out branch and stored review commits always pass
+      expect(() => assertRefAllowed("HEAD")).not.toThrow();
+      expect(() => assertRefAllowed("a".repeat(40))).not.toThrow();
+      expect(() => assertRefAllowed("a".repeat(7))).toThrow(RefNotAllowedError);
+      // Internal lookups aren't checked
+      expect(await git.resolveCommit("HEAD~0")).toMatch(/^[0-9a-f]{40}$/);
+    } finally {
+      setAllowedRefs([]);
+    }
+    expect(() => assertRefAllowed("dev")).not.toThrow();
+  });
+});
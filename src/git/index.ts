@@ -1,17 +1,98 @@
 import simpleGit, { type SimpleGit, type StatusResult } from "simple-git";
-import { basename, join } from "path";
-import type { DiffMode, DiffResponse, FileDiff, FileContents } from "../types";
+import { Glob } from "bun";
+import { AsyncLocalStorage } from "async_hooks";
+import { lstat, readlink } from "fs/promises";
+import { basename, join, resolve } from "path";
+import { NO_NEWLINE_MARKER, parseCombinedPatch, splitPatchByFile } from "./patch";
+import { patchCacheKey, type PatchCache } from "./patchCache";
+import type {
+  BlameLine,
+  BranchRewrite,
+  ChangedIn,
+  DiffEstimate,
+  DiffMode,
+  DiffResponse,
+  FileDiff,
+  FileContents,
+  RangeCommit,
+  RangeCommitList,
+  CommitGraph,
+  ConflictedFile,
+  DiffOverride,
+  GraphCommit,
+  RangeAttribution,
+  RangeInfo,
+  Remote,
+  ReviewNote,
+  UserIdentity,
+} from "../types";
+
+export interface DiffOptions {
+  baseBranch: string;
+  mode: DiffMode;
+  // Pathspecs limiting which paths git diffs (all paths when empty)
+  paths?: string[];
+}
+
+export type FileSide = "old" | "new";
+
+// `<base>..<head>` diffs the two commits; `<base>...<head>` diffs from their merge base
+export interface RevisionRange {
+  base: string;
+  head: string;
+  threeDot: boolean;
+}
+
+// Raw bytes of one side of a file, sized up front so callers can refuse large blobs
+export interface FileBlob {
+  size: number;
+  read(): Promise<Uint8Array>;
+}
 
 export interface GitManager {
   repoPath: string;
   git: SimpleGit;
-  getDiff(options: { baseBranch: string; mode: DiffMode }): Promise<DiffResponse>;
+  getDiff(options: DiffOptions): Promise<DiffResponse>;
+  estimateDiff(options: DiffOptions): Promise<DiffEstimate>;
   getFileDiff(options: { baseBranch: string; mode: DiffMode; filePath: string }): Promise<FileDiff | null>;
+  resolveCommit(rev: string): Promise<string | null>;
+  getParentCount(oid: string): Promise<number>;
+  getCommitDiff(oid: string, paths?: string[], parent?: number): Promise<DiffResponse>;
+  getCombinedCommitDiff(oid: string, paths?: string[]): Promise<DiffResponse>;
+  getCommitFileDiff(oid: string, filePath: string, parent?: number): Promise<FileDiff | null>;
+  resolveRange(range: RevisionRange): Promise<{ from: string; to: string } | null>;
+  getRevisionDiff(from: string, to: string, paths?: string[]): Promise<DiffResponse>;
+  getRevisionFileDiff(from: string, to: string, filePath: string): Promise<FileDiff | null>;
+  attributeLines(from: string, to: string, filePaths: string[]): Promise<LineAttribution>;
+  getRevisionPatch(from: string, to: string, paths?: string[]): Promise<string>;
+  getBlobOids(rev: string, paths: string[]): Promise<Record<string, string>>;
+  getWorkingBlobOids(paths: string[]): Promise<Record<string, string>>;
+  getRenames(from: string, to: string): Promise<Record<string, string>>;
+  getFileBlob(options: { baseBranch: string; mode: DiffMode; filePath: string; side: FileSide }): Promise<FileBlob | null>;
+  getCommitFileBlob(oid: string, filePath: string, side: FileSide, parent?: number): Promise<FileBlob | null>;
   getCurrentBranch(): Promise<string>;
   getCurrentCommit(): Promise<string>;
   getDefaultBranch(): Promise<string>;
+  getUpstream(): Promise<string | null>;
   getRemoteUrl(): Promise<string | undefined>;
+  getRemotes(): Promise<Remote[]>;
+  getUserName(): Promise<string | undefined>;
+  getUserIdentity(): Promise<UserIdentity>;
   getBranches(): Promise<string[]>;
+  isBare(): Promise<boolean>;
+  isLinkedWorktree(): Promise<boolean>;
+  getGitDir(): Promise<string>;
+  isDirty(): Promise<boolean>;
+  getConflicts(): Promise<ConflictedFile[]>;
+  getBlame(options: { baseBranch: string; filePath: string }): Promise<BlameLine[] | null>;
+  getBranchPatch(baseBranch: string, paths?: string[]): Promise<string>;
+  findRewrite(ref?: string): Promise<BranchRewrite | null>;
+  getRangeInfo(baseBranch: string, limit?: number, author?: string): Promise<RangeInfo>;
+  getRangeCommits(baseBranch: string, limit?: number, author?: string): Promise<RangeCommitList>;
+  getCommitGraph(baseBranch: string, limit?: number): Promise<CommitGraph>;
+  getCommitsDiff(oids: string[]): Promise<FileDiff[]>;
+  getReviewNote(commit: string): Promise<ReviewNote>;
+  setReviewNote(commit: string, note: ReviewNote): Promise<void>;
   stageFile(filePath: string): Promise<void>;
   unstageFile(filePath: string): Promise<void>;
   discardFile(filePath: string): Promise<void>;
@@ -19,21 +100,60 @@ export interface GitManager {
   status(): Promise<StatusResult>;
 }
 
+// Most commits any history walk returns
+export const MAX_RANGE_COMMITS = 1000;
+
+// Most files one range attribution blames (a `git blame` each)
+export const MAX_ATTRIBUTED_FILES = 200;
+
+// Most reflog entries findRewrite checks (an ancestry check each), newest first
+const MAX_REFLOG_SCAN = 100;
+
+// Per-line attribution of a range: file path -> new-side line -> short oid
+export interface LineAttribution extends RangeAttribution {
+  lines: Record<string, Record<number, string>>;
+}
+
 // Cache of git managers by repo path
 const managers = new Map<string, GitManager>();
 
+// Blame results keyed by repo path, base and head commits and file (commits are immutable),
+// least recently used first
+const blameCache = new Map<string, BlameLine[] | null>();
+const MAX_BLAME_CACHE = 500;
+
+function cacheBlame(key: string, blame: BlameLine[] | null): void {
+  blameCache.delete(key);
+  blameCache.set(key, blame);
+  const oldest = blameCache.keys().next();
+  if (blameCache.size > MAX_BLAME_CACHE && !oldest.done) blameCache.delete(oldest.value);
+}
+
 export function getGitManager(repoPath: string): GitManager {
   const cached = managers.get(repoPath);
   if (cached) {
     return cached;
   }
 
-  const git = simpleGit(repoPath);
+  // Past the blob size limit git treats files as binary rather than diffing them line by line
+  const git = simpleGit(repoPath, {
+    ...(maxBlobSize !== null && { config: [`core.bigFileThreshold=${maxBlobSize}`] }),
+    ...(gitTimeout !== null && { timeout: { block: gitTimeout } }),
+  });
+  gitRepoPaths.set(git, repoPath);
   const manager = createGitManager(repoPath, git);
   managers.set(repoPath, manager);
   return manager;
 }
 
+// Drop the cached manager and blame results for a repo so the next call starts clean
+export function resetGitManager(repoPath: string): void {
+  managers.delete(repoPath);
+  for (const key of blameCache.keys()) {
+    if (key.startsWith(`${repoPath}\0`)) blameCache.delete(key);
+  }
+}
+
 function createGitManager(repoPath: string, git: SimpleGit): GitManager {
   return {
     repoPath,
@@ -41,12 +161,26 @@ function createGitManager(repoPath: string, git: SimpleGit): GitManager {
 
     async getCurrentBranch(): Promise<string> {
       const result = await git.branch();
-      return result.current;
+      if (result.current) return result.current;
+      // An unborn branch isn't listed by `git branch`, but HEAD already names it
+      try {
+        return (await git.raw(["symbolic-ref", "--short", "-q", "HEAD"])).trim();
+      } catch {
+        return "";
+      }
     },
 
+    // Short HEAD oid, or "" on an unborn branch (git init with no commits yet): HEAD names a
+    // branch that doesn't resolve. Anything else (a broken HEAD, a git failure) throws
     async getCurrentCommit(): Promise<string> {
-      const result = await git.revparse(["HEAD"]);
-      return result.trim().slice(0, 7);
+      const head = await Bun.$`git rev-parse --verify --quiet ${"HEAD^{commit}"}`.cwd(repoPath).quiet().nothrow();
+      if (head.exitCode === 0) return head.stdout.toString().trim().slice(0, 7);
+      if (head.exitCode === 1) {
+        const symbolic = await Bun.$`git symbolic-ref -q HEAD`.cwd(repoPath).quiet().nothrow();
+        if (symbolic.exitCode === 0) return "";
+      }
+      const reason = head.stderr.toString().trim() || `git rev-parse exited with code ${head.exitCode}`;
+      throw new Error(`Cannot resolve HEAD in ${repoPath}: ${reason}`);
     },
 
     async getBranches(): Promise<string[]> {
@@ -54,33 +188,272 @@ function createGitManager(repoPath: string, git: SimpleGit): GitManager {
       return branches.all;
     },
 
+    async isBare(): Promise<boolean> {
+      const result = await git.revparse(["--is-bare-repository"]);
+      return result.trim() === "true";
+    },
+
+    // A checkout made with `git worktree add`: its git dir lives under the main repo's
+    async isLinkedWorktree(): Promise<boolean> {
+      const [gitDir, commonDir] = (await git.revparse(["--absolute-git-dir", "--git-common-dir"])).trim().split("\n");
+      return resolve(repoPath, commonDir ?? "") !== gitDir;
+    },
+
+    // Absolute path of HEAD and the index: <repo>/.git, or under the main repo for linked worktrees
+    async getGitDir(): Promise<string> {
+      return (await git.revparse(["--absolute-git-dir"])).trim();
+    },
+
+    // Any staged, unstaged or untracked (non-ignored) changes; bare repos are never dirty
+    async isDirty(): Promise<boolean> {
+      if (await this.isBare()) return false;
+      const status = await git.status();
+      return !status.isClean();
+    },
+
+    async getBlame(options: { baseBranch: string; filePath: string }): Promise<BlameLine[] | null> {
+      const mergeBase = await resolveMergeBase(git, options.baseBranch);
+      // The base file a path maps to depends on renames up to HEAD, so HEAD is part of the key
+      const head = (await git.revparse(["HEAD"])).trim();
+      // Only resolved commits make a stable key (an unknown base falls through as its name)
+      const cacheKey = FULL_OID.test(mergeBase) ? `${repoPath}\0${mergeBase}\0${head}\0${options.filePath}` : null;
+      const cached = cacheKey !== null ? blameCache.get(cacheKey) : undefined;
+      if (cacheKey !== null && cached !== undefined) {
+        cacheBlame(cacheKey, cached);
+        return cached;
+      }
+
+      // A file renamed on the branch is blamed under its name in the base
+      const renames = await this.getRenames(mergeBase, head);
+      const basePath = Object.keys(renames).find((oldPath) => renames[oldPath] === options.filePath) ?? options.filePath;
+
+      let blame: BlameLine[] | null;
+      try {
+        const output = await git.raw(["blame", "--porcelain", mergeBase, "--", basePath]);
+        blame = parseBlamePorcelain(output);
+      } catch (error) {
+        // File didn't exist in the base; other failures (a timeout, say) aren't cached
+        if (!(error instanceof Error && /no such path/i.test(error.message))) return null;
+        blame = null;
+      }
+
+      if (cacheKey !== null) cacheBlame(cacheKey, blame);
+      return blame;
+    },
+
+    async getBranchPatch(baseBranch: string, paths?: string[]): Promise<string> {
+      const mergeBase = await resolveMergeBase(git, baseBranch);
+      return git.diff([...contentArgs(), mergeBase, "HEAD", ...toPathspecArgs(paths)]);
+    },
+
+    async getRangeInfo(baseBranch: string, limit: number = MAX_RANGE_COMMITS, author?: string): Promise<RangeInfo> {
+      const { merge_base, commits, truncated } = await this.getRangeCommits(baseBranch, limit, author);
+      return {
+        base_branch: baseBranch,
+        merge_base,
+        commit_count: commits.length,
+        // The true oldest commit is past the cap
+        oldest: truncated ? undefined : commits[commits.length - 1],
+        newest: commits[0],
+        ...(truncated && { truncated }),
+      };
+    },
+
+    // author keeps commits whose author name or email contains it, ignoring case
+    async getRangeCommits(baseBranch: string, limit: number = MAX_RANGE_COMMITS, author?: string): Promise<RangeCommitList> {
+      const mergeBase = await resolveMergeBase(git, baseBranch);
+      // Newest first, like `git log`. Excluding the merge base's history bounds the walk;
+      // the cap covers unrelated or very deep histories. One extra commit detects truncation
+      const cap = Math.min(limit, MAX_RANGE_COMMITS);
+      const log = await git.raw([
+        "log",
+        `--max-count=${cap + 1}`,
+        "--format=%H%x00%s%x00%an%x00%ct",
+        ...(author ? [`--author=${author}`, "--fixed-strings", "--regexp-ignore-case"] : []),
+        `${mergeBase}..HEAD`,
+      ]);
+      const commits = parseRangeLog(log);
+      const truncated = commits.length > cap;
+
+      return {
+        base_branch: baseBranch,
+        merge_base: mergeBase,
+        commits: commits.slice(0, cap),
+        ...(truncated && { truncated }),
+      };
+    },
+
+    async getCommitGraph(baseBranch: string, limit: number = MAX_RANGE_COMMITS): Promise<CommitGraph> {
+      const mergeBase = await resolveMergeBase(git, baseBranch);
+      const cap = Math.min(limit, MAX_RANGE_COMMITS);
+      const [head, log] = await Promise.all([
+        git.revparse(["HEAD"]),
+        // Same bounds as getRangeCommits; topo order keeps each line of history together
+        git.raw(["log", "--topo-order", `--max-count=${cap + 1}`, "--format=%H%x00%P%x00%s%x00%an%x00%ct", `${mergeBase}..HEAD`]),
+      ]);
+      const commits = parseGraphLog(log);
+      const truncated = commits.length > cap;
+
+      return {
+        base_branch: baseBranch,
+        merge_base: mergeBase,
+        head: head.trim(),
+        commits: commits.slice(0, cap),
+        ...(truncated && { truncated }),
+      };
+    },
+
+    // Several commits folded into one file list (oids oldest first): one diff from the first
+    // commit's parent to the last, limited to the paths the listed commits touch. Commits in
+    // between that aren't listed still show in those files
+    async getCommitsDiff(oids: string[]): Promise<FileDiff[]> {
+      const first = oids[0];
+      const last = oids[oids.length - 1];
+      if (first === undefined || last === undefined) return [];
+
+      // %x00 before each oid; --no-renames lists both sides of a rename so the diff can pair them
+      const log = await git.raw(["show", "--no-renames", "--name-only", "--format=%x00%h", ...oids]);
+      const commitsByPath = new Map<string, string[]>();
+      for (const entry of log.split("\0").slice(1)) {
+        const [short = "", ...paths] = entry.split("\n").filter(Boolean);
+        for (const path of paths) {
+          const commits = commitsByPath.get(path) ?? [];
+          if (!commits.includes(short)) commits.push(short);
+          commitsByPath.set(path, commits);
+        }
+      }
+      if (commitsByPath.size === 0) return [];
+
+      const base = await resolveParent(git, first);
+      const pathspec = toPathspecArgs([...commitsByPath.keys()]);
+      const [files, patch] = await Promise.all([
+        getRangeDiff(git, base, last, pathspec),
+        patchOutput(git, ["diff", ...contentArgs(), ...findArgs(), base, last, ...pathspec]),
+      ]);
+      const patches = splitPatchByFile(patch);
+      const touching = (path?: string) => (path !== undefined && commitsByPath.get(path)) || [];
+      return files.map((file) => ({
+        ...file,
+        patch: patches.get(file.path) ?? "",
+        commits: [...new Set([...touching(file.old_path), ...touching(file.path)])],
+      }));
+    },
+
+    async getReviewNote(commit: string): Promise<ReviewNote> {
+      try {
+        const raw = await git.raw(["notes", `--ref=${REVIEW_NOTES_REF}`, "show", commit]);
+        return parseReviewNote(raw);
+      } catch {
+        // No note on this commit yet
+        return { viewed: {} };
+      }
+    },
+
+    async setReviewNote(commit: string, note: ReviewNote): Promise<void> {
+      await git.raw(["notes", `--ref=${REVIEW_NOTES_REF}`, "add", "-f", "-m", JSON.stringify(note), commit]);
+    },
+
+    // Newest reflog update of `ref` (HEAD's branch by default) that wasn't a fast-forward, among
+    // its last MAX_REFLOG_SCAN. Reads the log file itself so entries whose commits were pruned
+    // still show up; reftable repos keep no such file and never report a rewrite
+    async findRewrite(ref = "HEAD"): Promise<BranchRewrite | null> {
+      if (ref !== "HEAD" && !(await resolveRevision(git, ref))) {
+        throw new RevisionError(`Unknown ref ${ref}`);
+      }
+      const fullName = (await git.raw(["rev-parse", "--symbolic-full-name", ref])).trim();
+      if (!fullName.startsWith("refs/")) {
+        throw new RevisionError(`${ref} is not a branch; force-push diffs need a ref with a reflog`);
+      }
+      const tip = (await git.raw(["rev-parse", fullName])).trim();
+
+      const logPath = resolve(repoPath, (await git.raw(["rev-parse", "--git-path", `logs/${fullName}`])).trim());
+      const log = Bun.file(logPath);
+      if (!(await log.exists())) return null;
+
+      for (const entry of parseReflog(await log.text()).reverse().slice(0, MAX_REFLOG_SCAN)) {
+        if (NULL_OID.test(entry.oldOid) || (await isAncestor(git, entry.oldOid, entry.newOid))) continue;
+        return {
+          ref: fullName,
+          old_tip: entry.oldOid,
+          new_tip: tip,
+          reason: entry.message,
+          timestamp: entry.timestamp,
+          available: await objectExists(git, entry.oldOid),
+        };
+      }
+      return null;
+    },
+
+    // Unmerged index entries grouped by path, with the conflict blocks left in each working file
+    async getConflicts(): Promise<ConflictedFile[]> {
+      const stages = await getUnmergedStages(git);
+      return Promise.all(
+        [...stages].map(async ([path, present]) => {
+          const file = Bun.file(join(repoPath, path));
+          const markers = (await file.exists()) ? countConflictMarkers(await file.text()) : 0;
+          return { path, base: present.has(1), ours: present.has(2), theirs: present.has(3), markers };
+        })
+      );
+    },
+
+    // The current branch's tracking branch (e.g. origin/feature), or null if none is configured
+    async getUpstream(): Promise<string | null> {
+      try {
+        return (await git.raw(["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{upstream}"])).trim() || null;
+      } catch {
+        return null;
+      }
+    },
+
     async getDefaultBranch(): Promise<string> {
-      // Try to detect from remote
+      // origin/HEAD is set by clone (or `git remote set-head origin -a`) and needs no network
       try {
-        const remote = await git.remote(["show", "origin"]);
-        if (remote) {
-          const match = remote.match(/HEAD branch:\s*(\S+)/);
-          if (match?.[1]) {
-            return match[1];
-          }
+        const ref = await git.raw(["symbolic-ref", "--quiet", "refs/remotes/origin/HEAD"]);
+        const name = ref.trim().replace(/^refs\/remotes\/origin\//, "");
+        if (name) {
+          return name;
         }
       } catch {
-        // Remote not available
+        // No remote HEAD recorded
       }
 
-      // Check common branch names
+      // Check common branch names, locally first, then as remote-tracking branches
       const branches = await git.branchLocal();
-      const commonNames = ["main", "master", "develop", "dev"];
+      const commonNames = ["main", "master", "trunk", "develop", "dev"];
       for (const name of commonNames) {
         if (branches.all.includes(name)) {
           return name;
         }
       }
 
+      try {
+        const remoteBranches = await git.branch(["-r"]);
+        for (const name of commonNames) {
+          if (remoteBranches.all.includes(`origin/${name}`)) {
+            return name;
+          }
+        }
+      } catch {
+        // No remotes
+      }
+
       // Fall back to current branch
       return branches.current || "main";
     },
 
+    async getRemotes(): Promise<Remote[]> {
+      try {
+        const remotes = await git.getRemotes(true);
+        return remotes.map((remote) => ({
+          name: remote.name,
+          fetch_url: remote.refs.fetch,
+          ...(remote.refs.push !== "" && remote.refs.push !== remote.refs.fetch && { push_url: remote.refs.push }),
+        }));
+      } catch {
+        return [];
+      }
+    },
+
     async getRemoteUrl(): Promise<string | undefined> {
       try {
         const remotes = await git.getRemotes(true);
@@ -91,18 +464,44 @@ function createGitManager(repoPath: string, git: SimpleGit): GitManager {
       }
     },
 
-    async getDiff(options: { baseBranch: string; mode: DiffMode }): Promise<DiffResponse> {
+    async getUserName(): Promise<string | undefined> {
+      return (await this.getUserIdentity()).name;
+    },
+
+    // user.name/user.email from repo, global or system config; unset keys are left out
+    async getUserIdentity(): Promise<UserIdentity> {
+      const read = async (key: string) => {
+        try {
+          return (await git.raw(["config", key])).trim() || undefined;
+        } catch {
+          return undefined;
+        }
+      };
+      const [name, email] = await Promise.all([read("user.name"), read("user.email")]);
+      return { name, email };
+    },
+
+    async getDiff(options: DiffOptions): Promise<DiffResponse> {
       const { baseBranch, mode } = options;
-      
-      // Run metadata fetches in parallel with diff
+      const pathspec = toPathspecArgs(options.paths);
+
+      // Run metadata fetches in parallel with diff. With no commits yet there's nothing
+      // to diff against, so an unborn branch gets an empty file list
+      const head = this.getCurrentCommit();
+      const listFiles = async (): Promise<FileDiff[]> => {
+        if (!(await head)) return [];
+        if (mode === "working") return getWorkingDiff(git, repoPath, pathspec);
+        if (mode === "unstaged") return getUnstagedDiff(git, repoPath, pathspec);
+        if (mode === "full") return getFullDiff(git, repoPath, baseBranch, pathspec);
+        return getBranchDiff(git, repoPath, baseBranch, pathspec);
+      };
       const [branch, commit, remoteUrl, files] = await Promise.all([
         this.getCurrentBranch(),
-        this.getCurrentCommit(),
+        head,
         this.getRemoteUrl(),
-        mode === "working"
-          ? getWorkingDiff(git, repoPath)
-          : getBranchDiff(git, repoPath, baseBranch),
+        listFiles(),
       ]);
+      if (mode !== "branch") await markConflicted(git, files);
 
       return {
         files,
@@ -115,15 +514,264 @@ function createGitManager(repoPath: string, git: SimpleGit): GitManager {
       };
     },
 
+    // File and line totals from numstat alone, without patches or file contents
+    // `exclude` leaves paths out of the totals (e.g. ones hidden by --ignore)
+    async estimateDiff(options: DiffOptions & { exclude?: (path: string) => boolean }): Promise<DiffEstimate> {
+      const pathspec = toPathspecArgs(options.paths);
+      const exclude = options.exclude ?? (() => false);
+      if (options.mode === "branch") {
+        const mergeBase = await resolveMergeBase(git, options.baseBranch);
+        return sumNumstat(await numstatOutput(git, [mergeBase, "HEAD", ...pathspec]), exclude);
+      }
+
+      // Staged and unstaged together (from the merge base in full mode, the index in unstaged mode);
+      // untracked files count without lines
+      const from =
+        options.mode === "full" ? [await resolveMergeBase(git, options.baseBranch)] : options.mode === "unstaged" ? [] : ["HEAD"];
+      const [tracked, untracked] = await Promise.all([
+        numstatOutput(git, [...from, ...pathspec]),
+        git.raw(["ls-files", "--others", "--exclude-standard", ...pathspec]),
+      ]);
+      const estimate = sumNumstat(tracked, exclude);
+      estimate.files += untracked.split("\n").filter((path) => path && !exclude(path)).length;
+      return estimate;
+    },
+
     async getFileDiff(options: { baseBranch: string; mode: DiffMode; filePath: string }): Promise<FileDiff | null> {
       const { baseBranch, mode, filePath } = options;
 
       if (mode === "branch") {
         return getSingleBranchFileDiff(git, baseBranch, filePath);
-      } else if (mode === "working") {
-        return getSingleWorkingFileDiff(git, repoPath, filePath);
       }
-      return null;
+
+      let file: FileDiff | null = null;
+      if (mode === "working") {
+        file = await getSingleWorkingFileDiff(git, repoPath, filePath);
+      } else if (mode === "unstaged") {
+        file = await getSingleUnstagedFileDiff(git, repoPath, filePath);
+      } else if (mode === "full") {
+        file = await getSingleFullFileDiff(git, repoPath, baseBranch, filePath);
+      }
+      if (file) await markConflicted(git, [file]);
+      return file;
+    },
+
+    // Full oid for a commit-ish (short oids, refs), or null if it doesn't name a commit
+    async resolveCommit(rev: string): Promise<string | null> {
+      return resolveRevision(git, rev);
+    },
+
+    async getParentCount(oid: string): Promise<number> {
+      const line = (await git.raw(["rev-list", "--parents", "-n", "1", oid])).trim();
+      return Math.max(line.split(" ").length - 1, 0);
+    },
+
+    // A single commit against one of its parents (the first by default)
+    async getCommitDiff(oid: string, paths?: string[], parent = 1): Promise<DiffResponse> {
+      const [base, parentCount] = await Promise.all([resolveParent(git, oid, parent), this.getParentCount(oid)]);
+      const [branch, remoteUrl, files] = await Promise.all([
+        this.getCurrentBranch(),
+        this.getRemoteUrl(),
+        getRangeDiff(git, base, oid, toPathspecArgs(paths)),
+      ]);
+
+      const diff: DiffResponse = {
+        files,
+        branch,
+        commit: oid.slice(0, 7),
+        repo_path: repoPath,
+        remote_url: remoteUrl,
+        mode: "branch",
+        base_branch: base === EMPTY_TREE ? EMPTY_TREE : `${oid.slice(0, 7)}^${parent === 1 ? "" : parent}`,
+      };
+      if (parentCount > 1) {
+        diff.is_merge = true;
+        diff.parent = parent;
+      }
+      return diff;
+    },
+
+    // A merge against all of its parents at once (`git show --cc`): only files that differ from
+    // every parent, with the lines no parent had flagged. Other commits get the usual diff
+    async getCombinedCommitDiff(oid: string, paths?: string[]): Promise<DiffResponse> {
+      if ((await this.getParentCount(oid)) < 2) {
+        return this.getCommitDiff(oid, paths);
+      }
+
+      const [branch, remoteUrl, patch] = await Promise.all([
+        this.getCurrentBranch(),
+        this.getRemoteUrl(),
+        patchOutput(git, ["show", "--cc", "--format=", ...contentArgs(), oid, ...toPathspecArgs(paths)]),
+      ]);
+      const files: FileDiff[] = parseCombinedPatch(patch).map((file) => ({
+        path: file.path,
+        status: file.status,
+        additions: file.additions,
+        deletions: file.deletions,
+        patch: file.patch,
+        viewed: false,
+        resolution_lines: file.resolution_lines,
+      }));
+
+      return {
+        files,
+        branch,
+        commit: oid.slice(0, 7),
+        repo_path: repoPath,
+        remote_url: remoteUrl,
+        mode: "branch",
+        base_branch: `${oid.slice(0, 7)}^@`,
+        is_merge: true,
+        combined: true,
+      };
+    },
+
+    async getCommitFileDiff(oid: string, filePath: string, parent = 1): Promise<FileDiff | null> {
+      return getSingleRangeFileDiff(git, await resolveParent(git, oid, parent), oid, filePath);
+    },
+
+    // Commit oids to diff between, or null if either end doesn't resolve
+    async resolveRange(range: RevisionRange): Promise<{ from: string; to: string } | null> {
+      const [base, head] = await Promise.all([resolveRevision(git, range.base), resolveRevision(git, range.head)]);
+      if (!base || !head) {
+        return null;
+      }
+      if (!range.threeDot) {
+        return { from: base, to: head };
+      }
+      try {
+        return { from: (await git.raw(["merge-base", base, head])).trim(), to: head };
+      } catch {
+        // Unrelated histories have no merge base
+        return null;
+      }
+    },
+
+    async getRevisionDiff(from: string, to: string, paths?: string[]): Promise<DiffResponse> {
+      const [branch, remoteUrl, files] = await Promise.all([
+        this.getCurrentBranch(),
+        this.getRemoteUrl(),
+        getRangeDiff(git, from, to, toPathspecArgs(paths)),
+      ]);
+
+      return {
+        files,
+        branch,
+        commit: to.slice(0, 7),
+        repo_path: repoPath,
+        remote_url: remoteUrl,
+        mode: "branch",
+        base_branch: from.slice(0, 7),
+      };
+    },
+
+    // Which commit of from..to introduced each line of the files at `to`: a blame limited to the
+    // range, so lines older than `from` stay unattributed. Deleted lines aren't attributed
+    async attributeLines(from: string, to: string, filePaths: string[]): Promise<LineAttribution> {
+      const log = await git.raw(["log", `--max-count=${MAX_RANGE_COMMITS + 1}`, "--format=%H%x00%s%x00%an%x00%ct", `${from}..${to}`]);
+      const commits = parseRangeLog(log);
+      // Blaming across a deeper range costs too much; report the commits alone
+      if (commits.length > MAX_RANGE_COMMITS) {
+        return { commits: commits.slice(0, MAX_RANGE_COMMITS), lines: {}, truncated: true };
+      }
+
+      const inRange = new Set(commits.map((c) => c.hash));
+      const lines: Record<string, Record<number, string>> = {};
+      const paths = filePaths.slice(0, MAX_ATTRIBUTED_FILES);
+      // A few blames at a time rather than one process per file at once
+      for (let i = 0; i < paths.length; i += 8) {
+        await Promise.all(
+          paths.slice(i, i + 8).map(async (filePath) => {
+            try {
+              const output = await git.raw(["blame", "--porcelain", `${from}..${to}`, "--", filePath]);
+              const attributed: Record<number, string> = {};
+              for (const line of parseBlamePorcelain(output)) {
+                if (inRange.has(line.commit)) attributed[line.line] = line.commit.slice(0, 7);
+              }
+              lines[filePath] = attributed;
+            } catch {
+              // Not at `to` (deleted) or not blameable
+            }
+          })
+        );
+      }
+
+      return {
+        commits,
+        lines,
+        ...(filePaths.length > MAX_ATTRIBUTED_FILES && { truncated: true }),
+      };
+    },
+
+    async getRevisionFileDiff(from: string, to: string, filePath: string): Promise<FileDiff | null> {
+      return getSingleRangeFileDiff(git, from, to, filePath);
+    },
+
+    async getRevisionPatch(from: string, to: string, paths?: string[]): Promise<string> {
+      return git.diff([...contentArgs(), from, to, ...toPathspecArgs(paths)]);
+    },
+
+    // Blob oid of each path at rev (paths missing from rev are left out)
+    async getBlobOids(rev: string, paths: string[]): Promise<Record<string, string>> {
+      if (paths.length === 0) {
+        return {};
+      }
+      try {
+        return parseLsTree(await git.raw(["ls-tree", "-r", "-z", "--full-tree", rev, "--", ...paths]));
+      } catch {
+        return {};
+      }
+    },
+
+    // Blob oid each working-tree file would get if added (deleted files and symlinks are left out)
+    async getWorkingBlobOids(paths: string[]): Promise<Record<string, string>> {
+      const present: string[] = [];
+      for (const filePath of paths) {
+        const stat = await lstat(join(repoPath, filePath)).catch(() => null);
+        if (stat?.isFile()) present.push(filePath);
+      }
+      if (present.length === 0) {
+        return {};
+      }
+      const oids = (await git.raw(["hash-object", "--", ...present])).trim().split("\n");
+      return Object.fromEntries(present.map((filePath, i) => [filePath, oids[i] ?? ""]));
+    },
+
+    // Files renamed between two commits (old path -> new path)
+    async getRenames(from: string, to: string): Promise<Record<string, string>> {
+      let output: string;
+      try {
+        output = await git.diff([from, to, "--name-status", "-M"]);
+      } catch {
+        return {};
+      }
+      const renames: Record<string, string> = {};
+      for (const line of output.split("\n")) {
+        const entry = parseNameStatusLine(line);
+        if (entry?.status === "renamed" && entry.oldPath) renames[entry.oldPath] = entry.path;
+      }
+      return renames;
+    },
+
+    // Branch mode: merge base vs HEAD. Working mode: HEAD vs the working tree. Unstaged mode: the index
+    // vs the working tree. Full mode: merge base vs the working tree
+    async getFileBlob(options: { baseBranch: string; mode: DiffMode; filePath: string; side: FileSide }): Promise<FileBlob | null> {
+      const { baseBranch, mode, filePath, side } = options;
+      if (mode === "working") {
+        return side === "old" ? getBlob(git, "HEAD", filePath) : getWorkingBlob(repoPath, filePath);
+      }
+      if (mode === "unstaged") {
+        // `:<path>` names the staged blob
+        return side === "old" ? getBlob(git, "", filePath) : getWorkingBlob(repoPath, filePath);
+      }
+      if (mode === "full") {
+        return side === "old" ? getBlob(git, await resolveMergeBase(git, baseBranch), filePath) : getWorkingBlob(repoPath, filePath);
+      }
+      return getBlob(git, side === "old" ? await resolveMergeBase(git, baseBranch) : "HEAD", filePath);
+    },
+
+    async getCommitFileBlob(oid: string, filePath: string, side: FileSide, parent = 1): Promise<FileBlob | null> {
+      return getBlob(git, side === "old" ? await resolveParent(git, oid, parent) : oid, filePath);
     },
 
     async stageFile(filePath: string): Promise<void> {
@@ -160,29 +808,328 @@ function createGitManager(repoPath: string, git: SimpleGit): GitManager {
   };
 }
 
+// How git pairs deleted and added paths in file lists; set once at server startup
+export interface DiffFindOptions {
+  // Report files copied from another file changed in the same diff (git -C)
+  copies: boolean;
+  // ...or from any file, changed or not; slower on large trees (--find-copies-harder)
+  copiesFromUnmodified: boolean;
+}
+
+let findOptions: DiffFindOptions = { copies: false, copiesFromUnmodified: false };
+
+export function setDiffFindOptions(options: Partial<DiffFindOptions>): void {
+  findOptions = { ...findOptions, ...options };
+}
+
+// Copy detection flags (-C also finds renames, which git reports either way)
+function findArgs(): string[] {
+  if (!findOptions.copies && !findOptions.copiesFromUnmodified) return [];
+  return findOptions.copiesFromUnmodified ? ["-C", "--find-copies-harder"] : ["-C"];
+}
+
+// What counts as a change inside a file; set once at server startup
+export interface DiffContentOptions {
+  // Hide changes that only add or remove trailing whitespace (--ignore-space-at-eol);
+  // indentation changes still show
+  ignoreEolWhitespace: boolean;
+  // Spend extra CPU on the smallest possible diff (--minimal). Refines git's default myers
+  // algorithm; noticeably slower on large rewrites, so off unless asked for
+  minimal: boolean;
+}
+
+let contentOptions: DiffContentOptions = { ignoreEolWhitespace: false, minimal: false };
+
+export function setDiffContentOptions(options: Partial<DiffContentOptions>): void {
+  contentOptions = { ...contentOptions, ...options };
+}
+
+const contentOverrides = new AsyncLocalStorage<Partial<DiffContentOptions>>();
+
+// Run `fn` with per-request overrides (e.g. ?minimal=true) on top of the startup options
+export function withDiffContentOptions<T>(overrides: Partial<DiffContentOptions>, fn: () => Promise<T>): Promise<T> {
+  return contentOverrides.run(overrides, fn);
+}
+
+// Whether this request's overrides differ from the startup options
+export function hasDiffContentOverrides(): boolean {
+  const overrides = contentOverrides.getStore() ?? {};
+  return (Object.keys(overrides) as Array<keyof DiffContentOptions>).some((key) => overrides[key] !== contentOptions[key]);
+}
+
+function currentContentOptions(): DiffContentOptions {
+  return { ...contentOptions, ...contentOverrides.getStore() };
+}
+
+// ?patch_encoding=base64 requests read patches as bytes: each byte of git's output becomes one
+// latin1 char, so the server can base64 exactly what git wrote. Anything else in the response
+// (paths, file contents, hunks the server parses) stays UTF-8 text
+const patchBytes = new AsyncLocalStorage<true>();
+const gitRepoPaths = new WeakMap<SimpleGit, string>();
+
+export function withPatchBytes<T>(fn: () => Promise<T>): Promise<T> {
+  return patchBytes.run(true, fn);
+}
+
+export function readingPatchBytes(): boolean {
+  return patchBytes.getStore() === true;
+}
+
+// Output of a patch-producing git command; raw bytes (as latin1) under withPatchBytes
+async function patchOutput(git: SimpleGit, args: string[]): Promise<string> {
+  const repoPath = gitRepoPaths.get(git);
+  if (!readingPatchBytes() || !repoPath) return git.raw(args);
+
+  const config = maxBlobSize !== null ? ["-c", `core.bigFileThreshold=${maxBlobSize}`] : [];
+  const proc = Bun.spawn(["git", ...config, ...args], {
+    cwd: repoPath,
+    stdout: "pipe",
+    stderr: "pipe",
+    ...(gitTimeout !== null && { timeout: gitTimeout }),
+  });
+  const [stdout, stderr, exitCode] = await Promise.all([
+    new Response(proc.stdout).arrayBuffer(),
+    new Response(proc.stderr).text(),
+    proc.exited,
+  ]);
+  if (exitCode !== 0) {
+    throw new Error(stderr.trim() || `git ${args[0]} exited with code ${exitCode}`);
+  }
+  return Buffer.from(stdout).toString("latin1");
+}
+
+// A patch cerebro builds from file text, in the same form patchOutput would return
+function asPatchOutput(patch: string): string {
+  return readingPatchBytes() ? Buffer.from(patch, "utf8").toString("latin1") : patch;
+}
+
+let pathOverrides: { glob: Glob; baseNameOnly: boolean; override: DiffOverride }[] = [];
+
+// Per-path options from config, set at server startup. They reach each file's own patch and
+// its numstat line (see numstatOutput); exports and aggregate patches keep the server-wide options
+export function setDiffOverrides(overrides: DiffOverride[]): void {
+  pathOverrides = overrides.map((override) => ({
+    glob: new Glob(override.path),
+    baseNameOnly: !override.path.includes("/"),
+    override,
+  }));
+}
+
+// Every override matching `filePath` merged in config order, so a later entry wins
+export function diffOverrideFor(filePath: string): Omit<DiffOverride, "path"> {
+  const merged: Omit<DiffOverride, "path"> = {};
+  for (const { glob, baseNameOnly, override } of pathOverrides) {
+    if (!glob.match(baseNameOnly ? basename(filePath) : filePath)) continue;
+    if (override.ignoreWhitespace !== undefined) merged.ignoreWhitespace = override.ignoreWhitespace;
+    if (override.context !== undefined) merged.context = override.context;
+  }
+  return merged;
+}
+
+// Parse `<glob>:<option>,...` (config `diff-override`), where options are ignore-whitespace and
+// context=<n>, or an error message. No options means the glob's override is removed
+export function parseDiffOverride(value: string): DiffOverride | string {
+  const separator = value.lastIndexOf(":");
+  const path = (separator === -1 ? value : value.slice(0, separator)).trim();
+  if (!path) return "Expected <glob>:<options>";
+
+  const override: DiffOverride = { path };
+  const options = separator === -1 ? [] : value.slice(separator + 1).split(",").map((o) => o.trim()).filter(Boolean);
+  for (const option of options) {
+    const [name, arg] = option.split("=", 2);
+    if (name === "ignore-whitespace" && arg === undefined) {
+      override.ignoreWhitespace = true;
+    } else if (name === "context" && arg !== undefined && /^\d+$/.test(arg)) {
+      override.context = parseInt(arg, 10);
+    } else {
+      return `Unknown diff override option: ${option} (expected ignore-whitespace, context=<n>)`;
+    }
+  }
+  return override;
+}
+
+// Part of every patch cache key, so patches made with different options never mix. A diff of
+// one file passes its path to pick up that path's overrides
+function contentArgs(filePath?: string): string[] {
+  const options = currentContentOptions();
+  const override = filePath !== undefined ? diffOverrideFor(filePath) : {};
+  return [
+    ...(options.ignoreEolWhitespace ? ["--ignore-space-at-eol"] : []),
+    ...(options.minimal ? ["--minimal"] : []),
+    ...(override.ignoreWhitespace ? ["--ignore-all-space"] : []),
+    ...(override.context !== undefined ? [`--unified=${override.context}`] : []),
+  ];
+}
+
+// `git diff <args> --numstat` with the content options. Paths overridden to ignore whitespace take
+// their line from a second, whitespace-blind numstat, and drop out when that has nothing for them
+async function numstatOutput(git: SimpleGit, args: string[]): Promise<string> {
+  const base = ["--numstat", ...findArgs(), ...contentArgs()];
+  if (!pathOverrides.some(({ override }) => override.ignoreWhitespace)) {
+    return git.diff([...base, ...args]);
+  }
+  const [numstat, blind] = await Promise.all([git.diff([...base, ...args]), git.diff([...base, "--ignore-all-space", ...args])]);
+  const pathOf = (line: string) => numstatPath(line.split("\t").slice(2).join("\t"));
+  const blindLines = new Map(blind.split("\n").filter(Boolean).map((line) => [pathOf(line), line]));
+  return numstat
+    .split("\n")
+    .filter(Boolean)
+    .flatMap((line) => {
+      const path = pathOf(line);
+      if (!diffOverrideFor(path).ignoreWhitespace) return [line];
+      const replacement = blindLines.get(path);
+      return replacement !== undefined ? [replacement] : [];
+    })
+    .join("\n");
+}
+
+// A working-tree file whose every change is ignored produces an empty patch; leave it out
+function isHiddenByContentOptions(patch: string, filePath: string): boolean {
+  const ignoring = currentContentOptions().ignoreEolWhitespace || diffOverrideFor(filePath).ignoreWhitespace === true;
+  return ignoring && patch === "";
+}
+
+// Files over this many MB are reported as too large instead of diffed
+export const DEFAULT_MAX_BLOB_MB = 50;
+
+let maxBlobSize: number | null = null;
+
+// Set at server startup; managers are rebuilt so their git processes pick up the threshold
+export function setMaxBlobSize(bytes: number | null): void {
+  maxBlobSize = bytes;
+  managers.clear();
+}
+
+let gitTimeout: number | null = null;
+
+// Set at server startup from --request-timeout: a git process silent for this long is killed,
+// which bounds a stuck process. A timed-out request's later git calls still run
+export function setGitTimeout(ms: number | null): void {
+  gitTimeout = ms;
+  managers.clear();
+}
+
+// Largest side of a change when it's over the limit, otherwise null
+async function oversizedBlob(git: SimpleGit, entry?: RawDiffEntry, workingFile?: string): Promise<number | null> {
+  const limit = maxBlobSize;
+  if (limit === null) return null;
+
+  const sizes = await Promise.all([
+    entry?.oldOid ? blobSize(git, entry.oldOid) : 0,
+    workingFile !== undefined ? Bun.file(workingFile).size : entry?.newOid ? blobSize(git, entry.newOid) : 0,
+  ]);
+  const size = Math.max(...sizes);
+  return size > limit ? size : null;
+}
+
+// Paths numstat reports as binary ("-"), which includes anything over core.bigFileThreshold.
+// Only these can be too large, so diffs of many files skip the size lookups for the rest
+async function getBinaryPaths(git: SimpleGit, args: string[]): Promise<Set<string>> {
+  const binary = new Set<string>();
+  if (maxBlobSize === null) return binary;
+  for (const line of (await git.diff(["--numstat", ...args])).trim().split("\n")) {
+    const [add, , ...pathParts] = line.split("\t");
+    const filePath = numstatPath(pathParts.join("\t"));
+    if (add === "-" && filePath) binary.add(filePath);
+  }
+  return binary;
+}
+
+async function blobSize(git: SimpleGit, oid: string): Promise<number> {
+  try {
+    return parseInt((await git.raw(["cat-file", "-s", oid])).trim(), 10) || 0;
+  } catch {
+    return 0;
+  }
+}
+
+// Old and new sizes of a binary change. A side that doesn't exist (added/deleted) is left out;
+// the working tree side is read from disk since it has no blob yet
+async function binarySizes(git: SimpleGit, entry?: RawDiffEntry, workingFile?: string): Promise<Partial<FileDiff>> {
+  const [oldSize, newSize] = await Promise.all([
+    entry?.oldOid ? blobSize(git, entry.oldOid) : undefined,
+    workingFile !== undefined ? workingFileSize(workingFile) : entry?.newOid ? blobSize(git, entry.newOid) : undefined,
+  ]);
+  return { binary: true, old_size: oldSize, new_size: newSize };
+}
+
+async function getTopLevel(git: SimpleGit): Promise<string> {
+  return (await git.revparse(["--show-toplevel"])).trim();
+}
+
+async function workingFileSize(path: string): Promise<number | undefined> {
+  const file = Bun.file(path);
+  return (await file.exists()) ? file.size : undefined;
+}
+
+// git prints this instead of hunks for binary files
+const BINARY_PATCH = /^Binary files .* differ$/m;
+
+// A brand-new text file, whose patch is nothing but additions: readable as plain code instead
+// (new_file in file diffs, or GET /api/file?side=new). Lazily loaded patches are "" until fetched
+export function isFullNewFile(file: FileDiff): boolean {
+  return (
+    (file.status === "added" || file.status === "untracked") &&
+    !file.binary &&
+    !file.too_large &&
+    !file.symlink &&
+    !file.submodule &&
+    !BINARY_PATCH.test(file.patch)
+  );
+}
+
+// Stand-in for a file whose patch and contents were skipped for size
+function tooLargeDiff(filePath: string, status: FileDiff["status"], size: number, staged?: boolean): FileDiff {
+  return { path: filePath, status, additions: 0, deletions: 0, patch: "", viewed: false, staged, too_large: true, blob_size: size };
+}
+
+// `-- <paths>` suffix for git commands, empty when unscoped
+function toPathspecArgs(paths?: string[]): string[] {
+  return paths && paths.length > 0 ? ["--", ...paths] : [];
+}
+
 // Get all working directory changes (both staged and unstaged)
-async function getWorkingDiff(git: SimpleGit, repoPath: string): Promise<FileDiff[]> {
-  const status = await git.status();
+async function getWorkingDiff(git: SimpleGit, repoPath: string, pathspec: string[] = []): Promise<FileDiff[]> {
+  const status = await git.status(pathspec);
   const files: FileDiff[] = [];
   const processedPaths = new Set<string>();
+  const [stagedEntries, unstagedEntries, unmerged, stagedBinary, unstagedBinary] = await Promise.all([
+    getRawEntries(git, ["--cached", ...findArgs(), ...pathspec]),
+    getRawEntries(git, pathspec),
+    getUnmergedStages(git, pathspec),
+    getBinaryPaths(git, ["--cached", ...findArgs(), ...pathspec]),
+    getBinaryPaths(git, pathspec),
+  ]);
 
   // Staged files first
-  const stagedDiff = await git.diff(["--cached", "--name-status"]);
+  const stagedDiff = await git.diff(["--cached", "--name-status", ...findArgs(), ...pathspec]);
   if (stagedDiff.trim()) {
     for (const line of stagedDiff.trim().split("\n")) {
-      const [statusCode, ...pathParts] = line.split("\t");
-      const filePath = pathParts.join("\t");
-      if (!filePath || !statusCode) continue;
+      const entry = parseNameStatusLine(line);
+      if (!entry || unmerged.has(entry.path)) continue;
+      const { path: filePath, status: fileStatus, oldPath } = entry;
 
       processedPaths.add(filePath);
-      const patchDiff = await git.diff(["--cached", "--", filePath]);
+      // A copy's source is still there (and may have its own changes)
+      if (oldPath && fileStatus === "renamed") processedPaths.add(oldPath);
+      const oversized = stagedBinary.has(filePath) ? await oversizedBlob(git, stagedEntries.get(filePath)) : null;
+      if (oversized !== null) {
+        files.push({ ...tooLargeDiff(filePath, fileStatus, oversized, true), old_path: oldPath, similarity: entry.similarity });
+        continue;
+      }
+      // Both sides of a rename or copy, so git pairs them instead of showing an add
+      const patchDiff = await patchOutput(git, [
+        "diff",
+        "--cached",
+        ...findArgs(),
+        ...contentArgs(filePath),
+        "--",
+        ...(oldPath ? [oldPath] : []),
+        filePath,
+      ]);
+      if (isHiddenByContentOptions(patchDiff, filePath)) continue;
       const { additions, deletions } = countChanges(patchDiff);
 
-      let fileStatus: FileDiff["status"] = "modified";
-      if (statusCode.startsWith("A")) fileStatus = "added";
-      else if (statusCode.startsWith("D")) fileStatus = "deleted";
-      else if (statusCode.startsWith("R")) fileStatus = "renamed";
-
       files.push({
         path: filePath,
         status: fileStatus,
@@ -191,18 +1138,36 @@ async function getWorkingDiff(git: SimpleGit, repoPath: string): Promise<FileDif
         patch: patchDiff,
         viewed: false,
         staged: true,
-        old_file: fileStatus !== "added" ? await getFileContents(git, "HEAD", filePath) : undefined,
+        old_path: oldPath,
+        similarity: entry.similarity,
+        old_file: fileStatus !== "added" ? await getFileContents(git, "HEAD", oldPath ?? filePath) : undefined,
         new_file: fileStatus !== "deleted" ? await getStagedFileContents(git, filePath) : undefined,
+        ...(BINARY_PATCH.test(patchDiff) && (await binarySizes(git, stagedEntries.get(filePath)))),
       });
     }
   }
 
+  for (const filePath of unmerged.keys()) {
+    processedPaths.add(filePath);
+    const conflicted = await getConflictedWorkingFileDiff(git, repoPath, filePath);
+    if (conflicted) files.push(conflicted);
+  }
+
   // Unstaged modified files
   for (const filePath of status.modified) {
     if (processedPaths.has(filePath)) continue;
     processedPaths.add(filePath);
 
-    const diff = await git.diff([filePath]);
+    const oversized = unstagedBinary.has(filePath)
+      ? await oversizedBlob(git, unstagedEntries.get(filePath), join(repoPath, filePath))
+      : null;
+    if (oversized !== null) {
+      files.push(tooLargeDiff(filePath, "modified", oversized, false));
+      continue;
+    }
+
+    const diff = await patchOutput(git, ["diff", ...contentArgs(filePath), filePath]);
+    if (isHiddenByContentOptions(diff, filePath)) continue;
     const { additions, deletions } = countChanges(diff);
 
     files.push({
@@ -215,6 +1180,7 @@ async function getWorkingDiff(git: SimpleGit, repoPath: string): Promise<FileDif
       staged: false,
       old_file: await getFileContents(git, "HEAD", filePath),
       new_file: await getWorkingFileContents(repoPath, filePath),
+      ...(BINARY_PATCH.test(diff) && (await binarySizes(git, unstagedEntries.get(filePath), join(repoPath, filePath)))),
     });
   }
 
@@ -223,65 +1189,414 @@ async function getWorkingDiff(git: SimpleGit, repoPath: string): Promise<FileDif
     if (processedPaths.has(filePath)) continue;
     processedPaths.add(filePath);
 
+    const oversized = await oversizedBlob(git, undefined, join(repoPath, filePath));
+    if (oversized !== null) {
+      files.push(tooLargeDiff(filePath, "untracked", oversized, false));
+      continue;
+    }
+
     const contents = await getWorkingFileContents(repoPath, filePath);
     const lines = contents?.contents.split("\n").length || 0;
 
-    files.push({
-      path: filePath,
-      status: "untracked",
-      additions: lines,
-      deletions: 0,
-      patch: createAddPatch(filePath, contents?.contents || ""),
-      viewed: false,
-      staged: false,
-      new_file: contents,
-    });
+    files.push({
+      path: filePath,
+      status: "untracked",
+      additions: lines,
+      deletions: 0,
+      patch: createAddPatch(filePath, contents?.contents || ""),
+      viewed: false,
+      staged: false,
+      new_file: contents,
+    });
+  }
+
+  // Unstaged deleted files
+  for (const filePath of status.deleted) {
+    if (processedPaths.has(filePath)) continue;
+    processedPaths.add(filePath);
+
+    const oversized = unstagedBinary.has(filePath) ? await oversizedBlob(git, unstagedEntries.get(filePath)) : null;
+    if (oversized !== null) {
+      files.push(tooLargeDiff(filePath, "deleted", oversized, false));
+      continue;
+    }
+
+    const oldContents = await getFileContents(git, "HEAD", filePath);
+    const lines = oldContents?.contents.split("\n").length || 0;
+
+    files.push({
+      path: filePath,
+      status: "deleted",
+      additions: 0,
+      deletions: lines,
+      patch: createDeletePatch(filePath, oldContents?.contents || ""),
+      viewed: false,
+      staged: false,
+      old_file: oldContents,
+    });
+  }
+
+  await Promise.all(
+    files.map((file) => applySpecialModes(git, file, file.staged ? stagedEntries : unstagedEntries, repoPath))
+  );
+
+  return files;
+}
+
+const STASH_SPEC = /^stash(?:@\{(\d+)\})?$/;
+// git's shorthand for the current branch's upstream, resolved per request so it follows checkouts
+export const UPSTREAM_SPEC = /^@\{(?:u|upstream)\}$/i;
+// A base or range that doesn't name anything usable (the server answers 400 rather than 500)
+export class RevisionError extends Error {}
+
+export const NO_UPSTREAM_MESSAGE =
+  "The current branch has no upstream configured; set one with `git branch --set-upstream-to` or pass --base <branch>";
+// Plain branch names, the only specs worth looking up under refs/remotes
+const BRANCH_NAME = /^[\w][\w./-]*$/;
+
+type RemoteFallbackHandler = (requested: string, used: string) => void;
+let remoteFallbackHandler: RemoteFallbackHandler | null = null;
+
+// Called whenever a branch with no local ref resolves to a remote-tracking branch
+export function setRemoteFallbackHandler(handler: RemoteFallbackHandler | null): void {
+  remoteFallbackHandler = handler;
+}
+
+// A ref outside the --allowed-base globs (the server answers 403)
+export class RefNotAllowedError extends Error {}
+
+let allowedRefs: Glob[] | null = null;
+
+const FULL_OID = /^[0-9a-f]{40}([0-9a-f]{24})?$/;
+
+// Globs every user-supplied base, range end and shown commit must match as written
+// (e.g. release/*); empty allows any ref
+export function setAllowedRefs(patterns: string[]): void {
+  allowedRefs = patterns.length > 0 ? patterns.map((pattern) => new Glob(pattern)) : null;
+}
+
+// Checks a ref taken from a request against the allowlist. HEAD and full oids always pass,
+// so lookups of the checked-out branch and of stored review commits keep working
+export function assertRefAllowed(spec: string): void {
+  if (!allowedRefs || spec === "HEAD" || FULL_OID.test(spec)) return;
+  if (!allowedRefs.some((glob) => glob.match(spec))) {
+    throw new RefNotAllowedError(`Ref ${spec} is not in the allowed list`);
+  }
+}
+
+// Commit for any compare spec: branches, tags, oids, HEAD~2, origin/<branch>, stash@{n}
+async function resolveRevision(git: SimpleGit, spec: string): Promise<string | null> {
+  const remoteRef = await resolveRemoteSpec(git, spec);
+  if (remoteRef) return remoteRef;
+
+  try {
+    const oid = (await git.raw(["rev-parse", "--verify", "--quiet", `${spec}^{commit}`])).trim();
+    if (oid) return oid;
+  } catch {
+    // Not a plain revision; try the stash reflog and remote-tracking branches below
+  }
+
+  const stash = STASH_SPEC.exec(spec);
+  if (stash) {
+    try {
+      const entries = (await git.raw(["stash", "list", "--format=%H"])).trim().split("\n");
+      return entries[Number(stash[1] ?? 0)] || null;
+    } catch {
+      return null;
+    }
+  }
+
+  const remote = BRANCH_NAME.test(spec) && !spec.includes("..") ? await resolveRemoteBranch(git, spec) : null;
+  if (remote) {
+    remoteFallbackHandler?.(spec, remote.ref);
+    return remote.oid;
+  }
+  return null;
+}
+
+// `<remote>/<branch>` (e.g. upstream/main in a fork) read from refs/remotes explicitly, so a tag
+// or other ref of the same name can't shadow it. A local branch with the slashed name still wins
+async function resolveRemoteSpec(git: SimpleGit, spec: string): Promise<string | null> {
+  const slash = spec.indexOf("/");
+  if (slash <= 0 || !BRANCH_NAME.test(spec) || spec.includes("..")) return null;
+  try {
+    const remotes = (await git.raw(["remote"])).trim().split("\n");
+    if (!remotes.includes(spec.slice(0, slash))) return null;
+    if (await refExists(git, `refs/heads/${spec}`)) return null;
+    return (await git.raw(["rev-parse", "--verify", "--quiet", `refs/remotes/${spec}^{commit}`])).trim() || null;
+  } catch {
+    return null;
+  }
+}
+
+async function refExists(git: SimpleGit, ref: string): Promise<boolean> {
+  try {
+    await git.raw(["show-ref", "--verify", "--quiet", ref]);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
+// `<remote>/<branch>` for a branch that only exists remotely (fresh clones keep
+// just origin/<default> in packed-refs), preferring origin
+async function resolveRemoteBranch(git: SimpleGit, branch: string): Promise<{ ref: string; oid: string } | null> {
+  try {
+    const output = await git.raw(["for-each-ref", "--format=%(refname:short)%00%(objectname)", `refs/remotes/*/${branch}`]);
+    const refs = output
+      .trim()
+      .split("\n")
+      .filter(Boolean)
+      .map((line) => {
+        const [ref = "", oid = ""] = line.split("\0");
+        return { ref, oid };
+      });
+    return refs.find(({ ref }) => ref === `origin/${branch}`) ?? refs[0] ?? null;
+  } catch {
+    return null;
+  }
+}
+
+// Resolve the merge base of baseBranch and HEAD (falls back to baseBranch itself)
+async function resolveMergeBase(git: SimpleGit, baseBranch: string): Promise<string> {
+  const base = await resolveRevision(git, baseBranch);
+  if (!base) {
+    if (UPSTREAM_SPEC.test(baseBranch)) {
+      throw new RevisionError(NO_UPSTREAM_MESSAGE);
+    }
+    // Base branch might not exist, use it directly
+    return baseBranch;
+  }
+  // A stash isn't part of HEAD's history; compare against its snapshot directly
+  if (STASH_SPEC.test(baseBranch)) {
+    return base;
+  }
+
+  try {
+    return (await git.raw(["merge-base", base, "HEAD"])).trim();
+  } catch {
+    // Unrelated histories
+    return base;
+  }
+}
+
+// Get branch diff against base - returns file list with stats only (lazy loading)
+async function getBranchDiff(
+  git: SimpleGit,
+  _repoPath: string,
+  baseBranch: string,
+  pathspec: string[] = []
+): Promise<FileDiff[]> {
+  const mergeBase = await resolveMergeBase(git, baseBranch);
+  // Compare mergeBase to HEAD explicitly to exclude working directory changes
+  return getRangeDiff(git, mergeBase, "HEAD", pathspec);
+}
+
+// Full mode: the merge base against the working tree, so committed work and local edits
+// show as one diff. Each file records which side its changes came from
+async function getFullDiff(git: SimpleGit, repoPath: string, baseBranch: string, pathspec: string[] = []): Promise<FileDiff[]> {
+  const mergeBase = await resolveMergeBase(git, baseBranch);
+  const [files, untracked, changedIn] = await Promise.all([
+    getRangeDiff(git, mergeBase, null, pathspec),
+    getUntrackedPaths(git, pathspec),
+    getChangedIn(git, mergeBase, pathspec),
+  ]);
+
+  for (const file of files) {
+    file.changed_in = changedIn.get(file.path);
+  }
+  for (const filePath of untracked) {
+    const contents = await getWorkingFileContents(repoPath, filePath);
+    files.push({
+      path: filePath,
+      status: "untracked",
+      additions: contents?.contents.split("\n").length || 0,
+      deletions: 0,
+      patch: "", // Loaded on demand
+      viewed: false,
+      changed_in: "uncommitted",
+    });
+  }
+  return files;
+}
+
+// What `git add` would pick up: the index against the working tree, plus untracked files.
+// Staged changes are left out, so a partly staged file shows only its unstaged part
+async function getUnstagedDiff(git: SimpleGit, repoPath: string, pathspec: string[] = []): Promise<FileDiff[]> {
+  const [files, untracked] = await Promise.all([getDiffList(git, [], true, pathspec), getUntrackedPaths(git, pathspec)]);
+  for (const file of files) file.staged = false;
+  for (const filePath of untracked) {
+    const contents = await getWorkingFileContents(repoPath, filePath);
+    files.push({
+      path: filePath,
+      status: "untracked",
+      additions: contents?.contents.split("\n").length || 0,
+      deletions: 0,
+      patch: "", // Loaded on demand
+      viewed: false,
+      staged: false,
+    });
+  }
+  return files;
+}
+
+async function getUntrackedPaths(git: SimpleGit, pathspec: string[] = []): Promise<string[]> {
+  const output = await git.raw(["ls-files", "--others", "--exclude-standard", ...pathspec]);
+  return output.split("\n").filter(Boolean);
+}
+
+export interface ReflogEntry {
+  oldOid: string;
+  newOid: string;
+  timestamp: number;
+  message: string;
+}
+
+// A reflog file, oldest entry first: "<old> <new> <name> <<email>> <unix time> <tz>\t<message>"
+export function parseReflog(text: string): ReflogEntry[] {
+  const entries: ReflogEntry[] = [];
+  for (const line of text.split("\n")) {
+    const tab = line.indexOf("\t");
+    const head = tab === -1 ? line : line.slice(0, tab);
+    const match = /^([0-9a-f]+) ([0-9a-f]+) .*> (\d+) [+-]\d{4}$/.exec(head);
+    if (!match) continue;
+    const [, oldOid = "", newOid = "", time = "0"] = match;
+    entries.push({ oldOid, newOid, timestamp: Number(time), message: tab === -1 ? "" : line.slice(tab + 1) });
+  }
+  return entries;
+}
+
+// False as well when either commit is missing (a pruned tip can't be in the new history)
+async function isAncestor(git: SimpleGit, ancestor: string, descendant: string): Promise<boolean> {
+  try {
+    return (await git.raw(["merge-base", ancestor, descendant])).trim() === ancestor;
+  } catch {
+    return false;
+  }
+}
+
+async function objectExists(git: SimpleGit, oid: string): Promise<boolean> {
+  try {
+    await git.raw(["cat-file", "-t", oid]);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
+// Unmerged paths and the index stages each has (1 base, 2 ours, 3 theirs), in index order.
+// `ls-files -u -z` lines are "<mode> <oid> <stage>\t<path>"
+async function getUnmergedStages(git: SimpleGit, pathspec: string[] = []): Promise<Map<string, Set<number>>> {
+  const output = await git.raw(["ls-files", "--unmerged", "-z", ...pathspec]);
+  const stages = new Map<string, Set<number>>();
+  for (const entry of output.split("\0")) {
+    const tab = entry.indexOf("\t");
+    if (tab === -1) continue;
+    const stage = parseInt(entry.slice(0, tab).split(" ")[2] ?? "", 10);
+    const path = entry.slice(tab + 1);
+    if (!stages.has(path)) stages.set(path, new Set());
+    stages.get(path)?.add(stage);
   }
+  return stages;
+}
 
-  // Unstaged deleted files
-  for (const filePath of status.deleted) {
-    if (processedPaths.has(filePath)) continue;
-    processedPaths.add(filePath);
+// Unmerged paths have no stage-0 entry to diff against, so working mode shows HEAD against the
+// working file, conflict markers and all; null when the file still matches HEAD
+async function getConflictedWorkingFileDiff(git: SimpleGit, repoPath: string, filePath: string): Promise<FileDiff | null> {
+  const diff = await patchOutput(git, ["diff", "HEAD", ...contentArgs(filePath), "--", filePath]);
+  if (!diff) return null;
+  const { additions, deletions } = countChanges(diff);
+  const [oldFile, newFile] = await Promise.all([
+    getFileContents(git, "HEAD", filePath),
+    getWorkingFileContents(repoPath, filePath),
+  ]);
+  return {
+    path: filePath,
+    status: !oldFile ? "added" : !newFile ? "deleted" : "modified",
+    additions,
+    deletions,
+    patch: diff,
+    viewed: false,
+    staged: false,
+    old_file: oldFile,
+    new_file: newFile,
+  };
+}
 
-    const oldContents = await getFileContents(git, "HEAD", filePath);
-    const lines = oldContents?.contents.split("\n").length || 0;
+async function markConflicted(git: SimpleGit, files: FileDiff[]): Promise<void> {
+  if (files.length === 0) return;
+  const unmerged = await getUnmergedStages(git);
+  for (const file of files) {
+    if (unmerged.has(file.path)) file.conflicted = true;
+  }
+}
 
-    files.push({
-      path: filePath,
-      status: "deleted",
-      additions: 0,
-      deletions: lines,
-      patch: createDeletePatch(filePath, oldContents?.contents || ""),
-      viewed: false,
-      staged: false,
-      old_file: oldContents,
-    });
+// Conflict blocks in a file: lines opening with git's default 7-character "<<<<<<<" marker
+export function countConflictMarkers(text: string): number {
+  return text.match(/^<{7}(?:[ \r]|$)/gm)?.length ?? 0;
+}
+
+// Whether each path changed in commits since the merge base, in the working tree, or both
+async function getChangedIn(git: SimpleGit, mergeBase: string, pathspec: string[] = []): Promise<Map<string, ChangedIn>> {
+  const [committed, uncommitted] = await Promise.all([
+    git.diff([mergeBase, "HEAD", "--name-only", ...findArgs(), ...pathspec]),
+    git.diff(["HEAD", "--name-only", ...findArgs(), ...pathspec]),
+  ]);
+  const changedIn = new Map<string, ChangedIn>();
+  for (const path of committed.split("\n").filter(Boolean)) changedIn.set(path, "committed");
+  for (const path of uncommitted.split("\n").filter(Boolean)) {
+    changedIn.set(path, changedIn.has(path) ? "both" : "uncommitted");
   }
+  return changedIn;
+}
 
-  return files;
+// Binary files ("-" counts) add a file but no lines
+export function sumNumstat(numstat: string, exclude: (path: string) => boolean = () => false): DiffEstimate {
+  const estimate: DiffEstimate = { files: 0, additions: 0, deletions: 0 };
+  for (const line of numstat.split("\n")) {
+    const [add, del, ...pathParts] = line.split("\t");
+    if (!line.trim() || del === undefined || exclude(numstatPath(pathParts.join("\t")))) continue;
+    estimate.files++;
+    estimate.additions += parseInt(add ?? "", 10) || 0;
+    estimate.deletions += parseInt(del, 10) || 0;
+  }
+  return estimate;
 }
 
-// Get branch diff against base - returns file list with stats only (lazy loading)
-async function getBranchDiff(git: SimpleGit, _repoPath: string, baseBranch: string): Promise<FileDiff[]> {
-  // Find merge base
-  let mergeBase: string;
+// Git's well-known empty tree, used as the "parent" of root commits
+const EMPTY_TREE = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+// First parent of a commit, or the empty tree for root commits
+async function resolveParent(git: SimpleGit, oid: string, parent = 1): Promise<string> {
   try {
-    mergeBase = (await git.raw(["merge-base", baseBranch, "HEAD"])).trim();
+    const base = (await git.raw(["rev-parse", "--verify", "--quiet", `${oid}^${parent}`])).trim();
+    return base || EMPTY_TREE;
   } catch {
-    // Base branch might not exist, use it directly
-    mergeBase = baseBranch;
+    return EMPTY_TREE;
   }
+}
+
+// File list with stats between two commits, or a commit and the working tree when `to`
+// is null (patches loaded on demand)
+async function getRangeDiff(git: SimpleGit, from: string, to: string | null, pathspec: string[] = []): Promise<FileDiff[]> {
+  return getDiffList(git, to === null ? [from] : [from, to], to === null, pathspec);
+}
 
+// File list with stats for `git diff <sides>`: no sides compares the index with the working
+// tree. `worktree` says whether the new side is the working tree
+async function getDiffList(git: SimpleGit, sides: string[], worktree: boolean, pathspec: string[]): Promise<FileDiff[]> {
   // Get numstat for additions/deletions counts (single git call)
-  // Compare mergeBase to HEAD explicitly to exclude working directory changes
-  const numstat = await git.diff([mergeBase, "HEAD", "--numstat"]);
+  const numstat = await numstatOutput(git, [...sides, ...pathspec]);
   const statsMap = new Map<string, { additions: number; deletions: number }>();
+  // Binary per numstat, which includes anything over core.bigFileThreshold
+  const binaryPaths = new Set<string>();
 
   if (numstat.trim()) {
     for (const line of numstat.trim().split("\n")) {
       const [add, del, ...pathParts] = line.split("\t");
-      const filePath = pathParts.join("\t");
+      const filePath = numstatPath(pathParts.join("\t"));
       if (filePath && add !== undefined && del !== undefined) {
+        if (add === "-") binaryPaths.add(filePath);
         statsMap.set(filePath, {
           additions: add === "-" ? 0 : parseInt(add, 10),
           deletions: del === "-" ? 0 : parseInt(del, 10),
@@ -291,92 +1606,303 @@ async function getBranchDiff(git: SimpleGit, _repoPath: string, baseBranch: stri
   }
 
   // Get name-status for file statuses (single git call)
-  const nameStatus = await git.diff([mergeBase, "HEAD", "--name-status"]);
+  const nameStatus = await git.diff([...sides, "--name-status", ...findArgs(), ...pathspec]);
   const files: FileDiff[] = [];
 
   if (!nameStatus.trim()) {
     return files;
   }
+  const rawEntries = await getRawEntries(git, [...sides, ...findArgs(), ...pathspec]);
 
+  // Against the index an unmerged path is listed twice ("U" then "M"); keep the first
+  const seen = new Set<string>();
   const lines = nameStatus.trim().split("\n");
   for (const line of lines) {
-    const [status, ...pathParts] = line.split("\t");
-    const filePath = pathParts.join("\t");
-
-    if (!filePath || !status) continue;
-
-    let fileStatus: FileDiff["status"] = "modified";
-    if (status.startsWith("A")) fileStatus = "added";
-    else if (status.startsWith("D")) fileStatus = "deleted";
-    else if (status.startsWith("R")) fileStatus = "renamed";
-
-    const stats = statsMap.get(filePath) || { additions: 0, deletions: 0 };
+    const entry = parseNameStatusLine(line);
+    if (!entry || seen.has(entry.path)) continue;
+    seen.add(entry.path);
+
+    if (
+      (currentContentOptions().ignoreEolWhitespace || diffOverrideFor(entry.path).ignoreWhitespace) &&
+      onlyIgnoredChanges(entry.status, statsMap.get(entry.path), rawEntries.get(entry.path), binaryPaths.has(entry.path))
+    ) {
+      continue;
+    }
+    const stats = statsMap.get(entry.path) || { additions: 0, deletions: 0 };
 
     // Don't load patch or file contents - will be loaded on demand
     files.push({
-      path: filePath,
-      status: fileStatus,
+      path: entry.path,
+      status: entry.status,
       additions: stats.additions,
       deletions: stats.deletions,
       patch: "", // Loaded on demand
       viewed: false,
+      old_path: entry.oldPath,
+      similarity: entry.similarity,
       // old_file and new_file not included - loaded on demand
     });
   }
 
+  await Promise.all(files.map((file) => applySpecialModes(git, file, rawEntries)));
+  await Promise.all(
+    files
+      .filter((file) => binaryPaths.has(file.path))
+      .map(async (file) => {
+        const entry = rawEntries.get(file.path);
+        const size = await oversizedBlob(git, entry);
+        if (size !== null) {
+          Object.assign(file, { too_large: true, blob_size: size });
+        } else if (!file.submodule) {
+          const workingFile = worktree && entry && !entry.newOid ? join(await getTopLevel(git), file.path) : undefined;
+          Object.assign(file, await binarySizes(git, entry, workingFile));
+        }
+      })
+  );
+
   return files;
 }
 
+// Under --ignore-space-at-eol or a path's ignore-whitespace override, a text file modified in
+// place whose numstat is missing (git leaves out files with nothing left to show) or 0/0. Mode changes, renames, additions and
+// deletions still count, and binary files always report "-"
+function onlyIgnoredChanges(
+  status: FileDiff["status"],
+  stats: { additions: number; deletions: number } | undefined,
+  raw: RawDiffEntry | undefined,
+  binary: boolean
+): boolean {
+  if (status !== "modified" || binary || (raw && raw.oldMode !== raw.newMode)) return false;
+  return !stats || (stats.additions === 0 && stats.deletions === 0);
+}
+
 // Single file diff loaders (for lazy loading)
 async function getSingleBranchFileDiff(git: SimpleGit, baseBranch: string, filePath: string): Promise<FileDiff | null> {
-  let mergeBase: string;
+  const mergeBase = await resolveMergeBase(git, baseBranch);
+  // Compare mergeBase to HEAD explicitly to exclude working directory changes
+  return getSingleRangeFileDiff(git, mergeBase, "HEAD", filePath);
+}
+
+async function getSingleFullFileDiff(
+  git: SimpleGit,
+  repoPath: string,
+  baseBranch: string,
+  filePath: string
+): Promise<FileDiff | null> {
+  const mergeBase = await resolveMergeBase(git, baseBranch);
+  if ((await getUntrackedPaths(git, ["--", filePath])).includes(filePath)) {
+    const oversized = await oversizedBlob(git, undefined, join(repoPath, filePath));
+    if (oversized !== null) return { ...tooLargeDiff(filePath, "untracked", oversized), changed_in: "uncommitted" };
+
+    const contents = await getWorkingFileContents(repoPath, filePath);
+    return {
+      path: filePath,
+      status: "untracked",
+      additions: contents?.contents.split("\n").length || 0,
+      deletions: 0,
+      patch: createAddPatch(filePath, contents?.contents || ""),
+      viewed: false,
+      new_file: contents,
+      changed_in: "uncommitted",
+    };
+  }
+
   try {
-    mergeBase = (await git.raw(["merge-base", baseBranch, "HEAD"])).trim();
+    // Like range diffs, a rename only pairs with both names in the pathspec
+    const oldPath = await getRenameSource(git, mergeBase, undefined, filePath);
+    const pathspec = ["--", ...(oldPath ? [oldPath] : []), filePath];
+    const [rawEntries, nameStatus, changedIn] = await Promise.all([
+      getRawEntries(git, [mergeBase, "-M", ...pathspec]),
+      git.diff([mergeBase, "--name-status", "-M", ...pathspec]),
+      getChangedIn(git, mergeBase, pathspec),
+    ]);
+    if (!nameStatus.trim()) return null;
+
+    let fileStatus: FileDiff["status"] = "modified";
+    if (nameStatus.startsWith("A")) fileStatus = "added";
+    else if (nameStatus.startsWith("D")) fileStatus = "deleted";
+    else if (nameStatus.startsWith("R")) fileStatus = "renamed";
+
+    const oversized = await oversizedBlob(git, rawEntries.get(filePath), fileStatus !== "deleted" ? join(repoPath, filePath) : undefined);
+    if (oversized !== null) {
+      return {
+        ...tooLargeDiff(filePath, fileStatus, oversized),
+        ...(oldPath !== undefined && { old_path: oldPath }),
+        changed_in: changedIn.get(filePath),
+      };
+    }
+
+    // The new side isn't a blob yet, so this skips the patch cache
+    const patchDiff = await patchOutput(git, ["diff", ...contentArgs(filePath), mergeBase, "-M", ...pathspec]);
+    const { additions, deletions } = countChanges(patchDiff);
+    const fileDiff: FileDiff = {
+      path: filePath,
+      status: fileStatus,
+      additions,
+      deletions,
+      patch: patchDiff,
+      viewed: false,
+      changed_in: changedIn.get(filePath),
+      ...(oldPath !== undefined && { old_path: oldPath, similarity: parseNameStatusLine(nameStatus.trim())?.similarity }),
+      old_file: fileStatus !== "added" ? await getFileContents(git, mergeBase, oldPath ?? filePath) : undefined,
+      new_file: fileStatus !== "deleted" ? await getWorkingFileContents(repoPath, filePath) : undefined,
+    };
+    await applySpecialModes(git, fileDiff, rawEntries, repoPath);
+    return fileDiff;
   } catch {
-    mergeBase = baseBranch;
+    return null;
+  }
+}
+
+let patchCache: PatchCache | null = null;
+
+// Cache branch and commit file patches across requests (null disables); set at server startup
+export function setPatchCache(cache: PatchCache | null): void {
+  patchCache = cache;
+}
+
+// `git diff` for one file, served from the patch cache when its blob oids are known
+async function diffFile(git: SimpleGit, args: string[], filePath: string, entry?: RawDiffEntry): Promise<string> {
+  const cache = patchCache;
+  const diffArgs = ["diff", ...contentArgs(filePath), ...args];
+  // Cached patches are text, not the bytes a base64 request wants
+  if (!cache || !entry || (!entry.oldOid && !entry.newOid) || readingPatchBytes()) {
+    return patchOutput(git, diffArgs);
   }
 
+  // Mode changes show up in the patch header too
+  const options = [filePath, entry.oldMode, entry.newMode, ...contentArgs(filePath)].join("\0");
+  const key = patchCacheKey(entry.oldOid, entry.newOid, options);
+  const cached = await cache.get(key);
+  if (cached !== null) return cached;
+
+  const patch = await git.raw(diffArgs);
+  await cache.set(key, patch);
+  return patch;
+}
+
+async function getSingleRangeFileDiff(git: SimpleGit, from: string, to: string, filePath: string): Promise<FileDiff | null> {
   try {
-    // Compare mergeBase to HEAD explicitly to exclude working directory changes
-    const patchDiff = await git.diff([mergeBase, "HEAD", "--", filePath]);
-    const { additions, deletions } = countChanges(patchDiff);
+    // git only pairs a rename when both names are in the pathspec; alone, the new path is an add
+    const oldPath = await getRenameSource(git, from, to, filePath);
+    const pathspec = ["--", ...(oldPath ? [oldPath] : []), filePath];
+    const rawEntries = await getRawEntries(git, [from, to, "-M", ...pathspec]);
 
     // Determine status
-    const nameStatus = await git.diff([mergeBase, "HEAD", "--name-status", "--", filePath]);
+    const nameStatus = await git.diff([from, to, "--name-status", "-M", ...pathspec]);
     let fileStatus: FileDiff["status"] = "modified";
     if (nameStatus.startsWith("A")) fileStatus = "added";
     else if (nameStatus.startsWith("D")) fileStatus = "deleted";
     else if (nameStatus.startsWith("R")) fileStatus = "renamed";
 
+    const oversized = await oversizedBlob(git, rawEntries.get(filePath));
+    if (oversized !== null) {
+      return { ...tooLargeDiff(filePath, fileStatus, oversized), ...(oldPath !== undefined && { old_path: oldPath }) };
+    }
+
+    const patchDiff = await diffFile(git, [from, to, "-M", ...pathspec], filePath, rawEntries.get(filePath));
+    const { additions, deletions } = countChanges(patchDiff);
+
+    const fileDiff: FileDiff = {
+      path: filePath,
+      status: fileStatus,
+      additions,
+      deletions,
+      patch: patchDiff,
+      viewed: false,
+      ...(oldPath !== undefined && { old_path: oldPath, similarity: parseNameStatusLine(nameStatus.trim())?.similarity }),
+      old_file: fileStatus !== "added" ? await getFileContents(git, from, oldPath ?? filePath) : undefined,
+      new_file: fileStatus !== "deleted" ? await getFileContents(git, to, filePath) : undefined,
+    };
+    await applySpecialModes(git, fileDiff, rawEntries);
+    return fileDiff;
+  } catch {
+    return null;
+  }
+}
+
+// The path `filePath` was renamed from between two commits (or a commit and the working tree
+// when `to` is undefined), if it was
+async function getRenameSource(git: SimpleGit, from: string, to: string | undefined, filePath: string): Promise<string | undefined> {
+  const renames = await git.diff([from, ...(to !== undefined ? [to] : []), "--name-status", "-M", "--diff-filter=R"]);
+  for (const line of renames.trim().split("\n")) {
+    const entry = parseNameStatusLine(line);
+    if (entry?.path === filePath) return entry.oldPath;
+  }
+  return undefined;
+}
+
+async function getSingleUnstagedFileDiff(git: SimpleGit, repoPath: string, filePath: string): Promise<FileDiff | null> {
+  if ((await getUntrackedPaths(git, ["--", filePath])).includes(filePath)) {
+    const oversized = await oversizedBlob(git, undefined, join(repoPath, filePath));
+    if (oversized !== null) return tooLargeDiff(filePath, "untracked", oversized, false);
+
+    const contents = await getWorkingFileContents(repoPath, filePath);
     return {
+      path: filePath,
+      status: "untracked",
+      additions: contents?.contents.split("\n").length || 0,
+      deletions: 0,
+      patch: createAddPatch(filePath, contents?.contents || ""),
+      viewed: false,
+      staged: false,
+      new_file: contents,
+    };
+  }
+
+  try {
+    const [rawEntries, nameStatus] = await Promise.all([
+      getRawEntries(git, ["--", filePath]),
+      git.diff(["--name-status", "--", filePath]),
+    ]);
+    if (!nameStatus.trim()) return null;
+    const fileStatus: FileDiff["status"] = nameStatus.startsWith("D") ? "deleted" : "modified";
+
+    const oversized = await oversizedBlob(git, rawEntries.get(filePath), fileStatus !== "deleted" ? join(repoPath, filePath) : undefined);
+    if (oversized !== null) return tooLargeDiff(filePath, fileStatus, oversized, false);
+
+    const patchDiff = await patchOutput(git, ["diff", ...contentArgs(filePath), "--", filePath]);
+    const { additions, deletions } = countChanges(patchDiff);
+    const fileDiff: FileDiff = {
       path: filePath,
       status: fileStatus,
       additions,
       deletions,
       patch: patchDiff,
       viewed: false,
-      old_file: fileStatus !== "added" ? await getFileContents(git, mergeBase, filePath) : undefined,
-      new_file: fileStatus !== "deleted" ? await getFileContents(git, "HEAD", filePath) : undefined,
+      staged: false,
+      old_file: await getStagedFileContents(git, filePath),
+      new_file: fileStatus !== "deleted" ? await getWorkingFileContents(repoPath, filePath) : undefined,
     };
+    await applySpecialModes(git, fileDiff, rawEntries, repoPath);
+    return fileDiff;
   } catch {
     return null;
   }
 }
 
 async function getSingleWorkingFileDiff(git: SimpleGit, repoPath: string, filePath: string): Promise<FileDiff | null> {
+  if ((await getUnmergedStages(git, ["--", filePath])).has(filePath)) {
+    return getConflictedWorkingFileDiff(git, repoPath, filePath);
+  }
+
   // Check if file is staged first
   const stagedDiff = await git.diff(["--cached", "--name-status", "--", filePath]);
   if (stagedDiff.trim()) {
     const [statusCode] = stagedDiff.trim().split("\t");
-    const patchDiff = await git.diff(["--cached", "--", filePath]);
-    const { additions, deletions } = countChanges(patchDiff);
-
     let fileStatus: FileDiff["status"] = "modified";
     if (statusCode?.startsWith("A")) fileStatus = "added";
     else if (statusCode?.startsWith("D")) fileStatus = "deleted";
     else if (statusCode?.startsWith("R")) fileStatus = "renamed";
 
-    return {
+    const stagedEntries = await getRawEntries(git, ["--cached", "--", filePath]);
+    const oversized = await oversizedBlob(git, stagedEntries.get(filePath));
+    if (oversized !== null) return tooLargeDiff(filePath, fileStatus, oversized, true);
+
+    const patchDiff = await patchOutput(git, ["diff", "--cached", ...contentArgs(filePath), "--", filePath]);
+    const { additions, deletions } = countChanges(patchDiff);
+
+    const fileDiff: FileDiff = {
       path: filePath,
       status: fileStatus,
       additions,
@@ -387,15 +1913,21 @@ async function getSingleWorkingFileDiff(git: SimpleGit, repoPath: string, filePa
       old_file: fileStatus !== "added" ? await getFileContents(git, "HEAD", filePath) : undefined,
       new_file: fileStatus !== "deleted" ? await getStagedFileContents(git, filePath) : undefined,
     };
+    await applySpecialModes(git, fileDiff, stagedEntries);
+    return fileDiff;
   }
 
   const status = await git.status();
 
   // Check if modified (unstaged)
   if (status.modified.includes(filePath)) {
-    const diff = await git.diff([filePath]);
+    const entries = await getRawEntries(git, ["--", filePath]);
+    const oversized = await oversizedBlob(git, entries.get(filePath), join(repoPath, filePath));
+    if (oversized !== null) return tooLargeDiff(filePath, "modified", oversized, false);
+
+    const diff = await patchOutput(git, ["diff", ...contentArgs(filePath), filePath]);
     const { additions, deletions } = countChanges(diff);
-    return {
+    const fileDiff: FileDiff = {
       path: filePath,
       status: "modified",
       additions,
@@ -406,10 +1938,15 @@ async function getSingleWorkingFileDiff(git: SimpleGit, repoPath: string, filePa
       old_file: await getFileContents(git, "HEAD", filePath),
       new_file: await getWorkingFileContents(repoPath, filePath),
     };
+    await applySpecialModes(git, fileDiff, entries, repoPath);
+    return fileDiff;
   }
 
   // Check if untracked
   if (status.not_added.includes(filePath)) {
+    const oversized = await oversizedBlob(git, undefined, join(repoPath, filePath));
+    if (oversized !== null) return tooLargeDiff(filePath, "untracked", oversized, false);
+
     const contents = await getWorkingFileContents(repoPath, filePath);
     const lines = contents?.contents.split("\n").length || 0;
     return {
@@ -426,6 +1963,9 @@ async function getSingleWorkingFileDiff(git: SimpleGit, repoPath: string, filePa
 
   // Check if deleted (unstaged)
   if (status.deleted.includes(filePath)) {
+    const oversized = await oversizedBlob(git, (await getRawEntries(git, ["--", filePath])).get(filePath));
+    if (oversized !== null) return tooLargeDiff(filePath, "deleted", oversized, false);
+
     const oldContents = await getFileContents(git, "HEAD", filePath);
     const lines = oldContents?.contents.split("\n").length || 0;
     return {
@@ -443,7 +1983,180 @@ async function getSingleWorkingFileDiff(git: SimpleGit, repoPath: string, filePa
   return null;
 }
 
+const SUBMODULE_MODE = "160000";
+const SYMLINK_MODE = "120000";
+const NULL_OID = /^0+$/;
+
+// One `git diff --raw` entry; oids are omitted when git reports the null oid
+export interface RawDiffEntry {
+  oldMode: string;
+  newMode: string;
+  oldOid?: string;
+  newOid?: string;
+}
+
+// Parse `git diff --raw --no-abbrev` output, keyed by (new) path
+export function parseRawDiff(raw: string): Map<string, RawDiffEntry> {
+  const entries = new Map<string, RawDiffEntry>();
+
+  for (const line of raw.split("\n")) {
+    if (!line.startsWith(":")) continue;
+    const [meta, ...paths] = line.split("\t");
+    const [oldMode, newMode, oldOid, newOid] = (meta ?? "").slice(1).split(" ");
+    const filePath = paths[paths.length - 1];
+    if (!filePath || !oldMode || !newMode) continue;
+
+    entries.set(filePath, {
+      oldMode,
+      newMode,
+      oldOid: oldOid && !NULL_OID.test(oldOid) ? oldOid : undefined,
+      newOid: newOid && !NULL_OID.test(newOid) ? newOid : undefined,
+    });
+  }
+
+  return entries;
+}
+
+async function getRawEntries(git: SimpleGit, args: string[]): Promise<Map<string, RawDiffEntry>> {
+  try {
+    return parseRawDiff(await git.diff(["--raw", "--no-abbrev", ...args]));
+  } catch {
+    return new Map();
+  }
+}
+
+// Annotate submodule pointer updates and symlink target changes, which have no
+// meaningful file contents to show. repoPath is needed to read working-tree links.
+async function applySpecialModes(
+  git: SimpleGit,
+  file: FileDiff,
+  entries: Map<string, RawDiffEntry>,
+  repoPath?: string
+): Promise<void> {
+  const entry = entries.get(file.path);
+  if (!entry) return;
+
+  if (entry.oldMode === SUBMODULE_MODE || entry.newMode === SUBMODULE_MODE) {
+    file.submodule = true;
+    file.submodule_old_commit = entry.oldMode === SUBMODULE_MODE ? entry.oldOid : undefined;
+    file.submodule_new_commit = entry.newMode === SUBMODULE_MODE ? entry.newOid : undefined;
+    file.old_file = undefined;
+    file.new_file = undefined;
+    return;
+  }
+
+  if (entry.oldMode === SYMLINK_MODE || entry.newMode === SYMLINK_MODE) {
+    const [oldTarget, newTarget] = await Promise.all([
+      entry.oldMode === SYMLINK_MODE && entry.oldOid ? readBlob(git, entry.oldOid) : undefined,
+      entry.newMode === SYMLINK_MODE ? readNewLinkTarget(git, file.path, entry.newOid, repoPath) : undefined,
+    ]);
+    file.symlink = true;
+    file.symlink_old_target = oldTarget;
+    file.symlink_new_target = newTarget;
+    file.old_file = undefined;
+    file.new_file = undefined;
+  }
+}
+
+async function readBlob(git: SimpleGit, oid: string): Promise<string | undefined> {
+  try {
+    return await git.raw(["cat-file", "-p", oid]);
+  } catch {
+    return undefined;
+  }
+}
+
+// The new side of an unstaged change has no blob yet, so read the link itself
+async function readNewLinkTarget(
+  git: SimpleGit,
+  filePath: string,
+  oid: string | undefined,
+  repoPath: string | undefined
+): Promise<string | undefined> {
+  if (oid) return readBlob(git, oid);
+  if (!repoPath) return undefined;
+  try {
+    return await readlink(join(repoPath, filePath));
+  } catch {
+    return undefined;
+  }
+}
+
+// Parse `git blame --porcelain` output into one entry per line
+export function parseBlamePorcelain(output: string): BlameLine[] {
+  const commits = new Map<string, { author: string; summary: string }>();
+  const lines: BlameLine[] = [];
+  let currentCommit = "";
+  let currentLine = 0;
+
+  for (const raw of output.split("\n")) {
+    if (raw.startsWith("\t")) {
+      const info = commits.get(currentCommit);
+      lines.push({
+        line: currentLine,
+        commit: currentCommit,
+        author: info?.author ?? "",
+        summary: info?.summary ?? "",
+      });
+      continue;
+    }
+
+    const header = raw.match(/^([0-9a-f]{40}) \d+ (\d+)/);
+    if (header?.[1] && header[2]) {
+      currentCommit = header[1];
+      currentLine = parseInt(header[2], 10);
+      if (!commits.has(currentCommit)) {
+        commits.set(currentCommit, { author: "", summary: "" });
+      }
+      continue;
+    }
+
+    const info = commits.get(currentCommit);
+    if (!info) continue;
+    if (raw.startsWith("author ")) info.author = raw.slice("author ".length);
+    else if (raw.startsWith("summary ")) info.summary = raw.slice("summary ".length);
+  }
+
+  return lines;
+}
+
 // Helper functions
+export interface NameStatusEntry {
+  path: string;
+  status: FileDiff["status"];
+  // Renames and copies: the source path and git's similarity score (100 = content unchanged)
+  oldPath?: string;
+  similarity?: number;
+}
+
+// One `git diff --name-status` line; renames and copies carry a score and both paths ("R100\told\tnew")
+export function parseNameStatusLine(line: string): NameStatusEntry | null {
+  const [code, ...paths] = line.split("\t");
+  if (!code || paths.length === 0) return null;
+
+  if ((code.startsWith("R") || code.startsWith("C")) && paths.length === 2) {
+    const [oldPath = "", path = ""] = paths;
+    const status = code.startsWith("R") ? "renamed" : "copied";
+    return { path, status, oldPath, similarity: parseInt(code.slice(1), 10) || 0 };
+  }
+
+  let status: FileDiff["status"] = "modified";
+  if (code.startsWith("A")) status = "added";
+  else if (code.startsWith("D")) status = "deleted";
+  return { path: paths.join("\t"), status };
+}
+
+// `--numstat` writes renames as "old => new" or "src/{a => b}/file"; keep the new path
+export function numstatPath(raw: string): string {
+  const brace = /^(.*)\{(.*) => (.*)\}(.*)$/.exec(raw);
+  if (brace) {
+    const [, prefix = "", , to = "", suffix = ""] = brace;
+    return `${prefix}${to}${suffix}`.replace(/\/{2,}/g, "/").replace(/^\//, "");
+  }
+  const arrow = raw.indexOf(" => ");
+  return arrow === -1 ? raw : raw.slice(arrow + 4);
+}
+
 function countChanges(patch: string): { additions: number; deletions: number } {
   let additions = 0;
   let deletions = 0;
@@ -472,6 +2185,31 @@ async function getFileContents(git: SimpleGit, ref: string, filePath: string): P
   }
 }
 
+// null when the path doesn't exist at ref (added/deleted files)
+async function getBlob(git: SimpleGit, ref: string, filePath: string): Promise<FileBlob | null> {
+  const object = `${ref}:${filePath}`;
+  try {
+    const size = parseInt((await git.raw(["cat-file", "-s", object])).trim(), 10);
+    return {
+      size,
+      read: async () => new Uint8Array(await git.showBuffer([object])),
+    };
+  } catch {
+    return null;
+  }
+}
+
+async function getWorkingBlob(repoPath: string, filePath: string): Promise<FileBlob | null> {
+  const file = Bun.file(join(repoPath, filePath));
+  if (!(await file.exists())) {
+    return null;
+  }
+  return {
+    size: file.size,
+    read: async () => new Uint8Array(await file.arrayBuffer()),
+  };
+}
+
 async function getWorkingFileContents(repoPath: string, filePath: string): Promise<FileContents | undefined> {
   try {
     const fullPath = join(repoPath, filePath);
@@ -503,38 +2241,161 @@ async function getStagedFileContents(git: SimpleGit, filePath: string): Promise<
 }
 
 function createAddPatch(filePath: string, contents: string): string {
-  const lines = contents.split("\n");
-  const patchLines = lines.map((line) => `+${line}`);
-  return `diff --git a/${filePath} b/${filePath}
+  return asPatchOutput(`diff --git a/${filePath} b/${filePath}
 new file mode 100644
---- /dev/null
-+++ b/${filePath}
-@@ -0,0 +1,${lines.length} @@
-${patchLines.join("\n")}`;
+${contents ? `--- /dev/null\n+++ b/${filePath}\n${createHunk("+", contents)}` : ""}`);
 }
 
 function createDeletePatch(filePath: string, contents: string): string {
-  const lines = contents.split("\n");
-  const patchLines = lines.map((line) => `-${line}`);
-  return `diff --git a/${filePath} b/${filePath}
+  return asPatchOutput(`diff --git a/${filePath} b/${filePath}
 deleted file mode 100644
---- a/${filePath}
-+++ /dev/null
-@@ -1,${lines.length} +0,0 @@
-${patchLines.join("\n")}`;
+${contents ? `--- a/${filePath}\n+++ /dev/null\n${createHunk("-", contents)}` : ""}`);
+}
+
+// One hunk adding or removing all of `contents`, marking a missing final newline the way git does
+function createHunk(origin: "+" | "-", contents: string): string {
+  const hasFinalNewline = contents.endsWith("\n");
+  const lines = (hasFinalNewline ? contents.slice(0, -1) : contents).split("\n");
+  const range = origin === "+" ? `-0,0 +1,${lines.length}` : `-1,${lines.length} +0,0`;
+  const body = lines.map((line) => `${origin}${line}\n`).join("");
+  return `@@ ${range} @@\n${body}${hasFinalNewline ? "" : `${NO_NEWLINE_MARKER}\n`}`;
+}
+
+// Parse `git log --format=%H%x00%s%x00%an%x00%ct` output
+export function parseRangeLog(output: string): RangeCommit[] {
+  const commits: RangeCommit[] = [];
+  for (const line of output.split("\n")) {
+    if (!line) continue;
+    const [hash = "", summary = "", author = "", timestamp = "0"] = line.split("\0");
+    commits.push({ hash, summary, author, timestamp: parseInt(timestamp, 10) });
+  }
+  return commits;
+}
+
+// Parse `git log --format=%H%x00%P%x00%s%x00%an%x00%ct` output
+export function parseGraphLog(output: string): GraphCommit[] {
+  const commits: GraphCommit[] = [];
+  for (const line of output.split("\n")) {
+    if (!line) continue;
+    const [hash = "", parents = "", summary = "", author = "", timestamp = "0"] = line.split("\0");
+    commits.push({ hash, parents: parents.split(" ").filter(Boolean), summary, author, timestamp: parseInt(timestamp, 10) });
+  }
+  return commits;
+}
+
+// Notes ref holding review state, shareable with `git push origin refs/notes/cerebro-review`
+export const REVIEW_NOTES_REF = "refs/notes/cerebro-review";
+
+// Parse a review note, treating anything unreadable (e.g. hand-edited) as empty
+export function parseReviewNote(raw: string): ReviewNote {
+  try {
+    const parsed = JSON.parse(raw) as Partial<ReviewNote>;
+    if (parsed && typeof parsed.viewed === "object" && parsed.viewed !== null) {
+      return { viewed: parsed.viewed };
+    }
+  } catch {
+    // fall through
+  }
+  return { viewed: {} };
 }
 
-// Check if a path is a git repository
+// Check if a path is a git repository (bare repositories included)
 export async function isGitRepo(path: string): Promise<boolean> {
   try {
     const git = simpleGit(path);
-    await git.status();
+    await git.revparse(["--git-dir"]);
     return true;
   } catch {
     return false;
   }
 }
 
+// File-level diff of two plain directories (no shared history needed), via `git diff --no-index`.
+// .git directories are always skipped; `isExcluded` gets paths relative to either root
+export async function diffDirectories(
+  oldDir: string,
+  newDir: string,
+  isExcluded: (path: string) => boolean = () => false
+): Promise<FileDiff[]> {
+  const list = async (dir: string) => {
+    const paths = new Set<string>();
+    for await (const path of new Glob("**/*").scan({ cwd: dir, dot: true, onlyFiles: true })) {
+      if (!path.split("/").includes(".git") && !isExcluded(path)) paths.add(path);
+    }
+    return paths;
+  };
+  const [oldPaths, newPaths] = await Promise.all([list(oldDir), list(newDir)]);
+
+  const files: FileDiff[] = [];
+  for (const path of [...new Set([...oldPaths, ...newPaths])].sort()) {
+    const oldPath = oldPaths.has(path) ? join(oldDir, path) : null;
+    const newPath = newPaths.has(path) ? join(newDir, path) : null;
+    if (oldPath && newPath && Buffer.from(await Bun.file(oldPath).bytes()).equals(await Bun.file(newPath).bytes())) {
+      continue;
+    }
+
+    const result = await Bun.$`git diff --no-index --no-color --no-ext-diff -- ${oldPath ?? "/dev/null"} ${newPath ?? "/dev/null"}`
+      .nothrow()
+      .quiet();
+    // Exit 1 just means the files differ
+    if (result.exitCode > 1) {
+      throw new Error(`git diff --no-index failed for ${path}: ${result.stderr.toString().trim()}`);
+    }
+    const patch = relabelNoIndexPatch(result.stdout.toString(), path, oldPath !== null, newPath !== null);
+    files.push({
+      path,
+      status: !oldPath ? "added" : !newPath ? "deleted" : "modified",
+      ...countChanges(patch),
+      patch,
+      viewed: false,
+    });
+  }
+  return files;
+}
+
+// --no-index headers name the files by their on-disk paths; point them at `path` like a repo diff
+function relabelNoIndexPatch(patch: string, path: string, hasOld: boolean, hasNew: boolean): string {
+  const oldLabel = hasOld ? `a/${path}` : "/dev/null";
+  const newLabel = hasNew ? `b/${path}` : "/dev/null";
+  const lines = patch.split("\n");
+  const headerEnd = lines.findIndex((line) => line.startsWith("@@"));
+  const header = lines.slice(0, headerEnd === -1 ? lines.length : headerEnd).map((line) => {
+    if (line.startsWith("diff --git ")) return `diff --git a/${path} b/${path}`;
+    if (line.startsWith("--- ")) return `--- ${oldLabel}`;
+    if (line.startsWith("+++ ")) return `+++ ${newLabel}`;
+    if (line.startsWith("Binary files ")) return `Binary files ${oldLabel} and ${newLabel} differ`;
+    return line;
+  });
+  return [...header, ...lines.slice(header.length)].join("\n");
+}
+
+// `git ls-tree -z` output -> path -> blob oid (submodules and trees are skipped)
+export function parseLsTree(output: string): Record<string, string> {
+  const result: Record<string, string> = {};
+  for (const entry of output.split("\0")) {
+    const tab = entry.indexOf("\t");
+    if (tab === -1) continue;
+    const [, type, oid] = entry.slice(0, tab).split(" ");
+    if (type === "blob" && oid) {
+      result[entry.slice(tab + 1)] = oid;
+    }
+  }
+  return result;
+}
+
+// Parse `base..head` / `base...head`; an empty side means HEAD, as in git. Null when malformed
+export function parseRange(spec: string): RevisionRange | null {
+  const match = /^([^.]*(?:\.(?!\.)[^.]*)*)(\.\.\.?)(.*)$/.exec(spec.trim());
+  if (!match) {
+    return null;
+  }
+  const [, base = "", dots, head = ""] = match;
+  if (head.includes("..") || (!base && !head) || base.endsWith(".") || head.startsWith(".")) {
+    return null;
+  }
+  return { base: base || "HEAD", head: head || "HEAD", threeDot: dots === "..." };
+}
+
 // Get repo name from path
 export function getRepoName(path: string): string {
   return basename(path);
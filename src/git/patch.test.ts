@@ -0,0 +1,259 @@
+import { describe, expect, it } from "bun:test";
+import {
+  addLineIds,
+  collapseContext,
+  detectMovedBlocks,
+  expandTabs,
+  parseCombinedPatch,
+  parsePatch,
+  splitPatchByFile,
+  toSplitView,
+} from "./patch";
+
+const patch = `diff --git a/a.ts b/a.ts
+--- a/a.ts
++++ b/a.ts
+@@ -1,4 +1,5 @@
+ const a = 1;
+-const b = 2;
+-const c = 3;
++const b = 20;
++const c = 30;
++const d = 40;
+ export { a };
+-// trailing`;
+
+describe("parsePatch", () => {
+  it("parses hunk ranges and numbers lines on each side", () => {
+    const hunks = parsePatch(patch);
+    expect(hunks.length).toBe(1);
+    const hunk = hunks[0]!;
+    expect(hunk.old_start).toBe(1);
+    expect(hunk.old_lines).toBe(4);
+    expect(hunk.new_start).toBe(1);
+    expect(hunk.new_lines).toBe(5);
+    expect(hunk.lines[0]).toEqual({ type: "context", content: "const a = 1;", old_line: 1, new_line: 1 });
+    expect(hunk.lines[1]).toEqual({ type: "delete", content: "const b = 2;", old_line: 2 });
+    expect(hunk.lines[3]).toEqual({ type: "add", content: "const b = 20;", new_line: 2 });
+  });
+
+  it("returns no hunks for an empty patch", () => {
+    expect(parsePatch("")).toEqual([]);
+  });
+
+  it("flags the line before a no-newline marker", () => {
+    const [hunk] = parsePatch(`@@ -1 +1 @@
+-last
+\\ No newline at end of file
++last
+`);
+    expect(hunk!.lines).toEqual([
+      { type: "delete", content: "last", old_line: 1, no_newline: true },
+      { type: "add", content: "last", new_line: 1 },
+    ]);
+  });
+});
+
+describe("hunk and line ids", () => {
+  it("keeps a hunk's id when only line numbers and context move", () => {
+    const [hunk] = parsePatch(patch);
+    const [shifted] = parsePatch(patch.replace("@@ -1,4 +1,5 @@", "@@ -40,4 +52,5 @@").replace(" const a = 1;", " const a = 2;"));
+    expect(hunk!.id).toMatch(/^[0-9a-f]{16}$/);
+    expect(shifted!.id).toBe(hunk!.id);
+    expect(parsePatch(patch.replace("+const d = 40;", "+const d = 41;"))[0]!.id).not.toBe(hunk!.id);
+  });
+
+  it("gives repeated changed lines distinct ids", () => {
+    const hunks = addLineIds(parsePatch(`@@ -1,0 +1,2 @@
++}
++}
+`));
+    const [first, second] = hunks[0]!.lines;
+    expect(first!.id).toBeDefined();
+    expect(first!.id).not.toBe(second!.id);
+    expect(addLineIds(parsePatch(`@@ -9,0 +30,1 @@\n+}\n`))[0]!.lines[0]!.id).toBe(first!.id);
+  });
+});
+
+describe("toSplitView", () => {
+  it("pairs deletions with additions and pads the shorter side", () => {
+    const [hunk] = toSplitView(patch);
+    const rows = hunk!.rows;
+
+    expect(rows.length).toBe(6);
+    expect(rows[0]?.left?.type).toBe("context");
+    expect(rows[1]?.left?.content).toBe("const b = 2;");
+    expect(rows[1]?.right?.content).toBe("const b = 20;");
+    expect(rows[3]?.left).toBeUndefined();
+    expect(rows[3]?.right?.content).toBe("const d = 40;");
+    expect(rows[5]?.left?.content).toBe("// trailing");
+    expect(rows[5]?.right).toBeUndefined();
+  });
+});
+
+describe("collapseContext", () => {
+  const context = Array.from({ length: 20 }, (_, i) => ` line ${i + 2}`);
+  const longPatch = ["@@ -1,22 +1,22 @@", "-old", "+new", ...context, "-old end", "+new end"].join("\n");
+
+  it("replaces long unchanged runs with a marker keeping edge lines", () => {
+    const [hunk] = parsePatch(longPatch);
+    const collapsed = collapseContext(hunk!, 10);
+    const marker = collapsed.lines.find((l) => l.type === "collapsed");
+
+    expect(marker?.hidden_count).toBe(14);
+    expect(marker?.old_line).toBe(5);
+    expect(marker?.new_line).toBe(5);
+    expect(marker?.content).toBe("… 14 unchanged lines …");
+    // 2 changes + 3 edge + marker + 3 edge + 2 changes
+    expect(collapsed.lines.length).toBe(11);
+  });
+
+  it("leaves runs at or below the threshold alone", () => {
+    const [hunk] = parsePatch(longPatch);
+    expect(collapseContext(hunk!, 20).lines.length).toBe(hunk!.lines.length);
+  });
+
+  it("carries markers through to the split view", () => {
+    const [hunk] = toSplitView(longPatch, 10);
+    const markerRow = hunk!.rows.find((r) => r.left?.type === "collapsed");
+    expect(markerRow?.right?.type).toBe("collapsed");
+  });
+});
+
+describe("moved block detection", () => {
+  const multiFilePatch = `diff --git a/src/a.ts b/src/a.ts
+index 1111111..2222222 100644
+--- a/src/a.ts
++++ b/src/a.ts
+@@ -1,5 +1,1 @@
+ keep();
+-function helper() {
+-  return compute(1, 2);
+-}
+-export { helper };
+diff --git a/src/b.ts b/src/b.ts
+index 3333333..4444444 100644
+--- a/src/b.ts
++++ b/src/b.ts
+@@ -10,1 +10,5 @@
+ other();
++  function helper() {
++    return compute(1, 2);
++  }
++  export { helper };
+`;
+
+  it("splitPatchByFile keys sections by path", () => {
+    const patches = splitPatchByFile(multiFilePatch);
+    expect([...patches.keys()]).toEqual(["src/a.ts", "src/b.ts"]);
+    expect(patches.get("src/b.ts")).toContain("+  function helper() {");
+  });
+
+  it("pairs deleted and added blocks across files ignoring indentation", () => {
+    const moves = detectMovedBlocks(splitPatchByFile(multiFilePatch));
+    expect(moves.get("src/a.ts")).toEqual([
+      { direction: "moved_to", line: 2, lines: 4, other_path: "src/b.ts", other_line: 11 },
+    ]);
+    expect(moves.get("src/b.ts")).toEqual([
+      { direction: "moved_from", line: 11, lines: 4, other_path: "src/a.ts", other_line: 2 },
+    ]);
+  });
+
+  it("ignores same-file rewrites and short blocks", () => {
+    const patch = `diff --git a/x.ts b/x.ts
+--- a/x.ts
++++ b/x.ts
+@@ -1,3 +1,3 @@
+-a();
+-b();
+-c();
++a();
++b();
++c();
+`;
+    expect(detectMovedBlocks(splitPatchByFile(patch)).size).toBe(0);
+  });
+});
+
+describe("whitespace-only hunks", () => {
+  it("flags re-indentation and blank-line churn", () => {
+    const [hunk] = parsePatch(`@@ -1,2 +1,3 @@
+-if (a) {
+-return 1;
++if (a)  {
++
++    return 1;`);
+    expect(hunk!.whitespace_only).toBe(true);
+    expect(toSplitView(`@@ -1 +1 @@
+-  x
++    x`)[0]!.whitespace_only).toBe(true);
+  });
+
+  it("does not flag content changes or context-only hunks", () => {
+    expect(parsePatch(patch)[0]!.whitespace_only).toBe(false);
+    expect(parsePatch(`@@ -1 +1 @@
+ same`)[0]!.whitespace_only).toBe(false);
+  });
+});
+
+describe("expandTabs", () => {
+  it("expands tabs to the next tab stop", () => {
+    expect(expandTabs("\tfoo", 4)).toBe("    foo");
+    expect(expandTabs("ab\tc", 4)).toBe("ab  c");
+    expect(expandTabs("abcd\te", 4)).toBe("abcd    e");
+  });
+
+  it("leaves lines without tabs alone", () => {
+    expect(expandTabs("no tabs", 2)).toBe("no tabs");
+  });
+
+  it("applies to split view content", () => {
+    const tabbed = `@@ -1 +1 @@
+-\told
++\tnew`;
+    const [hunk] = toSplitView(tabbed, undefined, 2);
+    expect(hunk!.rows[0]!.left?.content).toBe("  old");
+    expect(hunk!.rows[0]!.right?.content).toBe("  new");
+  });
+});
+
+describe("parseCombinedPatch", () => {
+  it("counts lines per parent column and flags lines in no parent", () => {
+    const [file, ...rest] = parseCombinedPatch(`diff --cc f.txt
+index 6dcce7d,7be73ce..4b15bd9
+--- a/f.txt
++++ b/f.txt
+@@@ -1,3 -1,3 +1,4 @@@
+  a
+- b
++ B
+ -c
+ +C
+++evil
+`);
+    expect(rest).toEqual([]);
+    expect(file).toMatchObject({ path: "f.txt", status: "modified", additions: 3, deletions: 2, resolution_lines: [4] });
+  });
+
+  it("reads added and deleted files from the mode lines", () => {
+    const files = parseCombinedPatch(`diff --cc added
+index 0000000,0000000..3e75765
+new file mode 100644
+--- /dev/null
++++ b/added
+@@@ -1,0 -1,0 +1,1 @@@
+++new
+diff --cc gone
+index 587be6b,587be6b..0000000
+deleted file mode 100644,100644
+--- a/gone
++++ /dev/null
+@@@ -1,1 -1,1 +1,0 @@@
+--x
+`);
+    expect(files.map((f) => [f.path, f.status])).toEqual([
+      ["added", "added"],
+      ["gone", "deleted"],
+    ]);
+  });
+});
@@ -0,0 +1,357 @@
+/**
+ * Unified patch parsing and split (side-by-side) alignment
+ */
+import type { DiffHunk, DiffLine, MovedBlock, SplitHunk, SplitRow } from "../types";
+
+const HUNK_HEADER = /^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@/;
+
+// Follows the last line of a side that doesn't end in a newline
+export const NO_NEWLINE_MARKER = "\\ No newline at end of file";
+
+// Parse the hunks of a single-file unified patch
+export function parsePatch(patch: string): DiffHunk[] {
+  const hunks: DiffHunk[] = [];
+  let current: DiffHunk | null = null;
+  let oldLine = 0;
+  let newLine = 0;
+
+  for (const line of patch.split("\n")) {
+    const header = line.match(HUNK_HEADER);
+    if (header) {
+      oldLine = parseInt(header[1] ?? "0", 10);
+      newLine = parseInt(header[3] ?? "0", 10);
+      current = {
+        header: line,
+        old_start: oldLine,
+        old_lines: header[2] !== undefined ? parseInt(header[2], 10) : 1,
+        new_start: newLine,
+        new_lines: header[4] !== undefined ? parseInt(header[4], 10) : 1,
+        lines: [],
+        whitespace_only: false,
+        id: "",
+      };
+      hunks.push(current);
+      continue;
+    }
+
+    if (!current) continue;
+
+    if (line.startsWith("+")) {
+      current.lines.push({ type: "add", content: line.slice(1), new_line: newLine++ });
+    } else if (line.startsWith("-")) {
+      current.lines.push({ type: "delete", content: line.slice(1), old_line: oldLine++ });
+    } else if (line.startsWith(" ")) {
+      current.lines.push({ type: "context", content: line.slice(1), old_line: oldLine++, new_line: newLine++ });
+    } else if (line.startsWith("\\")) {
+      // Applies to the line before it, so a change that only adds or drops the final newline still shows
+      const previous = current.lines[current.lines.length - 1];
+      if (previous) previous.no_newline = true;
+    }
+  }
+
+  for (const hunk of hunks) {
+    hunk.whitespace_only = isWhitespaceOnly(hunk.lines);
+    hunk.id = hunkId(hunk.lines);
+  }
+  return hunks;
+}
+
+const ANCHOR_LENGTH = 16;
+
+function anchorHash(text: string): string {
+  const hasher = new Bun.CryptoHasher("sha256");
+  hasher.update(text);
+  return hasher.digest("hex").slice(0, ANCHOR_LENGTH);
+}
+
+// Hunk anchor: the first 16 hex digits of SHA-256 over its deleted and added lines, in order,
+// each as "-<content>\n" / "+<content>\n". Line numbers, context lines and the header are left
+// out, so the id survives edits elsewhere in the file and small changes to the surroundings
+export function hunkId(lines: DiffLine[]): string {
+  return anchorHash(
+    lines
+      .filter((line) => line.type === "add" || line.type === "delete")
+      .map((line) => `${line.type === "add" ? "+" : "-"}${line.content}\n`)
+      .join("")
+  );
+}
+
+// Line anchor: SHA-256 (16 hex digits) of "<+|->\0<content>\0<n>", n counting earlier identical
+// changed lines in the same file's patch, so repeated lines (e.g. "}") get distinct ids that
+// still don't depend on line numbers. Sets `id` on added and deleted lines in place
+export function addLineIds(hunks: DiffHunk[]): DiffHunk[] {
+  const seen = new Map<string, number>();
+  for (const line of hunks.flatMap((hunk) => hunk.lines)) {
+    if (line.type !== "add" && line.type !== "delete") continue;
+    const key = `${line.type === "add" ? "+" : "-"}\0${line.content}`;
+    const n = seen.get(key) ?? 0;
+    seen.set(key, n + 1);
+    line.id = anchorHash(`${key}\0${n}`);
+  }
+  return hunks;
+}
+
+// True when a hunk changes something but the removed and added lines match once
+// whitespace (including blank lines) is ignored - e.g. pure re-indentation
+export function isWhitespaceOnly(lines: DiffLine[]): boolean {
+  const strip = (type: DiffLine["type"]) =>
+    lines
+      .filter((line) => line.type === type)
+      .map((line) => line.content.replace(/\s+/g, ""))
+      .filter((content) => content !== "");
+
+  const hasChanges = lines.some((line) => line.type === "add" || line.type === "delete");
+  const deleted = strip("delete");
+  const added = strip("add");
+  return hasChanges && deleted.length === added.length && deleted.every((content, i) => content === added[i]);
+}
+
+// Context lines kept next to a collapsed run so changes keep some surroundings
+const COLLAPSE_EDGE = 3;
+
+// Replace runs of more than `threshold` unchanged lines with a single "collapsed"
+// marker. The marker carries the hidden range (old_line/new_line + hidden_count)
+// so clients can fetch it on demand.
+export function collapseContext(hunk: DiffHunk, threshold: number): DiffHunk {
+  const lines: DiffLine[] = [];
+  let run: DiffLine[] = [];
+
+  const flush = () => {
+    const hidden = run.length - COLLAPSE_EDGE * 2;
+    if (run.length > threshold && hidden > 0) {
+      const first = run[COLLAPSE_EDGE]!;
+      lines.push(...run.slice(0, COLLAPSE_EDGE));
+      lines.push({
+        type: "collapsed",
+        content: `… ${hidden} unchanged lines …`,
+        old_line: first.old_line,
+        new_line: first.new_line,
+        hidden_count: hidden,
+      });
+      lines.push(...run.slice(-COLLAPSE_EDGE));
+    } else {
+      lines.push(...run);
+    }
+    run = [];
+  };
+
+  for (const line of hunk.lines) {
+    if (line.type === "context") {
+      run.push(line);
+    } else {
+      flush();
+      lines.push(line);
+    }
+  }
+  flush();
+
+  return { ...hunk, lines };
+}
+
+const anchor = (line: DiffLine) => (line.id !== undefined ? { id: line.id } : {});
+
+// Align a hunk into left/right rows: context (and collapsed markers) on both
+// sides, each run of deletions paired line-by-line with the additions that follow it
+export function toSplitHunk(hunk: DiffHunk): SplitHunk {
+  const rows: SplitRow[] = [];
+  let deletions: DiffLine[] = [];
+  let additions: DiffLine[] = [];
+
+  const flush = () => {
+    const count = Math.max(deletions.length, additions.length);
+    for (let i = 0; i < count; i++) {
+      const left = deletions[i];
+      const right = additions[i];
+      rows.push({
+        left: left ? { line: left.old_line ?? 0, content: left.content, type: "delete", ...anchor(left) } : undefined,
+        right: right ? { line: right.new_line ?? 0, content: right.content, type: "add", ...anchor(right) } : undefined,
+      });
+    }
+    deletions = [];
+    additions = [];
+  };
+
+  for (const line of hunk.lines) {
+    if (line.type === "delete") {
+      // A deletion after additions starts a new change block
+      if (additions.length > 0) flush();
+      deletions.push(line);
+    } else if (line.type === "add") {
+      additions.push(line);
+    } else {
+      flush();
+      rows.push({
+        left: { line: line.old_line ?? 0, content: line.content, type: line.type },
+        right: { line: line.new_line ?? 0, content: line.content, type: line.type },
+      });
+    }
+  }
+  flush();
+
+  return { header: hunk.header, rows, whitespace_only: hunk.whitespace_only, id: hunk.id };
+}
+
+// Expand tabs to spaces, advancing to the next multiple of `width` columns
+export function expandTabs(content: string, width: number): string {
+  if (!content.includes("\t")) return content;
+  let out = "";
+  for (const char of content) {
+    if (char === "\t") {
+      out += " ".repeat(width - (out.length % width));
+    } else {
+      out += char;
+    }
+  }
+  return out;
+}
+
+// Tab-expanded copy of a hunk for display; the raw patch is left untouched
+export function expandHunkTabs(hunk: DiffHunk, width: number): DiffHunk {
+  return {
+    ...hunk,
+    lines: hunk.lines.map((line) => ({ ...line, content: expandTabs(line.content, width) })),
+  };
+}
+
+export function toSplitView(patch: string, collapseThreshold?: number, tabWidth?: number, lineIds = false): SplitHunk[] {
+  const hunks = parsePatch(patch);
+  return (lineIds ? addLineIds(hunks) : hunks)
+    .map((hunk) => (tabWidth ? expandHunkTabs(hunk, tabWidth) : hunk))
+    .map((hunk) => (collapseThreshold ? collapseContext(hunk, collapseThreshold) : hunk))
+    .map(toSplitHunk);
+}
+
+// Split a multi-file patch into per-file patches keyed by path (new path, or old path for deletions)
+export function splitPatchByFile(patch: string): Map<string, string> {
+  const patches = new Map<string, string>();
+  const sections = patch.split(/^(?=diff --git )/m).filter((section) => section.startsWith("diff --git "));
+
+  for (const section of sections) {
+    const newPath = section.match(/^\+\+\+ b\/(.+)$/m)?.[1];
+    const oldPath = section.match(/^--- a\/(.+)$/m)?.[1];
+    const headerPath = section.match(/^diff --git a\/.+ b\/(.+)$/m)?.[1];
+    const filePath = newPath ?? oldPath ?? headerPath;
+    if (filePath) patches.set(filePath, section);
+  }
+
+  return patches;
+}
+
+// One file of a combined (--cc) merge diff
+export interface CombinedFile {
+  path: string;
+  // From the section's mode lines: added or deleted by the merge itself, else modified
+  status: "added" | "deleted" | "modified";
+  patch: string;
+  // Lines differing from at least one parent
+  additions: number;
+  deletions: number;
+  // Result lines found in no parent (+ in every column): edits made while resolving the merge
+  resolution_lines: number[];
+}
+
+const COMBINED_HUNK_HEADER = /^(@@@+) .*?\+(\d+)(?:,\d+)? @@@+/;
+
+// Parse `git show --cc` output. Each line has one prefix column per parent
+export function parseCombinedPatch(patch: string): CombinedFile[] {
+  const files: CombinedFile[] = [];
+  const sections = patch.split(/^(?=diff --cc )/m).filter((section) => section.startsWith("diff --cc "));
+
+  for (const section of sections) {
+    const path = section.slice("diff --cc ".length, section.indexOf("\n")).trim();
+    const status = /^new file mode /m.test(section) ? "added" : /^deleted file mode /m.test(section) ? "deleted" : "modified";
+    const file: CombinedFile = { path, status, patch: section, additions: 0, deletions: 0, resolution_lines: [] };
+    let columns = 0;
+    let newLine = 0;
+
+    for (const line of section.split("\n")) {
+      const header = line.match(COMBINED_HUNK_HEADER);
+      if (header) {
+        columns = (header[1]?.length ?? 1) - 1;
+        newLine = parseInt(header[2] ?? "0", 10);
+        continue;
+      }
+      if (columns === 0 || line.startsWith("\\") || line.length < columns) continue;
+
+      const prefix = line.slice(0, columns);
+      if (prefix.includes("-")) {
+        // Only in a parent, not in the result
+        file.deletions++;
+        continue;
+      }
+      if (prefix.includes("+")) file.additions++;
+      if (/^\++$/.test(prefix)) file.resolution_lines.push(newLine);
+      newLine++;
+    }
+    files.push(file);
+  }
+
+  return files;
+}
+
+// Blocks shorter than this are too likely to match by coincidence (braces, imports)
+const MIN_MOVED_LINES = 3;
+
+interface ChangeBlock {
+  path: string;
+  line: number;
+  lines: number;
+}
+
+// Contiguous runs of added or deleted lines, keyed by their whitespace-insensitive content
+function collectBlocks(path: string, patch: string, type: "add" | "delete", into: Map<string, ChangeBlock[]>): void {
+  for (const hunk of parsePatch(patch)) {
+    let run: DiffLine[] = [];
+    const flush = () => {
+      const meaningful = run.filter((l) => l.content.trim() !== "");
+      if (meaningful.length >= MIN_MOVED_LINES) {
+        const key = meaningful.map((l) => l.content.trim()).join("\n");
+        const first = run[0]!;
+        const block = { path, line: (type === "add" ? first.new_line : first.old_line) ?? 0, lines: run.length };
+        into.set(key, [...(into.get(key) ?? []), block]);
+      }
+      run = [];
+    };
+
+    for (const line of hunk.lines) {
+      if (line.type === type) {
+        run.push(line);
+      } else if (line.type !== (type === "add" ? "delete" : "add")) {
+        // Context ends a block; the opposite change type is interleaved and skipped
+        flush();
+      }
+    }
+    flush();
+  }
+}
+
+// Find blocks deleted from one file and added verbatim (ignoring indentation) to another
+export function detectMovedBlocks(patches: Map<string, string>): Map<string, MovedBlock[]> {
+  const deleted = new Map<string, ChangeBlock[]>();
+  const added = new Map<string, ChangeBlock[]>();
+  for (const [path, patch] of patches) {
+    collectBlocks(path, patch, "delete", deleted);
+    collectBlocks(path, patch, "add", added);
+  }
+
+  const moves = new Map<string, MovedBlock[]>();
+  const push = (path: string, block: MovedBlock) => moves.set(path, [...(moves.get(path) ?? []), block]);
+
+  for (const [key, sources] of deleted) {
+    const targets = added.get(key);
+    if (!targets) continue;
+
+    // Pair in order; only cross-file pairs count as moves
+    const usedTargets = new Set<ChangeBlock>();
+    for (const source of sources) {
+      const target = targets.find((t) => t.path !== source.path && !usedTargets.has(t));
+      if (!target) continue;
+      usedTargets.add(target);
+
+      push(source.path, { direction: "moved_to", line: source.line, lines: source.lines, other_path: target.path, other_line: target.line });
+      push(target.path, { direction: "moved_from", line: target.line, lines: target.lines, other_path: source.path, other_line: source.line });
+    }
+  }
+
+  return moves;
+}
@@ -0,0 +1,49 @@
+import { mkdtempSync, readdirSync, rmSync, utimesSync } from "fs";
+import { tmpdir } from "os";
+import { join } from "path";
+import { afterAll, describe, expect, it } from "bun:test";
+import { createPatchCache, patchCacheKey } from "./patchCache";
+
+const tempDir = mkdtempSync(join(tmpdir(), "cerebro-patch-cache-test-"));
+
+afterAll(() => {
+  rmSync(tempDir, { recursive: true, force: true });
+});
+
+describe("patchCacheKey", () => {
+  it("changes with either blob and the options", () => {
+    const key = patchCacheKey("a", "b", "src/x.ts");
+    expect(key).toMatch(/^[0-9a-f]{64}$/);
+    expect(patchCacheKey("a", "b", "src/x.ts")).toBe(key);
+    expect(patchCacheKey("a", "c", "src/x.ts")).not.toBe(key);
+    expect(patchCacheKey(undefined, "b", "src/x.ts")).not.toBe(key);
+    expect(patchCacheKey("a", "b", "src/y.ts")).not.toBe(key);
+  });
+});
+
+describe("createPatchCache", () => {
+  it("round-trips patches and misses unknown keys", async () => {
+    const cache = createPatchCache(join(tempDir, "roundtrip"), 1024);
+    expect(await cache.get("missing")).toBeNull();
+    await cache.set("k", "diff --git a/x b/x\n");
+    expect(await cache.get("k")).toBe("diff --git a/x b/x\n");
+    await cache.set("empty", "");
+    expect(await cache.get("empty")).toBe("");
+  });
+
+  it("evicts the least recently used entries past the limit", async () => {
+    const dir = join(tempDir, "evict");
+    const cache = createPatchCache(dir, 250);
+    await cache.set("old", "x".repeat(100));
+    await cache.set("used", "y".repeat(100));
+    // Make "old" the stalest, then touch "used" again
+    utimesSync(join(dir, "old"), new Date(1000), new Date(1000));
+    utimesSync(join(dir, "used"), new Date(2000), new Date(2000));
+    expect(await cache.get("used")).not.toBeNull();
+
+    await cache.set("new", "z".repeat(100));
+    // Eviction runs in the background
+    for (let i = 0; i < 100 && readdirSync(dir).length > 2; i++) await Bun.sleep(10);
+    expect(readdirSync(dir).sort()).toEqual(["new", "used"]);
+  });
+});
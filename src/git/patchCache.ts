@@ -0,0 +1,88 @@
+/**
+ * On-disk cache of generated file patches, keyed by the blob oids on each side,
+ * so a file's patch is computed once per content change rather than per request.
+ * Least recently used entries are evicted once the cache outgrows its limit.
+ */
+import { mkdir, readdir, stat, unlink, utimes } from "fs/promises";
+import { join } from "path";
+
+export const DEFAULT_PATCH_CACHE_MB = 64;
+
+export interface PatchCache {
+  get(key: string): Promise<string | null>;
+  set(key: string, patch: string): Promise<void>;
+}
+
+// The patch header names the file, so the path goes into `options` along with any diff flags
+export function patchCacheKey(oldOid: string | undefined, newOid: string | undefined, options: string): string {
+  const hasher = new Bun.CryptoHasher("sha256");
+  hasher.update(`${oldOid ?? "-"}\0${newOid ?? "-"}\0${options}`);
+  return hasher.digest("hex");
+}
+
+// Failures (unwritable dir, entries evicted mid-read) are treated as misses. Disk work is async
+// and eviction runs in the background, so a request never waits on a directory scan
+export function createPatchCache(dir: string, maxBytes: number): PatchCache {
+  // Total bytes on disk, scanned on the first write
+  let size: number | null = null;
+  let evicting = false;
+
+  // Entries removed while listing are skipped
+  const listEntries = async () => {
+    const entries = await Promise.all(
+      (await readdir(dir)).map(async (name) => {
+        const path = join(dir, name);
+        const info = await stat(path).catch(() => null);
+        return info ? { path, size: info.size, usedAt: info.mtimeMs } : null;
+      })
+    );
+    return entries.filter((entry) => entry !== null);
+  };
+
+  // Trim to 80% of the limit so a full cache doesn't evict on every write
+  const evict = async () => {
+    evicting = true;
+    try {
+      const entries = (await listEntries()).sort((a, b) => a.usedAt - b.usedAt);
+      let total = entries.reduce((sum, entry) => sum + entry.size, 0);
+      for (const entry of entries) {
+        if (total <= maxBytes * 0.8) break;
+        await unlink(entry.path).catch(() => {});
+        total -= entry.size;
+      }
+      size = total;
+    } catch {
+      // Best effort, like the writes
+    } finally {
+      evicting = false;
+    }
+  };
+
+  return {
+    async get(key) {
+      const path = join(dir, key);
+      try {
+        const patch = await Bun.file(path).text();
+        // mtime doubles as the last-used time for eviction
+        const now = new Date();
+        void utimes(path, now, now).catch(() => {});
+        return patch;
+      } catch {
+        return null;
+      }
+    },
+
+    async set(key, patch) {
+      try {
+        await mkdir(dir, { recursive: true });
+        if (size === null) {
+          size = (await listEntries()).reduce((total, entry) => total + entry.size, 0);
+        }
+        size += await Bun.write(join(dir, key), patch);
+        if (size > maxBytes && !evicting) void evict();
+      } catch {
+        // Caching is best effort
+      }
+    },
+  };
+}
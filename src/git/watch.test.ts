@@ -0,0 +1,40 @@
+import { mkdtempSync, rmSync, writeFileSync } from "fs";
+import { tmpdir } from "os";
+import { join } from "path";
+import { afterAll, describe, expect, it } from "bun:test";
+import { isGitStateChange, isWorktreeChange, watchDirectories } from "./watch";
+
+const tempDir = mkdtempSync(join(tmpdir(), "cerebro-watch-test-"));
+
+afterAll(() => {
+  rmSync(tempDir, { recursive: true, force: true });
+});
+
+describe("watch filters", () => {
+  it("keeps worktree edits and the git state a diff depends on", () => {
+    expect(isWorktreeChange("src/main.ts")).toBe(true);
+    expect(isWorktreeChange(".git/HEAD")).toBe(true);
+    expect(isWorktreeChange(".git/index")).toBe(true);
+    expect(isWorktreeChange(".git/refs/heads/feature")).toBe(true);
+    expect(isWorktreeChange(".git/index.lock")).toBe(false);
+    expect(isWorktreeChange(".git/objects/ab/cdef")).toBe(false);
+    expect(isWorktreeChange(".git/logs/HEAD")).toBe(false);
+    expect(isGitStateChange("packed-refs")).toBe(true);
+    expect(isGitStateChange("FETCH_HEAD")).toBe(false);
+  });
+});
+
+describe("watchDirectories", () => {
+  it("runs once after a burst of changes", async () => {
+    let runs = 0;
+    const stop = watchDirectories([{ dir: tempDir }], () => runs++, 50);
+    for (let i = 0; i < 5; i++) writeFileSync(join(tempDir, `file-${i}.txt`), `${i}\n`);
+    await Bun.sleep(300);
+    stop();
+    expect(runs).toBe(1);
+
+    writeFileSync(join(tempDir, "after.txt"), "stopped\n");
+    await Bun.sleep(150);
+    expect(runs).toBe(1);
+  });
+});
@@ -0,0 +1,61 @@
+/**
+ * Filesystem watching for --watch: reruns a command when the worktree, index
+ * or HEAD of a repository (or a plain directory) changes.
+ */
+import { watch, type FSWatcher } from "fs";
+import { relative, resolve, sep } from "path";
+
+export const WATCH_DEBOUNCE_MS = 200;
+
+// What a diff depends on inside the git dir; objects, logs and lock files churn without changing it
+const GIT_STATE = /^(HEAD|ORIG_HEAD|index|packed-refs|refs\/.*)$/;
+
+// `path` is relative to the git dir
+export function isGitStateChange(path: string): boolean {
+  const normalized = path.split(sep).join("/");
+  return !normalized.endsWith(".lock") && GIT_STATE.test(normalized);
+}
+
+// `path` is relative to the worktree root, whose .git may be the git dir itself
+export function isWorktreeChange(path: string): boolean {
+  const normalized = path.split(sep).join("/");
+  if (normalized === ".git") return false;
+  if (normalized.startsWith(".git/")) return isGitStateChange(normalized.slice(".git/".length));
+  return true;
+}
+
+export interface WatchTarget {
+  dir: string;
+  // Relative path of a changed entry; false skips it
+  filter?: (path: string) => boolean;
+}
+
+// Calls `onChange` once things go quiet after a burst of changes (a checkout touches many
+// files at once). Returns a function that stops watching
+export function watchDirectories(targets: WatchTarget[], onChange: () => void, debounceMs = WATCH_DEBOUNCE_MS): () => void {
+  let timer: ReturnType<typeof setTimeout> | undefined;
+  const schedule = () => {
+    clearTimeout(timer);
+    timer = setTimeout(onChange, debounceMs);
+  };
+
+  const watchers: FSWatcher[] = targets.map(({ dir, filter }) =>
+    watch(dir, { recursive: true }, (_event, filename) => {
+      // Some platforms omit the name; rerun rather than miss a change
+      if (!filename || !filter || filter(filename.toString())) schedule();
+    })
+  );
+
+  return () => {
+    clearTimeout(timer);
+    for (const watcher of watchers) watcher.close();
+  };
+}
+
+// A repository's worktree and, for linked worktrees whose git dir lives elsewhere, that git dir
+export function watchRepository(repoPath: string, gitDir: string, onChange: () => void, debounceMs?: number): () => void {
+  const targets: WatchTarget[] = [{ dir: repoPath, filter: isWorktreeChange }];
+  const insideRepo = relative(resolve(repoPath), resolve(gitDir)) === ".git";
+  if (!insideRepo) targets.push({ dir: gitDir, filter: isGitStateChange });
+  return watchDirectories(targets, onChange, debounceMs);
+}
@@ -24,6 +24,22 @@ export const FilePathRequestSchema = z.object({
   file_path: z.string().min(1, "File path is required"),
 });
 
+// scope: "branch" keeps the file viewed on later commits of the branch
+export const MarkViewedRequestSchema = z.object({
+  file_path: z.string().min(1, "File path is required"),
+  scope: z.enum(["commit", "branch"]).optional(),
+});
+
+export const CollapseRequestSchema = z.object({
+  file_path: z.string().min(1, "File path is required"),
+  collapsed: z.boolean(),
+});
+
+export const PositionRequestSchema = z.object({
+  file_path: z.string().min(1, "File path is required"),
+  line: z.number().int().positive().optional(),
+});
+
 // =============================================================================
 // Git Operations Schemas
 // =============================================================================
@@ -32,6 +48,15 @@ export const CommitRequestSchema = z.object({
   message: z.string().min(1, "Commit message is required"),
 });
 
+// =============================================================================
+// Editor Schemas
+// =============================================================================
+
+export const OpenFileRequestSchema = z.object({
+  file_path: z.string().min(1, "File path is required"),
+  line: z.number().int().positive().optional(),
+});
+
 // =============================================================================
 // Comments Schemas
 // =============================================================================
@@ -40,6 +65,8 @@ export const AddCommentRequestSchema = z.object({
   file_path: z.string().min(1, "File path is required"),
   line_number: z.number().int().positive().optional(),
   text: z.string().min(1, "Comment text is required"),
+  // Defaults to --reviewer, then git config user.name
+  author: z.string().min(1).optional(),
 });
 
 export const ResolveCommentRequestSchema = z.object({
@@ -65,6 +92,49 @@ export const DismissNoteRequestSchema = z.object({
   dismissed_by: z.string().optional(),
 });
 
+// =============================================================================
+// Config Schemas
+// =============================================================================
+
+export const ShortcutsRequestSchema = z.object({
+  // action -> key; merged over the saved bindings
+  shortcuts: z.record(z.string(), z.string().min(1, "Shortcut key is required")),
+});
+
+export const PreferencesRequestSchema = z.object({
+  // key -> any JSON value; null removes the key
+  prefs: z.record(z.string().min(1, "Preference key is required"), z.unknown()),
+});
+
+// =============================================================================
+// Review State Schemas
+// =============================================================================
+
+export const REVIEW_STATE_VERSION = 1;
+
+export const ReviewStateExportSchema = z.object({
+  version: z.literal(REVIEW_STATE_VERSION, {
+    message: `Unsupported state file version (expected ${REVIEW_STATE_VERSION})`,
+  }),
+  exported_at: z.number(),
+  repos: z.array(
+    z.object({
+      path: z.string().min(1),
+      name: z.string(),
+      viewed: z.array(
+        z.object({
+          branch: z.string(),
+          commit: z.string(),
+          file_path: z.string().min(1),
+          viewed_at: z.number(),
+          viewed_by: z.string().optional(),
+          blob_oid: z.string().regex(/^[0-9a-f]{40,64}$/, "blob_oid must be a full object id").optional(),
+        })
+      ),
+    })
+  ),
+});
+
 // =============================================================================
 // Type exports (inferred from schemas)
 // =============================================================================
@@ -72,7 +142,10 @@ export const DismissNoteRequestSchema = z.object({
 export type AddRepoRequest = z.infer<typeof AddRepoRequestSchema>;
 export type SetCurrentRepoRequest = z.infer<typeof SetCurrentRepoRequestSchema>;
 export type FilePathRequest = z.infer<typeof FilePathRequestSchema>;
+export type MarkViewedRequest = z.infer<typeof MarkViewedRequestSchema>;
+export type CollapseRequest = z.infer<typeof CollapseRequestSchema>;
 export type CommitRequest = z.infer<typeof CommitRequestSchema>;
+export type OpenFileRequest = z.infer<typeof OpenFileRequestSchema>;
 export type AddCommentRequest = z.infer<typeof AddCommentRequestSchema>;
 export type ResolveCommentRequest = z.infer<typeof ResolveCommentRequestSchema>;
 export type AddNoteRequest = z.infer<typeof AddNoteRequestSchema>;
@@ -0,0 +1,18 @@
+import { describe, expect, it } from "bun:test";
+import { assetContentType, looksLikeFile } from "./assets";
+
+describe("asset content types", () => {
+  it("maps build output extensions", () => {
+    expect(assetContentType("/assets/index-abc123.js")).toBe("text/javascript; charset=utf-8");
+    expect(assetContentType("/assets/index.CSS")).toBe("text/css; charset=utf-8");
+    expect(assetContentType("/logo.svg")).toBe("image/svg+xml");
+    expect(assetContentType("/logo.png")).toBe("image/png");
+    expect(assetContentType("/README")).toBeUndefined();
+  });
+
+  it("only treats paths with an extension on the last segment as files", () => {
+    expect(looksLikeFile("/assets/missing.js")).toBe(true);
+    expect(looksLikeFile("/repos/v1.2/files")).toBe(false);
+    expect(looksLikeFile("/.well-known")).toBe(false);
+  });
+});
@@ -0,0 +1,42 @@
+// Served at /favicon.ico when the web build doesn't ship one (browsers accept SVG there)
+export const FAVICON_SVG = `<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 32 32">
+<rect width="32" height="32" rx="7" fill="#161616"/>
+<path d="M21.5 10.5a8 8 0 1 0 0 11" fill="none" stroke="#6b8f71" stroke-width="3.5" stroke-linecap="round"/>
+</svg>
+`;
+
+const TEXT = "; charset=utf-8";
+
+const CONTENT_TYPES: Record<string, string> = {
+  html: `text/html${TEXT}`,
+  js: `text/javascript${TEXT}`,
+  mjs: `text/javascript${TEXT}`,
+  css: `text/css${TEXT}`,
+  json: `application/json${TEXT}`,
+  map: `application/json${TEXT}`,
+  txt: `text/plain${TEXT}`,
+  svg: "image/svg+xml",
+  png: "image/png",
+  jpg: "image/jpeg",
+  jpeg: "image/jpeg",
+  gif: "image/gif",
+  webp: "image/webp",
+  ico: "image/x-icon",
+  woff: "font/woff",
+  woff2: "font/woff2",
+  ttf: "font/ttf",
+  otf: "font/otf",
+  wasm: "application/wasm",
+};
+
+export function assetContentType(filePath: string): string | undefined {
+  const name = filePath.split("/").pop() ?? "";
+  const dot = name.lastIndexOf(".");
+  return dot > 0 ? CONTENT_TYPES[name.slice(dot + 1).toLowerCase()] : undefined;
+}
+
+// Paths that name a file (an extension on the last segment) 404 rather than fall back to the SPA
+export function looksLikeFile(path: string): boolean {
+  const name = path.split("/").pop() ?? "";
+  return name.lastIndexOf(".") > 0;
+}
@@ -0,0 +1,42 @@
+import { describe, expect, it } from "bun:test";
+import type { FileDiff } from "../types";
+import { parseStatusList, requiresReview, reviewProgress } from "./completion";
+
+const file = (path: string, status: FileDiff["status"], viewed = false, extra: Partial<FileDiff> = {}): FileDiff => ({
+  path,
+  status,
+  additions: 1,
+  deletions: 0,
+  patch: "",
+  viewed,
+  ...extra,
+});
+
+describe("review completion", () => {
+  it("requires every non-ignored file by default", () => {
+    expect(requiresReview({ status: "deleted" })).toBe(true);
+    expect(requiresReview({ status: "modified", ignored: true })).toBe(false);
+  });
+
+  it("requires only the configured statuses", () => {
+    const requireFor = ["added", "modified", "renamed"] as const;
+    expect(requiresReview({ status: "renamed" }, requireFor)).toBe(true);
+    expect(requiresReview({ status: "deleted" }, requireFor)).toBe(false);
+    expect(requiresReview({ status: "added" }, [])).toBe(false);
+  });
+
+  it("counts progress over required files only", () => {
+    const files = [
+      file("a.ts", "modified", true),
+      file("b.ts", "added"),
+      file("gone.ts", "deleted", false, { review_required: false }),
+    ];
+    expect(reviewProgress(files)).toEqual({ viewed: 1, required: 2 });
+  });
+
+  it("parses status lists", () => {
+    expect(parseStatusList("added, modified")).toEqual(["added", "modified"]);
+    expect(parseStatusList("")).toEqual([]);
+    expect(parseStatusList("added,removed")).toContain("Unknown file status: removed");
+  });
+});
@@ -0,0 +1,27 @@
+import type { FileDiff, ReviewProgress } from "../types";
+
+export type FileStatus = FileDiff["status"];
+
+export const FILE_STATUSES: readonly FileStatus[] = ["added", "modified", "deleted", "renamed", "copied", "untracked"];
+
+// Parse a comma-separated status list (config `require-review-for`), or an error message
+export function parseStatusList(value: string): FileStatus[] | string {
+  const statuses = value.split(",").map((s) => s.trim()).filter(Boolean);
+  const unknown = statuses.filter((s) => !FILE_STATUSES.includes(s as FileStatus));
+  if (unknown.length > 0) {
+    return `Unknown file status: ${unknown.join(", ")} (expected ${FILE_STATUSES.join(", ")})`;
+  }
+  return statuses as FileStatus[];
+}
+
+// Whether a file has to be viewed for the review to be complete: its status is one of
+// `requireFor` (all statuses when unset) and it isn't generated/vendored (--ignore)
+export function requiresReview(file: Pick<FileDiff, "status" | "ignored">, requireFor?: readonly FileStatus[]): boolean {
+  return !file.ignored && (requireFor === undefined || requireFor.includes(file.status));
+}
+
+// Completion over the files that count (those not flagged review_required: false)
+export function reviewProgress(files: FileDiff[]): ReviewProgress {
+  const required = files.filter((f) => f.review_required !== false);
+  return { viewed: required.filter((f) => f.viewed).length, required: required.length };
+}
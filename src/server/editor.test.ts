@@ -0,0 +1,25 @@
+import { describe, expect, it } from "bun:test";
+import { buildEditorCommand } from "./editor";
+
+describe("buildEditorCommand", () => {
+  it("uses file:line with -g for VS Code", () => {
+    expect(buildEditorCommand("code", "/repo/a.ts", 12)).toEqual(["code", "-g", "/repo/a.ts:12"]);
+  });
+
+  it("does not duplicate an explicit -g flag", () => {
+    expect(buildEditorCommand("code -g", "/repo/a.ts", 3)).toEqual(["code", "-g", "/repo/a.ts:3"]);
+  });
+
+  it("uses +line for terminal editors", () => {
+    expect(buildEditorCommand("vim", "/repo/a.ts", 7)).toEqual(["vim", "+7", "/repo/a.ts"]);
+    expect(buildEditorCommand("/usr/bin/nvim", "/repo/a.ts", 7)).toEqual(["/usr/bin/nvim", "+7", "/repo/a.ts"]);
+  });
+
+  it("keeps extra editor arguments", () => {
+    expect(buildEditorCommand("emacsclient -n", "/repo/a.ts", 2)).toEqual(["emacsclient", "-n", "+2", "/repo/a.ts"]);
+  });
+
+  it("omits the line when not given", () => {
+    expect(buildEditorCommand("subl", "/repo/a.ts")).toEqual(["subl", "/repo/a.ts"]);
+  });
+});
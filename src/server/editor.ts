@@ -0,0 +1,33 @@
+/**
+ * Launch a local editor at a file/line
+ * Editors differ in how they take a line number, so known ones get their own syntax
+ */
+import { basename } from "path";
+
+// Editors that take `<file>:<line>` (VS Code family needs -g/--goto for it)
+const COLON_LINE_EDITORS = new Set(["code", "code-insiders", "cursor", "codium", "subl", "zed", "mate"]);
+const GOTO_FLAG_EDITORS = new Set(["code", "code-insiders", "cursor", "codium"]);
+
+/**
+ * Build the argv for opening filePath at line with the given editor command
+ * (e.g. "code -g", "vim", "emacsclient -n")
+ */
+export function buildEditorCommand(editor: string, filePath: string, line?: number): string[] {
+  const [command, ...args] = editor.trim().split(/\s+/);
+  if (!command) {
+    throw new Error("Editor command is empty");
+  }
+
+  const name = basename(command);
+  if (!line) {
+    return [command, ...args, filePath];
+  }
+
+  if (COLON_LINE_EDITORS.has(name)) {
+    const needsGoto = GOTO_FLAG_EDITORS.has(name) && !args.includes("-g") && !args.includes("--goto");
+    return [command, ...args, ...(needsGoto ? ["-g"] : []), `${filePath}:${line}`];
+  }
+
+  // vim, nvim, emacs, nano, micro, helix-style +line
+  return [command, ...args, `+${line}`, filePath];
+}
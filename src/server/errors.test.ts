@@ -0,0 +1,42 @@
+import { describe, expect, it } from "bun:test";
+import { GitPluginError } from "simple-git";
+import { RefNotAllowedError, RevisionError } from "../git";
+import { ApiError, errorCodeForStatus, toApiError } from "./errors";
+
+describe("API errors", () => {
+  it("derives codes from statuses", () => {
+    expect(errorCodeForStatus(404)).toBe("not_found");
+    expect(errorCodeForStatus(422)).toBe("bad_request");
+    expect(errorCodeForStatus(503)).toBe("internal");
+  });
+
+  it("maps bad refs to 400 and everything else to 500", () => {
+    expect(toApiError(new RevisionError("no upstream")).status).toBe(400);
+
+    const gitError = toApiError(new Error("fatal: ambiguous argument 'nope': unknown revision or path not in the working tree.\n"));
+    expect(gitError.status).toBe(400);
+    expect(gitError.code).toBe("bad_ref");
+
+    const internal = toApiError(new Error("disk full"));
+    expect(internal.status).toBe(500);
+    expect(internal.code).toBe("internal");
+  });
+
+  it("maps refs outside --allowed-base to 403", () => {
+    const forbidden = toApiError(new RefNotAllowedError("Ref dev is not in the allowed list"));
+    expect(forbidden.status).toBe(403);
+    expect(forbidden.code).toBe("forbidden");
+  });
+
+  it("maps killed git processes to a 503 timeout", () => {
+    const timeout = toApiError(new GitPluginError(undefined, "timeout", "block timeout reached"));
+    expect(timeout.status).toBe(503);
+    expect(timeout.code).toBe("timeout");
+  });
+
+  it("passes ApiErrors through", () => {
+    const error = new ApiError(409, "Nothing staged");
+    expect(toApiError(error)).toBe(error);
+    expect(error.code).toBe("conflict");
+  });
+});
@@ -0,0 +1,58 @@
+import { GitPluginError } from "simple-git";
+import { RefNotAllowedError, RevisionError } from "../git";
+
+// Machine-readable `code` on every API error body ({ error, code }), so the UI
+// can tell a bad ref or missing file from a server failure
+export type ErrorCode =
+  | "bad_request"
+  | "bad_ref"
+  | "forbidden"
+  | "not_found"
+  | "gone"
+  | "unsupported"
+  | "conflict"
+  | "too_large"
+  | "rate_limited"
+  | "timeout"
+  | "internal";
+
+const STATUS_CODES: Record<number, ErrorCode> = {
+  400: "bad_request",
+  403: "forbidden",
+  404: "not_found",
+  409: "conflict",
+  410: "gone",
+  413: "too_large",
+  415: "unsupported",
+  429: "rate_limited",
+};
+
+export function errorCodeForStatus(status: number): ErrorCode {
+  return STATUS_CODES[status] ?? (status >= 500 ? "internal" : "bad_request");
+}
+
+// Thrown from handlers (or anything they call) to answer with a specific status
+export class ApiError extends Error {
+  constructor(
+    readonly status: number,
+    message: string,
+    readonly code: ErrorCode = errorCodeForStatus(status)
+  ) {
+    super(message);
+  }
+}
+
+// git's wording when a revision or object doesn't exist
+const BAD_REF = /unknown revision|bad revision|ambiguous argument|not a valid object name|invalid object name/i;
+
+export function toApiError(error: unknown): ApiError {
+  if (error instanceof ApiError) return error;
+  if (error instanceof RevisionError) return new ApiError(400, error.message, "bad_ref");
+  if (error instanceof RefNotAllowedError) return new ApiError(403, error.message, "forbidden");
+  // simple-git killed a git process that went quiet past --request-timeout
+  if (error instanceof GitPluginError && error.plugin === "timeout") return new ApiError(503, "Request timed out", "timeout");
+
+  const message = error instanceof Error ? error.message : "Internal server error";
+  if (BAD_REF.test(message)) return new ApiError(400, message.trim(), "bad_ref");
+  return new ApiError(500, message, "internal");
+}
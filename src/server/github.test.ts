@@ -0,0 +1,51 @@
+import { describe, expect, it } from "bun:test";
+import type { FileDiff } from "../types";
+import { toGitHubFile } from "./github";
+
+const PATCH = `diff --git a/src/a.ts b/src/b.ts
+similarity index 90%
+rename from src/a.ts
+rename to src/b.ts
+index 1111111..2222222 100644
+--- a/src/a.ts
++++ b/src/b.ts
+@@ -1,2 +1,2 @@
+-old
++new
+ same
+`;
+
+describe("GitHub file format", () => {
+  it("maps fields and strips the patch header", () => {
+    const file: FileDiff = {
+      path: "src/b.ts",
+      old_path: "src/a.ts",
+      status: "renamed",
+      additions: 1,
+      deletions: 1,
+      patch: PATCH,
+      viewed: false,
+    };
+    expect(toGitHubFile(file)).toEqual({
+      filename: "src/b.ts",
+      status: "renamed",
+      additions: 1,
+      deletions: 1,
+      changes: 2,
+      patch: "@@ -1,2 +1,2 @@\n-old\n+new\n same",
+      previous_filename: "src/a.ts",
+    });
+  });
+
+  it("uses GitHub's status names and omits empty patches", () => {
+    const base = { additions: 0, deletions: 3, patch: "Binary files differ\n", viewed: false };
+    expect(toGitHubFile({ ...base, path: "gone.png", status: "deleted" })).toEqual({
+      filename: "gone.png",
+      status: "removed",
+      additions: 0,
+      deletions: 3,
+      changes: 3,
+    });
+    expect(toGitHubFile({ ...base, path: "new.txt", status: "untracked" }).status).toBe("added");
+  });
+});
@@ -0,0 +1,41 @@
+import type { FileDiff } from "../types";
+
+// One entry of GitHub's "list pull request files" response, the fields scripts rely on
+export interface GitHubFile {
+  filename: string;
+  status: "added" | "removed" | "modified" | "renamed" | "copied";
+  additions: number;
+  deletions: number;
+  changes: number;
+  // Hunks only (from the first @@), like GitHub; left out for binary and oversized files
+  patch?: string;
+  previous_filename?: string;
+}
+
+const GITHUB_STATUSES: Record<FileDiff["status"], GitHubFile["status"]> = {
+  added: "added",
+  untracked: "added",
+  deleted: "removed",
+  modified: "modified",
+  renamed: "renamed",
+  copied: "copied",
+};
+
+// GitHub's patch starts at the first hunk: no diff --git/index/---/+++ header
+function hunksOnly(patch: string): string {
+  const start = patch.search(/^@@/m);
+  return start === -1 ? "" : patch.slice(start).trimEnd();
+}
+
+export function toGitHubFile(file: FileDiff): GitHubFile {
+  const patch = hunksOnly(file.patch);
+  return {
+    filename: file.path,
+    status: GITHUB_STATUSES[file.status],
+    additions: file.additions,
+    deletions: file.deletions,
+    changes: file.additions + file.deletions,
+    ...(patch !== "" && { patch }),
+    ...(file.old_path !== undefined && { previous_filename: file.old_path }),
+  };
+}
@@ -0,0 +1,48 @@
+import { describe, expect, it } from "bun:test";
+import { createIdleTimer } from "./idle";
+
+describe("createIdleTimer", () => {
+  it("fires after the timeout with no requests", async () => {
+    let fired = 0;
+    const timer = createIdleTimer(50, () => fired++);
+    await Bun.sleep(100);
+    expect(fired).toBe(1);
+    timer.stop();
+  });
+
+  it("restarts the countdown after each request", async () => {
+    let fired = 0;
+    const timer = createIdleTimer(80, () => fired++);
+    for (let i = 0; i < 3; i++) {
+      await Bun.sleep(40);
+      timer.begin()();
+    }
+    expect(fired).toBe(0);
+    await Bun.sleep(120);
+    expect(fired).toBe(1);
+    timer.stop();
+  });
+
+  it("waits for in-flight requests", async () => {
+    let fired = 0;
+    const timer = createIdleTimer(30, () => fired++);
+    const end = timer.begin();
+    await Bun.sleep(80);
+    expect(fired).toBe(0);
+    end();
+    end();
+    await Bun.sleep(60);
+    expect(fired).toBe(1);
+    timer.stop();
+  });
+
+  it("never fires once stopped", async () => {
+    let fired = 0;
+    const timer = createIdleTimer(30, () => fired++);
+    const end = timer.begin();
+    timer.stop();
+    end();
+    await Bun.sleep(60);
+    expect(fired).toBe(0);
+  });
+});
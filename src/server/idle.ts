@@ -0,0 +1,39 @@
+/**
+ * Idle shutdown for --idle-timeout
+ * Fires once no request has been in flight for the whole timeout, so a server started for a
+ * quick review doesn't linger. A slow request holds it off until it finishes
+ */
+
+export interface IdleTimer {
+  // Call when a request starts; call the returned function when it finishes
+  begin(): () => void;
+  stop(): void;
+}
+
+export function createIdleTimer(timeoutMs: number, onIdle: () => void): IdleTimer {
+  let active = 0;
+  let stopped = false;
+  let timer: ReturnType<typeof setTimeout> | undefined;
+  const arm = () => {
+    clearTimeout(timer);
+    if (!stopped) timer = setTimeout(onIdle, timeoutMs);
+  };
+  arm();
+
+  return {
+    begin(): () => void {
+      active++;
+      clearTimeout(timer);
+      let finished = false;
+      return () => {
+        if (finished) return;
+        finished = true;
+        if (--active === 0) arm();
+      };
+    },
+    stop(): void {
+      stopped = true;
+      clearTimeout(timer);
+    },
+  };
+}
@@ -0,0 +1,21 @@
+import { describe, expect, it } from "bun:test";
+import { createIgnoreMatcher } from "./ignore";
+
+describe("createIgnoreMatcher", () => {
+  it("matches globs against repo-relative paths", () => {
+    const matches = createIgnoreMatcher(["**/*.pb.go", "testdata/**"]);
+    expect(matches("api/v1/service.pb.go")).toBe(true);
+    expect(matches("testdata/fixtures/a.json")).toBe(true);
+    expect(matches("src/main.go")).toBe(false);
+  });
+
+  it("treats a trailing slash as the whole directory", () => {
+    const matches = createIgnoreMatcher(["generated/"]);
+    expect(matches("generated/schema/types.ts")).toBe(true);
+    expect(matches("src/generated.ts")).toBe(false);
+  });
+
+  it("matches nothing without patterns", () => {
+    expect(createIgnoreMatcher([" ", ""])("anything")).toBe(false);
+  });
+});
@@ -0,0 +1,15 @@
+import { Glob } from "bun";
+
+export const IGNORE_MODES = ["hide", "collapse"] as const;
+export type IgnoreMode = (typeof IGNORE_MODES)[number];
+
+// Build a path matcher for review ignore globs. A bare directory ("generated/")
+// covers everything beneath it.
+export function createIgnoreMatcher(patterns: string[]): (path: string) => boolean {
+  const globs = patterns
+    .map((pattern) => pattern.trim())
+    .filter(Boolean)
+    .map((pattern) => new Glob(pattern.endsWith("/") ? `${pattern}**` : pattern));
+
+  return (path) => globs.some((glob) => glob.match(path));
+}
@@ -0,0 +1,50 @@
+import { describe, expect, it } from "bun:test";
+import { fileContentType, imageContentType, imageTypeFromPath, sniffImageType } from "./image";
+
+const PNG = new Uint8Array([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00]);
+
+describe("image content types", () => {
+  it("maps extensions case-insensitively", () => {
+    expect(imageTypeFromPath("assets/logo.PNG")).toBe("image/png");
+    expect(imageTypeFromPath("icons/close.svg")).toBe("image/svg+xml");
+    expect(imageTypeFromPath("src/main.ts")).toBeUndefined();
+  });
+
+  it("sniffs magic bytes", () => {
+    expect(sniffImageType(PNG)).toBe("image/png");
+    expect(sniffImageType(new Uint8Array([0x52, 0x49, 0x46, 0x46, 1, 2, 3, 4, 0x57, 0x45, 0x42, 0x50]))).toBe("image/webp");
+    expect(sniffImageType(new TextEncoder().encode("hello"))).toBeUndefined();
+  });
+
+  it("falls back to magic bytes for extensionless files", () => {
+    expect(imageContentType("fixtures/screenshot", PNG)).toBe("image/png");
+    expect(imageContentType("README", new TextEncoder().encode("# hi"))).toBeUndefined();
+  });
+
+  it("prefers magic bytes over a misleading extension", () => {
+    expect(imageContentType("logo.gif", PNG)).toBe("image/png");
+  });
+});
+
+describe("file content types", () => {
+  const bytes = (text: string) => new TextEncoder().encode(text);
+
+  it("sniffs common formats", () => {
+    expect(fileContentType("doc", bytes("%PDF-1.4\n"))).toBe("application/pdf");
+    expect(fileContentType("archive", new Uint8Array([0x50, 0x4b, 0x03, 0x04, 0]))).toBe("application/zip");
+    expect(fileContentType("module", new Uint8Array([0x00, 0x61, 0x73, 0x6d, 1, 0, 0, 0]))).toBe("application/wasm");
+    expect(fileContentType("screenshot", PNG)).toBe("image/png");
+  });
+
+  it("falls back to the extension, then text or octet-stream", () => {
+    expect(fileContentType("icon.svg", bytes("<svg/>"))).toBe("image/svg+xml");
+    expect(fileContentType("data.json", bytes("{}"))).toBe("application/json");
+    expect(fileContentType("page.html", bytes("<html></html>"))).toBe("text/plain; charset=utf-8");
+    expect(fileContentType("blob.dat", new Uint8Array([1, 0, 2]))).toBe("application/octet-stream");
+  });
+
+  it("uses the extension alone when sniffing is off", () => {
+    expect(fileContentType("notes.txt", PNG, false)).toBe("application/octet-stream");
+    expect(fileContentType("archive.zip", bytes("not really"), false)).toBe("application/zip");
+  });
+});
@@ -0,0 +1,94 @@
+// Largest blob /api/image and /api/file will serve
+export const MAX_IMAGE_BYTES = 10 * 1024 * 1024;
+
+export const OCTET_STREAM = "application/octet-stream";
+const PLAIN_TEXT = "text/plain; charset=utf-8";
+
+const IMAGE_EXTENSIONS: Record<string, string> = {
+  png: "image/png",
+  jpg: "image/jpeg",
+  jpeg: "image/jpeg",
+  gif: "image/gif",
+  webp: "image/webp",
+  avif: "image/avif",
+  bmp: "image/bmp",
+  ico: "image/x-icon",
+  svg: "image/svg+xml",
+};
+
+// Everything else /api/file labels by extension; markup (html, xml) is left to the text
+// fallback so it's shown as source rather than rendered in our origin
+const FILE_EXTENSIONS: Record<string, string> = {
+  ...IMAGE_EXTENSIONS,
+  pdf: "application/pdf",
+  json: "application/json",
+  zip: "application/zip",
+  gz: "application/gzip",
+  wasm: "application/wasm",
+  woff: "font/woff",
+  woff2: "font/woff2",
+  ttf: "font/ttf",
+  otf: "font/otf",
+  mp3: "audio/mpeg",
+  wav: "audio/wav",
+  mp4: "video/mp4",
+  webm: "video/webm",
+};
+
+const IMAGE_SIGNATURES: Array<{ type: string; bytes: number[]; offset?: number }> = [
+  { type: "image/png", bytes: [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a] },
+  { type: "image/jpeg", bytes: [0xff, 0xd8, 0xff] },
+  { type: "image/gif", bytes: [0x47, 0x49, 0x46, 0x38] },
+  { type: "image/bmp", bytes: [0x42, 0x4d] },
+  { type: "image/x-icon", bytes: [0x00, 0x00, 0x01, 0x00] },
+  // RIFF....WEBP
+  { type: "image/webp", bytes: [0x57, 0x45, 0x42, 0x50], offset: 8 },
+];
+
+const FILE_SIGNATURES: Array<{ type: string; bytes: number[]; offset?: number }> = [
+  ...IMAGE_SIGNATURES,
+  // %PDF-
+  { type: "application/pdf", bytes: [0x25, 0x50, 0x44, 0x46, 0x2d] },
+  { type: "application/zip", bytes: [0x50, 0x4b, 0x03, 0x04] },
+  { type: "application/gzip", bytes: [0x1f, 0x8b] },
+  { type: "application/wasm", bytes: [0x00, 0x61, 0x73, 0x6d] },
+  { type: "font/woff", bytes: [0x77, 0x4f, 0x46, 0x46] },
+  { type: "font/woff2", bytes: [0x77, 0x4f, 0x46, 0x32] },
+];
+
+// Like git: a NUL in the first 8000 bytes means binary
+const TEXT_PROBE_BYTES = 8000;
+
+function extensionType(filePath: string, types: Record<string, string>): string | undefined {
+  const ext = filePath.split(".").pop()?.toLowerCase();
+  return ext ? types[ext] : undefined;
+}
+
+function signatureType(bytes: Uint8Array, signatures: typeof IMAGE_SIGNATURES): string | undefined {
+  const match = signatures.find(({ bytes: signature, offset = 0 }) =>
+    signature.every((byte, i) => bytes[offset + i] === byte)
+  );
+  return match?.type;
+}
+
+export function imageTypeFromPath(filePath: string): string | undefined {
+  return extensionType(filePath, IMAGE_EXTENSIONS);
+}
+
+export function sniffImageType(bytes: Uint8Array): string | undefined {
+  return signatureType(bytes, IMAGE_SIGNATURES);
+}
+
+// Magic bytes first, so a mislabelled file gets its real type; the extension covers formats
+// without a signature (SVGs are plain text)
+export function imageContentType(filePath: string, bytes: Uint8Array): string | undefined {
+  return sniffImageType(bytes) ?? imageTypeFromPath(filePath);
+}
+
+// Any file's type for /api/file: magic bytes (unless `sniff` is off), then the extension, then
+// plain text for anything without NULs; other bytes are an octet stream
+export function fileContentType(filePath: string, bytes: Uint8Array, sniff = true): string {
+  const known = (sniff ? signatureType(bytes, FILE_SIGNATURES) : undefined) ?? extensionType(filePath, FILE_EXTENSIONS);
+  if (known) return known;
+  return bytes.subarray(0, TEXT_PROBE_BYTES).includes(0) ? OCTET_STREAM : PLAIN_TEXT;
+}
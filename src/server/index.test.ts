@@ -1,14 +1,32 @@
-import { mkdtempSync, rmSync, mkdirSync, writeFileSync } from "fs";
+import { mkdtempSync, rmSync, mkdirSync, symlinkSync, writeFileSync } from "fs";
 import { tmpdir } from "os";
 import { join } from "path";
-import { beforeAll, afterAll, beforeEach, describe, expect, it } from "bun:test";
-import { startServer, stopServer } from "./index";
+import { beforeAll, afterAll, beforeEach, afterEach, describe, expect, it } from "bun:test";
+import { injectInitialData, startServer, stopServer } from "./index";
 import * as state from "../state";
-import type { Repository, Comment, DiffResponse } from "../types";
+import { getGitManager } from "../git";
+import type {
+  Repository,
+  Comment,
+  CommitGraph,
+  ConflictList,
+  DiffEstimate,
+  DiffResponse,
+  DiffStat,
+  FileDiff,
+  LocResponse,
+  RangeCommitList,
+  RangeInfo,
+  RefreshResponse,
+  ReviewSummary,
+  StatusResponse,
+  ViewedFileList,
+} from "../types";
 
 // Type for API responses
 type ApiResponse<T = unknown> = T & {
   error?: string;
+  code?: string;
   success?: boolean;
 };
 
@@ -17,6 +35,7 @@ let configDir: string;
 let testRepoPath: string;
 let baseUrl: string;
 let port: number;
+let mainServer: ReturnType<typeof Bun.serve>;
 
 // Helper to create a test git repo
 async function createTestGitRepo(dir: string, name = "test-repo"): Promise<string> {
@@ -66,11 +85,12 @@ beforeAll(async () => {
   baseUrl = `http://localhost:${port}`;
 
   // Start server
-  await startServer({ port });
+  mainServer = await startServer({ port });
 });
 
 afterAll(async () => {
   stopServer();
+  mainServer.stop();
   state.closeDb();
   rmSync(tempHome, { recursive: true, force: true });
   delete process.env["CEREBRO_CONFIG_DIR"];
@@ -96,10 +116,37 @@ describe("health", () => {
     expect(data.status).toBe("ok");
   });
 
-  it("OPTIONS requests return CORS headers", async () => {
-    const res = await api("/api/repos", { method: "OPTIONS" });
-    expect(res.headers.get("Access-Control-Allow-Origin")).toBe("*");
-    expect(res.headers.get("Access-Control-Allow-Methods")).toContain("GET");
+  it("GET /api/openapi.json documents every API route", async () => {
+    const res = await api("/api/openapi.json");
+    expect(res.status).toBe(200);
+    const doc = (await res.json()) as { openapi: string; paths: Record<string, Record<string, unknown>> };
+    expect(doc.openapi).toStartWith("3.");
+
+    const source = await Bun.file(join(import.meta.dir, "index.ts")).text();
+    const routes = [...source.matchAll(/path === "(\/api\/[^"]+)"(?: && method === "(\w+)")?/g)];
+    expect(routes.length).toBeGreaterThan(20);
+    for (const [, route, method] of routes) {
+      expect(doc.paths[route!]).toBeDefined();
+      if (method) expect(doc.paths[route!]?.[method.toLowerCase()]).toBeDefined();
+    }
+
+    // Prefix routes take their last segment as a parameter: /api/repos/ is /api/repos/{id}
+    const prefixRoutes = [...source.matchAll(/path\.startsWith\("(\/api\/[^"]+\/)"\) && method === "(\w+)"/g)];
+    expect(prefixRoutes.length).toBeGreaterThan(1);
+    for (const [, prefix, method] of prefixRoutes) {
+      const documented = Object.keys(doc.paths).find((p) => p.startsWith(prefix!) && /^\{\w+\}$/.test(p.slice(prefix!.length)));
+      expect(documented).toBeDefined();
+      expect(doc.paths[documented!]?.[method!.toLowerCase()]).toBeDefined();
+    }
+  });
+
+  it("OPTIONS requests omit CORS headers by default", async () => {
+    const res = await fetch(`${baseUrl}/api/repos`, {
+      method: "OPTIONS",
+      headers: { Origin: "http://elsewhere.test" },
+    });
+    expect(res.status).toBe(204);
+    expect(res.headers.get("Access-Control-Allow-Origin")).toBeNull();
   });
 
   it("unknown API routes return 404", async () => {
@@ -233,6 +280,118 @@ describe("repos API", () => {
   });
 });
 
+// =============================================================================
+// Status API
+// =============================================================================
+
+describe("status API", () => {
+  it("GET /api/status returns 400 without repo", async () => {
+    const res = await api("/api/status");
+    expect(res.status).toBe(400);
+  });
+
+  it("GET /api/status selects a repo by id", async () => {
+    const otherRepoPath = await createTestGitRepo(tempHome, "status-repo");
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const addRes = await api("/api/repos", { method: "POST", body: { path: otherRepoPath } });
+    const other = (await addRes.json()) as Repository;
+
+    const res = await api(`/api/status?repo=${other.id}`);
+    expect(res.status).toBe(200);
+    const data = (await res.json()) as StatusResponse;
+    expect(data.repo_path).toBe(otherRepoPath);
+    expect(data.commit?.length).toBe(7);
+    expect(data.dirty).toBe(false);
+  });
+
+  it("GET /api/status and /api/diff handle a repo with no commits yet", async () => {
+    const unbornPath = join(tempHome, "unborn-repo");
+    mkdirSync(unbornPath, { recursive: true });
+    await Bun.$`git -C ${unbornPath} init -q -b trunk`.quiet();
+    writeFileSync(join(unbornPath, "draft.txt"), "not committed\n");
+    const repo = (await (await api("/api/repos", { method: "POST", body: { path: unbornPath } })).json()) as Repository;
+
+    const res = await api(`/api/status?repo=${repo.id}`);
+    expect(res.status).toBe(200);
+    expect(await res.json()).toMatchObject({ branch: "trunk", commit: null, message: "no commits yet", dirty: true });
+
+    for (const mode of ["branch", "working"]) {
+      const diff = await api(`/api/diff?repo=${repo.id}&mode=${mode}`);
+      expect(diff.status).toBe(200);
+      expect(((await diff.json()) as DiffResponse).files).toEqual([]);
+    }
+  });
+
+  it("GET /api/status reports uncommitted changes as dirty", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "README.md"), "# Dirty\n");
+
+    const data = (await (await api("/api/status")).json()) as StatusResponse;
+    expect(data.dirty).toBe(true);
+
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+
+  it("GET /api/loc totals working-tree line changes", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "README.md"), "# One\n# Two\n");
+
+    const loc = (await (await api("/api/loc?mode=working")).json()) as LocResponse;
+    expect(loc).toEqual({ files: 1, additions: 2, deletions: 1, net: 1 });
+
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+
+  it("rejects an unknown mode on every route that takes one", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const routes = [
+      "/api/diff?",
+      "/api/file-diff?file=README.md&",
+      "/api/context?file=README.md&start=1&end=1&",
+      "/api/file?path=README.md&side=new&",
+      "/api/export.zip?",
+      "/api/estimate?",
+      "/api/loc?",
+      "/api/stats/languages?",
+      "/api/stats/distribution?",
+      "/api/stats/diffstat?",
+    ];
+    for (const route of routes) {
+      expect((await api(`${route}mode=bogus`)).status).toBe(400);
+    }
+  });
+
+  it("GET /api/context needs a working tree for working-tree modes", async () => {
+    const barePath = join(tempHome, "context-bare.git");
+    await Bun.$`git clone -q --bare ${testRepoPath} ${barePath}`.quiet();
+    const repo = await state.addRepo(barePath, "context-bare", "main");
+
+    const context = (mode: string) => api(`/api/context?repo=${repo.id}&mode=${mode}&file=README.md&start=1&end=1`);
+    const res = await context("working");
+    expect(res.status).toBe(400);
+    expect(((await res.json()) as { error: string }).error).toContain("requires a working tree");
+    expect((await context("branch")).status).not.toBe(400);
+  });
+
+  it("POST /api/refresh only reports a change when HEAD moves", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    await api("/api/refresh", { method: "POST" });
+
+    const unchanged = (await (await api("/api/refresh", { method: "POST" })).json()) as RefreshResponse;
+    expect(unchanged.changed).toBe(false);
+
+    writeFileSync(join(testRepoPath, "refresh.txt"), "new\n");
+    await Bun.$`git -C ${testRepoPath} add refresh.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add refresh.txt"`.quiet();
+
+    const moved = (await (await api("/api/refresh", { method: "POST" })).json()) as RefreshResponse;
+    expect(moved.changed).toBe(true);
+    expect(moved.commit).not.toBe(unchanged.commit);
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~1`.quiet();
+  });
+});
+
 // =============================================================================
 // Diff API
 // =============================================================================
@@ -243,6 +402,16 @@ describe("diff API", () => {
     expect(res.status).toBe(400);
     const data = (await res.json()) as ApiResponse;
     expect(data.error).toBe("No repository selected");
+    expect(data.code).toBe("bad_request");
+  });
+
+  it("GET /api/diff answers an unknown base with 400 bad_ref", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const res = await api("/api/diff?mode=branch&compare=no-such-branch");
+    expect(res.status).toBe(400);
+    const data = (await res.json()) as ApiResponse;
+    expect(data.code).toBe("bad_ref");
+    expect(data.error).toContain("no-such-branch");
   });
 
   it("GET /api/diff returns diff for repo", async () => {
@@ -296,6 +465,511 @@ describe("diff API", () => {
     await Bun.$`rm ${join(testRepoPath, "staged-test.txt")}`.quiet();
   });
 
+  it("GET /api/diff pages files with offset and limit", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    for (const name of ["page-a.txt", "page-b.txt", "page-c.txt"]) {
+      writeFileSync(join(testRepoPath, name), "page\n");
+    }
+
+    const all = (await (await api("/api/diff?mode=working")).json()) as DiffResponse;
+    const res = await api("/api/diff?mode=working&offset=1&limit=1");
+    expect(res.status).toBe(200);
+    const page = (await res.json()) as DiffResponse;
+    expect(page.total).toBe(all.files.length);
+    expect(page.files.map((f) => f.path)).toEqual([all.files[1]!.path]);
+
+    expect((await api("/api/diff?mode=working&limit=-1")).status).toBe(400);
+
+    await Bun.$`rm ${join(testRepoPath, "page-a.txt")} ${join(testRepoPath, "page-b.txt")} ${join(testRepoPath, "page-c.txt")}`.quiet();
+  });
+
+  it("GET /api/diff/show/<oid> diffs a commit against its parent", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "show.txt"), "shown\n");
+    await Bun.$`git -C ${testRepoPath} add show.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add show.txt"`.quiet();
+    const oid = (await Bun.$`git -C ${testRepoPath} rev-parse --short HEAD`.text()).trim();
+
+    const res = await api(`/api/diff/show/${oid}`);
+    expect(res.status).toBe(200);
+    const data = (await res.json()) as DiffResponse;
+    expect(data.files.map((f) => f.path)).toEqual(["show.txt"]);
+    expect(data.commit).toBe(oid.slice(0, 7));
+
+    // Viewed state is keyed on the shown commit
+    await api(`/api/mark-viewed?show=${oid}`, { method: "POST", body: { file_path: "show.txt" } });
+    const after = (await (await api(`/api/diff/show/${oid}`)).json()) as DiffResponse;
+    expect(after.files[0]?.viewed).toBe(true);
+
+    const fileRes = await api(`/api/file-diff?file=show.txt&show=${oid}`);
+    expect(fileRes.status).toBe(200);
+
+    // A regular commit has no second parent to diff against
+    const badParent = await api(`/api/diff/show/${oid}?parent=2`);
+    expect(badParent.status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~1`.quiet();
+  });
+
+  it("GET /api/diff/show/<oid> returns 404 for unknown commits", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const res = await api("/api/diff/show/deadbeef");
+    expect(res.status).toBe(404);
+  });
+
+  it("GET /api/image serves each side of a changed image", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const png = (marker: number) => new Uint8Array([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, marker]);
+    writeFileSync(join(testRepoPath, "logo.png"), png(1));
+    await Bun.$`git -C ${testRepoPath} add logo.png`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add logo"`.quiet();
+    writeFileSync(join(testRepoPath, "logo.png"), png(2));
+
+    const oldRes = await api("/api/image?path=logo.png&side=old&mode=working");
+    expect(oldRes.status).toBe(200);
+    expect(oldRes.headers.get("content-type")).toBe("image/png");
+    expect(new Uint8Array(await oldRes.arrayBuffer())[8]).toBe(1);
+
+    const newRes = await api("/api/image?path=logo.png&side=new&mode=working");
+    expect(new Uint8Array(await newRes.arrayBuffer())[8]).toBe(2);
+
+    // The commit that added it has no old side
+    const oid = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    expect((await api(`/api/image?path=logo.png&side=old&show=${oid}`)).status).toBe(404);
+    expect((await api(`/api/image?path=logo.png&side=new&show=${oid}`)).status).toBe(200);
+    expect((await api("/api/image?path=README.md&side=old&mode=working")).status).toBe(415);
+    expect((await api("/api/image?path=logo.png&side=both")).status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~1`.quiet();
+  });
+
+  it("GET /api/file types raw bytes by magic bytes, then extension", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "spec.pdf"), "%PDF-1.7\n");
+    writeFileSync(join(testRepoPath, "icon.svg"), "<svg xmlns='http://www.w3.org/2000/svg'/>\n");
+    writeFileSync(join(testRepoPath, "notes"), "plain\n");
+    writeFileSync(join(testRepoPath, "blob.dat"), new Uint8Array([0, 1, 2, 3]));
+    writeFileSync(join(testRepoPath, "shot.bin"), new Uint8Array([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0]));
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add raw files"`.quiet();
+    const oid = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+
+    const type = async (file: string, extra = "") =>
+      (await api(`/api/file?path=${file}&side=new&show=${oid}${extra}`)).headers.get("content-type");
+    expect(await type("spec.pdf")).toBe("application/pdf");
+    expect(await type("icon.svg")).toBe("image/svg+xml");
+    expect(await type("notes")).toBe("text/plain; charset=utf-8");
+    expect(await type("blob.dat")).toBe("application/octet-stream");
+    expect(await type("shot.bin")).toBe("image/png");
+    expect(await type("shot.bin", "&sniff=false")).toBe("application/octet-stream");
+
+    const svg = await api(`/api/file?path=icon.svg&side=new&show=${oid}`);
+    expect(svg.headers.get("content-security-policy")).toContain("default-src 'none'");
+    expect(await svg.text()).toStartWith("<svg");
+    expect((await api(`/api/file?path=spec.pdf&side=old&show=${oid}`)).status).toBe(404);
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~1`.quiet();
+  });
+
+  it("GET /api/file refuses .git and symlinks out of the repository", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const outside = mkdtempSync(join(tmpdir(), "cerebro-outside-"));
+    writeFileSync(join(outside, "secret.txt"), "secret\n");
+    symlinkSync(join(outside, "secret.txt"), join(testRepoPath, "escape.txt"));
+
+    expect((await api("/api/file?path=escape.txt&side=new&mode=working")).status).toBe(400);
+    expect((await api("/api/file?path=.git/config&side=new&mode=working")).status).toBe(400);
+    expect((await api("/api/file?path=../outside.txt&side=new&mode=working")).status).toBe(400);
+
+    rmSync(join(testRepoPath, "escape.txt"));
+    rmSync(outside, { recursive: true, force: true });
+  });
+
+  it("GET /api/diff accepts a range expression", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "range.txt"), "range\n");
+    await Bun.$`git -C ${testRepoPath} add range.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add range.txt"`.quiet();
+
+    const res = await api(`/api/diff?range=${encodeURIComponent("HEAD~1...HEAD")}`);
+    expect(res.status).toBe(200);
+    const data = (await res.json()) as DiffResponse;
+    expect(data.files.map((f) => f.path)).toEqual(["range.txt"]);
+
+    // Viewed marks follow the range, not the checkout
+    await api(`/api/mark-viewed?range=${encodeURIComponent("HEAD~1..HEAD")}`, {
+      method: "POST",
+      body: { file_path: "range.txt" },
+    });
+    const after = (await (await api(`/api/diff?range=${encodeURIComponent("HEAD~1..HEAD")}`)).json()) as DiffResponse;
+    expect(after.files[0]?.viewed).toBe(true);
+
+    const malformed = await api("/api/diff?range=HEAD");
+    expect(malformed.status).toBe(400);
+    expect(((await malformed.json()) as ApiResponse).error).toContain("expected <base>..<head>");
+    expect((await api("/api/diff?range=HEAD..nope")).status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~1`.quiet();
+  });
+
+  it("GET /api/diff?since=last shows only commits after the last review", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "reviewed.txt"), "seen\n");
+    await Bun.$`git -C ${testRepoPath} add reviewed.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add reviewed.txt"`.quiet();
+
+    // Never reviewed: the full branch diff
+    const full = (await (await api("/api/diff")).json()) as DiffResponse;
+    const first = (await (await api("/api/diff?since=last")).json()) as DiffResponse;
+    expect(first.since).toBeUndefined();
+    expect(first.files.map((f) => f.path)).toEqual(full.files.map((f) => f.path));
+
+    await api("/api/mark-viewed", { method: "POST", body: { file_path: "reviewed.txt" } });
+    const reviewed = (await Bun.$`git -C ${testRepoPath} rev-parse --short=7 HEAD`.text()).trim();
+    writeFileSync(join(testRepoPath, "later.txt"), "new\n");
+    await Bun.$`git -C ${testRepoPath} add later.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add later.txt"`.quiet();
+
+    const res = await api("/api/diff?since=last");
+    expect(res.status).toBe(200);
+    const data = (await res.json()) as DiffResponse;
+    expect(data.since).toBe(reviewed);
+    expect(data.files.map((f) => f.path)).toEqual(["later.txt"]);
+    expect((await api("/api/file-diff?file=later.txt&since=last")).status).toBe(200);
+    expect((await api("/api/diff?since=yesterday")).status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~2`.quiet();
+  });
+
+  it("GET /api/viewed lists viewed paths without computing the diff", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    await api("/api/mark-viewed", { method: "POST", body: { file_path: "viewed-b.txt" } });
+    await api("/api/mark-viewed", { method: "POST", body: { file_path: "viewed-a.txt", scope: "branch" } });
+
+    const res = await api("/api/viewed");
+    expect(res.status).toBe(200);
+    const list = (await res.json()) as ViewedFileList;
+    expect(list.commit).toBe((await Bun.$`git -C ${testRepoPath} rev-parse --short=7 HEAD`.text()).trim());
+    expect(list.files).toEqual(["viewed-a.txt", "viewed-b.txt"]);
+
+    for (const file_path of ["viewed-a.txt", "viewed-b.txt"]) {
+      await api("/api/unmark-viewed", { method: "POST", body: { file_path } });
+    }
+    expect(((await (await api("/api/viewed")).json()) as ViewedFileList).files).toEqual([]);
+  });
+
+  it("POST /api/mark-viewed?scope=branch keeps a file viewed across commits", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "scoped.txt"), "v1\n");
+    await Bun.$`git -C ${testRepoPath} add scoped.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -qm "Add scoped.txt"`.quiet();
+    await api("/api/diff?compare=HEAD~1");
+    expect((await api("/api/mark-viewed?scope=branch", { method: "POST", body: { file_path: "scoped.txt" } })).status).toBe(200);
+    expect((await api("/api/mark-viewed?scope=everywhere", { method: "POST", body: { file_path: "scoped.txt" } })).status).toBe(400);
+
+    writeFileSync(join(testRepoPath, "scoped.txt"), "v2\n");
+    await Bun.$`git -C ${testRepoPath} commit -qam "Change scoped.txt"`.quiet();
+    const diff = (await (await api("/api/diff?compare=HEAD~2")).json()) as DiffResponse;
+    expect(diff.files.find((f) => f.path === "scoped.txt")).toMatchObject({ viewed: true, viewed_scope: "branch" });
+
+    // The mark isn't a review of any one commit
+    const { reviews } = (await (await api("/api/reviews")).json()) as { reviews: ReviewSummary[] };
+    expect(reviews.map((r) => r.commit)).not.toContain("*");
+
+    await api("/api/unmark-viewed", { method: "POST", body: { file_path: "scoped.txt" } });
+    await Bun.$`git -C ${testRepoPath} reset -q --hard HEAD~2`.quiet();
+  });
+
+  it("GET /api/diff?detect_moves=true pairs moved blocks only among the listed files", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const block = "function moved() {\n  one();\n  two();\n  three();\n}\n";
+    writeFileSync(join(testRepoPath, "from.ts"), `export const a = 1;\n${block}`);
+    writeFileSync(join(testRepoPath, "to.ts"), "export const b = 2;\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -qm "Add from.ts and to.ts"`.quiet();
+    writeFileSync(join(testRepoPath, "from.ts"), "export const a = 1;\n");
+    writeFileSync(join(testRepoPath, "to.ts"), `export const b = 2;\n${block}`);
+    await Bun.$`git -C ${testRepoPath} commit -qam "Move the function"`.quiet();
+
+    const all = (await (await api("/api/diff?compare=HEAD~1&detect_moves=true")).json()) as DiffResponse;
+    expect(all.files.find((f) => f.path === "from.ts")?.moved_blocks?.[0]).toMatchObject({ direction: "moved_to", other_path: "to.ts" });
+    expect(all.files.find((f) => f.path === "to.ts")?.moved_blocks?.[0]).toMatchObject({ direction: "moved_from", other_path: "from.ts" });
+
+    const filtered = (await (await api("/api/diff?compare=HEAD~1&detect_moves=true&path=from.ts")).json()) as DiffResponse;
+    expect(filtered.files.map((f) => [f.path, f.moved_blocks])).toEqual([["from.ts", undefined]]);
+
+    await Bun.$`git -C ${testRepoPath} reset -q --hard HEAD~2`.quiet();
+  });
+
+  it("GET /api/diff gives similarity only on renames", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const start = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    const lines = Array.from({ length: 10 }, (_, i) => `line ${i}`);
+    writeFileSync(join(testRepoPath, "similar.txt"), lines.join("\n") + "\n");
+    await Bun.$`git -C ${testRepoPath} add similar.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add similar.txt"`.quiet();
+    try {
+      await Bun.$`git -C ${testRepoPath} mv similar.txt renamed.txt`.quiet();
+      writeFileSync(join(testRepoPath, "renamed.txt"), [...lines.slice(0, 9), "edited"].join("\n") + "\n");
+      writeFileSync(join(testRepoPath, "README.md"), "# Edited\n");
+      await Bun.$`git -C ${testRepoPath} commit -am "Rename and edit"`.quiet();
+
+      const range = encodeURIComponent("HEAD~1..HEAD");
+      const diff = (await (await api(`/api/diff?range=${range}`)).json()) as DiffResponse;
+      const modified = diff.files.find((f) => f.path === "README.md");
+      expect(modified?.status).toBe("modified");
+      expect(modified).not.toHaveProperty("similarity");
+      const renamed = diff.files.find((f) => f.path === "renamed.txt");
+      expect(renamed).toMatchObject({ status: "renamed", old_path: "similar.txt" });
+      expect(renamed?.similarity).toBeGreaterThanOrEqual(50);
+      expect(renamed?.similarity).toBeLessThan(100);
+    } finally {
+      await Bun.$`git -C ${testRepoPath} reset -q --hard ${start}`.quiet();
+    }
+  });
+
+  it("GET /api/diff groups unchanged files moved together", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    await Bun.$`mkdir -p ${join(testRepoPath, "moved/a")}`.quiet();
+    for (const name of ["one.txt", "two.txt"]) {
+      writeFileSync(join(testRepoPath, "moved/a", name), `${name}\n`);
+    }
+    await Bun.$`git -C ${testRepoPath} add moved`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add moved/a"`.quiet();
+    await Bun.$`git -C ${testRepoPath} mv moved/a moved/b`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Move moved/a"`.quiet();
+
+    const range = encodeURIComponent("HEAD~1..HEAD");
+    const plain = (await (await api(`/api/diff?range=${range}`)).json()) as DiffResponse;
+    expect(plain.files.map((f) => [f.old_path, f.path, f.similarity])).toEqual([
+      ["moved/a/one.txt", "moved/b/one.txt", 100],
+      ["moved/a/two.txt", "moved/b/two.txt", 100],
+    ]);
+    expect(plain.renames).toBeUndefined();
+
+    const grouped = (await (await api(`/api/diff?range=${range}&group_renames=true`)).json()) as DiffResponse;
+    expect(grouped.files).toEqual([]);
+    expect(grouped.renames).toEqual([
+      {
+        from: "moved/a",
+        to: "moved/b",
+        files: [
+          { old_path: "moved/a/one.txt", path: "moved/b/one.txt" },
+          { old_path: "moved/a/two.txt", path: "moved/b/two.txt" },
+        ],
+      },
+    ]);
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~2`.quiet();
+  });
+
+  it("GET /api/range-info bounds the walk with limit", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    for (const n of [1, 2]) {
+      writeFileSync(join(testRepoPath, `walk-${n}.txt`), `${n}\n`);
+      await Bun.$`git -C ${testRepoPath} add .`.quiet();
+      await Bun.$`git -C ${testRepoPath} commit -m ${`Walk ${n}`}`.quiet();
+    }
+
+    const res = await api("/api/range-info?compare=HEAD~2&limit=1");
+    expect(res.status).toBe(200);
+    const info = (await res.json()) as RangeInfo;
+    expect(info.commit_count).toBe(1);
+    expect(info.truncated).toBe(true);
+    expect((await api("/api/range-info?limit=0")).status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~2`.quiet();
+  });
+
+  it("GET /api/commits filters by author and aggregates their diffs", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const commitAs = async (name: string, file: string, contents: string) => {
+      writeFileSync(join(testRepoPath, file), contents);
+      await Bun.$`git -C ${testRepoPath} add .`.quiet();
+      await Bun.$`git -C ${testRepoPath} -c user.name=${name} -c user.email=${`${name.toLowerCase()}@example.com`} commit -m ${`${name} ${file}`}`.quiet();
+    };
+    await commitAs("Alice", "alice.txt", "1\n");
+    await commitAs("Bob", "bob.txt", "1\n");
+    await commitAs("Alice", "alice.txt", "1\n2\n");
+
+    const res = await api("/api/commits?compare=HEAD~3&author=ALICE&aggregate=true");
+    expect(res.status).toBe(200);
+    const list = (await res.json()) as RangeCommitList;
+    expect(list.commits.map((c) => c.summary)).toEqual(["Alice alice.txt", "Alice alice.txt"]);
+    expect(list.files?.map((f) => [f.path, f.status, f.additions, f.commits?.length])).toEqual([["alice.txt", "added", 2, 2]]);
+
+    // Email matches too
+    const bob = (await (await api("/api/commits?compare=HEAD~3&author=bob@example")).json()) as RangeCommitList;
+    expect(bob.commits.map((c) => c.author)).toEqual(["Bob"]);
+    expect(bob.files).toBeUndefined();
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~3`.quiet();
+  });
+
+  it("GET /api/commits?aggregate=true leaves out files added then deleted", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "scratch.txt"), "temp\n");
+    writeFileSync(join(testRepoPath, "kept.txt"), "1\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -qm "Add scratch"`.quiet();
+    await Bun.$`git -C ${testRepoPath} rm -q scratch.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -qm "Drop scratch"`.quiet();
+
+    const list = (await (await api("/api/commits?compare=HEAD~2&aggregate=true")).json()) as RangeCommitList;
+    expect(list.files?.map((f) => [f.path, f.status, f.commits?.length])).toEqual([["kept.txt", "added", 1]]);
+
+    await Bun.$`git -C ${testRepoPath} reset --hard HEAD~2`.quiet();
+  });
+
+  it("GET /api/diff?attribute=true attributes lines to the range commit that added them", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const base = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    writeFileSync(join(testRepoPath, "README.md"), "# Test Repo\nfirst\n");
+    await Bun.$`git -C ${testRepoPath} commit -qam "Add first"`.quiet();
+    const first = (await Bun.$`git -C ${testRepoPath} rev-parse --short=7 HEAD`.text()).trim();
+    writeFileSync(join(testRepoPath, "README.md"), "# Test Repo\nfirst\nsecond\n");
+    await Bun.$`git -C ${testRepoPath} commit -qam "Add second"`.quiet();
+    const second = (await Bun.$`git -C ${testRepoPath} rev-parse --short=7 HEAD`.text()).trim();
+
+    const diff = (await (await api(`/api/diff?range=${base}..HEAD&attribute=true`)).json()) as DiffResponse;
+    // Line 1 predates the range
+    expect(diff.files.find((f) => f.path === "README.md")?.line_commits).toEqual({ 2: first, 3: second });
+    expect(diff.attribution?.commits.map((c) => c.summary)).toEqual(["Add second", "Add first"]);
+
+    const file = (await (await api(`/api/file-diff?range=${base}..HEAD&file=README.md&attribute=true`)).json()) as FileDiff;
+    expect(file.line_commits).toEqual({ 2: first, 3: second });
+    expect((await api("/api/diff?attribute=true")).status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} reset -q --hard ${base}`.quiet();
+  });
+
+  it("GET /api/graph returns the commit DAG with merges", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const base = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    const commit = async (file: string) => {
+      writeFileSync(join(testRepoPath, file), `${file}\n`);
+      await Bun.$`git -C ${testRepoPath} add .`.quiet();
+      await Bun.$`git -C ${testRepoPath} commit -m ${`Add ${file}`}`.quiet();
+    };
+    await Bun.$`git -C ${testRepoPath} checkout -q -b graph-side`.quiet();
+    await commit("side.txt");
+    await Bun.$`git -C ${testRepoPath} checkout -q -`.quiet();
+    await commit("main.txt");
+    await Bun.$`git -C ${testRepoPath} merge -q --no-ff graph-side -m "Merge graph-side"`.quiet();
+
+    const res = await api(`/api/graph?compare=${base}`);
+    expect(res.status).toBe(200);
+    const graph = (await res.json()) as CommitGraph;
+    expect(graph.merge_base).toBe(base);
+    expect(graph.commits.map((c) => c.summary).sort()).toEqual(["Add main.txt", "Add side.txt", "Merge graph-side"]);
+    const [merge] = graph.commits;
+    expect(merge?.hash).toBe(graph.head);
+    expect(merge?.parents).toHaveLength(2);
+    // Both branches start from the merge base, outside the graph
+    expect(graph.commits.filter((c) => c.parents.includes(base))).toHaveLength(2);
+
+    const limited = (await (await api(`/api/graph?compare=${base}&limit=1`)).json()) as CommitGraph;
+    expect(limited.commits).toHaveLength(1);
+    expect(limited.truncated).toBe(true);
+    expect((await api("/api/graph?limit=0")).status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} reset -q --hard ${base}`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D graph-side`.quiet();
+  });
+
+  it("GET /api/diff/show/<merge>?combined=true diffs the merge against every parent", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const base = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    writeFileSync(join(testRepoPath, "shared.txt"), "a\nb\n");
+    await Bun.$`git -C ${testRepoPath} add shared.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -qm "Add shared.txt"`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout -q -b combined-side`.quiet();
+    writeFileSync(join(testRepoPath, "shared.txt"), "a\nside\n");
+    await Bun.$`git -C ${testRepoPath} commit -qam "Side edit"`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout -q -`.quiet();
+    writeFileSync(join(testRepoPath, "shared.txt"), "main\nb\n");
+    await Bun.$`git -C ${testRepoPath} commit -qam "Main edit"`.quiet();
+    await Bun.$`git -C ${testRepoPath} merge -q --no-commit combined-side`.quiet().nothrow();
+    // Resolve the conflict with a new line, and add a file only the merge has
+    writeFileSync(join(testRepoPath, "shared.txt"), "main\nside\nevil\n");
+    writeFileSync(join(testRepoPath, "merge-only.txt"), "new\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -qm "Merge combined-side"`.quiet();
+
+    const res = await api("/api/diff/show/HEAD?combined=true");
+    expect(res.status).toBe(200);
+    const diff = (await res.json()) as DiffResponse;
+    expect(diff.combined).toBe(true);
+    expect(diff.files.map((f) => [f.path, f.status]).sort()).toEqual([
+      ["merge-only.txt", "added"],
+      ["shared.txt", "modified"],
+    ]);
+    expect((await api("/api/diff/show/HEAD?combined=true&parent=2")).status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} reset -q --hard ${base}`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D combined-side`.quiet();
+  });
+
+  it("GET /api/conflicts lists unmerged paths mid-merge", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const base = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    await Bun.$`git -C ${testRepoPath} checkout -q -b conflict-side`.quiet();
+    writeFileSync(join(testRepoPath, "README.md"), "# Side\n");
+    await Bun.$`git -C ${testRepoPath} commit -qam "Side"`.quiet();
+    await Bun.$`git -C ${testRepoPath} checkout -q -`.quiet();
+    writeFileSync(join(testRepoPath, "README.md"), "# Here\n");
+    await Bun.$`git -C ${testRepoPath} commit -qam "Here"`.quiet();
+    await Bun.$`git -C ${testRepoPath} merge conflict-side`.quiet().nothrow();
+
+    const res = await api("/api/conflicts");
+    expect(res.status).toBe(200);
+    const list = (await res.json()) as ConflictList;
+    expect(list.files).toEqual([{ path: "README.md", base: true, ours: true, theirs: true, markers: 1 }]);
+
+    const diff = (await (await api("/api/diff?mode=working")).json()) as DiffResponse;
+    const readme = diff.files.find((f) => f.path === "README.md");
+    expect(readme?.conflicted).toBe(true);
+    expect(readme?.staged).toBe(false);
+
+    await Bun.$`git -C ${testRepoPath} merge --abort`.quiet();
+    expect(((await (await api("/api/conflicts")).json()) as ConflictList).files).toEqual([]);
+    await Bun.$`git -C ${testRepoPath} reset -q --hard ${base}`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D conflict-side`.quiet();
+  });
+
+  it("GET /api/force-push-diff diffs the tip before a rewrite against the current one", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const base = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    await Bun.$`git -C ${testRepoPath} checkout -q -b pushed`.quiet();
+    expect((await api("/api/force-push-diff")).status).toBe(404);
+
+    writeFileSync(join(testRepoPath, "pushed.txt"), "v1\n");
+    await Bun.$`git -C ${testRepoPath} add pushed.txt`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -qm "Pushed"`.quiet();
+    const oldTip = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    writeFileSync(join(testRepoPath, "pushed.txt"), "v2\n");
+    await Bun.$`git -C ${testRepoPath} commit -qa --amend -m "Pushed again"`.quiet();
+
+    const res = await api("/api/force-push-diff");
+    expect(res.status).toBe(200);
+    const diff = (await res.json()) as DiffResponse;
+    expect(diff.force_push?.old_tip).toBe(oldTip);
+    expect(diff.files.map((f) => [f.path, f.additions, f.deletions])).toEqual([["pushed.txt", 1, 1]]);
+    expect((await api("/api/force-push-diff?ref=no-such-branch")).status).toBe(400);
+
+    // As if `git gc` pruned the old tip
+    const log = join(testRepoPath, ".git", "logs", "refs", "heads", "pushed");
+    const tip = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+    writeFileSync(log, `${"1".repeat(40)} ${tip} Test <test@test.com> 1700000000 +0000\tpush: forced-update\n`);
+    const gone = await api("/api/force-push-diff");
+    expect(gone.status).toBe(410);
+    expect(((await gone.json()) as { code: string }).code).toBe("gone");
+
+    await Bun.$`git -C ${testRepoPath} checkout -q -`.quiet();
+    await Bun.$`git -C ${testRepoPath} reset -q --hard ${base}`.quiet();
+    await Bun.$`git -C ${testRepoPath} branch -D pushed`.quiet();
+  });
+
   it("GET /api/file-diff returns 400 without repo", async () => {
     const res = await api("/api/file-diff?file=README.md");
     expect(res.status).toBe(400);
@@ -342,6 +1016,72 @@ describe("viewed files API", () => {
     expect(data.success).toBe(true);
   });
 
+  it("POST /api/mark-viewed records git user.name as the reviewer", async () => {
+    await api("/api/repos", {
+      method: "POST",
+      body: { path: testRepoPath },
+    });
+    writeFileSync(join(testRepoPath, "README.md"), "# Reviewed\n");
+
+    await api("/api/mark-viewed", {
+      method: "POST",
+      body: { file_path: "README.md" },
+    });
+
+    const diff = (await (await api("/api/diff?mode=working")).json()) as DiffResponse;
+    const file = diff.files.find((f) => f.path === "README.md");
+    expect(file?.viewed).toBe(true);
+    expect(file?.viewed_by).toBe("Test");
+
+    // Cleanup
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+
+  it("carries viewed marks across an amend for unchanged files", async () => {
+    const repoPath = await createTestGitRepo(tempHome, "amend-repo");
+    const base = (await Bun.$`git -C ${repoPath} rev-parse --abbrev-ref HEAD`.text()).trim();
+    await Bun.$`git -C ${repoPath} checkout -b feature`.quiet();
+    writeFileSync(join(repoPath, "kept.txt"), "kept\n");
+    writeFileSync(join(repoPath, "edited.txt"), "before\n");
+    await Bun.$`git -C ${repoPath} add .`.quiet();
+    await Bun.$`git -C ${repoPath} commit -m "Feature"`.quiet();
+    await api("/api/repos", { method: "POST", body: { path: repoPath } });
+
+    const diffUrl = `/api/diff?mode=branch&compare=${base}`;
+    await api(diffUrl);
+    for (const file of ["kept.txt", "edited.txt"]) {
+      await api("/api/mark-viewed", { method: "POST", body: { file_path: file } });
+    }
+
+    writeFileSync(join(repoPath, "edited.txt"), "after\n");
+    await Bun.$`git -C ${repoPath} commit -a --amend -m "Feature (amended)"`.quiet();
+
+    const diff = (await (await api(diffUrl)).json()) as DiffResponse;
+    expect(diff.files.find((f) => f.path === "kept.txt")?.viewed).toBe(true);
+    expect(diff.files.find((f) => f.path === "edited.txt")?.viewed).toBe(false);
+  });
+
+  it("keeps a viewed mark when the file is renamed in a new commit", async () => {
+    const repoPath = await createTestGitRepo(tempHome, "rename-repo");
+    const base = (await Bun.$`git -C ${repoPath} rev-parse --abbrev-ref HEAD`.text()).trim();
+    await Bun.$`git -C ${repoPath} checkout -b feature`.quiet();
+    writeFileSync(join(repoPath, "before.txt"), "one\ntwo\nthree\n");
+    await Bun.$`git -C ${repoPath} add .`.quiet();
+    await Bun.$`git -C ${repoPath} commit -m "Feature"`.quiet();
+    await api("/api/repos", { method: "POST", body: { path: repoPath } });
+
+    const diffUrl = `/api/diff?mode=branch&compare=${base}`;
+    await api(diffUrl);
+    await api("/api/mark-viewed", { method: "POST", body: { file_path: "before.txt" } });
+
+    await Bun.$`git -C ${repoPath} mv before.txt after.txt`.quiet();
+    await Bun.$`git -C ${repoPath} commit -m "Rename"`.quiet();
+
+    const diff = (await (await api(diffUrl)).json()) as DiffResponse;
+    expect(diff.files.map((f) => f.path)).toEqual(["after.txt"]);
+    expect(diff.files[0]?.viewed).toBe(true);
+  });
+
   it("POST /api/unmark-viewed unmarks file", async () => {
     await api("/api/repos", {
       method: "POST",
@@ -363,6 +1103,207 @@ describe("viewed files API", () => {
     const data = (await res.json()) as ApiResponse;
     expect(data.success).toBe(true);
   });
+
+  it("GET /api/diff?patch_encoding=base64 encodes each patch", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "README.md"), "# Encoded \u00e9\n");
+
+    const diff = (await (await api("/api/diff?mode=working&patch_encoding=base64")).json()) as DiffResponse;
+    const file = diff.files.find((f) => f.path === "README.md");
+    expect(file?.patch_encoding).toBe("base64");
+    expect(Buffer.from(file?.patch ?? "", "base64").toString("utf8")).toContain("+# Encoded \u00e9");
+    expect((await api("/api/diff?patch_encoding=hex")).status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+
+  it("GET /api/file-diff?patch_encoding=base64 sends git's bytes even when they aren't UTF-8", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    // Latin-1 "café": invalid UTF-8 that a text round trip would turn into U+FFFD
+    writeFileSync(join(testRepoPath, "README.md"), Buffer.from([0x63, 0x61, 0x66, 0xe9, 0x0a]));
+
+    const file = (await (await api("/api/file-diff?mode=working&file=README.md&patch_encoding=base64")).json()) as FileDiff;
+    const expected = await Bun.$`git -C ${testRepoPath} diff -- README.md`.quiet().arrayBuffer();
+    expect(Buffer.from(file.patch, "base64").equals(Buffer.from(expected))).toBe(true);
+
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+
+  it("GET /api/file-diff doesn't mark files viewed", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "README.md"), "# Opened\n");
+
+    const opened = (await (await api("/api/file-diff?mode=working&file=README.md&mark=true")).json()) as FileDiff;
+    expect(opened.viewed).toBeFalsy();
+    const diff = (await (await api("/api/diff?mode=working")).json()) as DiffResponse;
+    expect(diff.files.find((f) => f.path === "README.md")?.viewed).toBe(false);
+    expect(diff.auto_mark_on_open).toBeUndefined();
+
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+
+  it("GET /api/file-diff?mode=full pairs a renamed file with its source", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    await Bun.$`git -C ${testRepoPath} mv README.md README-renamed.md`.quiet();
+    try {
+      const diff = (await (await api("/api/diff?mode=full")).json()) as DiffResponse;
+      expect(diff.files.find((f) => f.path === "README-renamed.md")).toMatchObject({ status: "renamed", old_path: "README.md" });
+
+      const file = (await (await api("/api/file-diff?mode=full&file=README-renamed.md")).json()) as FileDiff;
+      expect(file.status).toBe("renamed");
+      expect(file.old_path).toBe("README.md");
+      expect(file.additions).toBe(0);
+      expect(file.deletions).toBe(0);
+    } finally {
+      await Bun.$`git -C ${testRepoPath} mv README-renamed.md README.md`.quiet();
+    }
+  });
+
+  it("GET /api/diff tells the UI to mark files as they're opened under --auto-mark-on-open", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    const autoServer = await startServer({ port: port + 12, autoMarkOnOpen: true });
+    try {
+      const res = await fetch(`http://localhost:${port + 12}/api/diff?repo=${repo.id}`);
+      expect(((await res.json()) as DiffResponse).auto_mark_on_open).toBe(true);
+    } finally {
+      autoServer.stop();
+    }
+  });
+
+  it("rate-limits single-commit diffs, stats and blame and leaves cheap routes alone", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    const limitedServer = await startServer({ port: port + 13, rateLimit: 1 });
+    const get = (path: string) => fetch(`http://localhost:${port + 13}${path}?repo=${repo.id}`);
+    try {
+      expect((await get("/api/diff/show/HEAD")).status).toBe(200);
+      expect((await get("/api/diff/show/HEAD")).status).toBe(429);
+      expect((await get("/api/stats/diffstat")).status).toBe(429);
+      expect((await get("/api/blame")).status).toBe(429);
+      expect((await get("/api/status")).status).toBe(200);
+      expect((await get("/api/status")).status).toBe(200);
+    } finally {
+      limitedServer.stop();
+    }
+  });
+
+  it("a new server cancels the previous server's idle shutdown", async () => {
+    let shutdowns = 0;
+    const idleServer = await startServer({ port: port + 14, idleTimeout: 0.2, onIdleShutdown: () => shutdowns++ });
+    const nextServer = await startServer({ port: port + 15 });
+    try {
+      await Bun.sleep(400);
+      expect(shutdowns).toBe(0);
+    } finally {
+      idleServer.stop();
+      nextServer.stop();
+    }
+  });
+
+  it("flags added text files as full_new_file with their clean contents", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "brand-new.ts"), "export const x = 1;\n");
+    writeFileSync(join(testRepoPath, "README.md"), "# Changed\n");
+    await Bun.$`git -C ${testRepoPath} add .`.quiet();
+    await Bun.$`git -C ${testRepoPath} commit -m "Add brand-new.ts"`.quiet();
+
+    const diff = (await (await api("/api/diff?compare=HEAD~1")).json()) as DiffResponse;
+    expect(diff.files.find((f) => f.path === "brand-new.ts")?.full_new_file).toBe(true);
+    expect(diff.files.find((f) => f.path === "README.md")?.full_new_file).toBeUndefined();
+
+    const file = (await (await api("/api/file-diff?compare=HEAD~1&file=brand-new.ts")).json()) as FileDiff;
+    expect(file.full_new_file).toBe(true);
+    expect(file.new_file?.contents).toBe("export const x = 1;\n");
+
+    await Bun.$`git -C ${testRepoPath} reset -q --hard HEAD~1`.quiet();
+  });
+
+  it("GET /api/diff?format=github returns GitHub-shaped files", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "README.md"), "# GitHub\n");
+
+    const files = (await (await api("/api/diff?mode=working&format=github")).json()) as Array<Record<string, unknown>>;
+    const readme = files.find((f) => f["filename"] === "README.md");
+    expect(readme).toMatchObject({ status: "modified", additions: 1, deletions: 1, changes: 2 });
+    expect(String(readme?.["patch"])).toStartWith("@@");
+    expect((await api("/api/diff?format=gitlab")).status).toBe(400);
+
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+
+  it("GET /api/export.zip archives each patch with a summary", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    writeFileSync(join(testRepoPath, "README.md"), "# Exported\n");
+
+    const res = await api("/api/export.zip?mode=working");
+    expect(res.headers.get("content-type")).toBe("application/zip");
+    expect(res.headers.get("content-disposition")).toContain(".zip");
+    // Entries are stored uncompressed, so names and patch text show through
+    const body = Buffer.from(await res.arrayBuffer()).toString("utf8");
+    expect(body.startsWith("PK")).toBe(true);
+    expect(body).toContain("summary.json");
+    expect(body).toContain("patches/README.md.patch");
+    expect(body).toContain("+# Exported");
+    expect(body).toContain('"mode": "working"');
+
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+
+  it("POST /api/collapse persists collapsed state into /api/diff", async () => {
+    await api("/api/repos", {
+      method: "POST",
+      body: { path: testRepoPath },
+    });
+    writeFileSync(join(testRepoPath, "README.md"), "# Collapsed\n");
+
+    const res = await api("/api/collapse", {
+      method: "POST",
+      body: { file_path: "README.md", collapsed: true },
+    });
+    expect(res.status).toBe(200);
+
+    const diff = (await (await api("/api/diff?mode=working")).json()) as DiffResponse;
+    expect(diff.files.find((f) => f.path === "README.md")?.collapsed).toBe(true);
+
+    // Cleanup
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+
+  it("POST /api/position is returned with the diff as last_position", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+
+    const res = await api("/api/position", { method: "POST", body: { file_path: "README.md", line: 4 } });
+    expect(res.status).toBe(200);
+    expect((await api("/api/position", { method: "POST", body: { file_path: "README.md", line: 0 } })).status).toBe(400);
+
+    const diff = (await (await api("/api/diff")).json()) as DiffResponse;
+    expect(diff.last_position).toMatchObject({ file_path: "README.md", line: 4 });
+    const saved = (await (await api("/api/position")).json()) as { position: { file_path: string } | null };
+    expect(saved.position?.file_path).toBe("README.md");
+  });
+
+  it("GET /api/reviews lists viewed progress per branch", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    await api("/api/mark-viewed", { method: "POST", body: { file_path: "README.md" } });
+
+    const data = (await (await api("/api/reviews?totals=true")).json()) as { reviews: ReviewSummary[] };
+    expect(data.reviews).toHaveLength(1);
+    expect(data.reviews[0]).toMatchObject({ kind: "branch", viewed: 1, total: 0 });
+    expect(data.reviews[0]?.stale).toBeUndefined();
+    expect(data.reviews[0]?.url).toContain("repo=");
+  });
+
+  it("POST /api/collapse requires a collapsed flag", async () => {
+    await api("/api/repos", {
+      method: "POST",
+      body: { path: testRepoPath },
+    });
+
+    const res = await api("/api/collapse", {
+      method: "POST",
+      body: { file_path: "README.md" },
+    });
+    expect(res.status).toBe(400);
+  });
 });
 
 // =============================================================================
@@ -408,6 +1349,8 @@ describe("comments API", () => {
     expect(comment.line_number).toBe(1);
     expect(comment.text).toBe("Test comment");
     expect(comment.resolved).toBe(false);
+    // Defaults to the repo's git user.name
+    expect(comment.author).toBe("Test");
   });
 
   it("POST /api/comments/resolve resolves comment", async () => {
@@ -581,14 +1524,100 @@ describe("git operations API", () => {
   });
 });
 
+// =============================================================================
+// UI Config
+// =============================================================================
+
+describe("shortcuts config API", () => {
+  afterEach(() => {
+    rmSync(join(configDir, "config.json"), { force: true });
+  });
+
+  it("GET /api/config/shortcuts returns the defaults", async () => {
+    const res = await api("/api/config/shortcuts");
+    expect(res.status).toBe(200);
+    const data = (await res.json()) as { shortcuts: Record<string, string> };
+    expect(data.shortcuts["next_file"]).toBe("j");
+  });
+
+  it("POST /api/config/shortcuts persists overrides", async () => {
+    const res = await api("/api/config/shortcuts", {
+      method: "POST",
+      body: { shortcuts: { next_file: "n", prev_file: "p" } },
+    });
+    expect(res.status).toBe(200);
+
+    const data = (await (await api("/api/config/shortcuts")).json()) as { shortcuts: Record<string, string> };
+    expect(data.shortcuts["next_file"]).toBe("n");
+    expect(data.shortcuts["prev_file"]).toBe("p");
+    expect((await state.getConfig()).shortcuts).toEqual({ next_file: "n", prev_file: "p" });
+  });
+
+  it("POST /api/config/shortcuts rejects duplicate keys", async () => {
+    const res = await api("/api/config/shortcuts", { method: "POST", body: { shortcuts: { next_file: "k" } } });
+    expect(res.status).toBe(400);
+    const data = (await res.json()) as ApiResponse;
+    expect(data.error).toContain("next_file and prev_file");
+  });
+});
+
+describe("preferences API", () => {
+  it("POST /api/prefs merges into GET /api/prefs", async () => {
+    await api("/api/prefs", { method: "POST", body: { prefs: { theme: "dark", font_size: 14 } } });
+    const res = await api("/api/prefs", { method: "POST", body: { prefs: { font_size: null } } });
+    expect(res.status).toBe(200);
+
+    const data = (await (await api("/api/prefs")).json()) as { prefs: Record<string, unknown> };
+    expect(data.prefs).toEqual({ theme: "dark" });
+  });
+
+  it("POST /api/prefs requires a prefs object", async () => {
+    const res = await api("/api/prefs", { method: "POST", body: { theme: "dark" } });
+    expect(res.status).toBe(400);
+  });
+});
+
+// =============================================================================
+// Open in Editor
+// =============================================================================
+
+describe("open in editor API", () => {
+  it("POST /api/open is disabled without --allow-open", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+    const res = await api("/api/open", { method: "POST", body: { file_path: "README.md", line: 1 } });
+    expect(res.status).toBe(403);
+  });
+
+  it("POST /api/open refuses requests from other origins", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    const openServer = await startServer({ port: port + 11, allowOpen: true, editor: "true" });
+    try {
+      const open = (headers: Record<string, string>) =>
+        fetch(`http://localhost:${port + 11}/api/open?repo=${repo.id}`, {
+          method: "POST",
+          headers: { "Content-Type": "application/json", ...headers },
+          body: JSON.stringify({ file_path: "README.md" }),
+        });
+      expect((await open({ Origin: "http://elsewhere.test" })).status).toBe(403);
+      expect((await open({ Origin: `http://localhost:${port + 11}` })).status).toBe(200);
+      expect((await open({})).status).toBe(200);
+      expect((await fetch(`http://localhost:${port + 11}/api/open?path=README.md`)).status).toBe(404);
+    } finally {
+      openServer.stop();
+    }
+  });
+});
+
 // =============================================================================
 // Edge Cases
 // =============================================================================
 
 describe("edge cases", () => {
-  it("API returns CORS headers on all responses", async () => {
-    const res = await api("/api/health");
-    expect(res.headers.get("Access-Control-Allow-Origin")).toBe("*");
+  it("API omits CORS headers for cross-origin requests by default", async () => {
+    const res = await fetch(`${baseUrl}/api/health`, {
+      headers: { Origin: "http://elsewhere.test" },
+    });
+    expect(res.headers.get("Access-Control-Allow-Origin")).toBeNull();
   });
 
   it("handles repo query parameter", async () => {
@@ -608,3 +1637,513 @@ describe("edge cases", () => {
     expect(res.status).toBe(400);
   });
 });
+
+// =============================================================================
+// CORS
+// =============================================================================
+
+describe("CORS origins", () => {
+  const origin = "http://dashboard.test";
+  let corsServer: ReturnType<typeof Bun.serve>;
+  let corsUrl: string;
+
+  beforeAll(async () => {
+    const corsPort = port + 1;
+    corsUrl = `http://localhost:${corsPort}`;
+    corsServer = await startServer({ port: corsPort, corsOrigins: [origin] });
+  });
+
+  afterAll(() => {
+    corsServer.stop();
+  });
+
+  it("echoes an allowed origin on API responses", async () => {
+    const res = await fetch(`${corsUrl}/api/health`, { headers: { Origin: origin } });
+    expect(res.headers.get("Access-Control-Allow-Origin")).toBe(origin);
+    expect(res.headers.get("Vary")).toBe("Origin");
+  });
+
+  it("omits headers for origins not in the list", async () => {
+    const res = await fetch(`${corsUrl}/api/health`, { headers: { Origin: "http://other.test" } });
+    expect(res.headers.get("Access-Control-Allow-Origin")).toBeNull();
+  });
+
+  it("answers preflight requests for allowed origins", async () => {
+    const res = await fetch(`${corsUrl}/api/mark-viewed`, {
+      method: "OPTIONS",
+      headers: { Origin: origin, "Access-Control-Request-Method": "POST" },
+    });
+    expect(res.status).toBe(204);
+    expect(res.headers.get("Access-Control-Allow-Origin")).toBe(origin);
+    expect(res.headers.get("Access-Control-Allow-Methods")).toContain("POST");
+  });
+});
+
+// =============================================================================
+// Read-only Mode
+// =============================================================================
+
+describe("read-only mode", () => {
+  let readOnlyServer: ReturnType<typeof Bun.serve>;
+  let readOnlyUrl: string;
+
+  beforeAll(async () => {
+    const readOnlyPort = port + 2;
+    readOnlyUrl = `http://localhost:${readOnlyPort}`;
+    readOnlyServer = await startServer({ port: readOnlyPort, readOnly: true });
+  });
+
+  afterAll(() => {
+    readOnlyServer.stop();
+  });
+
+  it("rejects mark-viewed with 403", async () => {
+    const res = await fetch(`${readOnlyUrl}/api/mark-viewed`, {
+      method: "POST",
+      body: JSON.stringify({ file_path: "README.md" }),
+      headers: { "Content-Type": "application/json" },
+    });
+    expect(res.status).toBe(403);
+  });
+
+  it("rejects other mutating endpoints with 403", async () => {
+    const res = await fetch(`${readOnlyUrl}/api/comments`, {
+      method: "POST",
+      body: JSON.stringify({ file_path: "README.md", text: "nope" }),
+      headers: { "Content-Type": "application/json" },
+    });
+    expect(res.status).toBe(403);
+  });
+
+  it("still reports existing viewed state in the diff", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    const git = getGitManager(testRepoPath);
+    writeFileSync(join(testRepoPath, "read-only.txt"), "shown\n");
+    await state.setFileViewed(repo.id, await git.getCurrentBranch(), await git.getCurrentCommit(), "read-only.txt", true);
+
+    const res = await fetch(`${readOnlyUrl}/api/diff?repo=${repo.id}&mode=working`);
+    expect(res.status).toBe(200);
+    const data = (await res.json()) as DiffResponse;
+    expect(data.files.find((f) => f.path === "read-only.txt")?.viewed).toBe(true);
+
+    await Bun.$`rm ${join(testRepoPath, "read-only.txt")}`.quiet();
+  });
+
+  it("doesn't carry viewed marks forward when HEAD moves", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    const git = getGitManager(testRepoPath);
+    const branch = await git.getCurrentBranch();
+    const before = await git.getCurrentCommit();
+    await state.setBranchHead(repo.id, branch, before);
+    await state.setFileViewed(repo.id, branch, before, "README.md", true);
+    await Bun.$`git -C ${testRepoPath} commit -q --allow-empty -m "Empty"`.quiet();
+
+    const res = await fetch(`${readOnlyUrl}/api/diff?repo=${repo.id}`);
+    expect(res.status).toBe(200);
+    expect(await state.getBranchHead(repo.id, branch)).toBe(before);
+    expect(await state.getViewedFiles(repo.id, branch, await git.getCurrentCommit())).toEqual({});
+
+    await Bun.$`git -C ${testRepoPath} reset -q --hard HEAD~1`.quiet();
+  });
+});
+
+// =============================================================================
+// Initial Data Injection
+// =============================================================================
+
+describe("initial data injection", () => {
+  const indexHtml = "<!doctype html><html><head><title>Cerebro</title></head><body></body></html>";
+  let assetServer: ReturnType<typeof Bun.serve>;
+  let assetUrl: string;
+
+  beforeAll(async () => {
+    const assetPort = port + 3;
+    assetUrl = `http://localhost:${assetPort}`;
+    const assets = new Map([
+      ["index.html", { content: Buffer.from(indexHtml).toString("base64"), mimeType: "text/html" }],
+    ]);
+    assetServer = await startServer({ port: assetPort, assets });
+  });
+
+  afterAll(() => {
+    assetServer.stop();
+  });
+
+  it("injectInitialData escapes closing script tags", () => {
+    const html = injectInitialData(indexHtml, {
+      repo_id: "r1",
+      diff: {
+        files: [],
+        branch: "</script><b>$&",
+        commit: "abc1234",
+        repo_path: "/tmp/x",
+        mode: "branch",
+        base_branch: "main",
+      },
+    });
+    expect(html).toContain("window.__CEREBRO_INITIAL_DATA__");
+    expect(html).not.toContain("</script><b>");
+    expect(html).toContain("$&");
+    expect(html.indexOf("__CEREBRO_INITIAL_DATA__")).toBeLessThan(html.indexOf("</head>"));
+  });
+
+  it("serves index.html with the current repo's diff inlined", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+
+    const res = await fetch(`${assetUrl}/`);
+    expect(res.status).toBe(200);
+    const html = await res.text();
+    expect(html).toContain("window.__CEREBRO_INITIAL_DATA__");
+    expect(html).toContain(`"repo_path":"${testRepoPath}"`);
+  });
+
+  it("serves other client routes without computing a diff", async () => {
+    await api("/api/repos", { method: "POST", body: { path: testRepoPath } });
+
+    const res = await fetch(`${assetUrl}/settings`);
+    expect(res.status).toBe(200);
+    expect(await res.text()).toBe(indexHtml);
+  });
+
+  it("serves plain index.html when no repo is selected", async () => {
+    const res = await fetch(`${assetUrl}/`);
+    const html = await res.text();
+    expect(html).toBe(indexHtml);
+  });
+
+  it("serves a built-in favicon", async () => {
+    const res = await fetch(`${assetUrl}/favicon.ico`);
+    expect(res.status).toBe(200);
+    expect(res.headers.get("Content-Type")).toBe("image/svg+xml");
+    expect(await res.text()).toContain("<svg");
+  });
+
+  it("returns 404 for missing assets instead of index.html", async () => {
+    const res = await fetch(`${assetUrl}/assets/missing.js`);
+    expect(res.status).toBe(404);
+
+    const route = await fetch(`${assetUrl}/some/client/route`);
+    expect(route.status).toBe(200);
+    expect(route.headers.get("Content-Type")).toBe("text/html; charset=utf-8");
+  });
+});
+
+// =============================================================================
+// Git Notes Review State
+// =============================================================================
+
+describe("git notes review state", () => {
+  let notesServer: ReturnType<typeof Bun.serve>;
+  let notesUrl: string;
+
+  beforeAll(async () => {
+    const notesPort = port + 4;
+    notesUrl = `http://localhost:${notesPort}`;
+    notesServer = await startServer({ port: notesPort, gitNotes: true });
+  });
+
+  afterAll(async () => {
+    notesServer.stop();
+    await Bun.$`git -C ${testRepoPath} update-ref -d refs/notes/cerebro-review`.quiet().nothrow();
+  });
+
+  async function post(path: string, body: unknown, repoId: string): Promise<Response> {
+    return fetch(`${notesUrl}${path}?repo=${repoId}`, {
+      method: "POST",
+      body: JSON.stringify(body),
+      headers: { "Content-Type": "application/json" },
+    });
+  }
+
+  it("writes viewed marks to refs/notes/cerebro-review", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    await post("/api/mark-viewed", { file_path: "README.md" }, repo.id);
+
+    const note = await Bun.$`git -C ${testRepoPath} notes --ref=refs/notes/cerebro-review show HEAD`.text();
+    const branch = await getGitManager(testRepoPath).getCurrentBranch();
+    expect(JSON.parse(note).viewed[branch]["README.md"].viewed_by).toBe("Test");
+
+    await post("/api/unmark-viewed", { file_path: "README.md" }, repo.id);
+    const cleared = await Bun.$`git -C ${testRepoPath} notes --ref=refs/notes/cerebro-review show HEAD`.text();
+    expect(JSON.parse(cleared).viewed[branch]["README.md"]).toBeUndefined();
+  });
+
+  it("reports marks that only exist in notes as viewed", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    const git = getGitManager(testRepoPath);
+    const branch = await git.getCurrentBranch();
+    await git.setReviewNote(await git.getCurrentCommit(), {
+      viewed: { [branch]: { "notes-only.txt": { viewed_at: 1, viewed_by: "teammate" } } },
+    });
+    writeFileSync(join(testRepoPath, "notes-only.txt"), "shared\n");
+
+    const res = await fetch(`${notesUrl}/api/diff?repo=${repo.id}&mode=working`);
+    const data = (await res.json()) as DiffResponse;
+    const file = data.files.find((f) => f.path === "notes-only.txt");
+    expect(file?.viewed).toBe(true);
+    expect(file?.viewed_by).toBe("teammate");
+
+    await Bun.$`rm ${join(testRepoPath, "notes-only.txt")}`.quiet();
+  });
+});
+
+// =============================================================================
+// Ignore Patterns
+// =============================================================================
+
+describe("ignore patterns", () => {
+  let collapseServer: ReturnType<typeof Bun.serve>;
+  let hideServer: ReturnType<typeof Bun.serve>;
+  let collapseUrl: string;
+  let hideUrl: string;
+
+  beforeAll(async () => {
+    collapseUrl = `http://localhost:${port + 5}`;
+    hideUrl = `http://localhost:${port + 6}`;
+    collapseServer = await startServer({ port: port + 5, ignore: ["generated/"] });
+    hideServer = await startServer({ port: port + 6, ignore: ["generated/"], ignoreMode: "hide" });
+  });
+
+  afterAll(() => {
+    collapseServer.stop();
+    hideServer.stop();
+  });
+
+  it("collapses or hides matching files", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    mkdirSync(join(testRepoPath, "generated"), { recursive: true });
+    writeFileSync(join(testRepoPath, "generated", "types.ts"), "// generated\n");
+    writeFileSync(join(testRepoPath, "README.md"), "# Changed\n");
+
+    const collapsed = (await (await fetch(`${collapseUrl}/api/diff?repo=${repo.id}&mode=working`)).json()) as DiffResponse;
+    const generated = collapsed.files.find((f) => f.path === "generated/types.ts");
+    expect(generated?.ignored).toBe(true);
+    expect(generated?.collapsed).toBe(true);
+    expect(collapsed.files.find((f) => f.path === "README.md")?.collapsed).toBe(false);
+
+    const hidden = (await (await fetch(`${hideUrl}/api/diff?repo=${repo.id}&mode=working`)).json()) as DiffResponse;
+    expect(hidden.files.map((f) => f.path)).toEqual(["README.md"]);
+
+    const estimate = (await (await fetch(`${hideUrl}/api/estimate?repo=${repo.id}&mode=working`)).json()) as DiffEstimate;
+    expect(estimate.files).toBe(1);
+    const stat = (await (await fetch(`${hideUrl}/api/stats/diffstat?repo=${repo.id}&mode=working`)).json()) as DiffStat;
+    expect(stat.files.map((f) => f.path)).toEqual(["README.md"]);
+
+    rmSync(join(testRepoPath, "generated"), { recursive: true, force: true });
+    await Bun.$`git -C ${testRepoPath} checkout -- README.md`.quiet();
+  });
+});
+
+// =============================================================================
+// Content-keyed viewed state
+// =============================================================================
+
+describe("content-keyed viewed state", () => {
+  let contentServer: ReturnType<typeof Bun.serve>;
+  let contentUrl: string;
+
+  beforeAll(async () => {
+    contentUrl = `http://localhost:${port + 7}`;
+    contentServer = await startServer({ port: port + 7, stateKey: "content" });
+  });
+
+  afterAll(() => {
+    contentServer.stop();
+  });
+
+  it("keeps a file viewed on another branch with identical content", async () => {
+    const repoPath = await createTestGitRepo(tempHome, "content-key-repo");
+    const base = (await Bun.$`git -C ${repoPath} rev-parse --abbrev-ref HEAD`.text()).trim();
+    await Bun.$`git -C ${repoPath} checkout -b first`.quiet();
+    writeFileSync(join(repoPath, "feature.txt"), "feature\n");
+    await Bun.$`git -C ${repoPath} add .`.quiet();
+    await Bun.$`git -C ${repoPath} commit -m "Feature"`.quiet();
+    const repo = await state.addRepo(repoPath, "content-key-repo", base);
+
+    const query = `repo=${repo.id}&mode=branch&compare=${base}`;
+    const post = (path: string) =>
+      fetch(`${contentUrl}${path}?repo=${repo.id}`, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ file_path: "feature.txt" }),
+      });
+    await post("/api/mark-viewed");
+
+    await Bun.$`git -C ${repoPath} checkout -b second`.quiet();
+    const diff = (await (await fetch(`${contentUrl}/api/diff?${query}`)).json()) as DiffResponse;
+    expect(diff.files[0]?.viewed).toBe(true);
+
+    // Unmarking clears this branch's marks on the content, at any of its commits
+    await post("/api/mark-viewed");
+    writeFileSync(join(repoPath, "other.txt"), "other\n");
+    await Bun.$`git -C ${repoPath} add .`.quiet();
+    await Bun.$`git -C ${repoPath} commit -m "Other"`.quiet();
+    await post("/api/unmark-viewed");
+    const commitOf = async (ref: string) => (await Bun.$`git -C ${repoPath} rev-parse --short=7 ${ref}`.text()).trim();
+    expect(await state.getViewedFiles(repo.id, "second", await commitOf("HEAD~1"))).toEqual({});
+    expect(await state.getViewedFiles(repo.id, "first", await commitOf("first"))).toEqual({ "feature.txt": true });
+  });
+
+  it("matches working-tree content in working modes", async () => {
+    const repoPath = await createTestGitRepo(tempHome, "content-worktree-repo");
+    const base = (await Bun.$`git -C ${repoPath} rev-parse --abbrev-ref HEAD`.text()).trim();
+    const repo = await state.addRepo(repoPath, "content-worktree-repo", base);
+    writeFileSync(join(repoPath, "README.md"), "edited\n");
+    await fetch(`${contentUrl}/api/mark-viewed?repo=${repo.id}&mode=working`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ file_path: "README.md" }),
+    });
+
+    // The same edit on another branch is already viewed; other content isn't
+    await Bun.$`git -C ${repoPath} checkout -q -b elsewhere`.quiet();
+    const query = `repo=${repo.id}&mode=working`;
+    const same = (await (await fetch(`${contentUrl}/api/diff?${query}`)).json()) as DiffResponse;
+    expect(same.files.find((f) => f.path === "README.md")?.viewed).toBe(true);
+    writeFileSync(join(repoPath, "README.md"), "edited again\n");
+    const changed = (await (await fetch(`${contentUrl}/api/diff?${query}`)).json()) as DiffResponse;
+    expect(changed.files.find((f) => f.path === "README.md")?.viewed).toBe(false);
+  });
+});
+
+// =============================================================================
+// File limit
+// =============================================================================
+
+describe("max files", () => {
+  let limitServer: ReturnType<typeof Bun.serve>;
+  let limitUrl: string;
+
+  beforeAll(async () => {
+    limitUrl = `http://localhost:${port + 8}`;
+    limitServer = await startServer({ port: port + 8, maxFiles: 1 });
+  });
+
+  afterAll(() => {
+    limitServer.stop();
+  });
+
+  it("refuses unpaged diffs over the limit and reports it in the estimate", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    writeFileSync(join(testRepoPath, "one.txt"), "1\n");
+    writeFileSync(join(testRepoPath, "two.txt"), "2\n");
+    const query = `repo=${repo.id}&mode=working`;
+
+    const refused = await fetch(`${limitUrl}/api/diff?${query}`);
+    expect(refused.status).toBe(413);
+    expect(((await refused.json()) as { max_files: number }).max_files).toBe(1);
+
+    const scoped = await fetch(`${limitUrl}/api/diff?${query}&path=one.txt`);
+    expect(scoped.status).toBe(200);
+    const paged = await fetch(`${limitUrl}/api/diff?${query}&limit=1`);
+    expect(paged.status).toBe(200);
+
+    const estimate = (await (await fetch(`${limitUrl}/api/estimate?${query}`)).json()) as DiffEstimate;
+    expect(estimate.files).toBe(2);
+    expect(estimate.exceeds_max_files).toBe(true);
+
+    rmSync(join(testRepoPath, "one.txt"));
+    rmSync(join(testRepoPath, "two.txt"));
+  });
+});
+
+describe("allowed bases", () => {
+  let allowServer: ReturnType<typeof Bun.serve>;
+  let allowUrl: string;
+
+  beforeAll(async () => {
+    allowUrl = `http://localhost:${port + 10}`;
+    allowServer = await startServer({ port: port + 10, allowedBases: ["main"] });
+  });
+
+  afterAll(() => {
+    stopServer(allowServer);
+  });
+
+  it("checks request refs but not the review's own lookups", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    const query = `repo=${repo.id}&mode=branch`;
+    const head = (await Bun.$`git -C ${testRepoPath} rev-parse HEAD`.text()).trim();
+
+    expect((await fetch(`${allowUrl}/api/diff?${query}`)).status).toBe(200);
+    const refused = await fetch(`${allowUrl}/api/diff?${query}&compare=HEAD~1`);
+    expect(refused.status).toBe(403);
+    expect(((await refused.json()) as { code: string }).code).toBe("forbidden");
+    expect((await fetch(`${allowUrl}/api/diff?${query}&range=HEAD~1..HEAD`)).status).toBe(403);
+    expect((await fetch(`${allowUrl}/api/diff/show/${head.slice(0, 7)}?repo=${repo.id}`)).status).toBe(403);
+
+    // HEAD, full oids and since=last's stored commit resolve as usual
+    expect((await fetch(`${allowUrl}/api/diff/show/${head}?repo=${repo.id}`)).status).toBe(200);
+    expect((await fetch(`${allowUrl}/api/diff?${query}&range=main..HEAD`)).status).toBe(200);
+    await fetch(`${allowUrl}/api/mark-viewed?repo=${repo.id}`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ file_path: "README.md" }),
+    });
+    expect((await fetch(`${allowUrl}/api/diff?${query}&since=last`)).status).toBe(200);
+  });
+
+  it("stopServer takes the allowlist away so it doesn't reach the servers still running", async () => {
+    const repo = await state.addRepo(testRepoPath, "test-repo", "main");
+    const strictServer = await startServer({ port: port + 17, allowedBases: ["main"] });
+    stopServer(strictServer);
+    expect((await api(`/api/diff?repo=${repo.id}&mode=branch&compare=HEAD~1`)).status).toBe(200);
+  });
+});
+
+// =============================================================================
+// Startup precompute
+// =============================================================================
+
+describe("precompute", () => {
+  it("drops the startup diff once HEAD has moved", async () => {
+    const base = (await Bun.$`git -C ${testRepoPath} rev-parse --abbrev-ref HEAD`.text()).trim();
+    const repo = await state.addRepo(testRepoPath, "test-repo", base);
+    await state.setCurrentRepo(repo.id);
+    await Bun.$`git -C ${testRepoPath} checkout -b precompute`.quiet();
+    const precomputeServer = await startServer({ port: port + 9, precompute: true });
+    try {
+      writeFileSync(join(testRepoPath, "late.txt"), "late\n");
+      await Bun.$`git -C ${testRepoPath} add late.txt`.quiet();
+      await Bun.$`git -C ${testRepoPath} commit -m "Late"`.quiet();
+
+      const res = await fetch(`http://localhost:${port + 9}/api/diff?mode=branch`);
+      const diff = (await res.json()) as DiffResponse;
+      expect(diff.files.map((f) => f.path)).toEqual(["late.txt"]);
+    } finally {
+      precomputeServer.stop();
+      await Bun.$`git -C ${testRepoPath} checkout ${base}`.quiet();
+      await Bun.$`git -C ${testRepoPath} branch -D precompute`.quiet();
+    }
+  });
+
+  it("computes a ?minimal= request itself instead of using the startup diff", async () => {
+    // Seeded noise where --minimal finds fewer changed lines than the default algorithm
+    const noise = (seed: number) => {
+      let x = seed;
+      return Array.from({ length: 800 }, () => String((x = (x * 48271) % 2147483647) % 4)).join("\n") + "\n";
+    };
+    const repoPath = await createTestGitRepo(tempHome, "precompute-minimal");
+    const base = (await Bun.$`git -C ${repoPath} rev-parse --abbrev-ref HEAD`.text()).trim();
+    writeFileSync(join(repoPath, "noise.txt"), noise(1));
+    await Bun.$`git -C ${repoPath} add .`.quiet();
+    await Bun.$`git -C ${repoPath} commit -m "Base"`.quiet();
+    await Bun.$`git -C ${repoPath} checkout -b rewrite`.quiet();
+    writeFileSync(join(repoPath, "noise.txt"), noise(1001));
+    await Bun.$`git -C ${repoPath} commit -am "Rewrite"`.quiet();
+    const repo = await state.addRepo(repoPath, "precompute-minimal", base);
+    await state.setCurrentRepo(repo.id);
+
+    const precomputeServer = await startServer({ port: port + 16, precompute: true });
+    try {
+      const changed = async (query: string) => {
+        const diff = (await (await fetch(`http://localhost:${port + 16}/api/diff?mode=branch${query}`)).json()) as DiffResponse;
+        return diff.files.reduce((sum, f) => sum + f.additions + f.deletions, 0);
+      };
+      const minimal = await changed("&minimal=true");
+      expect(minimal).toBeLessThan(await changed(""));
+    } finally {
+      precomputeServer.stop();
+    }
+  });
+});
@@ -1,27 +1,188 @@
 import type { Server } from "bun";
-import { getGitManager, isGitRepo, getRepoName } from "../git";
+import {
+  getGitManager,
+  isFullNewFile,
+  isGitRepo,
+  getRepoName,
+  MAX_RANGE_COMMITS,
+  parseRange,
+  resetGitManager,
+  setAllowedRefs,
+  assertRefAllowed,
+  DEFAULT_MAX_BLOB_MB,
+  setDiffContentOptions,
+  setDiffOverrides,
+  setDiffFindOptions,
+  setGitTimeout,
+  setMaxBlobSize,
+  setPatchCache,
+  setRemoteFallbackHandler,
+  withDiffContentOptions,
+  withPatchBytes,
+  readingPatchBytes,
+  hasDiffContentOverrides,
+  type FileBlob,
+  type GitManager,
+} from "../git";
+import { createPatchCache, DEFAULT_PATCH_CACHE_MB } from "../git/patchCache";
+import { PRE_REVIEW_HOOK, PRE_REVIEW_HOOK_TIMEOUT_MS, runPreReviewHook } from "../git/hooks";
 import * as state from "../state";
-import type { DiffMode, Repository } from "../types";
+import { carryForwardViewed as carryViewedMarks, reviewBlobOids, type ReviewKey } from "../state/carry";
+import type {
+  DiffMode,
+  DiffOverride,
+  DiffResponse,
+  FileDiff,
+  InitialData,
+  LocResponse,
+  PatchEncoding,
+  RangeCommitList,
+  RefreshResponse,
+  Repository,
+  ReviewSummary,
+  StatusResponse,
+  ViewedFileList,
+  ConflictList,
+} from "../types";
+import { createRateLimiter } from "./rateLimit";
+import { createIdleTimer, type IdleTimer } from "./idle";
+import { createLogger, type LogFormat, type Logger } from "./logger";
+import { createIgnoreMatcher, type IgnoreMode } from "./ignore";
+import { fileContentType, imageContentType, MAX_IMAGE_BYTES } from "./image";
+import { assetContentType, FAVICON_SVG, looksLikeFile } from "./assets";
+import { ApiError, errorCodeForStatus, toApiError } from "./errors";
+import { buildEditorCommand } from "./editor";
+import { buildOpenApiDocument } from "./openapi";
+import { groupRenames } from "./renames";
+import { toGitHubFile } from "./github";
+import { requiresReview, reviewProgress, type FileStatus } from "./completion";
+import { createZip, type ZipEntry } from "./zip";
+import { DEFAULT_SHORTCUTS, diffFromDefaults, resolveShortcuts, validateShortcuts } from "./shortcuts";
+import { addLineIds, collapseContext, detectMovedBlocks, expandHunkTabs, parsePatch, splitPatchByFile, toSplitView } from "../git/patch";
+import { getChangeDistribution, getDiffStat, getLanguageStats } from "../stats";
 import {
   AddRepoRequestSchema,
   SetCurrentRepoRequestSchema,
   FilePathRequestSchema,
+  MarkViewedRequestSchema,
+  CollapseRequestSchema,
+  PositionRequestSchema,
   CommitRequestSchema,
+  OpenFileRequestSchema,
   AddCommentRequestSchema,
   ResolveCommentRequestSchema,
   DismissNoteRequestSchema,
+  ShortcutsRequestSchema,
+  PreferencesRequestSchema,
   validateRequest,
 } from "../schemas";
 
 export interface ServerOptions {
   port: number;
+  // Origins allowed to call /api/* cross-origin (default: same-origin only)
+  corsOrigins?: string[];
+  // Globs the refs a request diffs (base, range ends, shown commits) must match; others get 403
+  allowedBases?: string[];
+  // Max diff requests per second per client IP (default: unlimited)
+  rateLimit?: number;
+  // Reject every mutating API request (viewed marks, comments, git operations)
+  readOnly?: boolean;
+  // Tell the UI to mark files viewed as they're opened (DiffResponse.auto_mark_on_open)
+  autoMarkOnOpen?: boolean;
+  // Allow /api/open to launch an editor on the server host
+  allowOpen?: boolean;
+  // Editor command for /api/open (default: $EDITOR)
+  editor?: string;
+  // Also read/write viewed marks as git notes (refs/notes/cerebro-review)
+  gitNotes?: boolean;
+  // Reviewer recorded on viewed marks (default: git user.name, then "anonymous")
+  reviewer?: string;
+  // Collapse runs of more than N unchanged lines in structured hunk output
+  collapseContext?: number;
+  // Expand tabs to this many columns in structured hunk output (raw patch unchanged)
+  tabWidth?: number;
+  // Globs for tracked files to keep out of review (generated/vendored code)
+  ignore?: string[];
+  // hide drops ignored files from diffs; collapse (default) keeps them collapsed
+  ignoreMode?: IgnoreMode;
+  // Match viewed marks per commit (default) or also by file content
+  stateKey?: state.StateKey;
+  // Hide changes that only touch trailing whitespace (counts exclude them too)
+  ignoreEolWhitespace?: boolean;
+  // Compute minimal diffs (git --minimal) by default; ?minimal=true|false overrides per request
+  minimal?: boolean;
+  // Statuses that count toward completion (config require-review-for); all when unset
+  requireReviewFor?: FileStatus[];
+  // Per-path diff options (config diff-override), on top of the flags above for those files
+  diffOverrides?: DiffOverride[];
+  // Report copied files: copies from files changed in the diff, or from any file (slower)
+  detectCopies?: CopySources;
+  // On-disk patch cache size in MB; 0 disables (default: 64)
+  patchCacheMb?: number;
+  // Unpaged /api/diff responses with more files than this are refused; 0 disables (default: 5000)
+  maxFiles?: number;
+  // Files over this many MB are listed as too large instead of diffed; 0 disables (default: 50)
+  maxBlobMb?: number;
+  // Abort API requests running longer than this with 503 (and kill their git processes); 0 disables (default)
+  requestTimeout?: number;
+  // Compute the current repo's default diff at startup so the first page load doesn't wait on it
+  precompute?: boolean;
+  // Run the current repo's pre-review hook (or this script) before serving; it is arbitrary code
+  preReviewHook?: string | true;
+  // Log a failing pre-review hook instead of refusing to start
+  ignoreHookFailure?: boolean;
+  // Shut down after this many seconds without a request; off by default
+  idleTimeout?: number;
+  // Called after an idle shutdown has stopped the server (the CLI exits)
+  onIdleShutdown?: () => void;
+  // Log output format; json adds a line per API request (default: pretty)
+  logFormat?: LogFormat;
   // Optional embedded assets map (used by single-binary build)
   assets?: Map<string, { content: string; mimeType: string }>;
 }
 
+export const DEFAULT_MAX_FILES = 5000;
+
+// Bun caps a connection's idle timeout at 255s, and a timed-out request needs a few seconds
+// past its limit to get its 503 out
+export const MAX_REQUEST_TIMEOUT = 250;
+
+export const COPY_SOURCES = ["changed", "all"] as const;
+export type CopySources = (typeof COPY_SOURCES)[number];
+
 type BunServer = Server<unknown>;
 
+// Routes that compute diffs, walk history or read blobs; /api/diff/show/<oid> is matched by prefix
+const RATE_LIMITED_PATHS = new Set([
+  "/api/diff",
+  "/api/file-diff",
+  "/api/commits",
+  "/api/estimate",
+  "/api/loc",
+  "/api/range-info",
+  "/api/stats/languages",
+  "/api/stats/distribution",
+  "/api/stats/diffstat",
+  "/api/blame",
+  "/api/context",
+  "/api/file",
+  "/api/image",
+  "/api/graph",
+  "/api/export.zip",
+  "/api/force-push-diff",
+]);
+const RATE_LIMITED_PREFIXES = ["/api/diff/show/"];
+
+function isRateLimited(path: string): boolean {
+  return RATE_LIMITED_PATHS.has(path) || RATE_LIMITED_PREFIXES.some((prefix) => path.startsWith(prefix));
+}
+
+// Non-GET routes that don't write anything, so read-only mode still allows them
+const READ_ONLY_SAFE_PATHS = new Set(["/api/refresh", "/api/open"]);
+
 let serverInstance: BunServer | null = null;
+let idleTimer: IdleTimer | null = null;
+let logger: Logger = createLogger("pretty");
 
 export function getServer(): BunServer | null {
   return serverInstance;
@@ -29,6 +190,9 @@ export function getServer(): BunServer | null {
 
 export async function startServer(options: ServerOptions): Promise<BunServer> {
   const { port } = options;
+  // A previous server's idle timer would otherwise fire and stop (or exit) this one
+  idleTimer?.stop();
+  idleTimer = null;
 
   // Embedded assets (populated during build)
   const embeddedAssets: Map<string, { content: string; mimeType: string }> =
@@ -37,64 +201,187 @@ export async function startServer(options: ServerOptions): Promise<BunServer> {
     ((globalThis as any).__EMBEDDED_ASSETS__ as Map<string, { content: string; mimeType: string }>) ||
     new Map();
 
-  // CORS headers helper
-  const corsHeaders = {
-    "Access-Control-Allow-Origin": "*",
-    "Access-Control-Allow-Methods": "GET, POST, PUT, DELETE, OPTIONS",
-    "Access-Control-Allow-Headers": "Content-Type",
+  const corsOrigins = options.corsOrigins ?? [];
+  const rateLimiter = options.rateLimit ? createRateLimiter(options.rateLimit) : null;
+
+  logger = createLogger(options.logFormat ?? "pretty");
+
+  // Once per branch: the UI polls, and the fallback is expected on fresh clones
+  const remoteFallbacks = new Set<string>();
+  setRemoteFallbackHandler((requested, used) => {
+    if (remoteFallbacks.has(used)) return;
+    remoteFallbacks.add(used);
+    logger.info(`Base branch ${requested} has no local ref; using ${used}`, { requested, used });
+  });
+
+  setAllowedRefs(options.allowedBases ?? []);
+  setDiffFindOptions({ copies: options.detectCopies !== undefined, copiesFromUnmodified: options.detectCopies === "all" });
+  setDiffContentOptions({ ignoreEolWhitespace: options.ignoreEolWhitespace ?? false, minimal: options.minimal ?? false });
+  setDiffOverrides(options.diffOverrides ?? []);
+
+  const patchCacheMb = options.patchCacheMb ?? DEFAULT_PATCH_CACHE_MB;
+  setPatchCache(patchCacheMb > 0 ? createPatchCache(state.getPatchCacheDir(), patchCacheMb * 1024 * 1024) : null);
+
+  const maxBlobMb = options.maxBlobMb ?? DEFAULT_MAX_BLOB_MB;
+  setMaxBlobSize(maxBlobMb > 0 ? maxBlobMb * 1024 * 1024 : null);
+
+  const requestTimeoutMs = options.requestTimeout ? options.requestTimeout * 1000 : null;
+  setGitTimeout(requestTimeoutMs);
+
+  if (options.preReviewHook !== undefined) {
+    await runStartupHook(options.preReviewHook === true ? undefined : options.preReviewHook, options.ignoreHookFailure ?? false);
+  }
+
+  const handleApiRequest = async (req: Request, url: URL, server: BunServer): Promise<Response> => {
+    const path = url.pathname;
+    const corsHeaders = getCorsHeaders(req, corsOrigins);
+
+    // Handle preflight
+    if (req.method === "OPTIONS") {
+      return new Response(null, { status: 204, headers: corsHeaders });
+    }
+
+    if (options.readOnly && req.method !== "GET" && !READ_ONLY_SAFE_PATHS.has(path)) {
+      return Response.json(
+        { error: "Server is running in read-only mode", code: "forbidden" },
+        { status: 403, headers: corsHeaders }
+      );
+    }
+
+    // Diff computation is the expensive part, so only those routes are limited
+    if (rateLimiter && isRateLimited(path)) {
+      const clientIp = server.requestIP(req)?.address ?? "unknown";
+      if (!rateLimiter.check(clientIp)) {
+        return Response.json(
+          { error: "Too many requests", code: "rate_limited" },
+          { status: 429, headers: { ...corsHeaders, "Retry-After": "1" } }
+        );
+      }
+    }
+
+    try {
+      // ?minimal= swaps the diff algorithm's effort for this request only
+      const minimal = url.searchParams.get("minimal");
+      const overrides = minimal !== null ? { minimal: minimal === "true" } : {};
+      const handle = () => withDiffContentOptions(overrides, () => handleApi(req, url, options));
+      // Patches are read as raw bytes when they'll be sent base64-encoded
+      const pending = url.searchParams.get("patch_encoding") === "base64" ? withPatchBytes(handle) : handle();
+      const response = await withErrorCode(await withTimeout(pending, requestTimeoutMs));
+      const headers = new Headers(response.headers);
+      for (const [key, value] of Object.entries(corsHeaders)) {
+        headers.set(key, value);
+      }
+      return new Response(response.body, {
+        status: response.status,
+        headers,
+      });
+    } catch (error) {
+      const apiError = toApiError(error);
+      if (apiError.status >= 500) {
+        logger.error("API error:", { method: req.method, path, error });
+      }
+      return Response.json(
+        { error: apiError.message, code: apiError.code },
+        { status: apiError.status, headers: corsHeaders }
+      );
+    }
   };
 
+  const idleTimeout = options.idleTimeout;
+  idleTimer = idleTimeout
+    ? createIdleTimer(idleTimeout * 1000, () => {
+        logger.info(`No requests for ${idleTimeout}s; shutting down`);
+        stopServer();
+        options.onIdleShutdown?.();
+      })
+    : null;
+
   serverInstance = Bun.serve({
     port,
-    idleTimeout: 30,
-    async fetch(req) {
+    // Long enough that a timed-out request still gets its 503 before Bun drops the connection
+    idleTimeout: requestTimeoutMs ? Math.min(Math.max(30, requestTimeoutMs / 1000 + 5), 255) : 30,
+    async fetch(req, server) {
       const url = new URL(req.url);
       const path = url.pathname;
+      const finished = idleTimer?.begin();
 
-      // Handle preflight
-      if (req.method === "OPTIONS") {
-        return new Response(null, { headers: corsHeaders });
-      }
-
-      // API routes
-      if (path.startsWith("/api/")) {
-        try {
-          const response = await handleApi(req, url);
-          const headers = new Headers(response.headers);
-          for (const [key, value] of Object.entries(corsHeaders)) {
-            headers.set(key, value);
-          }
-          return new Response(response.body, {
+      try {
+        // API routes
+        if (path.startsWith("/api/")) {
+          const started = performance.now();
+          const response = await handleApiRequest(req, url, server);
+          logger.detail("request", {
+            method: req.method,
+            path,
             status: response.status,
-            headers,
+            duration_ms: Math.round(performance.now() - started),
           });
-        } catch (error) {
-          console.error("API error:", error);
-          return Response.json(
-            { error: error instanceof Error ? error.message : "Internal server error" },
-            { status: 500, headers: corsHeaders }
-          );
+          return response;
         }
-      }
 
-      // Production: serve from embedded assets
-      return serveStatic(path, embeddedAssets);
+        // Production: serve from embedded assets
+        return await serveStatic(path, url, embeddedAssets, options);
+      } finally {
+        finished?.();
+      }
     },
   });
 
-  console.log(`Server running at http://localhost:${port}`);
+  logger.info(`Server running at http://localhost:${port}${options.readOnly ? " (read-only)" : ""}`);
+  if (options.precompute) {
+    await precomputeDiff();
+  }
   return serverInstance;
 }
 
-export function stopServer(): void {
-  if (serverInstance) {
-    serverInstance.stop();
+// A browser request from a page on another origin that --cors-origin doesn't allow
+function isForeignOrigin(req: Request, allowedOrigins: string[]): boolean {
+  const origin = req.headers.get("Origin");
+  if (!origin || allowedOrigins.includes(origin) || allowedOrigins.includes("*")) return false;
+  try {
+    return new URL(origin).host !== req.headers.get("Host");
+  } catch {
+    return true;
+  }
+}
+
+// CORS headers for an allowed cross-origin caller (empty for same-origin/disallowed)
+function getCorsHeaders(req: Request, allowedOrigins: string[]): Record<string, string> {
+  const origin = req.headers.get("Origin");
+  if (!origin || !(allowedOrigins.includes(origin) || allowedOrigins.includes("*"))) {
+    return {};
+  }
+
+  return {
+    "Access-Control-Allow-Origin": origin,
+    "Access-Control-Allow-Methods": "GET, POST, PUT, DELETE, OPTIONS",
+    "Access-Control-Allow-Headers": "Content-Type",
+    Vary: "Origin",
+  };
+}
+
+// Stops a server (the last one started by default) and puts the git layer's process-wide
+// settings back to their unconfigured defaults, so one server's options don't carry into the next
+export function stopServer(server: BunServer | null = serverInstance): void {
+  if (server === serverInstance) {
+    idleTimer?.stop();
+    idleTimer = null;
     serverInstance = null;
   }
+  server?.stop();
+
+  setAllowedRefs([]);
+  setDiffFindOptions({ copies: false, copiesFromUnmodified: false });
+  setDiffContentOptions({ ignoreEolWhitespace: false, minimal: false });
+  setDiffOverrides([]);
+  setPatchCache(null);
+  setMaxBlobSize(null);
+  setGitTimeout(null);
+  precomputed = null;
 }
 
 // API route handler
-async function handleApi(req: Request, url: URL): Promise<Response> {
+async function handleApi(req: Request, url: URL, options: ServerOptions): Promise<Response> {
   const path = url.pathname;
   const method = req.method;
 
@@ -117,27 +404,142 @@ async function handleApi(req: Request, url: URL): Promise<Response> {
     return handleSetCurrentRepo(req);
   }
 
+  // UI config
+  if (path === "/api/config/shortcuts" && method === "GET") {
+    return handleGetShortcuts();
+  }
+
+  if (path === "/api/config/shortcuts" && method === "POST") {
+    return handleSetShortcuts(req);
+  }
+
+  if (path === "/api/prefs" && method === "GET") {
+    return Response.json({ prefs: await state.getPreferences() });
+  }
+
+  if (path === "/api/prefs" && method === "POST") {
+    return handleSetPreferences(req);
+  }
+
   // Branches route
   if (path === "/api/branches" && method === "GET") {
     return handleGetBranches(url);
   }
 
+  if (path === "/api/remotes" && method === "GET") {
+    return handleGetRemotes(url);
+  }
+
+  // Status route
+  if (path === "/api/status" && method === "GET") {
+    return handleGetStatus(url);
+  }
+
+  if (path === "/api/refresh" && method === "POST") {
+    return handleRefresh(url);
+  }
+
   // Diff routes
   if (path === "/api/diff" && method === "GET") {
-    return handleGetDiff(url);
+    return handleGetDiff(url, options);
+  }
+
+  if (path === "/api/force-push-diff" && method === "GET") {
+    return handleGetForcePushDiff(url, options);
+  }
+
+  if (path === "/api/export.zip" && method === "GET") {
+    return handleExportZip(url, options);
+  }
+
+  if (path.startsWith("/api/diff/show/") && method === "GET") {
+    return handleGetCommitDiff(url, decodeURIComponent(path.slice("/api/diff/show/".length)), options);
   }
 
   if (path === "/api/file-diff" && method === "GET") {
-    return handleGetFileDiff(url);
+    return handleGetFileDiff(url, options);
+  }
+
+  if (path === "/api/context" && method === "GET") {
+    return handleGetContext(url);
+  }
+
+  if (path === "/api/image" && method === "GET") {
+    return handleGetImage(url);
+  }
+
+  if (path === "/api/file" && method === "GET") {
+    return handleGetRawFile(url);
+  }
+
+  if (path === "/api/blame" && method === "GET") {
+    return handleGetBlame(url);
+  }
+
+  if (path === "/api/range-info" && method === "GET") {
+    return handleGetRangeInfo(url);
+  }
+
+  if (path === "/api/commits" && method === "GET") {
+    return handleGetCommits(url);
+  }
+
+  if (path === "/api/graph" && method === "GET") {
+    return handleGetGraph(url);
+  }
+
+  if (path === "/api/reviews" && method === "GET") {
+    return handleGetReviews(url, options);
+  }
+
+  if (path === "/api/estimate" && method === "GET") {
+    return handleGetEstimate(url, options);
+  }
+
+  if (path === "/api/loc" && method === "GET") {
+    return handleGetLoc(url, options);
+  }
+
+  // Stats
+  if (path === "/api/stats/languages" && method === "GET") {
+    return handleGetLanguageStats(url, options);
+  }
+
+  if (path === "/api/stats/distribution" && method === "GET") {
+    return handleGetDistribution(url, options);
+  }
+
+  if (path === "/api/stats/diffstat" && method === "GET") {
+    return handleGetDiffStat(url, options);
+  }
+
+  if (path === "/api/conflicts" && method === "GET") {
+    return handleGetConflicts(url);
   }
 
   // Viewed files
+  if (path === "/api/viewed" && method === "GET") {
+    return handleGetViewed(url);
+  }
+
   if (path === "/api/mark-viewed" && method === "POST") {
-    return handleMarkViewed(req, url);
+    return handleMarkViewed(req, url, options);
   }
 
   if (path === "/api/unmark-viewed" && method === "POST") {
-    return handleUnmarkViewed(req, url);
+    return handleUnmarkViewed(req, url, options);
+  }
+
+  if (path === "/api/collapse" && method === "POST") {
+    return handleSetCollapsed(req, url);
+  }
+
+  if (path === "/api/position" && method === "GET") {
+    return handleGetPosition(url);
+  }
+
+  if (path === "/api/position" && method === "POST") {
+    return handleSetPosition(req, url);
   }
 
   // Git operations
@@ -163,7 +565,7 @@ async function handleApi(req: Request, url: URL): Promise<Response> {
       return handleGetComments(url);
     }
     if (method === "POST") {
-      return handleAddComment(req, url);
+      return handleAddComment(req, url, options);
     }
   }
 
@@ -180,6 +582,11 @@ async function handleApi(req: Request, url: URL): Promise<Response> {
     return handleDismissNote(req, url);
   }
 
+  // Open in editor
+  if (path === "/api/open" && method === "POST") {
+    return handleOpenInEditor(req, url, options);
+  }
+
   // Directory browsing
   if (path === "/api/browse" && method === "GET") {
     return handleBrowseDirectory(url);
@@ -190,6 +597,10 @@ async function handleApi(req: Request, url: URL): Promise<Response> {
     return Response.json({ status: "ok" });
   }
 
+  if (path === "/api/openapi.json" && method === "GET") {
+    return Response.json(buildOpenApiDocument());
+  }
+
   return Response.json({ error: "Not found" }, { status: 404 });
 }
 
@@ -223,6 +634,52 @@ async function getCurrentRepoFromRequest(url: URL): Promise<Repository | null> {
   return repo;
 }
 
+// ?compare= (the repo's base branch by default), checked against --allowed-base
+function compareParam(url: URL, repo: Repository): string {
+  const compare = url.searchParams.get("compare") || repo.baseBranch;
+  assertRefAllowed(compare);
+  return compare;
+}
+
+// ?show=<rev>, checked against --allowed-base
+function showParam(url: URL): string | null {
+  const show = url.searchParams.get("show");
+  if (show) assertRefAllowed(show);
+  return show;
+}
+
+// Working-tree modes and git operations need a checkout; bare repos only support branch diffs
+async function requireWorktree(repo: Repository): Promise<Response | null> {
+  if (await getGitManager(repo.path).isBare()) {
+    return Response.json(
+      { error: "This operation requires a working tree, but the repository is bare" },
+      { status: 400 }
+    );
+  }
+  return null;
+}
+
+const DIFF_MODES: readonly DiffMode[] = ["branch", "working", "unstaged", "full"];
+
+// ?mode= (branch by default), or a 400 for an unknown mode or a working-tree mode in a bare repo
+async function modeParam(url: URL, repo: Repository): Promise<DiffMode | Response> {
+  const mode = url.searchParams.get("mode") || "branch";
+  if (!DIFF_MODES.includes(mode as DiffMode)) {
+    return Response.json({ error: `mode must be one of: ${DIFF_MODES.join(", ")}` }, { status: 400 });
+  }
+  if (mode !== "branch") {
+    const worktreeError = await requireWorktree(repo);
+    if (worktreeError) return worktreeError;
+  }
+  return mode as DiffMode;
+}
+
+// Paths --ignore-mode hide leaves out of diffs (none in the other modes)
+function hiddenByIgnore(options: ServerOptions): (path: string) => boolean {
+  if (options.ignoreMode !== "hide") return () => false;
+  return createIgnoreMatcher(options.ignore ?? []);
+}
+
 // Repository handlers
 async function handleGetRepos(): Promise<Response> {
   const allRepos = await state.getRepos();
@@ -305,204 +762,1488 @@ async function handleGetBranches(url: URL): Promise<Response> {
   return Response.json({ branches });
 }
 
-// Diff handler
-async function handleGetDiff(url: URL): Promise<Response> {
+// Configured remotes, for picking a base like upstream/main in a fork
+async function handleGetRemotes(url: URL): Promise<Response> {
   const repo = await getCurrentRepoFromRequest(url);
   if (!repo) {
     return Response.json({ error: "No repository selected" }, { status: 400 });
   }
-
-  const mode = (url.searchParams.get("mode") || "branch") as DiffMode;
-  const compareBranch = url.searchParams.get("compare") || repo.baseBranch;
-  const git = getGitManager(repo.path);
-
-  const diff = await git.getDiff({ baseBranch: compareBranch, mode });
-
-  // Apply viewed state
-  const branch = await git.getCurrentBranch();
-  const commit = await git.getCurrentCommit();
-  const viewed = await state.getViewedFiles(repo.id, branch, commit);
-
-  diff.files = diff.files.map((f) => ({
-    ...f,
-    viewed: viewed[f.path] || false,
-  }));
-
-  return Response.json(diff);
+  const remotes = await getGitManager(repo.path).getRemotes();
+  return Response.json({ remotes });
 }
 
-// Single file diff handler (lazy loading)
-async function handleGetFileDiff(url: URL): Promise<Response> {
+// Status handler
+async function handleGetStatus(url: URL): Promise<Response> {
   const repo = await getCurrentRepoFromRequest(url);
   if (!repo) {
     return Response.json({ error: "No repository selected" }, { status: 400 });
   }
 
-  const filePath = url.searchParams.get("file");
-  if (!filePath) {
-    return Response.json({ error: "File path required" }, { status: 400 });
-  }
+  return Response.json(await readStatus(repo, getGitManager(repo.path)));
+}
 
-  const mode = (url.searchParams.get("mode") || "branch") as DiffMode;
-  const compareBranch = url.searchParams.get("compare") || repo.baseBranch;
-  const git = getGitManager(repo.path);
-  const fileDiff = await git.getFileDiff({ baseBranch: compareBranch, mode, filePath });
+// Branch, HEAD and dirty flag; an unborn branch (git init, no commits) answers with a null
+// commit and a message rather than failing
+async function readStatus(repo: Repository, git: GitManager): Promise<StatusResponse> {
+  const [branch, commit, dirty] = await Promise.all([git.getCurrentBranch(), git.getCurrentCommit(), git.isDirty()]);
+  if (!commit) {
+    return { repo_path: repo.path, branch, commit: null, dirty, message: "no commits yet" };
+  }
+  return { repo_path: repo.path, branch, commit, dirty };
+}
 
-  if (!fileDiff) {
-    return Response.json({ error: "File not found" }, { status: 404 });
+// Settles with the handler's response, or throws a 503 once `ms` has passed (null waits forever).
+// The handler isn't cancelled: it runs on to completion in the background, though each of its git
+// processes is killed once it has been silent for the same limit (see setGitTimeout)
+async function withTimeout(pending: Promise<Response>, ms: number | null): Promise<Response> {
+  if (ms === null) return pending;
+  let timer: ReturnType<typeof setTimeout> | undefined;
+  const timeout = new Promise<never>((_, reject) => {
+    timer = setTimeout(() => reject(new ApiError(503, `Request took longer than ${ms / 1000}s`, "timeout")), ms);
+  });
+  try {
+    return await Promise.race([pending, timeout]);
+  } finally {
+    clearTimeout(timer);
   }
+}
 
-  return Response.json(fileDiff);
+// Handlers answer with plain { error } bodies; add the code their status implies
+async function withErrorCode(response: Response): Promise<Response> {
+  if (response.ok || !response.headers.get("Content-Type")?.startsWith("application/json")) {
+    return response;
+  }
+  const body = (await response.json()) as Record<string, unknown>;
+  if (typeof body["error"] === "string" && body["code"] === undefined) {
+    body["code"] = errorCodeForStatus(response.status);
+  }
+  return Response.json(body, { status: response.status, headers: response.headers });
 }
 
-// Viewed file handlers
-async function handleMarkViewed(req: Request, url: URL): Promise<Response> {
+// Git state each repo had at its last refresh, to skip invalidation when nothing moved
+const refreshedStatus = new Map<string, StatusResponse>();
+
+async function handleRefresh(url: URL): Promise<Response> {
   const repo = await getCurrentRepoFromRequest(url);
   if (!repo) {
     return Response.json({ error: "No repository selected" }, { status: 400 });
   }
 
-  const body = await req.json();
-  const validation = validateRequest(FilePathRequestSchema, body);
-  if (!validation.success) return validation.response;
+  const status = await readStatus(repo, getGitManager(repo.path));
+  const { branch, commit, dirty } = status;
 
-  const git = getGitManager(repo.path);
-  const branch = await git.getCurrentBranch();
-  const commit = await git.getCurrentCommit();
+  const previous = refreshedStatus.get(repo.path);
+  const changed = !previous || previous.branch !== branch || previous.commit !== commit || previous.dirty !== dirty;
+  if (changed) {
+    resetGitManager(repo.path);
+    refreshedStatus.set(repo.path, status);
+  }
 
-  await state.setFileViewed(repo.id, branch, commit, validation.data.file_path, true);
-  return Response.json({ success: true });
+  const response: RefreshResponse = { ...status, changed };
+  return Response.json(response);
 }
 
-async function handleUnmarkViewed(req: Request, url: URL): Promise<Response> {
+async function handleGetEstimate(url: URL, options: ServerOptions): Promise<Response> {
   const repo = await getCurrentRepoFromRequest(url);
   if (!repo) {
     return Response.json({ error: "No repository selected" }, { status: 400 });
   }
 
-  const body = await req.json();
-  const validation = validateRequest(FilePathRequestSchema, body);
-  if (!validation.success) return validation.response;
-
+  const mode = await modeParam(url, repo);
+  if (mode instanceof Response) return mode;
+  const compareBranch = compareParam(url, repo);
   const git = getGitManager(repo.path);
-  const branch = await git.getCurrentBranch();
-  const commit = await git.getCurrentCommit();
+  const estimate = await git.estimateDiff({
+    baseBranch: compareBranch,
+    mode,
+    paths: url.searchParams.getAll("path"),
+    exclude: hiddenByIgnore(options),
+  });
 
-  await state.setFileViewed(repo.id, branch, commit, validation.data.file_path, false);
-  return Response.json({ success: true });
+  const maxFiles = getMaxFiles(options);
+  if (maxFiles !== null) {
+    estimate.max_files = maxFiles;
+    estimate.exceeds_max_files = estimate.files > maxFiles;
+  }
+  return Response.json(estimate);
 }
 
-// Git operation handlers
-async function handleStage(req: Request, url: URL): Promise<Response> {
+// Lines changed in the current diff, for shell prompts and status bars (numstat only, no patches)
+async function handleGetLoc(url: URL, options: ServerOptions): Promise<Response> {
   const repo = await getCurrentRepoFromRequest(url);
   if (!repo) {
     return Response.json({ error: "No repository selected" }, { status: 400 });
   }
 
-  const body = await req.json();
-  const validation = validateRequest(FilePathRequestSchema, body);
-  if (!validation.success) return validation.response;
-
+  const mode = await modeParam(url, repo);
+  if (mode instanceof Response) return mode;
+  const compareBranch = compareParam(url, repo);
   const git = getGitManager(repo.path);
-  await git.stageFile(validation.data.file_path);
-  return Response.json({ success: true });
+  const { files, additions, deletions } = await git.estimateDiff({
+    baseBranch: compareBranch,
+    mode,
+    paths: url.searchParams.getAll("path"),
+    exclude: hiddenByIgnore(options),
+  });
+  const loc: LocResponse = { files, additions, deletions, net: additions - deletions };
+  return Response.json(loc);
 }
 
-async function handleUnstage(req: Request, url: URL): Promise<Response> {
+// File cap for unpaged diffs, or null when disabled
+function getMaxFiles(options: ServerOptions): number | null {
+  const maxFiles = options.maxFiles ?? DEFAULT_MAX_FILES;
+  return maxFiles > 0 ? maxFiles : null;
+}
+
+function tooManyFilesResponse(files: number, maxFiles: number): Response {
+  return Response.json(
+    {
+      error: `Diff has ${files} files, more than the limit of ${maxFiles}. Narrow it with ?path=<pathspec> or page it with ?offset/&limit`,
+      files,
+      max_files: maxFiles,
+    },
+    { status: 413 }
+  );
+}
+
+async function handleGetRangeInfo(url: URL): Promise<Response> {
   const repo = await getCurrentRepoFromRequest(url);
   if (!repo) {
     return Response.json({ error: "No repository selected" }, { status: 400 });
   }
 
-  const body = await req.json();
-  const validation = validateRequest(FilePathRequestSchema, body);
-  if (!validation.success) return validation.response;
+  const limitParam = url.searchParams.get("limit");
+  const limit = limitParam !== null ? Number(limitParam) : MAX_RANGE_COMMITS;
+  if (!Number.isInteger(limit) || limit <= 0) {
+    return Response.json({ error: "limit must be a positive integer" }, { status: 400 });
+  }
 
+  const compareBranch = compareParam(url, repo);
   const git = getGitManager(repo.path);
-  await git.unstageFile(validation.data.file_path);
-  return Response.json({ success: true });
+  return Response.json(await git.getRangeInfo(compareBranch, limit, url.searchParams.get("author") || undefined));
 }
 
-async function handleDiscard(req: Request, url: URL): Promise<Response> {
-  const repo = await getCurrentRepoFromRequest(url);
-  if (!repo) {
-    return Response.json({ error: "No repository selected" }, { status: 400 });
+// Every repo/branch/commit with viewed marks, for resuming reviews. Totals mean rebuilding each
+// diff, so they're only computed with ?totals=true
+async function handleGetReviews(url: URL, options: ServerOptions): Promise<Response> {
+  const withTotals = url.searchParams.get("totals") === "true";
+  const repos = new Map((await state.getRepos()).map((repo) => [repo.id, repo]));
+  const staleness = new Map<string, Promise<boolean>>();
+  const isStale = (repo: Repository) => {
+    if (!staleness.has(repo.id)) staleness.set(repo.id, isGitRepo(repo.path).then((ok) => !ok));
+    return staleness.get(repo.id)!;
+  };
+
+  const reviews: ReviewSummary[] = [];
+  for (const entry of await state.getViewedSummaries()) {
+    const repo = repos.get(entry.repo_id);
+    if (!repo) continue;
+
+    const review = toReviewSummary(repo, entry);
+    if (await isStale(repo)) {
+      review.stale = true;
+    } else if (withTotals) {
+      review.total = await countReviewFiles(repo, review, options);
+    }
+    reviews.push(review);
   }
+  return Response.json({ reviews });
+}
 
-  const body = await req.json();
-  const validation = validateRequest(FilePathRequestSchema, body);
-  if (!validation.success) return validation.response;
+function toReviewSummary(
+  repo: Repository,
+  entry: { branch: string; commit: string; viewed: number; last_viewed_at: number }
+): ReviewSummary {
+  const repoQuery = `repo=${encodeURIComponent(repo.id)}`;
+  const base = { repo_id: repo.id, repo_name: repo.name, repo_path: repo.path, ...entry };
+  if (entry.branch === SHOW_REVIEW_BRANCH) {
+    return { ...base, kind: "show", url: `/show/${entry.commit}?${repoQuery}` };
+  }
+  if (entry.branch.startsWith(RANGE_REVIEW_PREFIX)) {
+    const range = `${entry.branch.slice(RANGE_REVIEW_PREFIX.length)}..${entry.commit}`;
+    return { ...base, kind: "range", url: `/api/diff?${repoQuery}&range=${encodeURIComponent(range)}` };
+  }
+  return { ...base, kind: "branch", url: `/?${repoQuery}` };
+}
 
+// How many files of the diff a review was made against must be viewed, or undefined once
+// its commits are gone
+async function countReviewFiles(repo: Repository, review: ReviewSummary, options: ServerOptions): Promise<number | undefined> {
   const git = getGitManager(repo.path);
-  await git.discardFile(validation.data.file_path);
-  return Response.json({ success: true });
+  const isIgnored = createIgnoreMatcher(options.ignore ?? []);
+  const countRequired = (files: FileDiff[]) =>
+    files.filter((f) => requiresReview({ status: f.status, ignored: isIgnored(f.path) }, options.requireReviewFor)).length;
+  try {
+    if (review.kind === "show") {
+      return countRequired((await git.getCommitDiff(review.commit)).files);
+    }
+    const from =
+      review.kind === "range"
+        ? review.branch.slice(RANGE_REVIEW_PREFIX.length)
+        : (await git.resolveRange({ base: repo.baseBranch, head: review.commit, threeDot: true }))?.from;
+    return from ? countRequired((await git.getRevisionDiff(from, review.commit)).files) : undefined;
+  } catch {
+    return undefined;
+  }
 }
 
-async function handleCommit(req: Request, url: URL): Promise<Response> {
+// The branch's commit DAG since the merge base, for drawing its shape (merges included)
+async function handleGetGraph(url: URL): Promise<Response> {
   const repo = await getCurrentRepoFromRequest(url);
   if (!repo) {
     return Response.json({ error: "No repository selected" }, { status: 400 });
   }
 
-  const body = await req.json();
-  const validation = validateRequest(CommitRequestSchema, body);
-  if (!validation.success) return validation.response;
+  const limitParam = url.searchParams.get("limit");
+  const limit = limitParam !== null ? Number(limitParam) : MAX_RANGE_COMMITS;
+  if (!Number.isInteger(limit) || limit <= 0) {
+    return Response.json({ error: "limit must be a positive integer" }, { status: 400 });
+  }
 
+  const compareBranch = compareParam(url, repo);
   const git = getGitManager(repo.path);
-  const commitHash = await git.commit(validation.data.message);
-  return Response.json({ commit: commitHash });
+  return Response.json(await git.getCommitGraph(compareBranch, limit));
 }
 
-// Comment handlers
-async function handleGetComments(url: URL): Promise<Response> {
+// Commits since the merge base, optionally one author's, with their combined diff on request
+async function handleGetCommits(url: URL): Promise<Response> {
   const repo = await getCurrentRepoFromRequest(url);
   if (!repo) {
     return Response.json({ error: "No repository selected" }, { status: 400 });
   }
 
-  const git = getGitManager(repo.path);
-  const branch = await git.getCurrentBranch();
+  const limitParam = url.searchParams.get("limit");
+  const limit = limitParam !== null ? Number(limitParam) : MAX_RANGE_COMMITS;
+  if (!Number.isInteger(limit) || limit <= 0) {
+    return Response.json({ error: "limit must be a positive integer" }, { status: 400 });
+  }
 
-  const comments = await state.getComments(repo.id, branch);
-  return Response.json(comments);
+  const encoding = resolvePatchEncoding(url);
+  if (encoding instanceof Response) return encoding;
+
+  const compareBranch = compareParam(url, repo);
+  const author = url.searchParams.get("author") || undefined;
+  const git = getGitManager(repo.path);
+  const list: RangeCommitList = { ...(await git.getRangeCommits(compareBranch, limit, author)), author };
+  if (url.searchParams.get("aggregate") === "true") {
+    // Oldest first so later commits apply on top
+    list.files = await git.getCommitsDiff(list.commits.map((c) => c.hash).reverse());
+    encodePatches(list.files, encoding);
+  }
+  return Response.json(list);
 }
 
-async function handleAddComment(req: Request, url: URL): Promise<Response> {
+// Diff handler
+async function handleGetDiff(url: URL, options: ServerOptions): Promise<Response> {
   const repo = await getCurrentRepoFromRequest(url);
   if (!repo) {
     return Response.json({ error: "No repository selected" }, { status: 400 });
   }
 
-  const body = await req.json();
-  const validation = validateRequest(AddCommentRequestSchema, body);
-  if (!validation.success) return validation.response;
+  const mode = await modeParam(url, repo);
+  if (mode instanceof Response) return mode;
 
+  // ?range=<base>..<head> or <base>...<head> takes the place of mode/compare
   const git = getGitManager(repo.path);
-  const branch = await git.getCurrentBranch();
-  const commit = await git.getCurrentCommit();
+  const rangeSpec = url.searchParams.get("range");
+  const range = rangeSpec !== null ? await resolveRangeParam(git, rangeSpec) : null;
+  if (range instanceof Response) return range;
 
-  const comment = await state.addComment(repo.id, {
-    file_path: validation.data.file_path,
-    line_number: validation.data.line_number,
-    text: validation.data.text,
-    branch,
-    commit,
-  });
+  const since = range ? null : await resolveSinceParam(repo, git, url);
+  if (since instanceof Response) return since;
 
-  return Response.json(comment);
-}
+  const encoding = resolvePatchEncoding(url);
+  if (encoding instanceof Response) return encoding;
 
-async function handleResolveComment(req: Request, url: URL): Promise<Response> {
-  const body = await req.json();
-  const validation = validateRequest(ResolveCommentRequestSchema, body);
-  if (!validation.success) return validation.response;
+  const format = url.searchParams.get("format") ?? "cerebro";
+  if (format !== "cerebro" && format !== "github") {
+    return Response.json({ error: "format must be cerebro or github" }, { status: 400 });
+  }
 
-  const success = await state.resolveComment(validation.data.comment_id, validation.data.resolved_by || "user");
-  if (!success) {
-    return Response.json({ error: "Comment not found" }, { status: 404 });
+  const attribute = url.searchParams.get("attribute") === "true";
+  if (attribute && rangeSpec === null && !url.searchParams.has("since")) {
+    return Response.json({ error: "attribute=true needs a range (range= or since=last)" }, { status: 400 });
   }
-  return Response.json({ success: true });
-}
+
+  const compareBranch = compareParam(url, repo);
+  const paths = url.searchParams.getAll("path");
+  const started = performance.now();
+  const diff = range
+    ? await buildRangeDiffResponse(repo, range, options, paths)
+    : since
+      ? await buildSinceDiffResponse(repo, since, options, paths)
+      : await buildDiffResponse(repo, mode, compareBranch, options, paths);
+  logDiffTiming(repo, diff, rangeSpec ?? compareBranch, performance.now() - started);
+
+  // Paged requests stay small however big the diff is
+  const paged = url.searchParams.has("offset") || url.searchParams.has("limit");
+  const maxFiles = getMaxFiles(options);
+  if (!paged && maxFiles !== null && diff.files.length > maxFiles) {
+    return tooManyFilesResponse(diff.files.length, maxFiles);
+  }
+
+  // Every listed file's patch in one git call (limited to ?path= like the list, so moves are only
+  // matched among the files shown); branch and range diffs normally load them lazily
+  const revisions = range ?? since;
+  const loadPatches = async () =>
+    revisions
+      ? splitPatchByFile(await git.getRevisionPatch(revisions.from, revisions.to, paths))
+      : mode === "branch"
+        ? splitPatchByFile(await git.getBranchPatch(compareBranch, paths))
+        : new Map(diff.files.map((f) => [f.path, f.patch]));
+
+  // Expensive: needs every patch
+  if (url.searchParams.get("detect_moves") === "true") {
+    // Hidden (ignored) files aren't listed, so they can't be a move's other end either
+    const listed = new Set(diff.files.map((f) => f.path));
+    const moves = detectMovedBlocks(new Map([...(await loadPatches())].filter(([path]) => listed.has(path))));
+    for (const file of diff.files) {
+      const blocks = moves.get(file.path);
+      if (blocks) file.moved_blocks = blocks;
+    }
+  }
+
+  // Directory moves: fold the unchanged files into one entry per move (the group lists them all)
+  if (url.searchParams.get("group_renames") === "true") {
+    const grouped = groupRenames(diff.files);
+    diff.files = grouped.files;
+    diff.renames = grouped.renames;
+  }
+
+  // ?offset=<n>&limit=<n> returns one page of files (in git's stable order) plus the total
+  const offsetParam = url.searchParams.get("offset");
+  const limitParam = url.searchParams.get("limit");
+  if (offsetParam !== null || limitParam !== null) {
+    const offset = offsetParam !== null ? Number(offsetParam) : 0;
+    const limit = limitParam !== null ? Number(limitParam) : diff.files.length;
+    if (!Number.isInteger(offset) || offset < 0 || !Number.isInteger(limit) || limit < 0) {
+      return Response.json({ error: "offset and limit must be non-negative integers" }, { status: 400 });
+    }
+    diff.total = diff.files.length;
+    diff.files = diff.files.slice(offset, offset + limit);
+  }
+
+  // ?attribute=true: which range commit introduced each line, for this page of files only
+  if (attribute && revisions) {
+    const { lines, ...attribution } = await git.attributeLines(
+      revisions.from,
+      revisions.to,
+      diff.files.filter((f) => f.status !== "deleted" && !f.binary).map((f) => f.path)
+    );
+    for (const file of diff.files) {
+      const attributed = lines[file.path];
+      if (attributed) file.line_commits = attributed;
+    }
+    diff.attribution = attribution;
+  }
+
+  // ?format=github: the file list shaped like GitHub's pull request files API
+  if (format === "github") {
+    const patches = await loadPatches();
+    return Response.json(
+      diff.files.map((f) => toGitHubFile({ ...f, patch: (f.patch && patchText(f.patch)) || patches.get(f.path) || "" }))
+    );
+  }
+
+  encodePatches(diff.files, encoding);
+  return Response.json(diff);
+}
+
+// What a rebase or force-push actually changed: the branch's tip before its last rewrite
+// against its current tip. Served as that range, so file-diff and marks take range=<old>..<new>
+async function handleGetForcePushDiff(url: URL, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const git = getGitManager(repo.path);
+  const ref = url.searchParams.get("ref") || undefined;
+  if (ref) assertRefAllowed(ref);
+  const rewrite = await git.findRewrite(ref);
+  if (!rewrite) {
+    return Response.json(
+      { error: `No rebase or force-push of ${ref ?? "the current branch"} in its reflog`, code: "not_found" },
+      { status: 404 }
+    );
+  }
+  if (!rewrite.available) {
+    return Response.json(
+      {
+        error: `The tip before the rewrite (${rewrite.old_tip.slice(0, 7)}) has been garbage collected`,
+        code: "gone",
+        force_push: rewrite,
+      },
+      { status: 410 }
+    );
+  }
+
+  const encoding = resolvePatchEncoding(url);
+  if (encoding instanceof Response) return encoding;
+
+  const range = { from: rewrite.old_tip, to: rewrite.new_tip };
+  const diff = await buildRangeDiffResponse(repo, range, options, url.searchParams.getAll("path"));
+  encodePatches(diff.files, encoding);
+  return Response.json({ ...diff, force_push: rewrite });
+}
+
+// Archive of a review: one patches/<path>.patch per changed file and a summary.json naming
+// the branch, base and commit it was taken at, so the zip stands on its own
+async function handleExportZip(url: URL, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const mode = await modeParam(url, repo);
+  if (mode instanceof Response) return mode;
+
+  const git = getGitManager(repo.path);
+  const compareBranch = compareParam(url, repo);
+  const paths = url.searchParams.getAll("path");
+  const diff = await buildDiffResponse(repo, mode, compareBranch, options, paths);
+
+  // Branch mode lists files without patches; one git diff fills them all in
+  const patches =
+    mode === "branch"
+      ? splitPatchByFile(await git.getBranchPatch(compareBranch, paths))
+      : new Map(diff.files.map((f) => [f.path, f.patch]));
+
+  const entries: ZipEntry[] = [];
+  const files = diff.files.map((file) => {
+    // Binary and too-large files have no patch; the summary still lists them
+    const patch = patches.get(file.path);
+    const name = patch ? `patches/${file.path}.patch` : null;
+    if (patch && name) entries.push({ name, data: patch.endsWith("\n") ? patch : `${patch}\n` });
+    return {
+      path: file.path,
+      ...(file.old_path !== undefined && { old_path: file.old_path }),
+      status: file.status,
+      additions: file.additions,
+      deletions: file.deletions,
+      viewed: file.viewed,
+      patch: name,
+    };
+  });
+
+  const summary = {
+    repo: repo.name,
+    mode: diff.mode,
+    branch: diff.branch,
+    base: diff.base_branch,
+    commit: diff.commit,
+    exported_at: new Date().toISOString(),
+    files,
+  };
+  entries.unshift({ name: "summary.json", data: `${JSON.stringify(summary, null, 2)}\n` });
+
+  const filename = `${repo.name}-${diff.branch.replace(/[^\w.-]+/g, "-")}-${diff.commit.slice(0, 7)}.zip`;
+  return new Response(createZip(entries), {
+    headers: {
+      "Content-Type": "application/zip",
+      "Content-Disposition": `attachment; filename="${filename}"`,
+    },
+  });
+}
+
+// A single commit against its first parent, with viewed state keyed on that commit
+async function handleGetCommitDiff(url: URL, rev: string, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const git = getGitManager(repo.path);
+  assertRefAllowed(rev);
+  const oid = await git.resolveCommit(rev);
+  if (!oid) {
+    return Response.json({ error: "Commit not found" }, { status: 404 });
+  }
+
+  const encoding = resolvePatchEncoding(url);
+  if (encoding instanceof Response) return encoding;
+
+  const paths = url.searchParams.getAll("path");
+  if (url.searchParams.get("combined") === "true") {
+    if (url.searchParams.has("parent")) {
+      return Response.json({ error: "combined=true diffs against every parent; drop parent" }, { status: 400 });
+    }
+    const combined = await git.getCombinedCommitDiff(oid, paths);
+    const diff = await applyReviewState(repo, git, combined, { branch: SHOW_REVIEW_BRANCH, commit: oid.slice(0, 7) }, options);
+    encodePatches(diff.files, encoding);
+    return Response.json(diff);
+  }
+
+  const parent = await resolveParentParam(git, oid, url);
+  if (parent instanceof Response) return parent;
+
+  const diff = await buildCommitDiffResponse(repo, oid, options, paths, parent);
+  encodePatches(diff.files, encoding);
+  return Response.json(diff);
+}
+
+// ?patch_encoding=base64 asks for every patch as base64 (utf8, the default, leaves them as text), or a 400 response
+function resolvePatchEncoding(url: URL): PatchEncoding | Response {
+  const param = url.searchParams.get("patch_encoding") ?? "utf8";
+  if (param !== "utf8" && param !== "base64") {
+    return Response.json({ error: "patch_encoding must be utf8 or base64" }, { status: 400 });
+  }
+  return param;
+}
+
+// Applied last, once nothing else needs to read the patch text. Under base64 git's patches
+// were read as bytes (withPatchBytes), one latin1 char each
+function encodePatches(files: FileDiff[], encoding: PatchEncoding): void {
+  if (encoding !== "base64") return;
+  for (const file of files) {
+    file.patch = Buffer.from(file.patch, "latin1").toString("base64");
+    file.patch_encoding = "base64";
+  }
+}
+
+// A patch as UTF-8 text, for parsing into hunks and split rows
+function patchText(patch: string): string {
+  return readingPatchBytes() ? Buffer.from(patch, "latin1").toString("utf8") : patch;
+}
+
+// Which parent ?parent=<n> diffs a commit against (1 when absent), or a 400 response
+async function resolveParentParam(git: GitManager, oid: string, url: URL): Promise<number | Response> {
+  const param = url.searchParams.get("parent");
+  if (param === null) return 1;
+
+  const parent = Number(param);
+  if (!Number.isInteger(parent) || parent <= 0) {
+    return Response.json({ error: "parent must be a positive integer" }, { status: 400 });
+  }
+  // Root commits still diff against parent 1 (the empty tree)
+  const parentCount = await git.getParentCount(oid);
+  if (parent > Math.max(parentCount, 1)) {
+    return Response.json({ error: `Commit ${oid.slice(0, 7)} has ${parentCount} parent(s)` }, { status: 400 });
+  }
+  return parent;
+}
+
+async function buildCommitDiffResponse(
+  repo: Repository,
+  oid: string,
+  options: ServerOptions,
+  paths: string[] = [],
+  parent = 1
+): Promise<DiffResponse> {
+  const git = getGitManager(repo.path);
+  const diff = await git.getCommitDiff(oid, paths, parent);
+  return applyReviewState(repo, git, diff, { branch: SHOW_REVIEW_BRANCH, commit: oid.slice(0, 7) }, options);
+}
+
+interface ResolvedRange {
+  from: string;
+  to: string;
+}
+
+// Endpoints for a ?range= spec, or a 400 response explaining what's wrong with it
+async function resolveRangeParam(git: GitManager, spec: string): Promise<ResolvedRange | Response> {
+  const range = parseRange(spec);
+  if (!range) {
+    return Response.json(
+      { error: `Malformed range "${spec}": expected <base>..<head> or <base>...<head>` },
+      { status: 400 }
+    );
+  }
+  assertRefAllowed(range.base);
+  assertRefAllowed(range.head);
+  const resolved = await git.resolveRange(range);
+  if (!resolved) {
+    const reason = range.threeDot ? "unknown revision or no merge base" : "unknown revision";
+    return Response.json({ error: `Cannot resolve range "${spec}": ${reason}`, code: "bad_ref" }, { status: 400 });
+  }
+  return resolved;
+}
+
+// Viewed state for ?range= is keyed by the resolved endpoints, so any spelling of a range shares marks
+function rangeReviewKey(range: ResolvedRange): ReviewKey {
+  return { branch: `${RANGE_REVIEW_PREFIX}${range.from.slice(0, 7)}`, commit: range.to.slice(0, 7) };
+}
+
+async function buildRangeDiffResponse(
+  repo: Repository,
+  range: ResolvedRange,
+  options: ServerOptions,
+  paths: string[] = []
+): Promise<DiffResponse> {
+  const git = getGitManager(repo.path);
+  const diff = await git.getRevisionDiff(range.from, range.to, paths);
+  return applyReviewState(repo, git, diff, rangeReviewKey(range), options);
+}
+
+// ?since=last: the branch's last reviewed commit to HEAD. Null without the param, or
+// when there's no earlier review to start from (callers fall back to the full diff)
+async function resolveSinceParam(repo: Repository, git: GitManager, url: URL): Promise<ResolvedRange | Response | null> {
+  const since = url.searchParams.get("since");
+  if (since === null) return null;
+  if (since !== "last") {
+    return Response.json({ error: `Unsupported since value "${since}": expected "last"` }, { status: 400 });
+  }
+
+  const branch = await git.getCurrentBranch();
+  const last = await state.getLastReviewedCommit(repo.id, branch, await git.getCurrentCommit());
+  // The commit may be gone after a force-push and gc
+  const [from, to] = await Promise.all([last ? git.resolveCommit(last) : null, git.resolveCommit("HEAD")]);
+  return from && to ? { from, to } : null;
+}
+
+// Changes since the last review, with viewed state shared with the branch's full diff
+async function buildSinceDiffResponse(
+  repo: Repository,
+  since: ResolvedRange,
+  options: ServerOptions,
+  paths: string[] = []
+): Promise<DiffResponse> {
+  const git = getGitManager(repo.path);
+  const diff = await git.getRevisionDiff(since.from, since.to, paths);
+  const commit = await git.getCurrentCommit();
+  const key = { branch: diff.branch, commit };
+  await carryForwardViewed(repo, git, key, options);
+  const reviewed = await applyReviewState(repo, git, diff, key, options);
+  return { ...reviewed, since: since.from.slice(0, 7) };
+}
+
+// Diffs slower than this are logged as warnings in every log format
+const SLOW_DIFF_MS = 2000;
+
+function logDiffTiming(repo: Repository, diff: DiffResponse, compareBranch: string, elapsedMs: number): void {
+  const fields = {
+    repo: repo.name,
+    branch: diff.branch,
+    compare: compareBranch,
+    mode: diff.mode,
+    files: diff.files.length,
+    duration_ms: Math.round(elapsedMs),
+  };
+  if (elapsedMs > SLOW_DIFF_MS) {
+    logger.warn("Slow diff", fields);
+  } else {
+    logger.detail("diff computed", fields);
+  }
+}
+
+// Default-base diff started at startup (--precompute), handed to the first request that asks for it
+let precomputed: { repoPath: string; baseBranch: string; diff: Promise<DiffResponse | null> } | null = null;
+
+// --pre-review-hook: runs before the server listens, so the first diff sees what it generated.
+// A non-zero exit throws (the server doesn't start) unless the failure is ignored
+async function runStartupHook(script: string | undefined, ignoreFailure: boolean): Promise<void> {
+  const repo = await state.getCurrentRepo();
+  if (!repo) {
+    logger.warn("No repository selected; skipping the pre-review hook");
+    return;
+  }
+
+  const started = performance.now();
+  const result = await runPreReviewHook(repo.path, repo.baseBranch, script);
+  if (!result) {
+    if (script) throw new Error(`Pre-review hook not found: ${script}`);
+    logger.warn(`No ${PRE_REVIEW_HOOK} hook in ${repo.name}; skipping`);
+    return;
+  }
+
+  for (const line of result.output.trimEnd().split("\n").filter(Boolean)) {
+    logger.info(`[${PRE_REVIEW_HOOK}] ${line}`);
+  }
+  const fields = { hook: result.path, exit_code: result.exitCode, duration_ms: Math.round(performance.now() - started) };
+  if (result.timedOut) {
+    const message = `Pre-review hook ${result.path} timed out after ${PRE_REVIEW_HOOK_TIMEOUT_MS / 1000}s`;
+    if (!ignoreFailure) throw new Error(`${message} (use --ignore-hook-failure to start anyway)`);
+    logger.warn(`${message}; starting anyway`, fields);
+  } else if (result.exitCode === 0) {
+    logger.detail("Pre-review hook finished", fields);
+  } else if (ignoreFailure) {
+    logger.warn(`Pre-review hook exited with ${result.exitCode}; starting anyway`, fields);
+  } else {
+    throw new Error(`Pre-review hook ${result.path} exited with ${result.exitCode} (use --ignore-hook-failure to start anyway)`);
+  }
+}
+
+// Runs in the background: a failure (e.g. a bad base) is logged and the server keeps serving
+async function precomputeDiff(): Promise<void> {
+  const repo = await state.getCurrentRepo();
+  if (!repo) return;
+
+  const started = performance.now();
+  const diff = getGitManager(repo.path)
+    .getDiff({ baseBranch: repo.baseBranch, mode: "branch" })
+    .then((result) => {
+      const additions = result.files.reduce((sum, f) => sum + f.additions, 0);
+      const deletions = result.files.reduce((sum, f) => sum + f.deletions, 0);
+      logger.info(`Ready: ${result.files.length} files, +${additions}/-${deletions} against ${repo.baseBranch}`, {
+        duration_ms: Math.round(performance.now() - started),
+      });
+      return result;
+    })
+    .catch((error: unknown) => {
+      logger.error(`Failed to precompute diff against ${repo.baseBranch}:`, { error });
+      return null;
+    });
+  precomputed = { repoPath: repo.path, baseBranch: repo.baseBranch, diff };
+}
+
+// The precomputed diff if it matches this request and HEAD hasn't moved since (used once)
+async function takePrecomputedDiff(repo: Repository, git: GitManager, compareBranch: string): Promise<DiffResponse | null> {
+  const entry = precomputed;
+  if (!entry || entry.repoPath !== repo.path || entry.baseBranch !== compareBranch) return null;
+  precomputed = null;
+
+  const diff = await entry.diff;
+  return diff && diff.commit === (await git.getCurrentCommit()) ? diff : null;
+}
+
+// Compute a repo's diff with viewed state applied
+async function buildDiffResponse(
+  repo: Repository,
+  mode: DiffMode,
+  compareBranch: string,
+  options: ServerOptions,
+  paths: string[] = []
+): Promise<DiffResponse> {
+  const git = getGitManager(repo.path);
+  // The startup diff's patches are text with the startup content options, so a base64 request
+  // or one overriding them (?minimal=) computes its own
+  const usePrecomputed = mode === "branch" && paths.length === 0 && !readingPatchBytes() && !hasDiffContentOverrides();
+  const precomputedDiff = usePrecomputed ? await takePrecomputedDiff(repo, git, compareBranch) : null;
+  const diff = precomputedDiff ?? (await git.getDiff({ baseBranch: compareBranch, mode, paths }));
+
+  const branch = await git.getCurrentBranch();
+  const commit = await git.getCurrentCommit();
+  const key: ReviewKey = { branch, commit, ...(mode !== "branch" && { worktree: true as const }) };
+  await carryForwardViewed(repo, git, key, options);
+  return applyReviewState(repo, git, diff, key, options);
+}
+
+// Carries marks across a moved HEAD before a diff's viewed state is read. A read-only server
+// leaves stored marks alone
+async function carryForwardViewed(repo: Repository, git: GitManager, key: ReviewKey, options: ServerOptions): Promise<void> {
+  if (options.readOnly) return;
+  const result = await carryViewedMarks(repo.id, git, key);
+  if (result && result.carried > 0) {
+    logger.detail("carried viewed marks", { repo: repo.name, branch: key.branch, from: result.from, to: key.commit, files: result.carried });
+  }
+}
+
+// Viewed state for ?show=<oid> is keyed by the reviewed commit under this pseudo-branch
+const SHOW_REVIEW_BRANCH = "@show";
+// ...and for ?range= under "@range:<from>"
+const RANGE_REVIEW_PREFIX = "@range:";
+
+// Key for viewed state: the ?show commit or ?range if given (null when they don't resolve), else the checkout
+async function getReviewKey(git: GitManager, url: URL): Promise<ReviewKey | null> {
+  const show = showParam(url);
+  if (show) {
+    const oid = await git.resolveCommit(show);
+    return oid ? { branch: SHOW_REVIEW_BRANCH, commit: oid.slice(0, 7) } : null;
+  }
+  const rangeSpec = url.searchParams.get("range");
+  if (rangeSpec !== null) {
+    const range = await resolveRangeParam(git, rangeSpec);
+    return range instanceof Response ? null : rangeReviewKey(range);
+  }
+  const mode = url.searchParams.get("mode") as DiffMode | null;
+  const worktree = mode !== null && mode !== "branch" && DIFF_MODES.includes(mode);
+  return { branch: await git.getCurrentBranch(), commit: await git.getCurrentCommit(), ...(worktree && { worktree: true as const }) };
+}
+
+// Apply viewed and collapsed state
+async function applyReviewState(
+  repo: Repository,
+  git: GitManager,
+  diff: DiffResponse,
+  key: ReviewKey,
+  options: ServerOptions
+): Promise<DiffResponse> {
+  const { branch, commit } = key;
+  const [viewed, branchViewed, viewedBy, collapsed, position] = await Promise.all([
+    state.getViewedFiles(repo.id, branch, commit),
+    state.getBranchViewedFiles(repo.id, branch),
+    state.getViewedBy(repo.id, branch, commit),
+    state.getCollapsedFiles(repo.id, branch, commit),
+    state.getReviewPosition(repo.id, branch),
+  ]);
+  if (position) diff.last_position = position;
+
+  // Content keying: a file also counts as viewed wherever this exact blob was marked
+  if (options.stateKey === "content") {
+    const blobs = await reviewBlobOids(git, key, diff.files.map((f) => f.path));
+    for (const [filePath, mark] of Object.entries(await state.getViewedByContent(repo.id, blobs))) {
+      viewed[filePath] = true;
+      if (mark.viewed_by) viewedBy[filePath] ??= mark.viewed_by;
+    }
+  }
+
+  // Marks shared through git notes count alongside local ones
+  if (options.gitNotes) {
+    const note = await git.getReviewNote(commit);
+    for (const [filePath, mark] of Object.entries(note.viewed[branch] ?? {})) {
+      viewed[filePath] = true;
+      if (mark.viewed_by) viewedBy[filePath] ??= mark.viewed_by;
+    }
+  }
+
+  const isIgnored = createIgnoreMatcher(options.ignore ?? []);
+  const isHidden = hiddenByIgnore(options);
+  const files = diff.files.filter((f) => !isHidden(f.path));
+
+  diff.files = files.map((f) => {
+    const ignored = isIgnored(f.path);
+    return {
+      ...f,
+      viewed: viewed[f.path] || false,
+      viewed_by: viewedBy[f.path],
+      ...(branchViewed[f.path] && { viewed_scope: "branch" as const }),
+      ...(isFullNewFile(f) && { full_new_file: true as const }),
+      collapsed: collapsed[f.path] || ignored,
+      ...(ignored && { ignored }),
+      ...(!requiresReview({ status: f.status, ignored }, options.requireReviewFor) && { review_required: false as const }),
+    };
+  });
+  diff.progress = reviewProgress(diff.files);
+  if (options.autoMarkOnOpen && !options.readOnly) diff.auto_mark_on_open = true;
+
+  return diff;
+}
+
+// Single file diff handler (lazy loading)
+async function handleGetFileDiff(url: URL, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const filePath = url.searchParams.get("file");
+  if (!filePath) {
+    return Response.json({ error: "File path required" }, { status: 400 });
+  }
+
+  const mode = await modeParam(url, repo);
+  if (mode instanceof Response) return mode;
+
+  const encoding = resolvePatchEncoding(url);
+  if (encoding instanceof Response) return encoding;
+
+  const compareBranch = compareParam(url, repo);
+  const git = getGitManager(repo.path);
+
+  // ?show=<oid> reads the file from a single commit, ?range= between two, instead of the branch/working diff
+  const show = showParam(url);
+  const rangeSpec = url.searchParams.get("range");
+  let fileDiff: FileDiff | null;
+  // Ends of a range or since=last diff, for ?attribute=true
+  let revisions: ResolvedRange | null = null;
+  if (show) {
+    const oid = await git.resolveCommit(show);
+    if (!oid) {
+      return Response.json({ error: "Commit not found" }, { status: 404 });
+    }
+    const parent = await resolveParentParam(git, oid, url);
+    if (parent instanceof Response) return parent;
+    fileDiff = await git.getCommitFileDiff(oid, filePath, parent);
+  } else if (rangeSpec !== null) {
+    const range = await resolveRangeParam(git, rangeSpec);
+    if (range instanceof Response) return range;
+    revisions = range;
+    fileDiff = await git.getRevisionFileDiff(range.from, range.to, filePath);
+  } else {
+    const since = await resolveSinceParam(repo, git, url);
+    if (since instanceof Response) return since;
+    revisions = since;
+    fileDiff = since
+      ? await git.getRevisionFileDiff(since.from, since.to, filePath)
+      : await git.getFileDiff({ baseBranch: compareBranch, mode, filePath });
+  }
+
+  if (!fileDiff) {
+    return Response.json({ error: "File not found" }, { status: 404 });
+  }
+  if (isFullNewFile(fileDiff)) fileDiff.full_new_file = true;
+
+  if (url.searchParams.get("attribute") === "true" && revisions && fileDiff.status !== "deleted") {
+    const attributed = (await git.attributeLines(revisions.from, revisions.to, [filePath])).lines[filePath];
+    if (attributed) fileDiff.line_commits = attributed;
+  }
+
+  const view = url.searchParams.get("view");
+  if (view === "split" || view === "hunks") {
+    // ?collapse=<n> overrides the server default; 0 disables collapsing
+    const collapseParam = url.searchParams.get("collapse");
+    const threshold = collapseParam !== null ? parseInt(collapseParam, 10) || 0 : options.collapseContext ?? 0;
+    // ?tab_width=<n> likewise; 0 keeps tabs. `patch` always keeps the raw bytes
+    const tabWidthParam = url.searchParams.get("tab_width");
+    const tabWidth = tabWidthParam !== null ? parseInt(tabWidthParam, 10) || 0 : options.tabWidth ?? 0;
+
+    const lineIds = url.searchParams.get("line_ids") === "true";
+    if (view === "split") {
+      fileDiff.split = toSplitView(patchText(fileDiff.patch), threshold, tabWidth, lineIds);
+    } else {
+      const hunks = parsePatch(patchText(fileDiff.patch));
+      fileDiff.hunks = (lineIds ? addLineIds(hunks) : hunks)
+        .map((hunk) => (tabWidth ? expandHunkTabs(hunk, tabWidth) : hunk))
+        .map((hunk) => (threshold ? collapseContext(hunk, threshold) : hunk));
+    }
+  }
+
+  encodePatches([fileDiff], encoding);
+  return Response.json(fileDiff);
+}
+
+// Lines of the old or new side of a file, for expanding collapsed context
+async function handleGetContext(url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const filePath = url.searchParams.get("file");
+  if (!filePath) {
+    return Response.json({ error: "File path required" }, { status: 400 });
+  }
+
+  const start = parseInt(url.searchParams.get("start") ?? "", 10);
+  const end = parseInt(url.searchParams.get("end") ?? "", 10);
+  if (!Number.isInteger(start) || !Number.isInteger(end) || start < 1 || end < start) {
+    return Response.json({ error: "start and end must be line numbers with start <= end" }, { status: 400 });
+  }
+
+  const side = url.searchParams.get("side") === "old" ? "old" : "new";
+  const mode = await modeParam(url, repo);
+  if (mode instanceof Response) return mode;
+  const compareBranch = compareParam(url, repo);
+  const git = getGitManager(repo.path);
+  const fileDiff = await git.getFileDiff({ baseBranch: compareBranch, mode, filePath });
+
+  const contents = side === "old" ? fileDiff?.old_file?.contents : fileDiff?.new_file?.contents;
+  if (contents === undefined) {
+    return Response.json({ error: "File not found" }, { status: 404 });
+  }
+
+  const lines = contents.split("\n").slice(start - 1, end);
+  return Response.json({ path: filePath, side, start, lines });
+}
+
+// Inside the worktree once symlinks are resolved, and not in .git (hooks, config and objects
+// aren't part of any diff)
+async function isServableRepoPath(repoPath: string, filePath: string): Promise<boolean> {
+  const { resolve, relative, isAbsolute, sep } = await import("path");
+  const { realpath } = await import("fs/promises");
+  const inside = (root: string, path: string) => {
+    const rel = relative(root, path);
+    return !rel.startsWith("..") && !isAbsolute(rel) && !rel.split(sep).includes(".git");
+  };
+
+  const fullPath = resolve(repoPath, filePath);
+  if (!inside(repoPath, fullPath)) return false;
+  // Old sides and deleted files have nothing on disk to follow
+  const real = await realpath(fullPath).catch(() => null);
+  return real === null || inside(await realpath(repoPath), real);
+}
+
+// One side of a file's bytes for /api/image and /api/file (same params as file-diff plus side)
+async function readSideBytes(url: URL): Promise<{ filePath: string; bytes: Uint8Array } | Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const filePath = url.searchParams.get("path");
+  if (!filePath) {
+    return Response.json({ error: "File path required" }, { status: 400 });
+  }
+
+  if (!(await isServableRepoPath(repo.path, filePath))) {
+    return Response.json({ error: "Path is outside the repository" }, { status: 400 });
+  }
+
+  const side = url.searchParams.get("side");
+  if (side !== "old" && side !== "new") {
+    return Response.json({ error: "side must be old or new" }, { status: 400 });
+  }
+
+  const mode = await modeParam(url, repo);
+  if (mode instanceof Response) return mode;
+
+  const compareBranch = compareParam(url, repo);
+  const git = getGitManager(repo.path);
+
+  const show = showParam(url);
+  let blob: FileBlob | null;
+  if (show) {
+    const oid = await git.resolveCommit(show);
+    if (!oid) {
+      return Response.json({ error: "Commit not found" }, { status: 404 });
+    }
+    const parent = await resolveParentParam(git, oid, url);
+    if (parent instanceof Response) return parent;
+    blob = await git.getCommitFileBlob(oid, filePath, side, parent);
+  } else {
+    blob = await git.getFileBlob({ baseBranch: compareBranch, mode, filePath, side });
+  }
+
+  if (!blob) {
+    return Response.json({ error: "File not found" }, { status: 404 });
+  }
+  if (blob.size > MAX_IMAGE_BYTES) {
+    return Response.json({ error: `File exceeds ${MAX_IMAGE_BYTES} bytes` }, { status: 413 });
+  }
+  return { filePath, bytes: await blob.read() };
+}
+
+function rawFileResponse(bytes: Uint8Array, contentType: string): Response {
+  return new Response(bytes, {
+    headers: {
+      "Content-Type": contentType,
+      // SVGs can carry scripts; never let them run in our origin
+      ...(contentType === "image/svg+xml" && { "Content-Security-Policy": "default-src 'none'; style-src 'unsafe-inline'" }),
+      "X-Content-Type-Options": "nosniff",
+    },
+  });
+}
+
+// Raw bytes of the old or new side of an image, for before/after rendering
+async function handleGetImage(url: URL): Promise<Response> {
+  const side = await readSideBytes(url);
+  if (side instanceof Response) return side;
+
+  const contentType = imageContentType(side.filePath, side.bytes);
+  if (!contentType) {
+    return Response.json({ error: "Not an image" }, { status: 415 });
+  }
+  return rawFileResponse(side.bytes, contentType);
+}
+
+// Raw bytes of either side of any file, typed so browsers render PDFs, images and text inline
+// instead of downloading them; ?sniff=false types by extension alone
+async function handleGetRawFile(url: URL): Promise<Response> {
+  const side = await readSideBytes(url);
+  if (side instanceof Response) return side;
+
+  const contentType = fileContentType(side.filePath, side.bytes, url.searchParams.get("sniff") !== "false");
+  return rawFileResponse(side.bytes, contentType);
+}
+
+// Keyboard shortcuts
+async function handleGetShortcuts(): Promise<Response> {
+  const config = await state.getConfig();
+  return Response.json({ shortcuts: resolveShortcuts(config.shortcuts), defaults: DEFAULT_SHORTCUTS });
+}
+
+async function handleSetShortcuts(req: Request): Promise<Response> {
+  const body = await req.json();
+  const validation = validateRequest(ShortcutsRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const config = await state.getConfig();
+  // Stale actions in the saved config are dropped rather than rejected
+  const overrides = { ...diffFromDefaults(config.shortcuts ?? {}), ...validation.data.shortcuts };
+  const error = validateShortcuts(overrides);
+  if (error) {
+    return Response.json({ error }, { status: 400 });
+  }
+
+  config.shortcuts = diffFromDefaults(overrides);
+  await state.saveConfig(config);
+  return Response.json({ shortcuts: resolveShortcuts(config.shortcuts), defaults: DEFAULT_SHORTCUTS });
+}
+
+async function handleSetPreferences(req: Request): Promise<Response> {
+  const body = await req.json();
+  const validation = validateRequest(PreferencesRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  return Response.json({ prefs: await state.setPreferences(validation.data.prefs) });
+}
+
+// Blame for the base version of a file
+async function handleGetBlame(url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const filePath = url.searchParams.get("path");
+  if (!filePath) {
+    return Response.json({ error: "File path required" }, { status: 400 });
+  }
+
+  const compareBranch = compareParam(url, repo);
+  const git = getGitManager(repo.path);
+  const lines = await git.getBlame({ baseBranch: compareBranch, filePath });
+
+  return Response.json({ path: filePath, exists_in_base: lines !== null, lines: lines ?? [] });
+}
+
+// Stats handlers
+
+// The diff's files for the stats endpoints, without the ones --ignore-mode hide leaves out
+async function statsFiles(url: URL, repo: Repository, options: ServerOptions): Promise<FileDiff[] | Response> {
+  const mode = await modeParam(url, repo);
+  if (mode instanceof Response) return mode;
+  const compareBranch = compareParam(url, repo);
+  const git = getGitManager(repo.path);
+  const diff = await git.getDiff({ baseBranch: compareBranch, mode, paths: url.searchParams.getAll("path") });
+  const isHidden = hiddenByIgnore(options);
+  return diff.files.filter((f) => !isHidden(f.path));
+}
+
+async function handleGetLanguageStats(url: URL, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const files = await statsFiles(url, repo, options);
+  if (files instanceof Response) return files;
+
+  return Response.json({ languages: getLanguageStats(files) });
+}
+
+async function handleGetDistribution(url: URL, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const files = await statsFiles(url, repo, options);
+  if (files instanceof Response) return files;
+
+  return Response.json({ buckets: getChangeDistribution(files) });
+}
+
+// git --stat summary: per-file bars as JSON, or just the text with ?format=text
+async function handleGetDiffStat(url: URL, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const widthParam = url.searchParams.get("width");
+  const width = widthParam !== null ? Number(widthParam) : 80;
+  if (!Number.isInteger(width) || width < 20 || width > 1000) {
+    return Response.json({ error: "width must be an integer between 20 and 1000" }, { status: 400 });
+  }
+
+  const files = await statsFiles(url, repo, options);
+  if (files instanceof Response) return files;
+  const stat = getDiffStat(files, width);
+
+  if (url.searchParams.get("format") === "text") {
+    return new Response(stat.text, { headers: { "Content-Type": "text/plain; charset=utf-8" } });
+  }
+  return Response.json(stat);
+}
+
+// Unmerged paths mid-merge/rebase, including ones the working diff can't show (e.g. a
+// modify/delete conflict left at our version)
+async function handleGetConflicts(url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const git = getGitManager(repo.path);
+  const [branch, files] = await Promise.all([git.getCurrentBranch(), git.getConflicts()]);
+  const list: ConflictList = { branch, files };
+  return Response.json(list);
+}
+
+// Viewed file handlers
+// Just the viewed paths, straight from state, so the UI can resync after bulk marks without
+// refetching the diff. Marks kept in git notes or matched by content need the diff; not here
+async function handleGetViewed(url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const git = getGitManager(repo.path);
+  const key = await getReviewKey(git, url);
+  if (!key) {
+    return Response.json({ error: "Commit not found" }, { status: 404 });
+  }
+
+  const [viewed, branchViewed] = await Promise.all([
+    state.getViewedFiles(repo.id, key.branch, key.commit),
+    state.getBranchViewedFiles(repo.id, key.branch),
+  ]);
+  const files = Object.keys({ ...viewed, ...branchViewed }).filter((f) => viewed[f] || branchViewed[f]);
+  const list: ViewedFileList = { branch: key.branch, commit: key.commit, files: files.sort() };
+  return Response.json(list);
+}
+
+async function handleMarkViewed(req: Request, url: URL, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const body = await req.json();
+  const validation = validateRequest(MarkViewedRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  // ?scope= works too, for clients that can't change the body; the body wins
+  const scopeParam = url.searchParams.get("scope");
+  if (scopeParam !== null && !state.VIEWED_SCOPES.includes(scopeParam as state.ViewedScope)) {
+    return Response.json({ error: `scope must be one of: ${state.VIEWED_SCOPES.join(", ")}` }, { status: 400 });
+  }
+  const scope = validation.data.scope ?? (scopeParam as state.ViewedScope | null) ?? "commit";
+
+  const git = getGitManager(repo.path);
+  const key = await getReviewKey(git, url);
+  if (!key) {
+    return Response.json({ error: "Commit not found" }, { status: 404 });
+  }
+  await markFileViewed(repo, git, key, validation.data.file_path, options, scope);
+  return Response.json({ success: true });
+}
+
+async function markFileViewed(
+  repo: Repository,
+  git: GitManager,
+  key: ReviewKey,
+  filePath: string,
+  options: ServerOptions,
+  scope: state.ViewedScope = "commit"
+): Promise<void> {
+  const { branch, commit } = key;
+  const reviewer = options.reviewer ?? (await git.getUserName()) ?? "anonymous";
+  const blobs = await reviewBlobOids(git, key, [filePath]);
+
+  await state.setFileViewed(repo.id, branch, commit, filePath, true, reviewer, blobs[filePath], scope);
+  if (options.gitNotes) {
+    await updateReviewNote(git, branch, commit, filePath, reviewer);
+  }
+}
+
+async function handleUnmarkViewed(req: Request, url: URL, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const body = await req.json();
+  const validation = validateRequest(FilePathRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const git = getGitManager(repo.path);
+  const key = await getReviewKey(git, url);
+  if (!key) {
+    return Response.json({ error: "Commit not found" }, { status: 404 });
+  }
+  const { branch, commit } = key;
+
+  await state.setFileViewed(repo.id, branch, commit, validation.data.file_path, false);
+  if (options.stateKey === "content") {
+    // Otherwise a mark on the same content elsewhere would keep it viewed
+    const blob = (await reviewBlobOids(git, key, [validation.data.file_path]))[validation.data.file_path];
+    if (blob) await state.clearViewedContent(repo.id, branch, validation.data.file_path, blob);
+  }
+  if (options.gitNotes) {
+    await updateReviewNote(git, branch, commit, validation.data.file_path, null);
+  }
+  return Response.json({ success: true });
+}
+
+// Add (reviewer given) or remove (null) a file's mark in the commit's review note
+async function updateReviewNote(
+  git: GitManager,
+  branch: string,
+  commit: string,
+  filePath: string,
+  reviewer: string | null
+): Promise<void> {
+  const note = await git.getReviewNote(commit);
+  const marks = (note.viewed[branch] ??= {});
+  if (reviewer !== null) {
+    marks[filePath] = { viewed_at: Date.now(), viewed_by: reviewer };
+  } else {
+    delete marks[filePath];
+  }
+  await git.setReviewNote(commit, note);
+}
+
+async function handleSetCollapsed(req: Request, url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const body = await req.json();
+  const validation = validateRequest(CollapseRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const git = getGitManager(repo.path);
+  const key = await getReviewKey(git, url);
+  if (!key) {
+    return Response.json({ error: "Commit not found" }, { status: 404 });
+  }
+  const { branch, commit } = key;
+
+  await state.setFileCollapsed(repo.id, branch, commit, validation.data.file_path, validation.data.collapsed);
+  return Response.json({ success: true });
+}
+
+// Review position is per branch (or per ?show/?range review), not per commit, so it survives new pushes
+async function handleGetPosition(url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const key = await getReviewKey(getGitManager(repo.path), url);
+  if (!key) {
+    return Response.json({ error: "Commit not found" }, { status: 404 });
+  }
+  return Response.json({ position: (await state.getReviewPosition(repo.id, key.branch)) ?? null });
+}
+
+async function handleSetPosition(req: Request, url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const body = await req.json();
+  const validation = validateRequest(PositionRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const key = await getReviewKey(getGitManager(repo.path), url);
+  if (!key) {
+    return Response.json({ error: "Commit not found" }, { status: 404 });
+  }
+
+  await state.setReviewPosition(repo.id, key.branch, validation.data.file_path, validation.data.line);
+  return Response.json({ success: true });
+}
+
+// Git operation handlers
+async function handleStage(req: Request, url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const worktreeError = await requireWorktree(repo);
+  if (worktreeError) return worktreeError;
+
+  const body = await req.json();
+  const validation = validateRequest(FilePathRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const git = getGitManager(repo.path);
+  await git.stageFile(validation.data.file_path);
+  return Response.json({ success: true });
+}
+
+async function handleUnstage(req: Request, url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const worktreeError = await requireWorktree(repo);
+  if (worktreeError) return worktreeError;
+
+  const body = await req.json();
+  const validation = validateRequest(FilePathRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const git = getGitManager(repo.path);
+  await git.unstageFile(validation.data.file_path);
+  return Response.json({ success: true });
+}
+
+async function handleDiscard(req: Request, url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const worktreeError = await requireWorktree(repo);
+  if (worktreeError) return worktreeError;
+
+  const body = await req.json();
+  const validation = validateRequest(FilePathRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const git = getGitManager(repo.path);
+  await git.discardFile(validation.data.file_path);
+  return Response.json({ success: true });
+}
+
+async function handleCommit(req: Request, url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const worktreeError = await requireWorktree(repo);
+  if (worktreeError) return worktreeError;
+
+  const body = await req.json();
+  const validation = validateRequest(CommitRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const git = getGitManager(repo.path);
+  const commitHash = await git.commit(validation.data.message);
+  return Response.json({ commit: commitHash });
+}
+
+// Comment handlers
+async function handleGetComments(url: URL): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const git = getGitManager(repo.path);
+  const branch = await git.getCurrentBranch();
+
+  const comments = await state.getComments(repo.id, branch);
+  return Response.json(comments);
+}
+
+async function handleAddComment(req: Request, url: URL, options: ServerOptions): Promise<Response> {
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const body = await req.json();
+  const validation = validateRequest(AddCommentRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const git = getGitManager(repo.path);
+  const branch = await git.getCurrentBranch();
+  const commit = await git.getCurrentCommit();
+
+  const comment = await state.addComment(repo.id, {
+    file_path: validation.data.file_path,
+    line_number: validation.data.line_number,
+    text: validation.data.text,
+    branch,
+    commit,
+    author: validation.data.author ?? options.reviewer ?? (await git.getUserName()),
+  });
+
+  return Response.json(comment);
+}
+
+async function handleResolveComment(req: Request, url: URL): Promise<Response> {
+  const body = await req.json();
+  const validation = validateRequest(ResolveCommentRequestSchema, body);
+  if (!validation.success) return validation.response;
+
+  const success = await state.resolveComment(validation.data.comment_id, validation.data.resolved_by || "user");
+  if (!success) {
+    return Response.json({ error: "Comment not found" }, { status: 404 });
+  }
+  return Response.json({ success: true });
+}
 
 // Note handlers
 async function handleGetNotes(url: URL): Promise<Response> {
@@ -530,6 +2271,53 @@ async function handleDismissNote(req: Request, _url: URL): Promise<Response> {
   return Response.json({ success: true });
 }
 
+// Open-in-editor handler (local use only, requires --allow-open). It launches a program on
+// the host, so a request another site's page sent (any Origin but ours or --cors-origin's) is refused
+async function handleOpenInEditor(req: Request, url: URL, options: ServerOptions): Promise<Response> {
+  if (!options.allowOpen) {
+    return Response.json({ error: "Opening files is disabled (start with --allow-open)" }, { status: 403 });
+  }
+  if (isForeignOrigin(req, options.corsOrigins ?? [])) {
+    return Response.json({ error: "Cross-origin requests can't open files" }, { status: 403 });
+  }
+
+  const editor = options.editor ?? process.env["EDITOR"];
+  if (!editor) {
+    return Response.json({ error: "No editor configured (set $EDITOR or --editor)" }, { status: 400 });
+  }
+
+  const repo = await getCurrentRepoFromRequest(url);
+  if (!repo) {
+    return Response.json({ error: "No repository selected" }, { status: 400 });
+  }
+
+  const body = await req.json();
+  const validation = validateRequest(OpenFileRequestSchema, body);
+  if (!validation.success) return validation.response;
+  const { file_path: filePath, line } = validation.data;
+
+  const { resolve, relative, isAbsolute } = await import("path");
+  const fullPath = resolve(repo.path, filePath);
+  const relPath = relative(repo.path, fullPath);
+  if (relPath.startsWith("..") || isAbsolute(relPath)) {
+    return Response.json({ error: "Path is outside the repository" }, { status: 400 });
+  }
+
+  try {
+    Bun.spawn(buildEditorCommand(editor, fullPath, line), {
+      cwd: repo.path,
+      stdio: ["inherit", "inherit", "inherit"],
+    });
+  } catch (error) {
+    return Response.json(
+      { error: `Failed to launch editor: ${error instanceof Error ? error.message : String(error)}` },
+      { status: 500 }
+    );
+  }
+
+  return Response.json({ success: true });
+}
+
 // Directory browser handler
 async function handleBrowseDirectory(url: URL): Promise<Response> {
   const { readdir, stat } = await import("fs/promises");
@@ -617,10 +2405,12 @@ async function handleBrowseDirectory(url: URL): Promise<Response> {
 }
 
 // Static file serving (production only - assets embedded in binary)
-function serveStatic(
+async function serveStatic(
   path: string,
-  embeddedAssets: Map<string, { content: string; mimeType: string }>
-): Response {
+  url: URL,
+  embeddedAssets: Map<string, { content: string; mimeType: string }>,
+  options: ServerOptions
+): Promise<Response> {
   // Normalize path
   let filePath = path === "/" ? "/index.html" : path;
 
@@ -628,19 +2418,86 @@ function serveStatic(
   const embedded = embeddedAssets.get(filePath) || embeddedAssets.get(filePath.slice(1));
   if (embedded) {
     const content = Buffer.from(embedded.content, "base64");
+    if (filePath === "/index.html") {
+      return serveIndex(content, url, options);
+    }
     return new Response(content, {
-      headers: { "Content-Type": embedded.mimeType },
+      headers: { "Content-Type": assetContentType(filePath) ?? embedded.mimeType },
     });
   }
 
+  if (filePath === "/favicon.ico") {
+    return new Response(FAVICON_SVG, {
+      headers: { "Content-Type": "image/svg+xml", "Cache-Control": "public, max-age=86400" },
+    });
+  }
+
+  // A missing asset, not a client-side route
+  if (looksLikeFile(filePath)) {
+    return new Response("Not found", { status: 404 });
+  }
+
   // Fallback: return index.html for SPA routing
   const indexAsset = embeddedAssets.get("index.html") || embeddedAssets.get("/index.html");
   if (indexAsset) {
     const content = Buffer.from(indexAsset.content, "base64");
-    return new Response(content, {
-      headers: { "Content-Type": "text/html" },
-    });
+    return serveIndex(content, url, options);
   }
 
   return new Response("Not found", { status: 404 });
 }
+
+// Serve index.html with the initial diff inlined so the first render doesn't wait on /api/diff
+async function serveIndex(content: Buffer, url: URL, options: ServerOptions): Promise<Response> {
+  let html = content.toString("utf8");
+
+  const initialData = await getInitialData(url, options);
+  if (initialData) {
+    html = injectInitialData(html, initialData);
+  }
+
+  return new Response(html, {
+    headers: { "Content-Type": "text/html; charset=utf-8" },
+  });
+}
+
+// Only the review pages start from a diff; other client routes fetch what they need
+function wantsInitialData(pathname: string): boolean {
+  return pathname === "/" || pathname === "/index.html" || pathname.startsWith("/show/");
+}
+
+async function getInitialData(url: URL, options: ServerOptions): Promise<InitialData | null> {
+  if (!wantsInitialData(url.pathname)) return null;
+  try {
+    const repo = await getCurrentRepoFromRequest(url);
+    if (!repo) return null;
+
+    // /show/<oid> deep-links a single commit review
+    if (url.pathname.startsWith("/show/")) {
+      const rev = decodeURIComponent(url.pathname.slice("/show/".length));
+      assertRefAllowed(rev);
+      const oid = await getGitManager(repo.path).resolveCommit(rev);
+      if (!oid) return null;
+      return { repo_id: repo.id, diff: await buildCommitDiffResponse(repo, oid, options), show: oid };
+    }
+
+    const diff = await buildDiffResponse(repo, "branch", repo.baseBranch, options);
+    // Leave oversized diffs to the client's fetch, which gets the too-many-files error
+    const maxFiles = getMaxFiles(options);
+    if (maxFiles !== null && diff.files.length > maxFiles) return null;
+    return { repo_id: repo.id, diff };
+  } catch (error) {
+    // The page still works without it - the client falls back to fetching
+    logger.error("Failed to compute initial diff:", { error });
+    return null;
+  }
+}
+
+// Insert a <script> assigning window.__CEREBRO_INITIAL_DATA__ just before </head>
+export function injectInitialData(html: string, data: InitialData): string {
+  // Escape "<" so "</script>" inside file contents can't end the tag early
+  const json = JSON.stringify(data).replace(/</g, "\\u003c");
+  const script = `<script>window.__CEREBRO_INITIAL_DATA__ = ${json};</script>`;
+  // Replacer function so "$" sequences in the JSON aren't treated as patterns
+  return html.includes("</head>") ? html.replace("</head>", () => `${script}</head>`) : script + html;
+}
@@ -0,0 +1,46 @@
+import { describe, expect, it } from "bun:test";
+import { createLogger, type LogLevel } from "./logger";
+
+function capture() {
+  const lines: Array<{ level: LogLevel; args: unknown[] }> = [];
+  return { lines, write: (level: LogLevel, ...args: unknown[]) => lines.push({ level, args }) };
+}
+
+describe("createLogger", () => {
+  it("json format writes one object per line with fields", () => {
+    const { lines, write } = capture();
+    createLogger("json", write).info("hello", { path: "/api/diff", duration_ms: 12 });
+
+    expect(lines.length).toBe(1);
+    const entry = JSON.parse(lines[0]!.args[0] as string);
+    expect(entry.level).toBe("info");
+    expect(entry.msg).toBe("hello");
+    expect(entry.path).toBe("/api/diff");
+    expect(entry.duration_ms).toBe(12);
+    expect(typeof entry.time).toBe("string");
+  });
+
+  it("json format serializes errors", () => {
+    const { lines, write } = capture();
+    createLogger("json", write).error("failed", { error: new Error("boom") });
+
+    expect(lines[0]!.level).toBe("error");
+    expect(JSON.parse(lines[0]!.args[0] as string).error.message).toBe("boom");
+  });
+
+  it("pretty format appends key=value pairs", () => {
+    const { lines, write } = capture();
+    createLogger("pretty", write).warn("slow", { path: "/api/diff", ms: 5 });
+    expect(lines[0]!.args).toEqual(["slow path=/api/diff ms=5"]);
+  });
+
+  it("detail logs are only emitted in json format", () => {
+    const pretty = capture();
+    createLogger("pretty", pretty.write).detail("request", { method: "GET", path: "/api/diff", status: 200 });
+    expect(pretty.lines.length).toBe(0);
+
+    const json = capture();
+    createLogger("json", json.write).detail("request", { method: "GET", path: "/api/diff", status: 200 });
+    expect(JSON.parse(json.lines[0]!.args[0] as string).status).toBe(200);
+  });
+});
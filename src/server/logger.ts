@@ -0,0 +1,68 @@
+export const LOG_FORMATS = ["pretty", "json"] as const;
+export type LogFormat = (typeof LOG_FORMATS)[number];
+
+export type LogLevel = "info" | "warn" | "error";
+export type LogFields = Record<string, unknown>;
+
+export interface Logger {
+  info(message: string, fields?: LogFields): void;
+  warn(message: string, fields?: LogFields): void;
+  error(message: string, fields?: LogFields): void;
+  // Info-level detail (access logs, timings) only emitted in json format to keep
+  // interactive output quiet while the UI polls
+  detail(message: string, fields?: LogFields): void;
+}
+
+type Writer = (level: LogLevel, ...args: unknown[]) => void;
+
+const consoleWriter: Writer = (level, ...args) => {
+  if (level === "info") console.log(...args);
+  else if (level === "warn") console.warn(...args);
+  else console.error(...args);
+};
+
+// Errors don't survive JSON.stringify, so flatten them to message/stack
+function toJsonValue(value: unknown): unknown {
+  if (value instanceof Error) {
+    return { message: value.message, stack: value.stack };
+  }
+  return value;
+}
+
+function formatPretty(message: string, fields: LogFields): unknown[] {
+  const errors: unknown[] = [];
+  const pairs: string[] = [];
+  for (const [key, value] of Object.entries(fields)) {
+    if (value === undefined) continue;
+    if (value instanceof Error) {
+      // Let the console print the stack trace
+      errors.push(value);
+    } else {
+      pairs.push(`${key}=${typeof value === "string" ? value : JSON.stringify(value)}`);
+    }
+  }
+  return [[message, ...pairs].join(" "), ...errors];
+}
+
+export function createLogger(format: LogFormat, write: Writer = consoleWriter): Logger {
+  const log = (level: LogLevel, message: string, fields: LogFields = {}) => {
+    if (format === "json") {
+      const entry: LogFields = { time: new Date().toISOString(), level, msg: message };
+      for (const [key, value] of Object.entries(fields)) {
+        entry[key] = toJsonValue(value);
+      }
+      write(level, JSON.stringify(entry));
+    } else {
+      write(level, ...formatPretty(message, fields));
+    }
+  };
+
+  return {
+    info: (message, fields) => log("info", message, fields),
+    warn: (message, fields) => log("warn", message, fields),
+    error: (message, fields) => log("error", message, fields),
+    detail: (message, fields) => {
+      if (format === "json") log("info", message, fields);
+    },
+  };
+}
@@ -0,0 +1,654 @@
+/**
+ * OpenAPI 3.1 description of the HTTP API, served at /api/openapi.json.
+ * Request bodies come from the zod schemas; response shapes mirror src/types.
+ */
+import { z } from "zod";
+import packageJson from "../../package.json";
+import {
+  AddCommentRequestSchema,
+  AddRepoRequestSchema,
+  CollapseRequestSchema,
+  PositionRequestSchema,
+  CommitRequestSchema,
+  DismissNoteRequestSchema,
+  FilePathRequestSchema,
+  MarkViewedRequestSchema,
+  OpenFileRequestSchema,
+  PreferencesRequestSchema,
+  ResolveCommentRequestSchema,
+  SetCurrentRepoRequestSchema,
+  ShortcutsRequestSchema,
+} from "../schemas";
+
+type JsonSchema = Record<string, unknown>;
+
+interface Param {
+  name: string;
+  description: string;
+  schema?: JsonSchema;
+  required?: boolean;
+  in?: "query" | "path";
+}
+
+interface Operation {
+  summary: string;
+  params?: Param[];
+  body?: z.ZodType;
+  response?: JsonSchema;
+}
+
+const str: JsonSchema = { type: "string" };
+const int: JsonSchema = { type: "integer" };
+const bool: JsonSchema = { type: "boolean" };
+const ref = (name: string): JsonSchema => ({ $ref: `#/components/schemas/${name}` });
+const arrayOf = (items: JsonSchema): JsonSchema => ({ type: "array", items });
+const object = (properties: Record<string, JsonSchema>, required: string[] = []): JsonSchema => ({
+  type: "object",
+  properties,
+  required,
+});
+
+const repoParam: Param = { name: "repo", description: "Repository id (default: the current repository)" };
+const modeParam: Param = {
+  name: "mode",
+  description: "branch: merge base vs HEAD; working: uncommitted changes; full: merge base vs the working tree",
+  schema: { enum: ["branch", "working", "unstaged", "full"] },
+};
+const compareParam: Param = { name: "compare", description: "Base branch or any revision (default: the repo's base branch)" };
+const showParam: Param = { name: "show", description: "Review a single commit against its first parent" };
+const authorParam: Param = { name: "author", description: "Only commits whose author name or email contains this (any case)" };
+const parentParam: Param = { name: "parent", description: "Parent of a merge commit to diff against (default 1)", schema: int };
+const rangeParam: Param = { name: "range", description: "Git range, <base>..<head> or <base>...<head> (replaces mode/compare)" };
+const sinceParam: Param = {
+  name: "since",
+  description: "last: only changes since the branch was last reviewed (the full diff if never reviewed)",
+  schema: { enum: ["last"] },
+};
+const attributeParam: Param = {
+  name: "attribute",
+  description: "true with range or since: add `line_commits`, the range commit that introduced each new-side line",
+  schema: bool,
+};
+const pathsParam: Param = {
+  name: "path",
+  description: "Pathspec limiting the diff (repeatable)",
+  schema: arrayOf(str),
+};
+const patchEncodingParam: Param = {
+  name: "patch_encoding",
+  description: "base64 encodes each `patch` (flagged by `patch_encoding` on the file) for consumers that can't take raw text",
+  schema: { enum: ["utf8", "base64"] },
+};
+const minimalParam: Param = {
+  name: "minimal",
+  description: "true spends extra time finding the smallest diff (git --minimal); false overrides `--minimal`",
+  schema: bool,
+};
+const fileParam = (name: string): Param => ({ name, description: "Repository-relative file path", required: true });
+
+const diffParams = [repoParam, modeParam, compareParam];
+const success = object({ success: bool }, ["success"]);
+
+const schemas: Record<string, JsonSchema> = {
+  Error: object(
+    {
+      error: str,
+      code: {
+        enum: ["bad_request", "bad_ref", "forbidden", "not_found", "gone", "unsupported", "conflict", "too_large", "rate_limited", "timeout", "internal"],
+      },
+    },
+    ["error", "code"]
+  ),
+  FileContents: object({ name: str, contents: str }, ["name", "contents"]),
+  DiffLine: object(
+    {
+      type: { enum: ["context", "add", "delete", "collapsed"] },
+      content: str,
+      old_line: int,
+      new_line: int,
+      hidden_count: int,
+      no_newline: bool,
+      id: str,
+    },
+    ["type", "content"]
+  ),
+  DiffHunk: object(
+    {
+      header: str,
+      old_start: int,
+      old_lines: int,
+      new_start: int,
+      new_lines: int,
+      lines: arrayOf(ref("DiffLine")),
+      whitespace_only: bool,
+      id: str,
+    },
+    ["header", "old_start", "old_lines", "new_start", "new_lines", "lines", "whitespace_only", "id"]
+  ),
+  SplitCell: object({ line: int, content: str, type: { enum: ["context", "add", "delete", "collapsed"] }, id: str }, [
+    "line",
+    "content",
+    "type",
+  ]),
+  SplitHunk: object(
+    {
+      header: str,
+      rows: arrayOf(object({ left: ref("SplitCell"), right: ref("SplitCell") })),
+      whitespace_only: bool,
+      id: str,
+    },
+    ["header", "rows", "whitespace_only", "id"]
+  ),
+  MovedBlock: object(
+    { direction: { enum: ["moved_from", "moved_to"] }, line: int, lines: int, other_path: str, other_line: int },
+    ["direction", "line", "lines", "other_path", "other_line"]
+  ),
+  FileDiff: object(
+    {
+      path: str,
+      status: { enum: ["added", "modified", "deleted", "renamed", "copied", "untracked"] },
+      additions: int,
+      deletions: int,
+      patch: str,
+      patch_encoding: { enum: ["base64"] },
+      viewed: bool,
+      viewed_by: str,
+      viewed_scope: { enum: ["branch"] },
+      collapsed: bool,
+      ignored: bool,
+      review_required: { enum: [false] },
+      old_file: ref("FileContents"),
+      new_file: ref("FileContents"),
+      full_new_file: { enum: [true] },
+      staged: bool,
+      submodule: bool,
+      submodule_old_commit: str,
+      submodule_new_commit: str,
+      old_path: str,
+      similarity: int,
+      symlink: bool,
+      symlink_old_target: str,
+      symlink_new_target: str,
+      split: arrayOf(ref("SplitHunk")),
+      hunks: arrayOf(ref("DiffHunk")),
+      moved_blocks: arrayOf(ref("MovedBlock")),
+      binary: bool,
+      old_size: int,
+      new_size: int,
+      too_large: bool,
+      blob_size: int,
+      changed_in: { enum: ["committed", "uncommitted", "both"] },
+      commits: arrayOf(str),
+      resolution_lines: arrayOf(int),
+      line_commits: { type: "object", additionalProperties: str },
+      conflicted: { enum: [true] },
+    },
+    ["path", "status", "additions", "deletions", "patch", "viewed"]
+  ),
+  RenameGroup: object(
+    { from: str, to: str, files: arrayOf(object({ old_path: str, path: str }, ["old_path", "path"])) },
+    ["from", "to", "files"]
+  ),
+  DiffResponse: object(
+    {
+      files: arrayOf(ref("FileDiff")),
+      branch: str,
+      commit: str,
+      repo_path: str,
+      remote_url: str,
+      mode: { enum: ["branch", "working", "unstaged", "full"] },
+      base_branch: str,
+      total: int,
+      since: str,
+      renames: arrayOf(ref("RenameGroup")),
+      is_merge: bool,
+      parent: int,
+      combined: bool,
+      last_position: ref("ReviewPosition"),
+      progress: object({ viewed: int, required: int }, ["viewed", "required"]),
+      attribution: object({ commits: arrayOf(ref("RangeCommit")), truncated: bool }, ["commits"]),
+      force_push: ref("BranchRewrite"),
+      auto_mark_on_open: bool,
+    },
+    ["files", "branch", "commit", "repo_path", "mode", "base_branch"]
+  ),
+  DiffEstimate: object({ files: int, additions: int, deletions: int, max_files: int, exceeds_max_files: bool }, [
+    "files",
+    "additions",
+    "deletions",
+  ]),
+  Remote: object({ name: str, fetch_url: str, push_url: str }, ["name", "fetch_url"]),
+  DiffStat: object(
+    {
+      files: arrayOf(
+        object(
+          { path: str, additions: int, deletions: int, binary: bool, bar_additions: int, bar_deletions: int },
+          ["path", "additions", "deletions", "bar_additions", "bar_deletions"]
+        )
+      ),
+      additions: int,
+      deletions: int,
+      bar_width: int,
+      text: str,
+    },
+    ["files", "additions", "deletions", "bar_width", "text"]
+  ),
+  ReviewSummary: object(
+    {
+      repo_id: str,
+      repo_name: str,
+      repo_path: str,
+      kind: { enum: ["branch", "show", "range"] },
+      branch: str,
+      commit: str,
+      viewed: int,
+      last_viewed_at: int,
+      total: int,
+      stale: bool,
+      url: str,
+    },
+    ["repo_id", "repo_name", "repo_path", "kind", "branch", "commit", "viewed", "last_viewed_at", "url"]
+  ),
+  ReviewPosition: object({ file_path: str, line: int, updated_at: int }, ["file_path", "updated_at"]),
+  RangeCommit: object({ hash: str, summary: str, author: str, timestamp: int }, ["hash", "summary", "author", "timestamp"]),
+  RangeCommitList: object(
+    {
+      base_branch: str,
+      merge_base: str,
+      commits: arrayOf(ref("RangeCommit")),
+      truncated: bool,
+      author: str,
+      files: arrayOf(ref("FileDiff")),
+    },
+    ["base_branch", "merge_base", "commits"]
+  ),
+  GraphCommit: object({ hash: str, parents: arrayOf(str), summary: str, author: str, timestamp: int }, [
+    "hash",
+    "parents",
+    "summary",
+    "author",
+    "timestamp",
+  ]),
+  CommitGraph: object(
+    { base_branch: str, merge_base: str, head: str, commits: arrayOf(ref("GraphCommit")), truncated: bool },
+    ["base_branch", "merge_base", "head", "commits"]
+  ),
+  BranchRewrite: object(
+    { ref: str, old_tip: str, new_tip: str, reason: str, timestamp: int, available: bool },
+    ["ref", "old_tip", "new_tip", "reason", "timestamp", "available"]
+  ),
+  ConflictedFile: object(
+    { path: str, base: bool, ours: bool, theirs: bool, markers: int },
+    ["path", "base", "ours", "theirs", "markers"]
+  ),
+  ConflictList: object({ branch: str, files: arrayOf(ref("ConflictedFile")) }, ["branch", "files"]),
+  ViewedFileList: object({ branch: str, commit: str, files: arrayOf(str) }, ["branch", "commit", "files"]),
+  LocResponse: object({ files: int, additions: int, deletions: int, net: int }, ["files", "additions", "deletions", "net"]),
+  StatusResponse: object({ repo_path: str, branch: str, commit: { type: ["string", "null"] }, dirty: bool, message: str }, [
+    "repo_path",
+    "branch",
+    "commit",
+    "dirty",
+  ]),
+  RefreshResponse: object({ repo_path: str, branch: str, commit: str, dirty: bool, changed: bool }, [
+    "repo_path",
+    "branch",
+    "commit",
+    "dirty",
+    "changed",
+  ]),
+  Repository: object({ id: str, path: str, name: str, baseBranch: str, addedAt: int }, [
+    "id",
+    "path",
+    "name",
+    "baseBranch",
+    "addedAt",
+  ]),
+};
+
+// Every route handled in ./index.ts (index.test.ts checks the two stay in sync)
+const routes: Record<string, Partial<Record<"get" | "post" | "delete", Operation>>> = {
+  "/api/health": { get: { summary: "Health check", response: object({ status: str }) } },
+  "/api/openapi.json": { get: { summary: "This document" } },
+  "/api/repos": {
+    get: {
+      summary: "List repositories",
+      response: object({ repos: arrayOf(ref("Repository")), currentRepo: str }, ["repos"]),
+    },
+    post: { summary: "Add a repository", body: AddRepoRequestSchema, response: ref("Repository") },
+  },
+  "/api/repos/{id}": {
+    delete: { summary: "Remove a repository", params: [{ name: "id", in: "path", required: true, description: "Repository id" }] },
+  },
+  "/api/repos/current": { post: { summary: "Select the current repository", body: SetCurrentRepoRequestSchema } },
+  "/api/config/shortcuts": {
+    get: { summary: "Keyboard shortcuts with overrides applied, plus the defaults" },
+    post: { summary: "Merge shortcut overrides (400 on unknown actions or duplicate keys)", body: ShortcutsRequestSchema },
+  },
+  "/api/prefs": {
+    get: { summary: "UI preferences" },
+    post: { summary: "Merge UI preferences; null removes a key", body: PreferencesRequestSchema },
+  },
+  "/api/branches": { get: { summary: "Local and remote branches", params: [repoParam] } },
+  "/api/remotes": {
+    get: {
+      summary: "Configured remotes; any <remote>/<branch> works as compare or --base",
+      params: [repoParam],
+      response: object({ remotes: arrayOf(ref("Remote")) }, ["remotes"]),
+    },
+  },
+  "/api/status": { get: { summary: "Current branch, commit and dirty flag", params: [repoParam], response: ref("StatusResponse") } },
+  "/api/refresh": {
+    post: {
+      summary: "Re-read HEAD, branch and dirty state, dropping cached git state if any moved",
+      params: [repoParam],
+      response: ref("RefreshResponse"),
+    },
+  },
+  "/api/diff": {
+    get: {
+      summary: "Changed files with viewed state (patches load lazily via /api/file-diff in branch mode; 413 past --max-files unless paged)",
+      params: [
+        ...diffParams,
+        rangeParam,
+        sinceParam,
+        attributeParam,
+        pathsParam,
+        { name: "detect_moves", description: "Annotate blocks moved between files", schema: bool },
+        { name: "group_renames", description: "Fold unchanged files moved together into `renames`", schema: bool },
+        { name: "offset", description: "First file of the page", schema: int },
+        { name: "limit", description: "Page size; adds `total`", schema: int },
+        {
+          name: "format",
+          description: "github returns just the files, shaped like GitHub's pull request files API (filename, status, additions, deletions, changes, patch, previous_filename)",
+          schema: { enum: ["cerebro", "github"] },
+        },
+        patchEncodingParam,
+        minimalParam,
+      ],
+      response: ref("DiffResponse"),
+    },
+  },
+  "/api/export.zip": {
+    get: {
+      summary: "Zip archive of the review: patches/<path>.patch per changed file plus summary.json (branch, base, commit, files)",
+      params: [...diffParams, pathsParam],
+    },
+  },
+  "/api/diff/show/{oid}": {
+    get: {
+      summary: "A single commit against its first parent (merges set `is_merge`; ?parent=<n> picks another parent)",
+      params: [
+        repoParam,
+        { name: "oid", in: "path", required: true, description: "Commit oid or ref" },
+        pathsParam,
+        parentParam,
+        {
+          name: "combined",
+          description:
+            "true diffs a merge against all parents at once (git show --cc), listing only files changed relative to every parent and flagging `resolution_lines`. Patches are computed for every file up front, so large merges are slower than the lazy per-parent diff. Can't be combined with `parent` (400)",
+          schema: bool,
+        },
+        patchEncodingParam,
+        minimalParam,
+      ],
+      response: ref("DiffResponse"),
+    },
+  },
+  "/api/file-diff": {
+    get: {
+      summary: "One file's diff",
+      params: [
+        fileParam("file"),
+        ...diffParams,
+        showParam,
+        parentParam,
+        rangeParam,
+        sinceParam,
+        attributeParam,
+        { name: "view", description: "Add structured hunks", schema: { enum: ["split", "hunks"] } },
+        { name: "collapse", description: "Collapse unchanged runs longer than N lines", schema: int },
+        { name: "tab_width", description: "Expand tabs to N columns", schema: int },
+        { name: "line_ids", description: "true adds `id` anchors to added/deleted lines (hunks always have one)", schema: bool },
+        patchEncodingParam,
+        minimalParam,
+      ],
+      response: ref("FileDiff"),
+    },
+  },
+  "/api/context": {
+    get: {
+      summary: "Lines of one side of a file, for expanding context",
+      params: [
+        fileParam("file"),
+        ...diffParams,
+        { name: "start", description: "First line (1-based)", schema: int, required: true },
+        { name: "end", description: "Last line (inclusive)", schema: int, required: true },
+        { name: "side", description: "Side to read", schema: { enum: ["old", "new"] } },
+      ],
+    },
+  },
+  "/api/image": {
+    get: {
+      summary: "Raw image bytes for one side of a diff",
+      params: [
+        fileParam("path"),
+        { name: "side", description: "Side to read", schema: { enum: ["old", "new"] }, required: true },
+        ...diffParams,
+        showParam,
+        parentParam,
+      ],
+    },
+  },
+  "/api/file": {
+    get: {
+      summary:
+        "Raw bytes of one side of any file, typed by magic bytes then extension (text/plain when NUL-free, else application/octet-stream)",
+      params: [
+        fileParam("path"),
+        { name: "side", description: "Side to read", schema: { enum: ["old", "new"] }, required: true },
+        ...diffParams,
+        showParam,
+        parentParam,
+        { name: "sniff", description: "false types by extension only, ignoring magic bytes", schema: bool },
+      ],
+    },
+  },
+  "/api/blame": { get: { summary: "Blame for the base version of a file", params: [fileParam("path"), repoParam, compareParam] } },
+  "/api/range-info": {
+    get: {
+      summary: "Commits since the merge base",
+      params: [
+        repoParam,
+        compareParam,
+        { name: "limit", description: "Most commits to walk (capped at 1000); adds `truncated` when hit", schema: int },
+        authorParam,
+      ],
+    },
+  },
+  "/api/commits": {
+    get: {
+      summary: "Commits since the merge base, optionally one author's and their combined diff",
+      params: [
+        repoParam,
+        compareParam,
+        { name: "limit", description: "Most commits to walk (capped at 1000); adds `truncated` when hit", schema: int },
+        authorParam,
+        { name: "aggregate", description: "true adds `files`: the listed commits' changes as one diff", schema: bool },
+        patchEncodingParam,
+        minimalParam,
+      ],
+      response: ref("RangeCommitList"),
+    },
+  },
+  "/api/graph": {
+    get: {
+      summary: "Commit DAG since the merge base (parents included), newest first in topological order",
+      params: [
+        repoParam,
+        compareParam,
+        { name: "limit", description: "Most commits to walk (capped at 1000); adds `truncated` when hit", schema: int },
+      ],
+      response: ref("CommitGraph"),
+    },
+  },
+  "/api/reviews": {
+    get: {
+      summary: "Every repo/branch/commit with viewed marks, most recent first, for resuming a review",
+      params: [
+        {
+          name: "totals",
+          description: "true adds `total` (files in each reviewed diff); rebuilds every diff, so it is slow with many reviews",
+          schema: bool,
+        },
+      ],
+      response: object({ reviews: arrayOf(ref("ReviewSummary")) }, ["reviews"]),
+    },
+  },
+  "/api/estimate": {
+    get: {
+      summary: "File count and line totals without generating patches, to check before a large load",
+      params: [...diffParams, pathsParam],
+      response: ref("DiffEstimate"),
+    },
+  },
+  "/api/loc": {
+    get: { summary: "Lines added, deleted and net for the diff (numstat only)", params: [...diffParams, pathsParam], response: ref("LocResponse") },
+  },
+  "/api/stats/languages": { get: { summary: "Changed lines per language", params: [...diffParams, pathsParam] } },
+  "/api/stats/distribution": { get: { summary: "Files bucketed by lines changed", params: [...diffParams, pathsParam] } },
+  "/api/stats/diffstat": {
+    get: {
+      summary: "git --stat style summary: per-file +/- bars scaled to the largest file, plus the text",
+      params: [
+        ...diffParams,
+        pathsParam,
+        { name: "width", description: "Terminal width the bars fit (20-1000, default 80)", schema: int },
+        { name: "format", description: "text returns just the --stat text as text/plain", schema: { enum: ["json", "text"] } },
+      ],
+      response: ref("DiffStat"),
+    },
+  },
+  "/api/force-push-diff": {
+    get: {
+      summary:
+        "The branch's tip before its last rebase/amend/force-push (from the reflog) diffed against its current tip; 404 with no rewrite, 410 once the old tip was garbage collected",
+      params: [
+        repoParam,
+        { name: "ref", description: "Branch or remote-tracking branch, e.g. origin/feature (default: the current branch)" },
+        pathsParam,
+        patchEncodingParam,
+      ],
+      response: ref("DiffResponse"),
+    },
+  },
+  "/api/conflicts": {
+    get: {
+      summary: "Unmerged paths in the index, with the stages present and conflict markers left in each working file",
+      params: [repoParam],
+      response: ref("ConflictList"),
+    },
+  },
+  "/api/viewed": {
+    get: {
+      summary: "Viewed file paths for the current commit (or show/range), from state alone without computing the diff",
+      params: [repoParam, showParam, rangeParam],
+      response: ref("ViewedFileList"),
+    },
+  },
+  "/api/mark-viewed": {
+    post: {
+      summary: "Mark a file viewed (scope=branch keeps it viewed on later commits of the branch)",
+      params: [
+        repoParam,
+        showParam,
+        rangeParam,
+        { name: "scope", description: "commit (default) or branch; the body's `scope` wins", schema: { enum: ["commit", "branch"] } },
+      ],
+      body: MarkViewedRequestSchema,
+      response: success,
+    },
+  },
+  "/api/unmark-viewed": {
+    post: { summary: "Unmark a viewed file (clears a branch-scoped mark too)", params: [repoParam, showParam, rangeParam], body: FilePathRequestSchema, response: success },
+  },
+  "/api/collapse": {
+    post: { summary: "Persist a file's collapsed state", params: [repoParam, showParam, rangeParam], body: CollapseRequestSchema },
+  },
+  "/api/position": {
+    get: {
+      summary: "Where the reviewer left off on this branch (null when never saved)",
+      params: [repoParam, showParam, rangeParam],
+      response: object({ position: ref("ReviewPosition") }, ["position"]),
+    },
+    post: {
+      summary: "Save the file (and optionally line) the reviewer is at, per branch",
+      params: [repoParam, showParam, rangeParam],
+      body: PositionRequestSchema,
+      response: success,
+    },
+  },
+  "/api/stage": { post: { summary: "Stage a file", params: [repoParam], body: FilePathRequestSchema } },
+  "/api/unstage": { post: { summary: "Unstage a file", params: [repoParam], body: FilePathRequestSchema } },
+  "/api/discard": { post: { summary: "Discard a file's changes", params: [repoParam], body: FilePathRequestSchema } },
+  "/api/commit": { post: { summary: "Commit staged changes", params: [repoParam], body: CommitRequestSchema } },
+  "/api/comments": {
+    get: { summary: "Comments on the current branch", params: [repoParam] },
+    post: { summary: "Add a comment", params: [repoParam], body: AddCommentRequestSchema },
+  },
+  "/api/comments/resolve": { post: { summary: "Resolve a comment", params: [repoParam], body: ResolveCommentRequestSchema } },
+  "/api/notes": { get: { summary: "AI notes on the current branch", params: [repoParam] } },
+  "/api/notes/dismiss": { post: { summary: "Dismiss a note", params: [repoParam], body: DismissNoteRequestSchema } },
+  "/api/open": {
+    post: {
+      summary: "Open a file in the server's editor (requires --allow-open; refused cross-origin)",
+      params: [repoParam],
+      body: OpenFileRequestSchema,
+    },
+  },
+  "/api/browse": { get: { summary: "List directories for the repository picker", params: [{ name: "path", description: "Directory to list" }] } },
+};
+
+function toOperation(operation: Operation): JsonSchema {
+  return {
+    summary: operation.summary,
+    ...(operation.params && {
+      parameters: operation.params.map((param) => ({
+        name: param.name,
+        in: param.in ?? "query",
+        description: param.description,
+        required: param.in === "path" || param.required || undefined,
+        schema: param.schema ?? str,
+      })),
+    }),
+    ...(operation.body && {
+      requestBody: {
+        required: true,
+        content: { "application/json": { schema: z.toJSONSchema(operation.body) } },
+      },
+    }),
+    responses: {
+      "200": {
+        description: "OK",
+        ...(operation.response && { content: { "application/json": { schema: operation.response } } }),
+      },
+      default: { description: "Error", content: { "application/json": { schema: ref("Error") } } },
+    },
+  };
+}
+
+export function buildOpenApiDocument(): JsonSchema {
+  const paths = Object.fromEntries(
+    Object.entries(routes).map(([path, methods]) => [
+      path,
+      Object.fromEntries(Object.entries(methods).map(([method, operation]) => [method, toOperation(operation)])),
+    ])
+  );
+
+  return {
+    openapi: "3.1.0",
+    info: { title: "Cerebro API", version: packageJson.version },
+    paths,
+    components: { schemas },
+  };
+}
@@ -0,0 +1,27 @@
+import { describe, expect, it } from "bun:test";
+import { createRateLimiter } from "./rateLimit";
+
+describe("createRateLimiter", () => {
+  it("allows up to the limit within a window", () => {
+    const limiter = createRateLimiter(2, () => 1000);
+    expect(limiter.check("1.2.3.4")).toBe(true);
+    expect(limiter.check("1.2.3.4")).toBe(true);
+    expect(limiter.check("1.2.3.4")).toBe(false);
+  });
+
+  it("tracks clients independently", () => {
+    const limiter = createRateLimiter(1, () => 1000);
+    expect(limiter.check("1.2.3.4")).toBe(true);
+    expect(limiter.check("5.6.7.8")).toBe(true);
+    expect(limiter.check("1.2.3.4")).toBe(false);
+  });
+
+  it("resets once the window elapses", () => {
+    let time = 1000;
+    const limiter = createRateLimiter(1, () => time);
+    expect(limiter.check("1.2.3.4")).toBe(true);
+    expect(limiter.check("1.2.3.4")).toBe(false);
+    time += 1000;
+    expect(limiter.check("1.2.3.4")).toBe(true);
+  });
+});
@@ -0,0 +1,34 @@
+/**
+ * Fixed-window per-client rate limiter
+ * Keeps a request count per key (client IP) for the current one-second window
+ */
+
+export interface RateLimiter {
+  // Returns true if the request is allowed, false if the limit was exceeded
+  check(key: string): boolean;
+}
+
+const WINDOW_MS = 1000;
+
+export function createRateLimiter(requestsPerSecond: number, now: () => number = Date.now): RateLimiter {
+  const windows = new Map<string, { start: number; count: number }>();
+
+  return {
+    check(key: string): boolean {
+      const timestamp = now();
+      const current = windows.get(key);
+
+      if (!current || timestamp - current.start >= WINDOW_MS) {
+        // Drop stale windows so the map doesn't grow unbounded
+        for (const [k, w] of windows) {
+          if (timestamp - w.start >= WINDOW_MS) windows.delete(k);
+        }
+        windows.set(key, { start: timestamp, count: 1 });
+        return true;
+      }
+
+      current.count++;
+      return current.count <= requestsPerSecond;
+    },
+  };
+}
@@ -0,0 +1,49 @@
+import { describe, expect, it } from "bun:test";
+import type { FileDiff } from "../types";
+import { directoryMove, groupRenames } from "./renames";
+
+function renamed(oldPath: string, path: string, similarity = 100): FileDiff {
+  return { path, old_path: oldPath, similarity, status: "renamed", additions: 0, deletions: 0, patch: "", viewed: false };
+}
+
+describe("directoryMove", () => {
+  it("strips the shared tail of both paths", () => {
+    expect(directoryMove("src/a/x/f.ts", "src/b/x/f.ts")).toEqual({ from: "src/a", to: "src/b" });
+    expect(directoryMove("lib/f.ts", "src/lib/f.ts")).toEqual({ from: ".", to: "src" });
+  });
+
+  it("ignores renamed files", () => {
+    expect(directoryMove("src/a.ts", "src/b.ts")).toBeNull();
+  });
+});
+
+describe("groupRenames", () => {
+  it("folds unchanged files that moved together", () => {
+    const edited = renamed("src/a/y.ts", "src/b/y.ts", 96);
+    const modified: FileDiff = { path: "README.md", status: "modified", additions: 1, deletions: 0, patch: "", viewed: false };
+    const result = groupRenames([
+      renamed("src/a/x/f1.ts", "src/b/x/f1.ts"),
+      renamed("src/a/f2.ts", "src/b/f2.ts"),
+      edited,
+      modified,
+    ]);
+
+    expect(result.renames).toEqual([
+      {
+        from: "src/a",
+        to: "src/b",
+        files: [
+          { old_path: "src/a/x/f1.ts", path: "src/b/x/f1.ts" },
+          { old_path: "src/a/f2.ts", path: "src/b/f2.ts" },
+        ],
+      },
+    ]);
+    expect(result.files).toEqual([edited, modified]);
+  });
+
+  it("leaves a lone rename in the file list", () => {
+    const result = groupRenames([renamed("old/f.ts", "new/f.ts")]);
+    expect(result.renames).toEqual([]);
+    expect(result.files).toHaveLength(1);
+  });
+});
@@ -0,0 +1,39 @@
+import type { FileDiff, RenameGroup } from "../types";
+
+// Fewer unchanged renames than this under one directory move stay in the file list
+export const MIN_RENAME_GROUP = 2;
+
+// Directory move implied by a rename: both parent directories minus their shared
+// trailing segments ("." for the repository root). Null when the file name changed
+export function directoryMove(oldPath: string, newPath: string): { from: string; to: string } | null {
+  const oldDir = oldPath.split("/");
+  const newDir = newPath.split("/");
+  if (oldDir.pop() !== newDir.pop()) {
+    return null;
+  }
+  while (oldDir.length > 0 && newDir.length > 0 && oldDir[oldDir.length - 1] === newDir[newDir.length - 1]) {
+    oldDir.pop();
+    newDir.pop();
+  }
+  return { from: oldDir.join("/") || ".", to: newDir.join("/") || "." };
+}
+
+// Fold rename-only files (similarity 100) that moved together into one group each,
+// leaving every other file in place
+export function groupRenames(files: FileDiff[]): { files: FileDiff[]; renames: RenameGroup[] } {
+  const groups = new Map<string, RenameGroup>();
+  for (const file of files) {
+    if (file.status !== "renamed" || file.similarity !== 100 || !file.old_path) continue;
+    const move = directoryMove(file.old_path, file.path);
+    if (!move) continue;
+
+    const key = `${move.from}\0${move.to}`;
+    const group = groups.get(key) ?? { ...move, files: [] };
+    group.files.push({ old_path: file.old_path, path: file.path });
+    groups.set(key, group);
+  }
+
+  const renames = [...groups.values()].filter((group) => group.files.length >= MIN_RENAME_GROUP);
+  const folded = new Set(renames.flatMap((group) => group.files.map((f) => f.path)));
+  return { files: files.filter((file) => !folded.has(file.path)), renames };
+}
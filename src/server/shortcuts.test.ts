@@ -0,0 +1,24 @@
+import { describe, expect, it } from "bun:test";
+import { DEFAULT_SHORTCUTS, diffFromDefaults, resolveShortcuts, validateShortcuts } from "./shortcuts";
+
+describe("shortcuts", () => {
+  it("applies overrides over the defaults", () => {
+    const shortcuts = resolveShortcuts({ next_file: "n", bogus: "z" });
+    expect(shortcuts.next_file).toBe("n");
+    expect(shortcuts.prev_file).toBe(DEFAULT_SHORTCUTS.prev_file);
+    expect("bogus" in shortcuts).toBe(false);
+  });
+
+  it("rejects keys bound to two actions", () => {
+    expect(validateShortcuts({ next_file: "k" })).toBe('"k" is bound to next_file and prev_file');
+    expect(validateShortcuts({ next_file: "k", prev_file: "j" })).toBeNull();
+  });
+
+  it("rejects unknown actions", () => {
+    expect(validateShortcuts({ launch_rockets: "r" })).toBe("Unknown shortcut action: launch_rockets");
+  });
+
+  it("keeps only bindings that differ from the defaults", () => {
+    expect(diffFromDefaults({ next_file: "j", prev_file: "p", bogus: "z" })).toEqual({ prev_file: "p" });
+  });
+});
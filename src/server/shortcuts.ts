@@ -0,0 +1,63 @@
+// Single-key bindings the UI reads from /api/config/shortcuts. Modifier combos
+// (Ctrl+d/u, Cmd+K), the gg sequence, Enter and Escape stay fixed
+export const DEFAULT_SHORTCUTS = {
+  next_file: "j",
+  prev_file: "k",
+  last_file: "G",
+  toggle_file: "o",
+  expand_file: "l",
+  collapse_file: "h",
+  toggle_viewed: "v",
+  stage_file: "s",
+  unstage_file: "u",
+  discard_file: "x",
+  commit: "c",
+  branch_mode: "1",
+  working_mode: "2",
+  toggle_split: "t",
+  show_help: "?",
+} as const;
+
+export type ShortcutAction = keyof typeof DEFAULT_SHORTCUTS;
+export type ShortcutMap = Record<ShortcutAction, string>;
+
+function isShortcutAction(action: string): action is ShortcutAction {
+  return Object.hasOwn(DEFAULT_SHORTCUTS, action);
+}
+
+// Defaults with any configured overrides applied (unknown actions are dropped)
+export function resolveShortcuts(overrides: Record<string, string> = {}): ShortcutMap {
+  const shortcuts: ShortcutMap = { ...DEFAULT_SHORTCUTS };
+  for (const [action, key] of Object.entries(overrides)) {
+    if (isShortcutAction(action)) {
+      shortcuts[action] = key;
+    }
+  }
+  return shortcuts;
+}
+
+// Error message for an unusable override set, or null if it can be saved
+export function validateShortcuts(overrides: Record<string, string>): string | null {
+  const unknown = Object.keys(overrides).filter((action) => !isShortcutAction(action));
+  if (unknown.length > 0) {
+    return `Unknown shortcut action: ${unknown.join(", ")}`;
+  }
+
+  const byKey = new Map<string, ShortcutAction[]>();
+  for (const [action, key] of Object.entries(resolveShortcuts(overrides)) as Array<[ShortcutAction, string]>) {
+    byKey.set(key, [...(byKey.get(key) ?? []), action]);
+  }
+  const duplicates = [...byKey].filter(([, actions]) => actions.length > 1);
+  if (duplicates.length > 0) {
+    return duplicates.map(([key, actions]) => `"${key}" is bound to ${actions.join(" and ")}`).join("; ");
+  }
+
+  return null;
+}
+
+// Overrides worth persisting: only keys that differ from the defaults
+export function diffFromDefaults(shortcuts: Record<string, string>): Record<string, string> {
+  return Object.fromEntries(
+    Object.entries(shortcuts).filter(([action, key]) => isShortcutAction(action) && DEFAULT_SHORTCUTS[action] !== key)
+  );
+}
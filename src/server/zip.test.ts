@@ -0,0 +1,42 @@
+import { describe, expect, it } from "bun:test";
+import { createZip } from "./zip";
+
+describe("createZip", () => {
+  it("writes stored entries with a central directory", () => {
+    const zip = createZip([
+      { name: "hello.txt", data: "hello" },
+      { name: "dir/é.bin", data: new Uint8Array([1, 2, 3]) },
+    ]);
+    const view = new DataView(zip.buffer);
+
+    // End of central directory record is the last 22 bytes (no comment)
+    const end = zip.length - 22;
+    expect(view.getUint32(end, true)).toBe(0x06054b50);
+    expect(view.getUint16(end + 10, true)).toBe(2);
+    const centralOffset = view.getUint32(end + 16, true);
+
+    // First central record points at the first local header
+    expect(view.getUint32(centralOffset, true)).toBe(0x02014b50);
+    expect(view.getUint32(centralOffset + 16, true)).toBe(0x3610a686); // crc32("hello")
+    expect(view.getUint32(centralOffset + 42, true)).toBe(0);
+
+    expect(view.getUint32(0, true)).toBe(0x04034b50);
+    expect(view.getUint16(8, true)).toBe(0); // stored
+    expect(view.getUint32(18, true)).toBe(5);
+    const nameLength = view.getUint16(26, true);
+    expect(new TextDecoder().decode(zip.slice(30, 30 + nameLength))).toBe("hello.txt");
+    expect(new TextDecoder().decode(zip.slice(30 + nameLength, 35 + nameLength))).toBe("hello");
+
+    // Second entry follows directly, its UTF-8 name flagged
+    const second = 35 + nameLength;
+    expect(view.getUint32(second, true)).toBe(0x04034b50);
+    expect(view.getUint16(second + 6, true) & 0x0800).toBe(0x0800);
+    expect(new TextDecoder().decode(zip.slice(second + 30, second + 30 + view.getUint16(second + 26, true)))).toBe("dir/é.bin");
+  });
+
+  it("writes an empty archive", () => {
+    const zip = createZip([]);
+    expect(zip.length).toBe(22);
+    expect(new DataView(zip.buffer).getUint16(10, true)).toBe(0);
+  });
+});
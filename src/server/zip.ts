@@ -0,0 +1,83 @@
+// Minimal zip writer for /api/export.zip: entries are stored uncompressed (patches are small
+// and text, and it keeps this dependency-free); no zip64, so archives stay under 4 GB
+export interface ZipEntry {
+  name: string;
+  data: Uint8Array | string;
+}
+
+// General purpose flag bit 11: names are UTF-8
+const UTF8_NAMES = 0x0800;
+
+// MS-DOS date/time fields, local time at two-second resolution
+function dosDateTime(date: Date): { time: number; date: number } {
+  return {
+    time: (date.getHours() << 11) | (date.getMinutes() << 5) | Math.floor(date.getSeconds() / 2),
+    date: ((Math.max(date.getFullYear(), 1980) - 1980) << 9) | ((date.getMonth() + 1) << 5) | date.getDate(),
+  };
+}
+
+export function createZip(entries: ZipEntry[], modified: Date = new Date()): Uint8Array {
+  const encoder = new TextEncoder();
+  const stamp = dosDateTime(modified);
+  const locals: Uint8Array[] = [];
+  const centrals: Uint8Array[] = [];
+  let offset = 0;
+
+  for (const entry of entries) {
+    const name = encoder.encode(entry.name);
+    const data = typeof entry.data === "string" ? encoder.encode(entry.data) : entry.data;
+    const crc = Bun.hash.crc32(data);
+
+    const local = new Uint8Array(30 + name.length + data.length);
+    const header = new DataView(local.buffer);
+    header.setUint32(0, 0x04034b50, true);
+    header.setUint16(4, 20, true); // version needed: 2.0
+    header.setUint16(6, UTF8_NAMES, true);
+    header.setUint16(8, 0, true); // stored
+    header.setUint16(10, stamp.time, true);
+    header.setUint16(12, stamp.date, true);
+    header.setUint32(14, crc, true);
+    header.setUint32(18, data.length, true);
+    header.setUint32(22, data.length, true);
+    header.setUint16(26, name.length, true);
+    local.set(name, 30);
+    local.set(data, 30 + name.length);
+
+    const central = new Uint8Array(46 + name.length);
+    const record = new DataView(central.buffer);
+    record.setUint32(0, 0x02014b50, true);
+    record.setUint16(4, 20, true); // made by
+    record.setUint16(6, 20, true);
+    record.setUint16(8, UTF8_NAMES, true);
+    record.setUint16(10, 0, true);
+    record.setUint16(12, stamp.time, true);
+    record.setUint16(14, stamp.date, true);
+    record.setUint32(16, crc, true);
+    record.setUint32(20, data.length, true);
+    record.setUint32(24, data.length, true);
+    record.setUint16(28, name.length, true);
+    record.setUint32(42, offset, true);
+    central.set(name, 46);
+
+    locals.push(local);
+    centrals.push(central);
+    offset += local.length;
+  }
+
+  const centralSize = centrals.reduce((sum, c) => sum + c.length, 0);
+  const end = new Uint8Array(22);
+  const trailer = new DataView(end.buffer);
+  trailer.setUint32(0, 0x06054b50, true);
+  trailer.setUint16(8, entries.length, true);
+  trailer.setUint16(10, entries.length, true);
+  trailer.setUint32(12, centralSize, true);
+  trailer.setUint32(16, offset, true);
+
+  const zip = new Uint8Array(offset + centralSize + end.length);
+  let position = 0;
+  for (const part of [...locals, ...centrals, end]) {
+    zip.set(part, position);
+    position += part.length;
+  }
+  return zip;
+}
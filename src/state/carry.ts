@@ -0,0 +1,59 @@
+/**
+ * Carrying viewed marks across a moved branch HEAD, run by both the server and the TUI when
+ * they load a diff
+ */
+import type { GitManager } from "../git";
+import * as state from "./index";
+
+export interface ReviewKey {
+  branch: string;
+  commit: string;
+  // The checkout reviewed in a working-tree mode, where --state-key content matches the files on disk
+  worktree?: true;
+}
+
+// Blob oids of the content a review shows: the files on disk in working-tree modes, else at its commit
+export function reviewBlobOids(git: GitManager, key: ReviewKey, paths: string[]): Promise<Record<string, string>> {
+  return key.worktree ? git.getWorkingBlobOids(paths) : git.getBlobOids(key.commit, paths);
+}
+
+// When a branch's HEAD moves (new commit, amend, rebase), keep viewed marks for
+// files whose content is unchanged between the previous HEAD and the new one (the
+// files on disk in working-tree modes), following renames so a moved file keeps its
+// mark under the new path. Returns the previous HEAD and how many marks moved, or null
+// when HEAD hasn't moved since the last load (or this is the branch's first)
+export async function carryForwardViewed(
+  repoId: string,
+  git: GitManager,
+  key: ReviewKey
+): Promise<{ from: string; carried: number } | null> {
+  const { branch, commit } = key;
+  const previous = await state.getBranchHead(repoId, branch);
+  if (previous === commit) return null;
+  await state.setBranchHead(repoId, branch, commit);
+  if (!previous) return null;
+
+  // Branch-scoped marks already hold on the new commit
+  const [marks, branchViewed] = await Promise.all([
+    state.getViewedBlobs(repoId, branch, previous),
+    state.getBranchViewedFiles(repoId, branch),
+  ]);
+  const paths = Object.keys(marks).filter((p) => !branchViewed[p]);
+  if (paths.length === 0) return { from: previous, carried: 0 };
+
+  // Marks from before blob tracking: read the blob from the previous commit instead
+  const untracked = paths.filter((p) => !marks[p]);
+  const [fallback, allRenames] = await Promise.all([
+    git.getBlobOids(previous, untracked),
+    git.getRenames(previous, commit),
+  ]);
+  const renamed = Object.fromEntries(Object.entries(allRenames).filter(([oldPath]) => Object.hasOwn(marks, oldPath)));
+  const current = await reviewBlobOids(git, key, paths.map((p) => renamed[p] ?? p));
+  const unchanged = paths.filter((p) => {
+    const blob = marks[p] ?? fallback[p];
+    return blob !== undefined && current[renamed[p] ?? p] === blob;
+  });
+
+  const carried = await state.carryViewedFiles(repoId, branch, previous, commit, unchanged, renamed);
+  return { from: previous, carried };
+}
@@ -3,14 +3,25 @@
  * Uses Bun's built-in SQLite support (bun:sqlite)
  */
 import { Database } from "bun:sqlite";
-import { homedir } from "os";
-import { join, resolve } from "path";
-import { mkdirSync, existsSync } from "fs";
+import { homedir, tmpdir } from "os";
+import { dirname, join, resolve } from "path";
+import { accessSync, constants, lstatSync, mkdirSync, existsSync, realpathSync, renameSync, statSync } from "fs";
 
 // Singleton database instance
 let db: Database | null = null;
 let currentDbPath: string | null = null;
 
+// Where state ended up: the configured file, a fallback directory when that can't be
+// created, or memory only (nothing persists past the process) when no directory works
+export type StateMode = "configured" | "data-dir" | "temp-dir" | "memory";
+
+export interface StateLocation {
+  mode: StateMode;
+  path: string | null; // null in memory mode
+}
+
+let currentLocation: StateLocation | null = null;
+
 /**
  * Get the config directory path (reads env var at runtime for testability)
  */
@@ -21,11 +32,37 @@ export function getConfigDir(): string {
 }
 
 /**
- * Get or create the database connection
+ * Get the state database path (CEREBRO_STATE_FILE overrides the config dir default)
+ */
+export function getDbPath(): string {
+  return process.env["CEREBRO_STATE_FILE"]
+    ? resolve(process.env["CEREBRO_STATE_FILE"])
+    : join(getConfigDir(), "cerebro.db");
+}
+
+/**
+ * Locations to try for the database, in order. An explicitly configured location
+ * (CEREBRO_STATE_FILE, CEREBRO_CONFIG_DIR) is the only one tried before memory
+ */
+function stateCandidates(dbPath: string): Array<StateLocation & { path: string }> {
+  const candidates: Array<StateLocation & { path: string }> = [{ mode: "configured", path: dbPath }];
+  if (!process.env["CEREBRO_STATE_FILE"] && !process.env["CEREBRO_CONFIG_DIR"]) {
+    const dataDir =
+      process.platform === "darwin"
+        ? join(homedir(), "Library", "Application Support")
+        : process.env["XDG_DATA_HOME"] || join(homedir(), ".local", "share");
+    candidates.push({ mode: "data-dir", path: join(dataDir, "cerebro", "cerebro.db") });
+    candidates.push({ mode: "temp-dir", path: join(tmpdir(), `cerebro-${process.getuid?.() ?? "user"}`, "cerebro.db") });
+  }
+  return candidates;
+}
+
+/**
+ * Get or create the database connection, falling back through stateCandidates()
+ * and finally to an in-memory database when none can be created
  */
 export function getDb(): Database {
-  const configDir = getConfigDir();
-  const dbPath = join(configDir, "cerebro.db");
+  const dbPath = getDbPath();
 
   // If db exists but path changed (e.g., env var changed), close and reopen
   if (db && currentDbPath !== dbPath) {
@@ -35,19 +72,196 @@ export function getDb(): Database {
 
   if (db) return db;
 
-  // Ensure config directory exists
-  if (!existsSync(configDir)) {
-    mkdirSync(configDir, { recursive: true });
+  // Only a directory that can't be created or written moves on to the next location; any
+  // other failure (a lock held past the retries, a permission error on the file) is thrown, as
+  // opening a second, empty database would split review state between two files
+  const failures: string[] = [];
+  for (const candidate of stateCandidates(dbPath)) {
+    try {
+      prepareStateDir(dirname(candidate.path), candidate.mode === "temp-dir");
+    } catch (error) {
+      failures.push(`${candidate.path}: ${(error as Error).message}`);
+      continue;
+    }
+    db = openStateFile(candidate.path);
+    currentLocation = candidate;
+    break;
+  }
+
+  if (failures.length > 0) {
+    console.warn("Warning: could not open the state database at:");
+    for (const failure of failures) console.warn(`  ${failure}`);
+  }
+  if (!db) {
+    console.warn("  Keeping review state in memory only; it is lost when cerebro exits");
+    db = openDatabase(":memory:");
+    currentLocation = { mode: "memory", path: null };
+  } else if (failures.length > 0) {
+    console.warn(`  Using ${currentLocation?.path} instead`);
   }
 
-  db = new Database(dbPath);
   currentDbPath = dbPath;
-  db.exec("PRAGMA journal_mode = WAL"); // Better concurrent access
-  db.exec("PRAGMA foreign_keys = ON");
-  initSchema();
   return db;
 }
 
+/**
+ * Where getDb() put the database (opening it if needed)
+ */
+export function getStateLocation(): StateLocation {
+  getDb();
+  return currentLocation ?? { mode: "memory", path: null };
+}
+
+/**
+ * Where getDb() would put the database, judged by directory permissions alone so
+ * nothing is created (for `cerebro doctor`)
+ */
+export function probeStateLocation(): StateLocation {
+  if (db && currentLocation && currentDbPath === getDbPath()) return currentLocation;
+  const writable = stateCandidates(getDbPath()).find(
+    (candidate) => isCreatable(candidate.path) && (candidate.mode !== "temp-dir" || isPrivateOrMissing(dirname(candidate.path)))
+  );
+  return writable ?? { mode: "memory", path: null };
+}
+
+function isPrivateOrMissing(dir: string): boolean {
+  try {
+    if (existsSync(dir)) checkPrivateDir(dir);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
+// The file can be written, or its nearest existing ancestor is a directory that can
+function isCreatable(path: string): boolean {
+  let target = path;
+  while (!existsSync(target)) {
+    const parent = dirname(target);
+    if (parent === target) return false;
+    target = parent;
+  }
+  try {
+    if (target !== path && !statSync(target).isDirectory()) return false;
+    accessSync(target, constants.W_OK);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
+// Creates the directory if needed; throws when it can't be created or written. A `shared`
+// parent (the temp dir) gets a directory only we can use
+function prepareStateDir(dir: string, shared = false): void {
+  if (shared) {
+    if (!existsSync(dir)) mkdirSync(dir, { mode: 0o700 });
+    checkPrivateDir(dir);
+    return;
+  }
+  if (!existsSync(dir)) {
+    mkdirSync(dir, { recursive: true });
+  }
+  accessSync(dir, constants.W_OK);
+}
+
+// Anyone can create cerebro-<uid> in /tmp first, or make it a symlink elsewhere: refuse
+// anything but a real directory we own that no one else can read or write
+export function checkPrivateDir(dir: string): void {
+  const stat = lstatSync(dir);
+  const uid = process.getuid?.();
+  if (!stat.isDirectory() || (uid !== undefined && stat.uid !== uid) || (stat.mode & 0o077) !== 0) {
+    throw new Error(`${dir} is not a private directory owned by the current user`);
+  }
+}
+
+/**
+ * Open a state file in an existing directory, recovering from corruption
+ */
+function openStateFile(dbPath: string): Database {
+  try {
+    return openWithRetry(dbPath);
+  } catch (error) {
+    // Permission, disk-full and migration errors leave the file alone: only SQLite saying the
+    // file itself is damaged justifies setting it aside
+    if (!existsSync(dbPath) || !isCorrupt(error)) throw error;
+
+    // Unreadable database: keep a copy for manual recovery and start fresh
+    const backupPath = backupCorruptFile(dbPath);
+    console.warn(`Warning: state database ${dbPath} is corrupt (${(error as Error).message})`);
+    console.warn(`  Moved it to ${backupPath} and started with empty review state`);
+    return openWithRetry(dbPath);
+  }
+}
+
+// Another process (a second server, the CLI) may briefly hold the lock while it sets up
+const OPEN_ATTEMPTS = 4;
+
+function isCorrupt(error: unknown): boolean {
+  const code = (error as { code?: unknown }).code;
+  if (code === "SQLITE_NOTADB" || code === "SQLITE_CORRUPT") return true;
+  return /file is not a database|database disk image is malformed/i.test((error as Error).message);
+}
+
+function isBusy(error: unknown): boolean {
+  return /database is locked|SQLITE_BUSY/i.test((error as Error).message);
+}
+
+function openWithRetry(dbPath: string): Database {
+  for (let attempt = 1; ; attempt++) {
+    try {
+      return openDatabase(dbPath);
+    } catch (error) {
+      if (!isBusy(error)) throw error;
+      if (attempt >= OPEN_ATTEMPTS) {
+        throw new Error(`State database ${dbPath} is locked by another process (${(error as Error).message})`);
+      }
+      Bun.sleepSync(50 * 2 ** (attempt - 1));
+    }
+  }
+}
+
+/**
+ * Open a database file and make sure the schema is in place
+ */
+function openDatabase(dbPath: string): Database {
+  const database = new Database(dbPath);
+  try {
+    database.exec("PRAGMA journal_mode = WAL"); // Better concurrent access
+    database.exec("PRAGMA foreign_keys = ON");
+    initSchema(database);
+  } catch (error) {
+    database.close();
+    throw error;
+  }
+  return database;
+}
+
+/**
+ * Move a corrupt state file (and any SQLite sidecar files) to `<path>.corrupt-<timestamp>`
+ */
+export function backupCorruptFile(path: string): string {
+  const backupPath = `${path}.corrupt-${Date.now()}`;
+  renameSync(path, backupPath);
+  for (const suffix of ["-wal", "-shm"]) {
+    if (existsSync(path + suffix)) {
+      renameSync(path + suffix, backupPath + suffix);
+    }
+  }
+  return backupPath;
+}
+
+/**
+ * The path repos are keyed on: symlinks resolved, so one checkout reached through
+ * different paths shares its review state (paths that don't exist are kept as given)
+ */
+export function canonicalRepoPath(path: string): string {
+  try {
+    return realpathSync(path);
+  } catch {
+    return path;
+  }
+}
+
 /**
  * Close the database connection
  */
@@ -56,14 +270,13 @@ export function closeDb(): void {
     db.close();
     db = null;
   }
+  currentLocation = null;
 }
 
 /**
  * Initialize database schema
  */
-function initSchema(): void {
-  const database = db!;
-
+function initSchema(database: Database): void {
   // Config table (key-value store for simple settings)
   database.exec(`
     CREATE TABLE IF NOT EXISTS config (
@@ -92,17 +305,85 @@ function initSchema(): void {
       commit_hash TEXT NOT NULL,
       file_path TEXT NOT NULL,
       viewed_at INTEGER NOT NULL,
+      viewed_by TEXT,
+      blob_oid TEXT,
       PRIMARY KEY (repo_id, branch, commit_hash, file_path),
       FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
     )
   `);
 
+  const viewedColumns = database.query("PRAGMA table_info(viewed_files)").all() as Array<{ name: string }>;
+  if (!viewedColumns.some((column) => column.name === "viewed_by")) {
+    database.exec("ALTER TABLE viewed_files ADD COLUMN viewed_by TEXT");
+  }
+  // Blob the file had when marked, so marks can follow unchanged files across amends
+  if (!viewedColumns.some((column) => column.name === "blob_oid")) {
+    database.exec("ALTER TABLE viewed_files ADD COLUMN blob_oid TEXT");
+  }
+
   // Create index for faster queries
   database.exec(`
     CREATE INDEX IF NOT EXISTS idx_viewed_files_lookup
     ON viewed_files(repo_id, branch, commit_hash)
   `);
 
+  // Content-keyed lookups (--state-key content)
+  database.exec(`
+    CREATE INDEX IF NOT EXISTS idx_viewed_files_blob
+    ON viewed_files(repo_id, file_path, blob_oid)
+  `);
+
+  // Last HEAD seen per branch, to notice amends and rebases
+  database.exec(`
+    CREATE TABLE IF NOT EXISTS branch_heads (
+      repo_id TEXT NOT NULL,
+      branch TEXT NOT NULL,
+      commit_hash TEXT NOT NULL,
+      PRIMARY KEY (repo_id, branch),
+      FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
+    )
+  `);
+
+  // Commit each branch was last reviewed at (a file marked viewed), and the one before it
+  database.exec(`
+    CREATE TABLE IF NOT EXISTS last_reviewed (
+      repo_id TEXT NOT NULL,
+      branch TEXT NOT NULL,
+      commit_hash TEXT NOT NULL,
+      previous_commit TEXT,
+      reviewed_at INTEGER NOT NULL,
+      PRIMARY KEY (repo_id, branch),
+      FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
+    )
+  `);
+
+  // Where the reviewer last was in each branch's diff; kept apart from review marks so
+  // clearing those can leave it in place
+  database.exec(`
+    CREATE TABLE IF NOT EXISTS review_positions (
+      repo_id TEXT NOT NULL,
+      branch TEXT NOT NULL,
+      file_path TEXT NOT NULL,
+      line INTEGER,
+      updated_at INTEGER NOT NULL,
+      PRIMARY KEY (repo_id, branch),
+      FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
+    )
+  `);
+
+  // Collapsed files (keyed like viewed_files)
+  database.exec(`
+    CREATE TABLE IF NOT EXISTS collapsed_files (
+      repo_id TEXT NOT NULL,
+      branch TEXT NOT NULL,
+      commit_hash TEXT NOT NULL,
+      file_path TEXT NOT NULL,
+      collapsed_at INTEGER NOT NULL,
+      PRIMARY KEY (repo_id, branch, commit_hash, file_path),
+      FOREIGN KEY (repo_id) REFERENCES repos(id) ON DELETE CASCADE
+    )
+  `);
+
   // Comments
   database.exec(`
     CREATE TABLE IF NOT EXISTS comments (
@@ -115,6 +396,7 @@ function initSchema(): void {
       commit_hash TEXT NOT NULL,
       created_at INTEGER NOT NULL,
       parent_id TEXT,
+      author TEXT,
       resolved INTEGER DEFAULT 0,
       resolved_by TEXT,
       resolved_at INTEGER,
@@ -127,6 +409,9 @@ function initSchema(): void {
   if (!hasParentId) {
     database.exec("ALTER TABLE comments ADD COLUMN parent_id TEXT");
   }
+  if (!commentColumns.some((column) => column.name === "author")) {
+    database.exec("ALTER TABLE comments ADD COLUMN author TEXT");
+  }
 
   // Create indexes for comments
   database.exec(`
@@ -160,6 +445,49 @@ function initSchema(): void {
     CREATE INDEX IF NOT EXISTS idx_notes_repo_branch
     ON notes(repo_id, branch, dismissed)
   `);
+
+  // UI preferences (JSON values), independent of repos and review state
+  database.exec(`
+    CREATE TABLE IF NOT EXISTS preferences (
+      key TEXT PRIMARY KEY,
+      value TEXT NOT NULL,
+      updated_at INTEGER NOT NULL
+    )
+  `);
+
+  mergeDuplicateRepos(database);
+}
+
+// Tables whose rows belong to a repo (all cascade when it's deleted)
+const REPO_TABLES = ["viewed_files", "collapsed_files", "branch_heads", "last_reviewed", "review_positions", "comments", "notes"];
+
+/**
+ * Repos added before paths were canonicalized may be tracked twice (e.g. once through a
+ * symlink). Fold each duplicate into the earliest-added entry, keeping its rows where
+ * both have one for the same key, and store the canonical path
+ */
+function mergeDuplicateRepos(database: Database): void {
+  const repos = database.query("SELECT id, path FROM repos ORDER BY added_at ASC").all() as Array<{ id: string; path: string }>;
+  const byPath = new Map<string, string[]>();
+  for (const repo of repos) {
+    const canonical = canonicalRepoPath(repo.path);
+    byPath.set(canonical, [...(byPath.get(canonical) ?? []), repo.id]);
+  }
+
+  const merge = database.transaction(() => {
+    for (const [canonical, [keep, ...duplicates]] of byPath) {
+      if (!keep) continue;
+      for (const duplicate of duplicates) {
+        for (const table of REPO_TABLES) {
+          database.query(`UPDATE OR IGNORE ${table} SET repo_id = ? WHERE repo_id = ?`).run(keep, duplicate);
+        }
+        database.query("UPDATE config SET value = ? WHERE key = 'currentRepo' AND value = ?").run(keep, duplicate);
+        database.query("DELETE FROM repos WHERE id = ?").run(duplicate);
+      }
+      database.query("UPDATE repos SET path = ? WHERE id = ? AND path != ?").run(canonical, keep, canonical);
+    }
+  });
+  merge();
 }
 
 /**
@@ -1,6 +1,7 @@
-import { mkdtempSync, rmSync, mkdirSync } from "fs";
+import { chmodSync, mkdtempSync, rmSync, mkdirSync, readdirSync, realpathSync, symlinkSync, writeFileSync } from "fs";
 import { tmpdir } from "os";
 import { join } from "path";
+import { Database } from "bun:sqlite";
 import { beforeAll, afterAll, beforeEach, describe, expect, it } from "bun:test";
 import * as state from "./index";
 
@@ -48,6 +49,108 @@ describe("config", () => {
     const file = Bun.file(join(configDir, "config.json"));
     expect(await file.exists()).toBe(true);
   });
+
+  it("getConfig backs up a corrupt config file", async () => {
+    writeFileSync(join(configDir, "config.json"), "{ not json");
+    const config = await state.getConfig();
+    expect(config).toEqual({ defaultPort: 3030 });
+
+    const backups = readdirSync(configDir).filter((f) => f.startsWith("config.json.corrupt-"));
+    expect(backups.length).toBe(1);
+  });
+});
+
+describe("corrupt state database", () => {
+  it("backs up a malformed database and starts fresh", async () => {
+    writeFileSync(join(configDir, "cerebro.db"), "this is not a sqlite database, just some text".repeat(50));
+
+    const repos = await state.getRepos();
+    expect(repos).toEqual([]);
+
+    const backups = readdirSync(configDir).filter((f) => f.startsWith("cerebro.db.corrupt-"));
+    expect(backups.length).toBe(1);
+
+    // Fresh database is usable
+    const repo = await state.addRepo("/tmp/after-corrupt", "after-corrupt", "main");
+    expect((await state.getRepo(repo.id))?.name).toBe("after-corrupt");
+  });
+
+  it("leaves a database it can't open for other reasons in place", async () => {
+    // SQLite can't open a directory, but nothing about it is corrupt
+    mkdirSync(join(configDir, "cerebro.db"));
+    await state.getRepos().catch(() => undefined);
+    expect(readdirSync(configDir).filter((f) => f.startsWith("cerebro.db.corrupt-"))).toEqual([]);
+    state.closeDb();
+    rmSync(join(configDir, "cerebro.db"), { recursive: true });
+  });
+});
+
+describe("state file location", () => {
+  it("CEREBRO_STATE_FILE overrides the database location", async () => {
+    const stateFile = join(tempHome, "custom", "review.db");
+    process.env["CEREBRO_STATE_FILE"] = stateFile;
+    try {
+      state.closeDb();
+      await state.addRepo("/tmp/state-file-repo", "state-file-repo", "main");
+      expect(await Bun.file(stateFile).exists()).toBe(true);
+      expect(await Bun.file(join(configDir, "cerebro.db")).exists()).toBe(false);
+    } finally {
+      state.closeDb();
+      delete process.env["CEREBRO_STATE_FILE"];
+    }
+  });
+
+  it("keeps state in memory when the configured location can't be created", async () => {
+    // A file where the state directory should be
+    writeFileSync(join(tempHome, "blocker"), "");
+    process.env["CEREBRO_STATE_FILE"] = join(tempHome, "blocker", "review.db");
+    try {
+      state.closeDb();
+      expect(state.probeStateLocation()).toEqual({ mode: "memory", path: null });
+      const repo = await state.addRepo("/tmp/memory-repo", "memory-repo", "main");
+      expect((await state.getRepo(repo.id))?.name).toBe("memory-repo");
+      expect(state.getStateLocation()).toEqual({ mode: "memory", path: null });
+    } finally {
+      state.closeDb();
+      delete process.env["CEREBRO_STATE_FILE"];
+    }
+  });
+
+  it("reports a locked database instead of opening another one", async () => {
+    const dbPath = join(configDir, "cerebro.db");
+    const holder = new Database(dbPath);
+    holder.exec("PRAGMA locking_mode = EXCLUSIVE");
+    holder.exec("BEGIN EXCLUSIVE");
+    try {
+      state.closeDb();
+      await expect(state.getRepos()).rejects.toThrow("locked by another process");
+    } finally {
+      holder.exec("ROLLBACK");
+      holder.close();
+      state.closeDb();
+    }
+  });
+
+  it("only trusts a private temp-dir fallback", () => {
+    const own = join(tempHome, "private");
+    mkdirSync(own, { mode: 0o700 });
+    expect(() => state.checkPrivateDir(own)).not.toThrow();
+
+    const open = join(tempHome, "shared");
+    mkdirSync(open, { mode: 0o755 });
+    chmodSync(open, 0o755);
+    expect(() => state.checkPrivateDir(open)).toThrow("not a private directory");
+
+    const link = join(tempHome, "redirected");
+    symlinkSync(own, link);
+    expect(() => state.checkPrivateDir(link)).toThrow("not a private directory");
+  });
+
+  it("reports the configured location when it can be created", () => {
+    state.closeDb();
+    expect(state.probeStateLocation()).toEqual({ mode: "configured", path: join(configDir, "cerebro.db") });
+    expect(state.getStateLocation()).toEqual({ mode: "configured", path: join(configDir, "cerebro.db") });
+  });
 });
 
 // =============================================================================
@@ -100,6 +203,46 @@ describe("repos", () => {
     expect(found).toBeUndefined();
   });
 
+  it("keys repos on the symlink-resolved path", async () => {
+    const realPath = join(realpathSync(tempHome), "real-repo");
+    const linkPath = join(tempHome, "linked-repo");
+    mkdirSync(realPath, { recursive: true });
+    symlinkSync(realPath, linkPath);
+
+    const viaLink = await state.addRepo(linkPath, "linked", "main");
+    expect(viaLink.path).toBe(realPath);
+    expect((await state.addRepo(realPath, "real", "main")).id).toBe(viaLink.id);
+    expect((await state.getRepoByPath(linkPath))?.id).toBe(viaLink.id);
+
+    rmSync(linkPath);
+    rmSync(realPath, { recursive: true, force: true });
+  });
+
+  it("merges repos tracked under a symlink and its target when the database opens", async () => {
+    const realPath = join(realpathSync(tempHome), "real-repo");
+    const linkPath = join(tempHome, "linked-repo");
+    mkdirSync(realPath, { recursive: true });
+    symlinkSync(realPath, linkPath);
+
+    const repo = await state.addRepo(realPath, "real", "main");
+    await state.setFileViewed(repo.id, "main", "abc123", "a.ts", true);
+    // A duplicate entry from before paths were canonicalized
+    state.closeDb();
+    const raw = new Database(state.getDbPath());
+    raw.query("INSERT INTO repos (id, path, name, base_branch, added_at) VALUES ('dup', ?, 'linked', 'main', ?)").run(linkPath, Date.now() + 1);
+    raw.query("INSERT INTO viewed_files (repo_id, branch, commit_hash, file_path, viewed_at) VALUES ('dup', 'main', 'abc123', 'b.ts', 1)").run();
+    raw.query("INSERT OR REPLACE INTO config (key, value) VALUES ('currentRepo', 'dup')").run();
+    raw.close();
+
+    const repos = await state.getRepos();
+    expect(repos.map((r) => [r.id, r.path])).toEqual([[repo.id, realPath]]);
+    expect(await state.getViewedFiles(repo.id, "main", "abc123")).toEqual({ "a.ts": true, "b.ts": true });
+    expect((await state.getCurrentRepo())?.id).toBe(repo.id);
+
+    rmSync(linkPath);
+    rmSync(realPath, { recursive: true, force: true });
+  });
+
   it("getRepos returns all repos sorted by addedAt DESC", async () => {
     await state.addRepo("/tmp/repo-a", "repo-a", "main");
     await Bun.sleep(10); // Ensure different timestamps
@@ -238,6 +381,46 @@ describe("viewed files", () => {
     expect(viewed["src/file.ts"]).toBeUndefined();
   });
 
+  it("setFileViewed records the reviewer", async () => {
+    const repo = await state.addRepo("/tmp/viewed-repo", "viewed", "main");
+    await state.setFileViewed(repo.id, "main", "abc123", "src/a.ts", true, "alice");
+    await state.setFileViewed(repo.id, "main", "abc123", "src/b.ts", true);
+
+    const viewedBy = await state.getViewedBy(repo.id, "main", "abc123");
+    expect(viewedBy).toEqual({ "src/a.ts": "alice" });
+  });
+
+  it("mergeViewedEntries unions marks and keeps the latest timestamp", async () => {
+    const repo = await state.addRepo("/tmp/viewed-repo", "viewed", "main");
+    await state.setFileViewed(repo.id, "main", "abc123", "local.ts", true);
+    const [local] = await state.getViewedEntries(repo.id);
+
+    const written = await state.mergeViewedEntries(repo.id, [
+      { branch: "main", commit: "abc123", file_path: "local.ts", viewed_at: 1, viewed_by: "old" },
+      { branch: "main", commit: "abc123", file_path: "remote.ts", viewed_at: 2, viewed_by: "bob" },
+    ]);
+    expect(written).toBe(1);
+
+    const entries = await state.getViewedEntries(repo.id);
+    expect(entries.map((e) => e.file_path).sort()).toEqual(["local.ts", "remote.ts"]);
+    expect(entries.find((e) => e.file_path === "local.ts")?.viewed_at).toBe(local!.viewed_at);
+    expect(entries.find((e) => e.file_path === "remote.ts")?.viewed_by).toBe("bob");
+  });
+
+  it("getViewedEntries and mergeViewedEntries round-trip blob oids and branch-scoped marks", async () => {
+    const blob = "a".repeat(40);
+    const source = await state.addRepo("/tmp/export-repo", "export", "main");
+    await state.setFileViewed(source.id, "main", "abc123", "content.ts", true, "alice", blob);
+    await state.setFileViewed(source.id, "feature", "abc123", "branch.ts", true, "alice", undefined, "branch");
+    const exported = await state.getViewedEntries(source.id);
+
+    const target = await state.addRepo("/tmp/import-repo", "import", "main");
+    expect(await state.mergeViewedEntries(target.id, exported)).toBe(2);
+    expect(await state.getViewedEntries(target.id)).toEqual(exported);
+    expect(await state.getViewedByContent(target.id, { "content.ts": blob })).toEqual({ "content.ts": { viewed_by: "alice" } });
+    expect((await state.getViewedFiles(target.id, "feature", "def456"))["branch.ts"]).toBe(true);
+  });
+
   it("viewed files are scoped to branch", async () => {
     const repo = await state.addRepo("/tmp/viewed-repo", "viewed", "main");
     await state.setFileViewed(repo.id, "feature", "abc123", "src/file.ts", true);
@@ -260,6 +443,21 @@ describe("viewed files", () => {
     expect(commit2Viewed["src/file.ts"]).toBeUndefined();
   });
 
+  it("branch-scoped marks apply to every commit until unmarked", async () => {
+    const repo = await state.addRepo("/tmp/viewed-repo", "viewed", "main");
+    await state.setFileViewed(repo.id, "feature", "abc123", "src/file.ts", true, "alice", undefined, "branch");
+
+    expect((await state.getViewedFiles(repo.id, "feature", "def456"))["src/file.ts"]).toBe(true);
+    expect((await state.getViewedBy(repo.id, "feature", "def456"))["src/file.ts"]).toBe("alice");
+    expect((await state.getViewedFiles(repo.id, "main", "def456"))["src/file.ts"]).toBeUndefined();
+    // Not a review of any one commit, so not listed; exported under commit "*"
+    expect(await state.getViewedSummaries()).toEqual([]);
+    expect((await state.getViewedEntries(repo.id)).map((e) => e.commit)).toEqual([state.BRANCH_SCOPE_COMMIT]);
+
+    await state.setFileViewed(repo.id, "feature", "def456", "src/file.ts", false);
+    expect((await state.getViewedFiles(repo.id, "feature", "abc123"))["src/file.ts"]).toBeUndefined();
+  });
+
   it("viewed files are scoped to repo", async () => {
     const repo1 = await state.addRepo("/tmp/repo-1", "repo-1", "main");
     const repo2 = await state.addRepo("/tmp/repo-2", "repo-2", "main");
@@ -274,6 +472,163 @@ describe("viewed files", () => {
   });
 });
 
+// =============================================================================
+// Review State Merge Tests
+// =============================================================================
+
+describe("mergeReviewStates", () => {
+  const entry = (file_path: string, viewed_at: number, viewed_by?: string) => ({
+    branch: "main",
+    commit: "abc123",
+    file_path,
+    viewed_at,
+    viewed_by,
+  });
+
+  it("unions overlapping repos and keeps the latest mark", () => {
+    const merged = state.mergeReviewStates(
+      { version: 1, exported_at: 10, repos: [{ path: "/r", name: "r", viewed: [entry("a.ts", 1, "alice"), entry("b.ts", 5)] }] },
+      { version: 1, exported_at: 20, repos: [{ path: "/r", name: "r", viewed: [entry("a.ts", 3, "bob"), entry("c.ts", 2)] }] }
+    );
+
+    expect(merged.exported_at).toBe(20);
+    expect(merged.repos.length).toBe(1);
+    const viewed = merged.repos[0]!.viewed;
+    expect(viewed.map((e) => e.file_path)).toEqual(["c.ts", "a.ts", "b.ts"]);
+    expect(viewed.find((e) => e.file_path === "a.ts")?.viewed_by).toBe("bob");
+  });
+
+  it("keeps disjoint repos side by side", () => {
+    const merged = state.mergeReviewStates(
+      { version: 1, exported_at: 1, repos: [{ path: "/one", name: "one", viewed: [entry("a.ts", 1)] }] },
+      { version: 1, exported_at: 1, repos: [{ path: "/two", name: "two", viewed: [entry("a.ts", 1)] }] }
+    );
+
+    expect(merged.repos.map((r) => r.path)).toEqual(["/one", "/two"]);
+    expect(merged.repos.every((r) => r.viewed.length === 1)).toBe(true);
+  });
+
+  it("does not mutate its inputs", () => {
+    const base = { version: 1, exported_at: 1, repos: [{ path: "/r", name: "r", viewed: [entry("a.ts", 1)] }] };
+    state.mergeReviewStates(base, { version: 1, exported_at: 1, repos: [{ path: "/r", name: "r", viewed: [entry("b.ts", 1)] }] });
+    expect(base.repos[0]!.viewed.length).toBe(1);
+  });
+});
+
+// =============================================================================
+// Collapsed Files Tests
+// =============================================================================
+
+describe("collapsed files", () => {
+  it("setFileCollapsed toggles collapsed state", async () => {
+    const repo = await state.addRepo("/tmp/collapsed-repo", "collapsed", "main");
+    await state.setFileCollapsed(repo.id, "main", "abc123", "src/file.ts", true);
+    expect((await state.getCollapsedFiles(repo.id, "main", "abc123"))["src/file.ts"]).toBe(true);
+
+    await state.setFileCollapsed(repo.id, "main", "abc123", "src/file.ts", false);
+    expect(await state.getCollapsedFiles(repo.id, "main", "abc123")).toEqual({});
+  });
+
+  it("collapsed files are independent of viewed files", async () => {
+    const repo = await state.addRepo("/tmp/collapsed-repo", "collapsed", "main");
+    await state.setFileCollapsed(repo.id, "main", "abc123", "src/file.ts", true);
+
+    const viewed = await state.getViewedFiles(repo.id, "main", "abc123");
+    const otherCommit = await state.getCollapsedFiles(repo.id, "main", "def456");
+    expect(viewed["src/file.ts"]).toBeUndefined();
+    expect(otherCommit["src/file.ts"]).toBeUndefined();
+  });
+});
+
+describe("review positions", () => {
+  it("keeps one position per branch and survives clearing when asked", async () => {
+    const repo = await state.addRepo("/tmp/position-repo", "position", "main");
+    await state.setReviewPosition(repo.id, "main", "a.ts", 12);
+    await state.setReviewPosition(repo.id, "main", "b.ts");
+    await state.setReviewPosition(repo.id, "feature", "c.ts", 3);
+    await state.setFileViewed(repo.id, "main", "abc123", "b.ts", true);
+    expect(await state.getReviewPosition(repo.id, "main")).toMatchObject({ file_path: "b.ts" });
+    expect((await state.getReviewPosition(repo.id, "main"))?.line).toBeUndefined();
+
+    expect(await state.clearReviewState(repo.id, { branch: "main", keepPosition: true })).toBe(1);
+    expect(await state.getViewedFiles(repo.id, "main", "abc123")).toEqual({});
+    expect((await state.getReviewPosition(repo.id, "main"))?.file_path).toBe("b.ts");
+
+    await state.clearReviewState(repo.id);
+    expect(await state.getReviewPosition(repo.id, "main")).toBeUndefined();
+    expect(await state.getReviewPosition(repo.id, "feature")).toBeUndefined();
+  });
+});
+
+describe("carrying viewed marks", () => {
+  it("carryViewedFiles copies marks with their blob and keeps existing ones", async () => {
+    const repo = await state.addRepo("/tmp/carry-repo", "carry", "main");
+    await state.setFileViewed(repo.id, "main", "old1234", "a.ts", true, "alice", "blob-a");
+    await state.setFileViewed(repo.id, "main", "old1234", "b.ts", true, "alice", "blob-b");
+    await state.setFileViewed(repo.id, "main", "new5678", "b.ts", true, "bob", "blob-b2");
+
+    expect(await state.carryViewedFiles(repo.id, "main", "old1234", "new5678", ["a.ts", "b.ts"])).toBe(1);
+    expect(await state.getViewedBlobs(repo.id, "main", "new5678")).toEqual({ "a.ts": "blob-a", "b.ts": "blob-b2" });
+    expect((await state.getViewedBy(repo.id, "main", "new5678"))["b.ts"]).toBe("bob");
+  });
+
+  it("carryViewedFiles moves marks for renamed files to the new path", async () => {
+    const repo = await state.addRepo("/tmp/carry-repo", "carry", "main");
+    await state.setFileViewed(repo.id, "main", "old1234", "src/a.ts", true, "alice", "blob-a");
+
+    expect(await state.carryViewedFiles(repo.id, "main", "old1234", "new5678", ["src/a.ts"], { "src/a.ts": "lib/a.ts" })).toBe(1);
+    expect(await state.getViewedBlobs(repo.id, "main", "new5678")).toEqual({ "lib/a.ts": "blob-a" });
+  });
+
+  it("tracks the last seen head per branch", async () => {
+    const repo = await state.addRepo("/tmp/carry-repo", "carry", "main");
+    expect(await state.getBranchHead(repo.id, "main")).toBeUndefined();
+    await state.setBranchHead(repo.id, "main", "abc1234");
+    await state.setBranchHead(repo.id, "main", "def5678");
+    expect(await state.getBranchHead(repo.id, "main")).toBe("def5678");
+  });
+});
+
+describe("last reviewed commit", () => {
+  it("keeps the previous review once marks land on a new commit", async () => {
+    const repo = await state.addRepo("/tmp/since-repo", "since", "main");
+    expect(await state.getLastReviewedCommit(repo.id, "main", "aaa1111")).toBeUndefined();
+
+    await state.setFileViewed(repo.id, "main", "aaa1111", "a.ts", true);
+    expect(await state.getLastReviewedCommit(repo.id, "main", "bbb2222")).toBe("aaa1111");
+    // Still reviewing the same commit: nothing earlier yet
+    expect(await state.getLastReviewedCommit(repo.id, "main", "aaa1111")).toBeUndefined();
+
+    await state.setFileViewed(repo.id, "main", "bbb2222", "a.ts", true);
+    await state.setFileViewed(repo.id, "main", "bbb2222", "b.ts", true);
+    expect(await state.getLastReviewedCommit(repo.id, "main", "bbb2222")).toBe("aaa1111");
+    expect(await state.getLastReviewedCommit(repo.id, "main", "ccc3333")).toBe("bbb2222");
+  });
+});
+
+// =============================================================================
+// Preferences Tests
+// =============================================================================
+
+describe("preferences", () => {
+  it("setPreferences stores JSON values and null removes them", async () => {
+    await state.setPreferences({ theme: "dark", font_size: 14 });
+    expect(await state.getPreferences()).toEqual({ theme: "dark", font_size: 14 });
+
+    const updated = await state.setPreferences({ theme: null, diff_style: "split" });
+    expect(updated).toEqual({ font_size: 14, diff_style: "split" });
+  });
+
+  it("preferences survive removing a repo's review state", async () => {
+    const repo = await state.addRepo("/tmp/prefs-repo", "prefs", "main");
+    await state.setFileViewed(repo.id, "main", "abc123", "src/file.ts", true);
+    await state.setPreferences({ theme: "dark" });
+
+    await state.removeRepo(repo.id);
+    expect(await state.getPreferences()).toEqual({ theme: "dark" });
+  });
+});
+
 // =============================================================================
 // Comments Tests
 // =============================================================================
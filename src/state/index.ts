@@ -1,13 +1,27 @@
 /**
  * State management using SQLite
- * Stores repos, viewed files, comments, and notes
+ * Stores repos, viewed and collapsed files, comments, notes, and UI preferences
  */
-import { isAbsolute, join, relative } from "path";
-import type { Comment, Config, Note, ReposState, Repository } from "../types";
-import { getDb, getConfigDir, generateId, closeDb } from "./db";
+import { Database } from "bun:sqlite";
+import { tmpdir } from "os";
+import { dirname, isAbsolute, join, relative } from "path";
+import type { Comment, Config, Note, ReposState, Repository, ReviewPosition, ReviewStateExport, ViewedEntry } from "../types";
+import {
+  getDb,
+  getConfigDir,
+  getDbPath,
+  getStateLocation,
+  probeStateLocation,
+  generateId,
+  closeDb,
+  backupCorruptFile,
+  canonicalRepoPath,
+  checkPrivateDir,
+} from "./db";
 
 // Re-export closeDb for cleanup
-export { closeDb };
+export { checkPrivateDir, closeDb, getConfigDir, getDbPath, getStateLocation, probeStateLocation };
+export type { StateLocation, StateMode } from "./db";
 
 // ============================================================================
 // Global Config (kept as JSON for simplicity)
@@ -20,7 +34,9 @@ export async function getConfig(): Promise<Config> {
     try {
       return await file.json();
     } catch {
-      // Corrupted file, return defaults
+      // Corrupted file: keep a backup so settings can be recovered, return defaults
+      const backupPath = backupCorruptFile(configFile);
+      console.warn(`Warning: ${configFile} is not valid JSON; moved it to ${backupPath} and using defaults`);
     }
   }
   return { defaultPort: 3030 };
@@ -31,6 +47,13 @@ export async function saveConfig(config: Config): Promise<void> {
   await Bun.write(configFile, JSON.stringify(config, null, 2));
 }
 
+// Generated patches are cached next to the state database (wherever it fell back to), or
+// in the temp dir when state is in memory only
+export function getPatchCacheDir(): string {
+  const { path } = getStateLocation();
+  return path ? join(dirname(path), "patch-cache") : join(tmpdir(), "cerebro-patch-cache");
+}
+
 // ============================================================================
 // Repository Management
 // ============================================================================
@@ -87,7 +110,7 @@ export async function getRepo(id: string): Promise<Repository | undefined> {
 
 export async function getRepoByPath(path: string): Promise<Repository | undefined> {
   const db = getDb();
-  const row = db.query("SELECT * FROM repos WHERE path = ?").get(path) as {
+  const row = db.query("SELECT * FROM repos WHERE path = ?").get(canonicalRepoPath(path)) as {
     id: string;
     path: string;
     name: string;
@@ -106,8 +129,9 @@ export async function getRepoByPath(path: string): Promise<Repository | undefine
   };
 }
 
-export async function addRepo(path: string, name: string, baseBranch: string = "main"): Promise<Repository> {
+export async function addRepo(givenPath: string, name: string, baseBranch: string = "main"): Promise<Repository> {
   const db = getDb();
+  const path = canonicalRepoPath(givenPath);
 
   // Check if already exists
   const existing = await getRepoByPath(path);
@@ -194,6 +218,27 @@ export async function getCurrentRepo(): Promise<Repository | undefined> {
   return undefined;
 }
 
+// The repo tracked at `repoPath` and the current repo, read from an existing state file opened
+// read-only, so inspecting state (cerebro doctor) never creates, migrates or locks it for writing
+export function inspectRepos(dbPath: string, repoPath: string): { tracked?: Repository; current?: Repository } {
+  const db = new Database(dbPath, { readonly: true });
+  try {
+    type Row = { id: string; path: string; name: string; base_branch: string; added_at: number };
+    const toRepo = (row: Row | null): Repository | undefined =>
+      row ? { id: row.id, path: row.path, name: row.name, baseBranch: row.base_branch, addedAt: row.added_at } : undefined;
+
+    const tracked = db.query("SELECT * FROM repos WHERE path = ?").get(canonicalRepoPath(repoPath)) as Row | null;
+    const currentId = db.query("SELECT value FROM config WHERE key = 'currentRepo'").get() as { value: string } | null;
+    // Like getCurrentRepo: the newest repo when none is set
+    const current = currentId?.value
+      ? db.query("SELECT * FROM repos WHERE id = ?").get(currentId.value)
+      : db.query("SELECT * FROM repos ORDER BY added_at DESC LIMIT 1").get();
+    return { tracked: toRepo(tracked), current: toRepo(current as Row | null) };
+  } finally {
+    db.close();
+  }
+}
+
 export async function updateRepo(id: string, updates: Partial<Pick<Repository, "baseBranch" | "name">>): Promise<boolean> {
   const db = getDb();
 
@@ -216,12 +261,20 @@ export async function updateRepo(id: string, updates: Partial<Pick<Repository, "
 // Viewed Files
 // ============================================================================
 
+// A mark's reach: this commit only, or every commit on the branch until unmarked
+export const VIEWED_SCOPES = ["commit", "branch"] as const;
+export type ViewedScope = (typeof VIEWED_SCOPES)[number];
+
+// Stands in for the commit on branch-scoped marks
+export const BRANCH_SCOPE_COMMIT = "*";
+
+// Marks for this commit, plus the branch-scoped ones as a fallback
 export async function getViewedFiles(repoId: string, branch: string, commit: string): Promise<Record<string, boolean>> {
   const db = getDb();
 
   const rows = db
-    .query("SELECT file_path FROM viewed_files WHERE repo_id = ? AND branch = ? AND commit_hash = ?")
-    .all(repoId, branch, commit) as Array<{ file_path: string }>;
+    .query("SELECT file_path FROM viewed_files WHERE repo_id = ? AND branch = ? AND commit_hash IN (?, ?)")
+    .all(repoId, branch, commit, BRANCH_SCOPE_COMMIT) as Array<{ file_path: string }>;
 
   const result: Record<string, boolean> = {};
   for (const row of rows) {
@@ -230,21 +283,313 @@ export async function getViewedFiles(repoId: string, branch: string, commit: str
   return result;
 }
 
+// Reviewer who marked each viewed file (file path -> reviewer), for entries that recorded one
+export async function getViewedBy(repoId: string, branch: string, commit: string): Promise<Record<string, string>> {
+  const db = getDb();
+
+  // Branch-scoped rows first so a commit's own mark wins
+  const rows = db
+    .query(
+      `SELECT file_path, viewed_by FROM viewed_files
+       WHERE repo_id = ? AND branch = ? AND commit_hash IN (?, ?) AND viewed_by IS NOT NULL
+       ORDER BY commit_hash = ? DESC`
+    )
+    .all(repoId, branch, commit, BRANCH_SCOPE_COMMIT, BRANCH_SCOPE_COMMIT) as Array<{ file_path: string; viewed_by: string }>;
+
+  const result: Record<string, string> = {};
+  for (const row of rows) {
+    result[row.file_path] = row.viewed_by;
+  }
+  return result;
+}
+
 export async function setFileViewed(
   repoId: string,
   branch: string,
   commit: string,
   filePath: string,
-  viewed: boolean
+  viewed: boolean,
+  viewedBy?: string,
+  blobOid?: string,
+  scope: ViewedScope = "commit"
 ): Promise<void> {
   const db = getDb();
 
   if (viewed) {
     db.query(
-      "INSERT OR REPLACE INTO viewed_files (repo_id, branch, commit_hash, file_path, viewed_at) VALUES (?, ?, ?, ?, ?)"
+      "INSERT OR REPLACE INTO viewed_files (repo_id, branch, commit_hash, file_path, viewed_at, viewed_by, blob_oid) VALUES (?, ?, ?, ?, ?, ?, ?)"
+    ).run(repoId, branch, scope === "branch" ? BRANCH_SCOPE_COMMIT : commit, filePath, Date.now(), viewedBy ?? null, blobOid ?? null);
+    recordReview(repoId, branch, commit);
+  } else {
+    // Unmarking drops the branch-scoped mark too, or the file would stay viewed
+    db.query("DELETE FROM viewed_files WHERE repo_id = ? AND branch = ? AND commit_hash IN (?, ?) AND file_path = ?").run(
+      repoId,
+      branch,
+      commit,
+      BRANCH_SCOPE_COMMIT,
+      filePath
+    );
+  }
+}
+
+// Files marked viewed for the whole branch
+export async function getBranchViewedFiles(repoId: string, branch: string): Promise<Record<string, boolean>> {
+  return getViewedFiles(repoId, branch, BRANCH_SCOPE_COMMIT);
+}
+
+// Remember the commit a branch is being reviewed at. When review moves on to a
+// new commit, the old one is kept as the previous review
+function recordReview(repoId: string, branch: string, commit: string): void {
+  getDb()
+    .query(
+      `INSERT INTO last_reviewed (repo_id, branch, commit_hash, previous_commit, reviewed_at) VALUES (?, ?, ?, NULL, ?)
+       ON CONFLICT (repo_id, branch) DO UPDATE SET
+         previous_commit = CASE WHEN commit_hash = excluded.commit_hash THEN previous_commit ELSE commit_hash END,
+         commit_hash = excluded.commit_hash,
+         reviewed_at = excluded.reviewed_at`
+    )
+    .run(repoId, branch, commit, Date.now());
+}
+
+// Most recent commit the branch was reviewed at before `current`, if any
+export async function getLastReviewedCommit(repoId: string, branch: string, current: string): Promise<string | undefined> {
+  const db = getDb();
+  const row = db
+    .query("SELECT commit_hash, previous_commit FROM last_reviewed WHERE repo_id = ? AND branch = ?")
+    .get(repoId, branch) as { commit_hash: string; previous_commit: string | null } | null;
+  if (!row) return undefined;
+  return row.commit_hash !== current ? row.commit_hash : (row.previous_commit ?? undefined);
+}
+
+// Blob each viewed file was marked at (file path -> blob oid; null for marks that predate blob tracking)
+export async function getViewedBlobs(repoId: string, branch: string, commit: string): Promise<Record<string, string | null>> {
+  const db = getDb();
+
+  const rows = db
+    .query("SELECT file_path, blob_oid FROM viewed_files WHERE repo_id = ? AND branch = ? AND commit_hash = ?")
+    .all(repoId, branch, commit) as Array<{ file_path: string; blob_oid: string | null }>;
+
+  const result: Record<string, string | null> = {};
+  for (const row of rows) {
+    result[row.file_path] = row.blob_oid;
+  }
+  return result;
+}
+
+// Copy viewed marks for the given files from one commit to another on the same
+// branch, under the new path for files in `renamed` (old path -> new path).
+// Marks already present on the target are kept. Returns the number copied.
+export async function carryViewedFiles(
+  repoId: string,
+  branch: string,
+  fromCommit: string,
+  toCommit: string,
+  filePaths: string[],
+  renamed: Record<string, string> = {}
+): Promise<number> {
+  const db = getDb();
+  const copy = db.query(`
+    INSERT OR IGNORE INTO viewed_files (repo_id, branch, commit_hash, file_path, viewed_at, viewed_by, blob_oid)
+    SELECT repo_id, branch, ?, ?, viewed_at, viewed_by, blob_oid FROM viewed_files
+    WHERE repo_id = ? AND branch = ? AND commit_hash = ? AND file_path = ?
+  `);
+
+  let copied = 0;
+  db.transaction(() => {
+    for (const filePath of filePaths) {
+      copied += copy.run(toCommit, renamed[filePath] ?? filePath, repoId, branch, fromCommit, filePath).changes;
+    }
+  })();
+  return copied;
+}
+
+// How viewed marks are matched: per commit (default), or also by file content so a
+// mark holds on any commit where the file's blob is identical
+export const STATE_KEYS = ["commit", "content"] as const;
+export type StateKey = (typeof STATE_KEYS)[number];
+
+// Files (path -> blob oid) previously marked viewed at that exact content, on any
+// branch or commit. Maps each hit to its most recent reviewer (if recorded).
+export async function getViewedByContent(
+  repoId: string,
+  blobs: Record<string, string>
+): Promise<Record<string, { viewed_by?: string }>> {
+  const db = getDb();
+  const lookup = db.query(
+    "SELECT viewed_by FROM viewed_files WHERE repo_id = ? AND file_path = ? AND blob_oid = ? ORDER BY viewed_at DESC LIMIT 1"
+  );
+
+  const result: Record<string, { viewed_by?: string }> = {};
+  for (const [filePath, blobOid] of Object.entries(blobs)) {
+    const row = lookup.get(repoId, filePath, blobOid) as { viewed_by: string | null } | null;
+    if (row) {
+      result[filePath] = { viewed_by: row.viewed_by ?? undefined };
+    }
+  }
+  return result;
+}
+
+// Remove a branch's marks on a file's content at any of its commits; other branches keep theirs
+export async function clearViewedContent(repoId: string, branch: string, filePath: string, blobOid: string): Promise<void> {
+  const db = getDb();
+  db.query("DELETE FROM viewed_files WHERE repo_id = ? AND branch = ? AND file_path = ? AND blob_oid = ?").run(
+    repoId,
+    branch,
+    filePath,
+    blobOid
+  );
+}
+
+export async function getBranchHead(repoId: string, branch: string): Promise<string | undefined> {
+  const db = getDb();
+  const row = db
+    .query("SELECT commit_hash FROM branch_heads WHERE repo_id = ? AND branch = ?")
+    .get(repoId, branch) as { commit_hash: string } | null;
+  return row?.commit_hash;
+}
+
+export async function setBranchHead(repoId: string, branch: string, commit: string): Promise<void> {
+  const db = getDb();
+  db.query("INSERT OR REPLACE INTO branch_heads (repo_id, branch, commit_hash) VALUES (?, ?, ?)").run(
+    repoId,
+    branch,
+    commit
+  );
+}
+
+// Every repo/branch/commit with viewed marks, most recently reviewed first (branch-scoped
+// marks belong to no one commit, so they aren't listed)
+export async function getViewedSummaries(): Promise<Array<{ repo_id: string; branch: string; commit: string; viewed: number; last_viewed_at: number }>> {
+  const db = getDb();
+  return db
+    .query(
+      `SELECT repo_id, branch, commit_hash AS "commit", COUNT(*) AS viewed, MAX(viewed_at) AS last_viewed_at
+       FROM viewed_files WHERE commit_hash != ? GROUP BY repo_id, branch, commit_hash ORDER BY last_viewed_at DESC`
+    )
+    .all(BRANCH_SCOPE_COMMIT) as Array<{ repo_id: string; branch: string; commit: string; viewed: number; last_viewed_at: number }>;
+}
+
+// All viewed entries for a repo, across branches and commits (for export); branch-scoped
+// marks are exported under commit "*"
+export async function getViewedEntries(repoId: string): Promise<ViewedEntry[]> {
+  const db = getDb();
+
+  const rows = db
+    .query("SELECT branch, commit_hash, file_path, viewed_at, viewed_by, blob_oid FROM viewed_files WHERE repo_id = ? ORDER BY viewed_at")
+    .all(repoId) as Array<{
+    branch: string;
+    commit_hash: string;
+    file_path: string;
+    viewed_at: number;
+    viewed_by: string | null;
+    blob_oid: string | null;
+  }>;
+
+  return rows.map((row) => ({
+    branch: row.branch,
+    commit: row.commit_hash,
+    file_path: row.file_path,
+    viewed_at: row.viewed_at,
+    viewed_by: row.viewed_by ?? undefined,
+    blob_oid: row.blob_oid ?? undefined,
+  }));
+}
+
+// Union viewed entries into a repo's state. Existing marks are kept; when both
+// sides have a mark, the later viewed_at wins. Returns the number of rows written.
+export async function mergeViewedEntries(repoId: string, entries: ViewedEntry[]): Promise<number> {
+  const db = getDb();
+  const upsert = db.query(`
+    INSERT INTO viewed_files (repo_id, branch, commit_hash, file_path, viewed_at, viewed_by, blob_oid)
+    VALUES (?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT (repo_id, branch, commit_hash, file_path) DO UPDATE SET
+      viewed_at = excluded.viewed_at,
+      viewed_by = excluded.viewed_by,
+      blob_oid = excluded.blob_oid
+    WHERE excluded.viewed_at > viewed_files.viewed_at
+  `);
+
+  let written = 0;
+  db.transaction(() => {
+    for (const entry of entries) {
+      const result = upsert.run(
+        repoId,
+        entry.branch,
+        entry.commit,
+        entry.file_path,
+        entry.viewed_at,
+        entry.viewed_by ?? null,
+        entry.blob_oid ?? null
+      );
+      written += result.changes;
+    }
+  })();
+  return written;
+}
+
+// Deep-merge two exported states: repos matched by path, viewed marks unioned
+// per branch/commit/file with the later viewed_at winning
+export function mergeReviewStates(base: ReviewStateExport, other: ReviewStateExport): ReviewStateExport {
+  const repos = new Map(base.repos.map((repo) => [repo.path, { ...repo, viewed: [...repo.viewed] }]));
+
+  for (const repo of other.repos) {
+    const existing = repos.get(repo.path);
+    if (!existing) {
+      repos.set(repo.path, { ...repo, viewed: [...repo.viewed] });
+      continue;
+    }
+
+    const entries = new Map(existing.viewed.map((e) => [`${e.branch}\0${e.commit}\0${e.file_path}`, e]));
+    for (const entry of repo.viewed) {
+      const key = `${entry.branch}\0${entry.commit}\0${entry.file_path}`;
+      const current = entries.get(key);
+      if (!current || entry.viewed_at > current.viewed_at) {
+        entries.set(key, entry);
+      }
+    }
+    existing.viewed = [...entries.values()].sort((a, b) => a.viewed_at - b.viewed_at);
+  }
+
+  return {
+    version: base.version,
+    exported_at: Math.max(base.exported_at, other.exported_at),
+    repos: [...repos.values()],
+  };
+}
+
+// ============================================================================
+// Collapsed Files
+// ============================================================================
+
+export async function getCollapsedFiles(repoId: string, branch: string, commit: string): Promise<Record<string, boolean>> {
+  const db = getDb();
+
+  const rows = db
+    .query("SELECT file_path FROM collapsed_files WHERE repo_id = ? AND branch = ? AND commit_hash = ?")
+    .all(repoId, branch, commit) as Array<{ file_path: string }>;
+
+  const result: Record<string, boolean> = {};
+  for (const row of rows) {
+    result[row.file_path] = true;
+  }
+  return result;
+}
+
+export async function setFileCollapsed(
+  repoId: string,
+  branch: string,
+  commit: string,
+  filePath: string,
+  collapsed: boolean
+): Promise<void> {
+  const db = getDb();
+
+  if (collapsed) {
+    db.query(
+      "INSERT OR REPLACE INTO collapsed_files (repo_id, branch, commit_hash, file_path, collapsed_at) VALUES (?, ?, ?, ?, ?)"
     ).run(repoId, branch, commit, filePath, Date.now());
   } else {
-    db.query("DELETE FROM viewed_files WHERE repo_id = ? AND branch = ? AND commit_hash = ? AND file_path = ?").run(
+    db.query("DELETE FROM collapsed_files WHERE repo_id = ? AND branch = ? AND commit_hash = ? AND file_path = ?").run(
       repoId,
       branch,
       commit,
@@ -253,6 +598,85 @@ export async function setFileViewed(
   }
 }
 
+// ============================================================================
+// Review Positions
+// ============================================================================
+
+export async function getReviewPosition(repoId: string, branch: string): Promise<ReviewPosition | undefined> {
+  const db = getDb();
+  const row = db
+    .query("SELECT file_path, line, updated_at FROM review_positions WHERE repo_id = ? AND branch = ?")
+    .get(repoId, branch) as { file_path: string; line: number | null; updated_at: number } | null;
+  if (!row) return undefined;
+  return { file_path: row.file_path, ...(row.line !== null && { line: row.line }), updated_at: row.updated_at };
+}
+
+export async function setReviewPosition(repoId: string, branch: string, filePath: string, line?: number): Promise<void> {
+  const db = getDb();
+  db.query(
+    "INSERT OR REPLACE INTO review_positions (repo_id, branch, file_path, line, updated_at) VALUES (?, ?, ?, ?, ?)"
+  ).run(repoId, branch, filePath, line ?? null, Date.now());
+}
+
+// Drop viewed and collapsed marks (one branch, or all), and the saved position unless keepPosition.
+// Returns the number of viewed marks removed
+export async function clearReviewState(
+  repoId: string,
+  options: { branch?: string; keepPosition?: boolean } = {}
+): Promise<number> {
+  const db = getDb();
+  const scope = options.branch !== undefined ? " AND branch = ?" : "";
+  const params = options.branch !== undefined ? [repoId, options.branch] : [repoId];
+
+  let removed = 0;
+  db.transaction(() => {
+    removed = db.query(`DELETE FROM viewed_files WHERE repo_id = ?${scope}`).run(...params).changes;
+    db.query(`DELETE FROM collapsed_files WHERE repo_id = ?${scope}`).run(...params);
+    db.query(`DELETE FROM last_reviewed WHERE repo_id = ?${scope}`).run(...params);
+    if (!options.keepPosition) {
+      db.query(`DELETE FROM review_positions WHERE repo_id = ?${scope}`).run(...params);
+    }
+  })();
+  return removed;
+}
+
+// ============================================================================
+// UI Preferences
+// ============================================================================
+
+export async function getPreferences(): Promise<Record<string, unknown>> {
+  const db = getDb();
+  const rows = db.query("SELECT key, value FROM preferences").all() as Array<{ key: string; value: string }>;
+
+  const result: Record<string, unknown> = {};
+  for (const row of rows) {
+    result[row.key] = JSON.parse(row.value);
+  }
+  return result;
+}
+
+// Upserts each key; a null value removes it
+export async function setPreferences(updates: Record<string, unknown>): Promise<Record<string, unknown>> {
+  const db = getDb();
+  const now = Date.now();
+
+  db.transaction(() => {
+    for (const [key, value] of Object.entries(updates)) {
+      if (value === null) {
+        db.query("DELETE FROM preferences WHERE key = ?").run(key);
+      } else {
+        db.query("INSERT OR REPLACE INTO preferences (key, value, updated_at) VALUES (?, ?, ?)").run(
+          key,
+          JSON.stringify(value),
+          now
+        );
+      }
+    }
+  })();
+
+  return getPreferences();
+}
+
 // ============================================================================
 // Comments
 // ============================================================================
@@ -267,6 +691,7 @@ export async function getComments(repoId: string, branch?: string): Promise<Comm
     line_number: number | null;
     text: string;
     parent_id: string | null;
+    author: string | null;
     branch: string;
     commit_hash: string;
     created_at: number;
@@ -289,6 +714,7 @@ export async function getComments(repoId: string, branch?: string): Promise<Comm
     line_number: r.line_number ?? undefined,
     text: r.text,
     parent_id: r.parent_id ?? undefined,
+    author: r.author ?? undefined,
     timestamp: r.created_at,
     branch: r.branch,
     commit: r.commit_hash,
@@ -303,7 +729,7 @@ export async function getCommentById(commentId: string): Promise<(Comment & { re
 
   const row = db
     .query(
-      "SELECT id, repo_id, file_path, line_number, text, parent_id, branch, commit_hash, created_at, resolved, resolved_by, resolved_at FROM comments WHERE id = ?"
+      "SELECT id, repo_id, file_path, line_number, text, parent_id, author, branch, commit_hash, created_at, resolved, resolved_by, resolved_at FROM comments WHERE id = ?"
     )
     .get(commentId) as
     | {
@@ -313,6 +739,7 @@ export async function getCommentById(commentId: string): Promise<(Comment & { re
         line_number: number | null;
         text: string;
         parent_id: string | null;
+        author: string | null;
         branch: string;
         commit_hash: string;
         created_at: number;
@@ -331,6 +758,7 @@ export async function getCommentById(commentId: string): Promise<(Comment & { re
     line_number: row.line_number ?? undefined,
     text: row.text,
     parent_id: row.parent_id ?? undefined,
+    author: row.author ?? undefined,
     timestamp: row.created_at,
     branch: row.branch,
     commit: row.commit_hash,
@@ -354,7 +782,7 @@ export async function addComment(repoId: string, comment: Omit<Comment, "id" | "
   const normalizedPath = relative(repo.path, absolutePath);
 
   db.query(
-    "INSERT INTO comments (id, repo_id, file_path, line_number, text, branch, commit_hash, created_at, parent_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    "INSERT INTO comments (id, repo_id, file_path, line_number, text, branch, commit_hash, created_at, parent_id, author) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
   ).run(
     id,
     repoId,
@@ -364,7 +792,8 @@ export async function addComment(repoId: string, comment: Omit<Comment, "id" | "
     comment.branch,
     comment.commit,
     createdAt,
-    comment.parent_id ?? null
+    comment.parent_id ?? null,
+    comment.author ?? null
   );
 
   return {
@@ -373,6 +802,7 @@ export async function addComment(repoId: string, comment: Omit<Comment, "id" | "
     line_number: comment.line_number,
     text: comment.text,
     parent_id: comment.parent_id,
+    author: comment.author,
     timestamp: createdAt,
     branch: comment.branch,
     commit: comment.commit,
@@ -0,0 +1,80 @@
+import { describe, expect, it } from "bun:test";
+import { detectLanguage, getChangeDistribution, getDiffStat, getLanguageStats } from "./index";
+import type { FileDiff } from "../types";
+
+function file(path: string, additions: number, deletions: number): FileDiff {
+  return { path, status: "modified", additions, deletions, patch: "", viewed: false };
+}
+
+describe("detectLanguage", () => {
+  it("classifies by extension and well-known filenames", () => {
+    expect(detectLanguage("src/main.rs")).toBe("Rust");
+    expect(detectLanguage("web/App.TSX")).toBe("TypeScript");
+    expect(detectLanguage("docker/Dockerfile")).toBe("Dockerfile");
+    expect(detectLanguage("LICENSE")).toBe("Other");
+  });
+});
+
+describe("getLanguageStats", () => {
+  it("aggregates and sorts languages by lines changed", () => {
+    const stats = getLanguageStats([
+      file("a.ts", 10, 5),
+      file("b.rs", 70, 10),
+      file("c.tsx", 0, 0),
+      file("notes.xyz", 5, 0),
+    ]);
+
+    expect(stats.map((s) => s.language)).toEqual(["Rust", "TypeScript", "Other"]);
+    expect(stats[0]).toEqual({ language: "Rust", files: 1, additions: 70, deletions: 10, percentage: 80 });
+    expect(stats[1]?.files).toBe(2);
+    expect(stats[1]?.percentage).toBe(15);
+    expect(stats[2]?.percentage).toBe(5);
+  });
+
+  it("returns an empty list for an empty diff", () => {
+    expect(getLanguageStats([])).toEqual([]);
+  });
+});
+
+describe("getChangeDistribution", () => {
+  it("buckets files by total lines changed", () => {
+    const buckets = getChangeDistribution([
+      file("a.ts", 0, 0),
+      file("b.ts", 6, 4),
+      file("c.ts", 11, 0),
+      file("d.ts", 500, 500),
+      file("e.ts", 1000, 1),
+    ]);
+
+    expect(buckets.map((b) => [b.label, b.files])).toEqual([
+      ["0-10", 2],
+      ["11-100", 1],
+      ["101-1000", 1],
+      ["1000+", 1],
+    ]);
+  });
+});
+
+describe("getDiffStat", () => {
+  it("renders git --stat rows and the summary line", () => {
+    const stat = getDiffStat([file("a.ts", 3, 1), file("src/b.ts", 0, 2)]);
+    expect(stat.text).toBe(" a.ts     | 4 +++-\n src/b.ts | 2 --\n 2 files changed, 3 insertions(+), 3 deletions(-)\n");
+    expect(stat.bar_width).toBe(4);
+  });
+
+  it("scales bars to the width, keeping small files visible", () => {
+    const stat = getDiffStat([file("big.ts", 200, 100), file("one.ts", 1, 0)], 40);
+    expect(stat.files.map((f) => [f.bar_additions, f.bar_deletions])).toEqual([
+      [18, 9],
+      [1, 0],
+    ]);
+    expect(stat.bar_width).toBe(27);
+    expect(stat.text.split("\n")[2]).toBe(" 2 files changed, 201 insertions(+), 100 deletions(-)");
+  });
+
+  it("shows binaries as Bin and drops a zero side from the summary", () => {
+    const stat = getDiffStat([{ ...file("logo.png", 0, 0), binary: true }, file("a.ts", 1, 0)]);
+    expect(stat.text).toBe(" logo.png | Bin\n a.ts     | 1 +\n 2 files changed, 1 insertion(+)\n");
+    expect(getDiffStat([]).text).toBe("");
+  });
+});
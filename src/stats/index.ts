@@ -0,0 +1,183 @@
+/**
+ * Aggregate statistics over a diff's file list
+ * Only uses per-file addition/deletion counts, so no patches need to be loaded
+ */
+import { basename, extname } from "path";
+import type { DiffStat, DiffStatFile, DistributionBucket, FileDiff, LanguageStat } from "../types";
+
+const LANGUAGES_BY_EXTENSION: Record<string, string> = {
+  ".ts": "TypeScript",
+  ".tsx": "TypeScript",
+  ".mts": "TypeScript",
+  ".cts": "TypeScript",
+  ".js": "JavaScript",
+  ".jsx": "JavaScript",
+  ".mjs": "JavaScript",
+  ".cjs": "JavaScript",
+  ".rs": "Rust",
+  ".go": "Go",
+  ".py": "Python",
+  ".rb": "Ruby",
+  ".java": "Java",
+  ".kt": "Kotlin",
+  ".kts": "Kotlin",
+  ".swift": "Swift",
+  ".m": "Objective-C",
+  ".c": "C",
+  ".h": "C",
+  ".cc": "C++",
+  ".cpp": "C++",
+  ".cxx": "C++",
+  ".hpp": "C++",
+  ".cs": "C#",
+  ".php": "PHP",
+  ".scala": "Scala",
+  ".ex": "Elixir",
+  ".exs": "Elixir",
+  ".erl": "Erlang",
+  ".hs": "Haskell",
+  ".lua": "Lua",
+  ".dart": "Dart",
+  ".zig": "Zig",
+  ".sh": "Shell",
+  ".bash": "Shell",
+  ".zsh": "Shell",
+  ".sql": "SQL",
+  ".html": "HTML",
+  ".css": "CSS",
+  ".scss": "SCSS",
+  ".vue": "Vue",
+  ".svelte": "Svelte",
+  ".json": "JSON",
+  ".yaml": "YAML",
+  ".yml": "YAML",
+  ".toml": "TOML",
+  ".xml": "XML",
+  ".md": "Markdown",
+  ".mdx": "Markdown",
+};
+
+// Extensionless files recognised by name
+const LANGUAGES_BY_FILENAME: Record<string, string> = {
+  Dockerfile: "Dockerfile",
+  Makefile: "Makefile",
+  Rakefile: "Ruby",
+  Gemfile: "Ruby",
+};
+
+export function detectLanguage(filePath: string): string {
+  const name = basename(filePath);
+  const byName = LANGUAGES_BY_FILENAME[name];
+  if (byName) return byName;
+  return LANGUAGES_BY_EXTENSION[extname(name).toLowerCase()] ?? "Other";
+}
+
+// Per-language additions/deletions, heaviest first
+export function getLanguageStats(files: FileDiff[]): LanguageStat[] {
+  const byLanguage = new Map<string, LanguageStat>();
+  let total = 0;
+
+  for (const file of files) {
+    const language = detectLanguage(file.path);
+    const stat = byLanguage.get(language) ?? { language, files: 0, additions: 0, deletions: 0, percentage: 0 };
+    stat.files++;
+    stat.additions += file.additions;
+    stat.deletions += file.deletions;
+    byLanguage.set(language, stat);
+    total += file.additions + file.deletions;
+  }
+
+  const stats = [...byLanguage.values()];
+  for (const stat of stats) {
+    stat.percentage = total > 0 ? Math.round(((stat.additions + stat.deletions) / total) * 1000) / 10 : 0;
+  }
+
+  return stats.sort(
+    (a, b) => b.additions + b.deletions - (a.additions + a.deletions) || a.language.localeCompare(b.language)
+  );
+}
+
+// Lines-changed buckets for /api/stats/distribution (max is inclusive, null = unbounded)
+const DISTRIBUTION_BUCKETS: Array<{ label: string; min: number; max: number | null }> = [
+  { label: "0-10", min: 0, max: 10 },
+  { label: "11-100", min: 11, max: 100 },
+  { label: "101-1000", min: 101, max: 1000 },
+  { label: "1000+", min: 1001, max: null },
+];
+
+// Histogram of files by lines changed (additions + deletions)
+export function getChangeDistribution(files: FileDiff[]): DistributionBucket[] {
+  const buckets = DISTRIBUTION_BUCKETS.map((bucket) => ({ ...bucket, files: 0 }));
+
+  for (const file of files) {
+    const changed = file.additions + file.deletions;
+    const bucket = buckets.find((b) => changed >= b.min && (b.max === null || changed <= b.max));
+    if (bucket) bucket.files++;
+  }
+
+  return buckets;
+}
+
+// git's scale_linear: any change gets at least one column
+function scaleLinear(changes: number, width: number, maxChanges: number): number {
+  if (changes === 0) return 0;
+  return 1 + Math.floor((changes * (width - 1)) / maxChanges);
+}
+
+// Bar columns for a file's additions and deletions, scaled like git so both sides stay
+// visible when the largest file overflows the width
+function statBar(additions: number, deletions: number, width: number, maxChanges: number): [number, number] {
+  if (maxChanges <= width) return [additions, deletions];
+  let total = scaleLinear(additions + deletions, width, maxChanges);
+  if (total < 2 && additions > 0 && deletions > 0) total = 2;
+  if (additions < deletions) {
+    const added = scaleLinear(additions, width, maxChanges);
+    return [added, total - added];
+  }
+  const deleted = scaleLinear(deletions, width, maxChanges);
+  return [total - deleted, deleted];
+}
+
+function plural(count: number, one: string, many: string): string {
+  return `${count} ${count === 1 ? one : many}`;
+}
+
+// `git diff --stat` for a file list, laid out for a terminal `width` columns wide
+export function getDiffStat(files: FileDiff[], width = 80): DiffStat {
+  const countWidth = Math.max(...files.map((f) => String(f.additions + f.deletions).length), 1);
+  // Long paths are cut from the front ("...") so the bars keep some room, as git does
+  const nameWidth = Math.min(Math.max(...files.map((f) => f.path.length), 0), Math.max(Math.floor((width * 5) / 8), 10));
+  const barWidth = Math.max(width - nameWidth - countWidth - 4, 6);
+  const maxChanges = Math.max(...files.map((f) => (f.binary ? 0 : f.additions + f.deletions)), 0);
+
+  const rows: DiffStatFile[] = files.map((file) => {
+    if (file.binary) {
+      return { path: file.path, additions: 0, deletions: 0, binary: true, bar_additions: 0, bar_deletions: 0 };
+    }
+    const [added, deleted] = statBar(file.additions, file.deletions, barWidth, maxChanges);
+    return { path: file.path, additions: file.additions, deletions: file.deletions, bar_additions: added, bar_deletions: deleted };
+  });
+
+  const lines = rows.map((row) => {
+    const name = row.path.length > nameWidth ? `...${row.path.slice(row.path.length - nameWidth + 3)}` : row.path.padEnd(nameWidth);
+    if (row.binary) return ` ${name} | ${"Bin".padStart(countWidth)}`;
+    const count = String(row.additions + row.deletions).padStart(countWidth);
+    return ` ${name} | ${count} ${"+".repeat(row.bar_additions)}${"-".repeat(row.bar_deletions)}`.trimEnd();
+  });
+
+  const additions = files.reduce((sum, f) => sum + f.additions, 0);
+  const deletions = files.reduce((sum, f) => sum + f.deletions, 0);
+  // git leaves out whichever of insertions/deletions is zero, unless both are
+  const summary = [plural(files.length, "file changed", "files changed")];
+  if (additions > 0 || deletions === 0) summary.push(plural(additions, "insertion(+)", "insertions(+)"));
+  if (deletions > 0 || additions === 0) summary.push(plural(deletions, "deletion(-)", "deletions(-)"));
+  if (files.length > 0) lines.push(` ${summary.join(", ")}`);
+
+  return {
+    files: rows,
+    additions,
+    deletions,
+    bar_width: Math.max(...rows.map((r) => r.bar_additions + r.bar_deletions), 0),
+    text: lines.length > 0 ? `${lines.join("\n")}\n` : "",
+  };
+}
@@ -4,21 +4,135 @@ export interface FileContents {
   contents: string;
 }
 
+// Parsed patch hunk
+export interface DiffLine {
+  type: "context" | "add" | "delete" | "collapsed";
+  content: string;
+  old_line?: number;
+  new_line?: number;
+  // For "collapsed" markers: number of unchanged lines hidden from old_line/new_line on
+  hidden_count?: number;
+  // The line ends its file without a trailing newline (git's "\ No newline at end of file")
+  no_newline?: boolean;
+  // Added/deleted lines with ?line_ids=true: anchor that ignores line numbers (see addLineIds in git/patch.ts)
+  id?: string;
+}
+
+export interface DiffHunk {
+  header: string;
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: DiffLine[];
+  // Removed and added lines differ only in whitespace
+  whitespace_only: boolean;
+  // Content hash of the hunk's changes, unchanged when line numbers or context shift (see hunkId in git/patch.ts)
+  id: string;
+}
+
+// Side-by-side view: each row pairs an old-side line with a new-side line
+export interface SplitCell {
+  line: number;
+  content: string;
+  type: DiffLine["type"];
+  // DiffLine.id of a changed line, with ?line_ids=true
+  id?: string;
+}
+
+export interface SplitRow {
+  left?: SplitCell;
+  right?: SplitCell;
+}
+
+export interface SplitHunk {
+  header: string;
+  rows: SplitRow[];
+  whitespace_only: boolean;
+  id: string;
+}
+
+// A block of lines moved between files (from ?detect_moves=true)
+export interface MovedBlock {
+  // moved_from: added here, deleted from other_path; moved_to: deleted here, added to other_path
+  direction: "moved_from" | "moved_to";
+  // First line of the block (new side for moved_from, old side for moved_to)
+  line: number;
+  lines: number;
+  other_path: string;
+  other_line: number;
+}
+
 // Individual file diff information
 export interface FileDiff {
   path: string;
-  status: "added" | "modified" | "deleted" | "renamed" | "untracked";
+  status: "added" | "modified" | "deleted" | "renamed" | "copied" | "untracked";
   additions: number;
   deletions: number;
   patch: string;
+  // Set when ?patch_encoding=base64 asked for `patch` as base64 of the bytes git wrote
+  patch_encoding?: "base64";
   viewed: boolean;
+  // Reviewer who marked the file viewed
+  viewed_by?: string;
+  // "branch" when marked viewed for every commit of the branch (mark-viewed scope=branch)
+  viewed_scope?: "branch";
+  // Collapsed by the reviewer (persisted like viewed), or auto-collapsed by an ignore pattern
+  collapsed?: boolean;
+  // Matched a --ignore pattern
+  ignored?: boolean;
+  // false when the file doesn't count toward completion (status outside require-review-for, or ignored)
+  review_required?: false;
   old_file?: FileContents;
   new_file?: FileContents;
+  // Added text file: new_file (or /api/file?side=new) is the whole change, without diff markers
+  full_new_file?: true;
   staged?: boolean;
+  // Submodule pointer updates (contents inside the submodule are not diffed)
+  submodule?: boolean;
+  submodule_old_commit?: string;
+  submodule_new_commit?: string;
+  // Renames and copies (--detect-copies): the source path and git's similarity score (100 = content unchanged)
+  old_path?: string;
+  similarity?: number;
+  // Symlinks are shown as target changes rather than content diffs
+  symlink?: boolean;
+  symlink_old_target?: string;
+  symlink_new_target?: string;
+  // Present when requested with ?view=split / ?view=hunks
+  split?: SplitHunk[];
+  hunks?: DiffHunk[];
+  // Present when requested with ?detect_moves=true
+  moved_blocks?: MovedBlock[];
+  // Binary changes: sizes in bytes of each side that exists, so reviewers see the magnitude
+  binary?: boolean;
+  old_size?: number;
+  new_size?: number;
+  // Over --max-blob-size: no patch or contents, just the larger side's size in bytes
+  too_large?: boolean;
+  blob_size?: number;
+  // Full mode: whether the file's changes are in commits since the base, the working tree, or both
+  changed_in?: ChangedIn;
+  // Aggregated commit diffs (/api/commits?aggregate=true): short oids of the commits touching the file
+  commits?: string[];
+  // Combined merge diffs (?combined=true): result line numbers found in no parent
+  resolution_lines?: number[];
+  // Unmerged in the index (mid-merge, rebase, cherry-pick...); only set in modes that read the working tree
+  conflicted?: true;
+  // Range diffs with ?attribute=true: short oid of the range commit that introduced each
+  // new-side line, keyed by line number (lines from before the range are left out)
+  line_commits?: Record<number, string>;
 }
 
-// Diff response from API
-export type DiffMode = "branch" | "working";
+export type ChangedIn = "committed" | "uncommitted" | "both";
+
+// Diff response from API. working is HEAD against the working tree (staged files flagged `staged`),
+// unstaged the index against the working tree (what `git add` would pick up), and full the merge
+// base against the working tree (committed plus uncommitted)
+export type DiffMode = "branch" | "working" | "unstaged" | "full";
+
+// How FileDiff.patch is transported; base64 is opt-in for programmatic consumers
+export type PatchEncoding = "utf8" | "base64";
 
 export interface DiffResponse {
   files: FileDiff[];
@@ -28,13 +142,226 @@ export interface DiffResponse {
   remote_url?: string;
   mode: DiffMode;
   base_branch: string;
+  // Total file count when `files` is one page (?offset/&limit)
+  total?: number;
+  // Previously reviewed commit the diff starts from (?since=last)
+  since?: string;
+  // Unchanged files moved together, folded out of `files` (?group_renames=true)
+  renames?: RenameGroup[];
+  // Set for merge commits on /api/diff/show: the diff is against parent `parent` only (?parent=<n>)
+  is_merge?: boolean;
+  parent?: number;
+  // ?combined=true on a merge: files are diffed against every parent (`git show --cc`)
+  combined?: boolean;
+  // Where the reviewer left off in this diff (POST /api/position)
+  last_position?: ReviewPosition;
+  // Viewed files out of those that must be viewed, over the whole diff even when paged
+  progress?: ReviewProgress;
+  // ?attribute=true on a range: the commits files' line_commits refer to
+  attribution?: RangeAttribution;
+  // GET /api/force-push-diff: the rewrite whose old tip the diff starts from
+  force_push?: BranchRewrite;
+  // --auto-mark-on-open: the UI marks each file viewed (POST /api/mark-viewed) when it's opened
+  auto_mark_on_open?: true;
+}
+
+// A branch's latest history rewrite (rebase, amend, reset, forced push or fetch), read from
+// the ref's reflog: an update whose previous tip isn't an ancestor of the new one
+export interface BranchRewrite {
+  // Full name of the ref whose reflog was read, e.g. refs/heads/feature
+  ref: string;
+  old_tip: string;
+  // The ref's current tip (commits may have landed on top since the rewrite)
+  new_tip: string;
+  // Reflog message, e.g. "rebase (finish): refs/heads/feature onto 1a2b3c4..."
+  reason: string;
+  timestamp: number;
+  // false once the old tip has been garbage collected and can no longer be diffed
+  available: boolean;
+}
+
+export interface RangeAttribution {
+  // Newest first
+  commits: RangeCommit[];
+  // The range or file list was over the cap: some files carry no line_commits
+  truncated?: boolean;
+}
+
+// Viewed paths for one review key, for GET /api/viewed (no diff is computed)
+export interface ViewedFileList {
+  branch: string;
+  commit: string;
+  // Sorted; includes branch-scoped marks
+  files: string[];
+}
+
+// An unmerged path, for GET /api/conflicts: which index stages exist (a missing side deleted
+// the file there) and how many conflict blocks are still in the working-tree file
+export interface ConflictedFile {
+  path: string;
+  base: boolean;
+  ours: boolean;
+  theirs: boolean;
+  // <<<<<<< lines; 0 once the markers are edited out, even before `git add`
+  markers: number;
+}
+
+export interface ConflictList {
+  branch: string;
+  files: ConflictedFile[];
+}
+
+export interface ReviewProgress {
+  viewed: number;
+  required: number;
+}
+
+// Rename-only files that share a directory move, e.g. src/a -> src/b
+export interface RenameGroup {
+  from: string;
+  to: string;
+  files: Array<{ old_path: string; path: string }>;
+}
+
+// Initial page data injected into index.html
+export interface InitialData {
+  repo_id: string;
+  diff: DiffResponse;
+  // Full oid when the page is a /show/<oid> single-commit review
+  show?: string;
+}
+
+// Blame for one line of a file's base version
+export interface BlameLine {
+  line: number;
+  commit: string;
+  author: string;
+  summary: string;
+}
+
+// A commit at one end of the reviewed range
+export interface RangeCommit {
+  hash: string;
+  summary: string;
+  author: string;
+  timestamp: number; // unix seconds
+}
+
+// Commits between the merge base and HEAD, for /api/range-info
+export interface RangeInfo {
+  base_branch: string;
+  merge_base: string;
+  commit_count: number;
+  oldest?: RangeCommit;
+  newest?: RangeCommit;
+  // The walk stopped at ?limit (or the server cap): commit_count is a lower bound and oldest is unknown
+  truncated?: boolean;
+}
+
+// Commits between the merge base and HEAD, for /api/commits (author narrows them)
+export interface RangeCommitList {
+  base_branch: string;
+  merge_base: string;
+  commits: RangeCommit[];
+  truncated?: boolean;
+  author?: string;
+  // ?aggregate=true: the listed commits' changes as one diff
+  files?: FileDiff[];
+}
+
+// A commit in /api/graph with its parents, which may lie outside the graph (the merge base,
+// or the base of a merged-in side branch)
+export interface GraphCommit extends RangeCommit {
+  parents: string[];
+}
+
+// The commit DAG between the merge base and HEAD, for /api/graph
+export interface CommitGraph {
+  base_branch: string;
+  merge_base: string;
+  head: string;
+  // Topological order, newest first: every commit comes before its parents
+  commits: GraphCommit[];
+  truncated?: boolean;
+}
+
+// Totals from numstat alone, for /api/estimate (untracked files count without lines)
+export interface DiffEstimate {
+  files: number;
+  additions: number;
+  deletions: number;
+  // Server's --max-files cap (absent when disabled) and whether an unpaged /api/diff would hit it
+  max_files?: number;
+  exceeds_max_files?: boolean;
+}
+
+// Change magnitude for /api/loc (same numstat totals as the estimate, plus net)
+export interface LocResponse {
+  files: number;
+  additions: number;
+  deletions: number;
+  net: number;
+}
+
+// Per-language change totals for /api/stats/languages
+export interface LanguageStat {
+  language: string;
+  files: number;
+  additions: number;
+  deletions: number;
+  // Share of all changed lines, 0-100 with one decimal
+  percentage: number;
+}
+
+// Histogram bucket for /api/stats/distribution
+export interface DistributionBucket {
+  label: string;
+  min: number;
+  max: number | null;
+  files: number;
+}
+
+// One row of a `git diff --stat`-style summary; the bar lengths are scaled so the largest
+// file fills the available width
+export interface DiffStatFile {
+  path: string;
+  additions: number;
+  deletions: number;
+  binary?: boolean;
+  bar_additions: number;
+  bar_deletions: number;
+}
+
+export interface DiffStat {
+  files: DiffStatFile[];
+  additions: number;
+  deletions: number;
+  // Widest bar (in columns) any file got
+  bar_width: number;
+  // git --stat text for the same rows, for terminals and copying
+  text: string;
+}
+
+// Committer identity from git config, used as the default reviewer and comment author
+export interface UserIdentity {
+  name?: string;
+  email?: string;
 }
 
 // Repository status
 export interface StatusResponse {
   repo_path: string;
   branch: string;
-  commit: string;
+  // null on an unborn branch, with `message` explaining why
+  commit: string | null;
+  // Working tree has uncommitted changes, so files on disk may differ from a branch diff
+  dirty: boolean;
+  message?: string;
+}
+
+export interface RefreshResponse extends StatusResponse {
+  // HEAD, branch or dirty state moved since the last refresh, so cached git state was dropped
+  changed: boolean;
 }
 
 // Comment on a file/line
@@ -43,6 +370,8 @@ export interface Comment {
   file_path: string;
   line_number?: number;
   text: string;
+  // Reviewer name (--reviewer or git config user.name when not given)
+  author?: string;
   parent_id?: string;
   timestamp: number;
   branch: string;
@@ -82,6 +411,23 @@ export interface Repository {
 export interface Config {
   defaultPort: number;
   currentRepo?: string;
+  // Tracked paths to keep out of review (globs), and whether to hide or collapse them
+  ignorePatterns?: string[];
+  ignoreMode?: "hide" | "collapse";
+  // UI keyboard shortcut overrides (action -> key), see /api/config/shortcuts
+  shortcuts?: Record<string, string>;
+  // File statuses that must be viewed for a review to be complete (all when unset)
+  requireReviewFor?: FileDiff["status"][];
+  // Diff options for particular paths, e.g. ignoring whitespace in minified bundles
+  diffOverrides?: DiffOverride[];
+}
+
+// Diff options for files matching `path`, a glob (one without a slash matches the file name
+// in any directory). They add to the server-wide diff flags for those files
+export interface DiffOverride {
+  path: string;
+  ignoreWhitespace?: boolean; // git diff --ignore-all-space
+  context?: number; // lines of context around each hunk (--unified)
 }
 
 // Repos state file
@@ -90,6 +436,68 @@ export interface ReposState {
   currentRepo?: string;
 }
 
+// Review state stored as a git note on a commit: branch -> file path -> mark
+export interface ReviewNote {
+  viewed: Record<string, Record<string, { viewed_at: number; viewed_by?: string }>>;
+}
+
+// A configured git remote (GET /api/remotes); push_url only when it differs from fetch_url
+export interface Remote {
+  name: string;
+  fetch_url: string;
+  push_url?: string;
+}
+
+// A review in flight, for GET /api/reviews
+export interface ReviewSummary {
+  repo_id: string;
+  repo_name: string;
+  repo_path: string;
+  // branch: a checkout's branch; show: one commit; range: a ?range= review
+  kind: "branch" | "show" | "range";
+  branch: string;
+  commit: string;
+  viewed: number;
+  last_viewed_at: number;
+  // Files in the reviewed diff, only with ?totals=true (absent when it can't be rebuilt)
+  total?: number;
+  // The repo is gone from disk or no longer a git repository
+  stale?: boolean;
+  // UI page for branch and show reviews, the API diff for ranges
+  url: string;
+}
+
+// Last file (and roughly the line) the reviewer was at, per repo and branch
+export interface ReviewPosition {
+  file_path: string;
+  line?: number;
+  updated_at: number;
+}
+
+// Portable review state written by `cerebro state export`
+export interface ViewedEntry {
+  branch: string;
+  // "*" for a branch-scoped mark
+  commit: string;
+  file_path: string;
+  viewed_at: number;
+  viewed_by?: string;
+  // Blob the file had when marked, for --state-key content
+  blob_oid?: string;
+}
+
+export interface ExportedRepoState {
+  path: string;
+  name: string;
+  viewed: ViewedEntry[];
+}
+
+export interface ReviewStateExport {
+  version: number;
+  exported_at: number;
+  repos: ExportedRepoState[];
+}
+
 // Per-repo state
 export interface RepoState {
   viewed: Record<string, boolean>; // file path -> viewed
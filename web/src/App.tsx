@@ -5,7 +5,9 @@ import { Icon } from "./components/Icon";
 import { Modal } from "./components/Modal";
 import { RepoPicker } from "./components/RepoPicker";
 import { useDiff } from "./hooks/useDiff";
+import { usePrefs } from "./hooks/usePrefs";
 import { useRepos } from "./hooks/useRepos";
+import { useShortcuts } from "./hooks/useShortcuts";
 import { buildCommentThreads } from "./utils/commentThreads";
 
 const HALF_PAGE_SIZE = 10;
@@ -20,6 +22,10 @@ export default function App() {
     addRepo,
     removeRepo,
   } = useRepos();
+  // Diff style and rename grouping are server-side preferences so they follow the user across browsers
+  const { prefs, setPref } = usePrefs();
+  // Folded files can't be opened or marked one by one, so grouping directory moves is opt-in
+  const groupRenames = prefs?.["group_renames"] === true;
 
   const {
     diff,
@@ -33,6 +39,7 @@ export default function App() {
     compareBranch,
     setCompareBranch,
     toggleViewed,
+    savePosition,
     addComment,
     resolveComment,
     dismissNote,
@@ -42,7 +49,7 @@ export default function App() {
     commit,
     loadFileDiff,
     refresh,
-  } = useDiff(currentRepo);
+  } = useDiff(currentRepo, { groupRenames });
 
   const [expandedFiles, setExpandedFiles] = useState<Set<string>>(new Set());
   const [loadingFiles, setLoadingFiles] = useState<Set<string>>(new Set());
@@ -53,7 +60,10 @@ export default function App() {
     setExpandedFiles(new Set());
     setFocusedIndex(0);
   }, [mode, compareBranch, currentRepo]);
-  const [diffStyle, setDiffStyle] = useState<"split" | "unified">("unified");
+  const diffStyle = prefs?.["diff_style"] === "split" ? "split" : "unified";
+  const toggleDiffStyle = useCallback(() => {
+    setPref("diff_style", diffStyle === "split" ? "unified" : "split");
+  }, [diffStyle, setPref]);
   const [showShortcuts, setShowShortcuts] = useState(false);
   const [showCommitModal, setShowCommitModal] = useState(false);
   const [commitMessage, setCommitMessage] = useState("");
@@ -66,6 +76,8 @@ export default function App() {
   const [showBranchPicker, setShowBranchPicker] = useState(false);
   const [showCommandPalette, setShowCommandPalette] = useState(false);
 
+  const translateKey = useShortcuts();
+
   // For vim multi-key sequences (gg)
   const lastKeyRef = useRef<string | null>(null);
   const lastKeyTimeRef = useRef<number>(0);
@@ -92,13 +104,37 @@ export default function App() {
     });
   }, [diff?.files]);
 
+  // Land on the file the reviewer left off at, once per repo/mode/branch view
+  const restoredViewRef = useRef<string | null>(null);
+  const savedPathRef = useRef<string | null>(null);
+  useEffect(() => {
+    const viewKey = `${currentRepo}:${mode}:${compareBranch}`;
+    if (!diff || restoredViewRef.current === viewKey) return;
+    restoredViewRef.current = viewKey;
+    savedPathRef.current = diff.last_position?.file_path ?? null;
+    const index = files.findIndex((f) => f.path === diff.last_position?.file_path);
+    if (index >= 0) setFocusedIndex(index);
+  }, [diff, files, currentRepo, mode, compareBranch]);
+
+  useEffect(() => {
+    const path = files[focusedIndex]?.path;
+    if (!path || !restoredViewRef.current || savedPathRef.current === path) return;
+    savedPathRef.current = path;
+    void savePosition(path);
+  }, [files, focusedIndex, savePosition]);
+
   const toggleFile = useCallback(
     async (path: string) => {
       const file = files.find((f) => f.path === path);
       const isExpanding = !expandedFiles.has(path);
 
+      // --auto-mark-on-open: opening a file counts as viewing it
+      if (isExpanding && file && !file.viewed && diff?.auto_mark_on_open) {
+        void toggleViewed(path, false).catch(() => undefined);
+      }
+
       // If expanding and file has no patch loaded (lazy loading), load it first
-      if (isExpanding && file && !file.patch) {
+      if (isExpanding && file && !file.patch && !file.too_large) {
         setLoadingFiles((prev) => new Set(prev).add(path));
         await loadFileDiff(path);
         setLoadingFiles((prev) => {
@@ -118,7 +154,7 @@ export default function App() {
         return next;
       });
     },
-    [expandedFiles, files, loadFileDiff],
+    [expandedFiles, files, diff, loadFileDiff, toggleViewed],
   );
 
   // Keyboard shortcuts
@@ -135,12 +171,18 @@ export default function App() {
         return;
       }
 
+      // Custom bindings resolve to the built-in key; modifier combos stay fixed
+      const key = e.ctrlKey || e.metaKey ? e.key : translateKey(e.key);
+      if (key === null) {
+        return;
+      }
+
       const now = Date.now();
       const lastKey = lastKeyRef.current;
       const timeSinceLastKey = now - lastKeyTimeRef.current;
 
       // Check for multi-key sequences (within 500ms)
-      if (lastKey === "g" && e.key === "g" && timeSinceLastKey < 500) {
+      if (lastKey === "g" && key === "g" && timeSinceLastKey < 500) {
         // gg - go to first file
         e.preventDefault();
         setFocusedIndex(0);
@@ -149,12 +191,12 @@ export default function App() {
       }
 
       // Store this key for potential sequence
-      lastKeyRef.current = e.key;
+      lastKeyRef.current = key;
       lastKeyTimeRef.current = now;
 
       const focusedFile = files[focusedIndex];
 
-      switch (e.key) {
+      switch (key) {
         case "j":
           e.preventDefault();
           setFocusedIndex((i) => Math.min(i + 1, files.length - 1));
@@ -246,7 +288,7 @@ export default function App() {
           break;
         case "t":
           e.preventDefault();
-          setDiffStyle((s) => (s === "split" ? "unified" : "split"));
+          toggleDiffStyle();
           break;
         case "c":
           e.preventDefault();
@@ -278,6 +320,8 @@ export default function App() {
     stageFile,
     unstageFile,
     setMode,
+    translateKey,
+    toggleDiffStyle,
   ]);
 
   const commentThreadsByFile = useMemo(() => {
@@ -328,6 +372,13 @@ export default function App() {
         action: () => focusedFile && void toggleViewed(focusedFile.path, focusedFile.viewed),
         disabled: !focusedFile,
       },
+      {
+        id: "mark-viewed-branch",
+        label: "Mark as reviewed for the whole branch",
+        category: "actions" as const,
+        action: () => focusedFile && void toggleViewed(focusedFile.path, false, "branch"),
+        disabled: !focusedFile || focusedFile.viewed_scope === "branch",
+      },
       {
         id: "stage-file",
         label: "Stage file",
@@ -426,12 +477,25 @@ export default function App() {
         action: () => setMode("working"),
         disabled: mode === "working",
       },
+      {
+        id: "full-mode",
+        label: "Switch to Full mode (committed + uncommitted)",
+        category: "settings" as const,
+        action: () => setMode("full"),
+        disabled: mode === "full",
+      },
       {
         id: "toggle-diff-style",
         label: diffStyle === "split" ? "Switch to Unified view" : "Switch to Split view",
         shortcut: "t",
         category: "settings" as const,
-        action: () => setDiffStyle((s) => (s === "split" ? "unified" : "split")),
+        action: toggleDiffStyle,
+      },
+      {
+        id: "toggle-group-renames",
+        label: groupRenames ? "List moved files one by one" : "Fold directory moves into groups",
+        category: "settings" as const,
+        action: () => setPref("group_renames", !groupRenames),
       },
       {
         id: "show-shortcuts",
@@ -453,6 +517,9 @@ export default function App() {
     unstageFile,
     refresh,
     setMode,
+    toggleDiffStyle,
+    groupRenames,
+    setPref,
   ]);
 
   const handleToggleViewed = async (path: string, viewed: boolean) => {
@@ -600,8 +667,10 @@ export default function App() {
     );
   }
 
-  const viewedCount = files.filter((f) => f.viewed).length;
-  const fileCount = files.length;
+  // Files the server flags review_required: false (require-review-for, ignored) don't count
+  const requiredFiles = files.filter((f) => f.review_required !== false);
+  const viewedCount = requiredFiles.filter((f) => f.viewed).length;
+  const fileCount = requiredFiles.length;
   const progressPercent = fileCount > 0 ? (viewedCount / fileCount) * 100 : 0;
   const currentRepoData = repos.find((r) => r.id === currentRepo);
 
@@ -633,9 +702,17 @@ export default function App() {
             >
               Working
             </button>
+            <button
+              type="button"
+              className={mode === "full" ? "active" : ""}
+              onClick={() => setMode("full")}
+              title="Committed and uncommitted changes against the base"
+            >
+              Full
+            </button>
           </div>
           <span className="branch">{diff?.branch}</span>
-          {mode === "branch" && (
+          {mode !== "working" && (
             <div className="branch-selector">
               <span className="compare-label">vs</span>
               <button
@@ -687,9 +764,7 @@ export default function App() {
           <button
             type="button"
             className={`view-toggle ${diffStyle === "split" ? "active" : ""}`}
-            onClick={() => {
-              setDiffStyle(diffStyle === "split" ? "unified" : "split");
-            }}
+            onClick={toggleDiffStyle}
             title="Toggle diff view"
           >
             {diffStyle === "split" ? "Split" : "Unified"}
@@ -718,7 +793,21 @@ export default function App() {
       )}
 
       <main className="file-list">
-        {fileCount === 0 ? (
+        {diff?.renames?.map((group) => (
+          <details key={`${group.from}\0${group.to}`} className="rename-group">
+            <summary>
+              Moved <code>{group.from}</code> → <code>{group.to}</code>: {group.files.length} files
+            </summary>
+            <ul>
+              {group.files.map((entry) => (
+                <li key={entry.path}>
+                  {entry.old_path} → {entry.path}
+                </li>
+              ))}
+            </ul>
+          </details>
+        ))}
+        {files.length === 0 && !diff?.renames?.length ? (
           <div className="empty">
             <p>No changes</p>
             <p className="muted">Your branch is up to date</p>
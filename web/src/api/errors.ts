@@ -0,0 +1,13 @@
+import type { ApiErrorBody } from "./types";
+
+// Message from a failed API response's { error, code } body (falls back to the raw text)
+export async function readApiError(res: Response, fallback: string): Promise<string> {
+  const text = await res.text();
+  try {
+    const body = JSON.parse(text) as Partial<ApiErrorBody>;
+    if (typeof body.error === "string") return body.error;
+  } catch {
+    // Not JSON (e.g. a proxy error page)
+  }
+  return text || fallback;
+}
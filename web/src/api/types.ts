@@ -3,16 +3,93 @@ export interface FileContents {
   contents: string;
 }
 
+export interface DiffLine {
+  type: "context" | "add" | "delete" | "collapsed";
+  content: string;
+  old_line?: number;
+  new_line?: number;
+  hidden_count?: number; // collapsed markers only
+  no_newline?: boolean; // last line of its file, without a trailing newline
+  id?: string; // changed lines, with ?line_ids=true
+}
+
+export interface DiffHunk {
+  header: string;
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  lines: DiffLine[];
+  // Removed and added lines differ only in whitespace
+  whitespace_only: boolean;
+  id: string; // content hash of the changes, stable across line shifts
+}
+
+export interface SplitCell {
+  line: number;
+  content: string;
+  type: DiffLine["type"];
+  id?: string;
+}
+
+export interface SplitRow {
+  left?: SplitCell;
+  right?: SplitCell;
+}
+
+export interface SplitHunk {
+  header: string;
+  rows: SplitRow[];
+  whitespace_only: boolean;
+  id: string;
+}
+
+export interface MovedBlock {
+  direction: "moved_from" | "moved_to";
+  line: number;
+  lines: number;
+  other_path: string;
+  other_line: number;
+}
+
 export interface FileDiff {
   path: string;
-  status: "added" | "modified" | "deleted" | "renamed" | "untracked";
+  status: "added" | "modified" | "deleted" | "renamed" | "copied" | "untracked";
   additions: number;
   deletions: number;
   patch: string;
+  patch_encoding?: "base64"; // only when the API was asked for ?patch_encoding=base64
   viewed: boolean;
+  viewed_by?: string; // reviewer who marked the file viewed
+  viewed_scope?: "branch"; // viewed on every commit of the branch, not just this one
+  collapsed?: boolean; // collapsed by the reviewer, persisted server-side
+  ignored?: boolean; // matched a --ignore pattern (auto-collapsed)
+  review_required?: false; // doesn't count toward completion (require-review-for, or ignored)
   old_file?: FileContents;
   new_file?: FileContents;
+  full_new_file?: true; // added text file: render new_file (or /api/file?side=new) as plain code
   staged?: boolean; // true if file has staged changes (for working mode)
+  submodule?: boolean; // submodule pointer update
+  submodule_old_commit?: string;
+  submodule_new_commit?: string;
+  old_path?: string; // previous path of a renamed file, or a copied file's source
+  similarity?: number; // rename/copy score, 100 when the content is unchanged
+  symlink?: boolean; // symlink target change
+  symlink_old_target?: string;
+  symlink_new_target?: string;
+  split?: SplitHunk[]; // aligned rows, returned for view=split
+  hunks?: DiffHunk[]; // parsed hunks, returned for view=hunks
+  moved_blocks?: MovedBlock[]; // returned for detect_moves=true
+  binary?: boolean; // binary change; old_size/new_size give each existing side in bytes
+  old_size?: number;
+  new_size?: number;
+  too_large?: boolean; // over --max-blob-size; patch and contents are skipped
+  blob_size?: number; // bytes, set with too_large
+  changed_in?: "committed" | "uncommitted" | "both"; // full mode: where the file's changes are
+  commits?: string[]; // aggregated commit diffs: short oids touching the file
+  resolution_lines?: number[]; // combined merge diffs: new-side lines found in no parent
+  line_commits?: Record<number, string>; // ?attribute=true on a range: new-side line -> introducing commit
+  conflicted?: true; // unmerged in the index; working/unstaged/full modes only
 }
 
 export interface DiffResponse {
@@ -21,14 +98,157 @@ export interface DiffResponse {
   commit: string;
   repo_path: string;
   remote_url?: string;
-  mode: "branch" | "working";
+  mode: "branch" | "working" | "unstaged" | "full";
   base_branch: string;
+  total?: number; // total file count when files is a page (?offset/&limit)
+  since?: string; // last reviewed commit the diff starts from (?since=last)
+  renames?: RenameGroup[]; // unchanged files moved together, folded out of files
+  is_merge?: boolean; // merge commit on /show pages; files are vs parent `parent` only
+  parent?: number;
+  combined?: boolean; // ?combined=true: merge diffed against every parent at once
+  last_position?: ReviewPosition; // where the reviewer left off on this branch
+  progress?: { viewed: number; required: number }; // completion over files that must be viewed
+  attribution?: { commits: RangeCommit[]; truncated?: boolean }; // ?attribute=true: commits line_commits refer to
+  force_push?: BranchRewrite; // /api/force-push-diff: the rewrite the diff starts from
+  auto_mark_on_open?: true; // --auto-mark-on-open: mark files viewed as they're opened
 }
 
-export interface StatusResponse {
+export interface BranchRewrite {
+  ref: string; // full ref name, e.g. refs/heads/feature
+  old_tip: string; // tip before the rebase/force-push
+  new_tip: string;
+  reason: string; // reflog message
+  timestamp: number;
+  available: boolean; // false once the old tip was garbage collected
+}
+
+export interface ViewedFileList {
+  branch: string;
+  commit: string;
+  files: string[]; // sorted; branch-scoped marks included
+}
+
+export interface ConflictedFile {
+  path: string;
+  base: boolean; // which index stages exist; a missing side deleted the file
+  ours: boolean;
+  theirs: boolean;
+  markers: number; // <<<<<<< blocks left in the working file
+}
+
+export interface ConflictList {
+  branch: string;
+  files: ConflictedFile[];
+}
+
+export interface Remote {
+  name: string;
+  fetch_url: string;
+  push_url?: string; // only when pushes go somewhere else
+}
+
+export interface ReviewSummary {
+  repo_id: string;
+  repo_name: string;
   repo_path: string;
+  kind: "branch" | "show" | "range";
   branch: string;
   commit: string;
+  viewed: number;
+  last_viewed_at: number;
+  total?: number; // only with ?totals=true
+  stale?: boolean; // repo missing from disk
+  url: string; // page (branch/show) or API diff (range) to resume at
+}
+
+export interface ReviewPosition {
+  file_path: string;
+  line?: number;
+  updated_at: number;
+}
+
+export interface RenameGroup {
+  from: string;
+  to: string;
+  files: Array<{ old_path: string; path: string }>;
+}
+
+// Injected by the server into index.html as window.__CEREBRO_INITIAL_DATA__
+export interface InitialData {
+  repo_id: string;
+  diff: DiffResponse;
+  show?: string; // set for /show/<oid> single-commit pages
+}
+
+export interface RangeCommit {
+  hash: string;
+  summary: string;
+  author: string;
+  timestamp: number; // unix seconds
+}
+
+export interface RangeInfo {
+  base_branch: string;
+  merge_base: string;
+  commit_count: number;
+  oldest?: RangeCommit;
+  newest?: RangeCommit;
+  truncated?: boolean; // walk hit the limit; commit_count is a lower bound
+}
+
+export interface RangeCommitList {
+  base_branch: string;
+  merge_base: string;
+  commits: RangeCommit[]; // newest first
+  truncated?: boolean;
+  author?: string; // ?author= filter that was applied
+  files?: FileDiff[]; // ?aggregate=true: the commits' changes as one diff
+}
+
+export interface GraphCommit extends RangeCommit {
+  parents: string[]; // may lie outside the graph (merge base, merged-in branch bases)
+}
+
+export interface CommitGraph {
+  base_branch: string;
+  merge_base: string;
+  head: string;
+  commits: GraphCommit[]; // topological order, newest first
+  truncated?: boolean;
+}
+
+export interface StatusResponse {
+  repo_path: string;
+  branch: string;
+  commit: string | null; // null on an unborn branch (no commits yet), see message
+  dirty: boolean; // uncommitted changes in the working tree
+  message?: string;
+}
+
+export interface RefreshResponse extends StatusResponse {
+  changed: boolean; // git state moved since the last refresh
+}
+
+// Cheap totals from /api/estimate, to warn before loading a huge diff
+export interface DiffEstimate {
+  files: number;
+  additions: number;
+  deletions: number;
+  max_files?: number; // server --max-files cap, absent when disabled
+  exceeds_max_files?: boolean; // an unpaged /api/diff would be refused
+}
+
+export interface LocResponse {
+  files: number;
+  additions: number;
+  deletions: number;
+  net: number; // additions - deletions
+}
+
+// Body of every non-2xx API response
+export interface ApiErrorBody {
+  error: string;
+  code: "bad_request" | "bad_ref" | "forbidden" | "not_found" | "gone" | "unsupported" | "conflict" | "too_large" | "rate_limited" | "timeout" | "internal";
 }
 
 export interface Comment {
@@ -37,6 +257,7 @@ export interface Comment {
   line_number?: number;
   text: string;
   parent_id?: string;
+  author?: string; // --reviewer or git user.name of whoever wrote it
   timestamp: number;
   branch: string;
   commit: string;
@@ -74,3 +295,14 @@ export interface ReposResponse {
   repos: Repository[];
   currentRepo?: string;
 }
+
+// Keyboard shortcuts (action -> key)
+export interface ShortcutsResponse {
+  shortcuts: Record<string, string>;
+  defaults: Record<string, string>; // built-in bindings the UI's handlers are written against
+}
+
+// Server-side UI preferences (any JSON values)
+export interface PrefsResponse {
+  prefs: Record<string, unknown>;
+}
@@ -44,6 +44,7 @@ function CommentThreadItem({ thread, depth, onResolve, variant }: CommentThreadI
       <div className={`comment-card ${variant === "inline" ? "comment-card-inline" : ""}`}>
         <div className="comment-text">{comment.text}</div>
         <div className="comment-footer">
+          {comment.author && <span className="comment-author">{comment.author}</span>}
           <span className="comment-time">{timeLabel}</span>
           {showResolve && (
             <button type="button" className="resolve-btn" onClick={() => onResolve(comment.id)}>
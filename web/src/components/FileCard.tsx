@@ -12,7 +12,7 @@ interface FileCardProps {
   isExpanded: boolean;
   isLoading?: boolean;
   isFocused: boolean;
-  mode: "branch" | "working";
+  mode: "branch" | "working" | "full";
   onToggle: () => void;
   onToggleViewed: () => void;
   onResolveComment: (id: string) => void;
@@ -28,11 +28,39 @@ const STATUS_STYLES: Record<string, { label: string; color: string }> = {
   modified: { label: "Modified", color: "var(--color-modified)" },
   deleted: { label: "Deleted", color: "var(--color-deleted)" },
   renamed: { label: "Renamed", color: "var(--color-renamed)" },
+  copied: { label: "Copied", color: "var(--color-renamed)" },
   untracked: { label: "Untracked", color: "var(--color-muted)" },
 };
 
 const DEFAULT_STATUS = { label: "Modified", color: "var(--color-modified)" };
 
+function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+}
+
+// "12.0 KB → 212.0 KB (+200.0 KB)" for a binary change; one side alone for adds and deletes
+function binarySizeLabel(file: FileDiff): string {
+  const { old_size: oldSize, new_size: newSize } = file;
+  if (oldSize === undefined) return formatBytes(newSize ?? 0);
+  if (newSize === undefined) return formatBytes(oldSize);
+  const delta = newSize - oldSize;
+  return `${formatBytes(oldSize)} → ${formatBytes(newSize)} (${delta < 0 ? "-" : "+"}${formatBytes(Math.abs(delta))})`;
+}
+
+// "Renamed (85% similar)": how much a moved file was edited decides how closely to read it
+function statusLabel(file: FileDiff, label: string): string {
+  if (file.similarity === undefined || (file.status !== "renamed" && file.status !== "copied")) return label;
+  return `${label} (${file.similarity}% similar)`;
+}
+
+const CHANGED_IN_LABELS: Record<NonNullable<FileDiff["changed_in"]>, string> = {
+  committed: "Committed",
+  uncommitted: "Uncommitted",
+  both: "Committed + local",
+};
+
 export function FileCard({
   file,
   comments,
@@ -64,15 +92,26 @@ export function FileCard({
       <div className="file-header">
         <button type="button" className="file-header-main" onClick={onToggle}>
           <span className={`expand-icon ${isExpanded ? "expanded" : ""}`}>▶</span>
-          <span className="file-path">{file.path}</span>
-          <span className="file-status" style={{ color: status.color }}>
-            {status.label}
+          <span className="file-path" title={file.old_path ? `from ${file.old_path}` : undefined}>
+            {file.path}
           </span>
-          <span className="file-stats">
-            <span className="additions">+{file.additions}</span>
-            <span className="deletions">-{file.deletions}</span>
+          <span className="file-status" style={{ color: status.color }}>
+            {statusLabel(file, status.label)}
           </span>
+          {file.binary ? (
+            <span className="file-stats binary-size">{binarySizeLabel(file)}</span>
+          ) : (
+            <span className="file-stats">
+              <span className="additions">+{file.additions}</span>
+              <span className="deletions">-{file.deletions}</span>
+            </span>
+          )}
           {file.staged && <span className="staged-indicator">Staged</span>}
+          {file.changed_in && (
+            <span className={`changed-in-indicator ${file.changed_in}`}>
+              {CHANGED_IN_LABELS[file.changed_in]}
+            </span>
+          )}
           {unresolvedComments > 0 && (
             <span className="badge comments-badge">{unresolvedComments}</span>
           )}
@@ -116,6 +155,10 @@ export function FileCard({
           <div className="file-diff">
             {isLoading ? (
               <div className="diff-loading">Loading diff...</div>
+            ) : file.too_large ? (
+              <div className="diff-loading">
+                Too large to diff ({((file.blob_size ?? 0) / 1024 / 1024).toFixed(1)} MB)
+              </div>
             ) : (
               <DiffView
                 file={file}
@@ -1,7 +1,14 @@
 import { useCallback, useEffect, useRef, useState } from "react";
-import type { Comment, DiffResponse, FileDiff, Note } from "../api/types";
+import { readApiError } from "../api/errors";
+import type { Comment, DiffResponse, FileDiff, InitialData, Note } from "../api/types";
 
-type DiffMode = "branch" | "working";
+declare global {
+  interface Window {
+    __CEREBRO_INITIAL_DATA__?: InitialData;
+  }
+}
+
+type DiffMode = "branch" | "working" | "unstaged" | "full";
 
 interface CachedData {
   diff: DiffResponse | null;
@@ -24,6 +31,7 @@ interface UseDiffResult {
   refresh: () => Promise<void>;
   loadFileDiff: (filePath: string) => Promise<FileDiff | null>;
   toggleViewed: (filePath: string, viewed: boolean) => Promise<void>;
+  savePosition: (filePath: string) => Promise<void>;
   addComment: (
     filePath: string,
     lineNumber: number,
@@ -38,12 +46,13 @@ interface UseDiffResult {
   commit: (message: string) => Promise<void>;
 }
 
-// Cache key includes mode and branch for branch mode
+// Cache key includes the compare branch for branch and full mode
 function getCacheKey(mode: DiffMode, compareBranch: string | null): string {
-  return mode === "branch" ? `branch:${compareBranch ?? "default"}` : mode;
+  return mode === "working" ? mode : `${mode}:${compareBranch ?? "default"}`;
 }
 
-export function useDiff(repoId?: string | null): UseDiffResult {
+export function useDiff(repoId?: string | null, options: { groupRenames?: boolean } = {}): UseDiffResult {
+  const { groupRenames = false } = options;
   const [diff, setDiff] = useState<DiffResponse | null>(null);
   const [comments, setComments] = useState<Comment[]>([]);
   const [notes, setNotes] = useState<Note[]>([]);
@@ -56,6 +65,22 @@ export function useDiff(repoId?: string | null): UseDiffResult {
   // Cache per mode/branch combination
   const cacheRef = useRef<Map<string, CachedData>>(new Map());
 
+  // Seed the cache with the server-injected diff so the first render has data;
+  // the regular fetch still runs and replaces it
+  const initialData = window.__CEREBRO_INITIAL_DATA__;
+  // A /show/<oid> page carries a single commit's diff, which isn't the branch diff
+  if (initialData && initialData.repo_id === repoId && !initialData.show) {
+    const key = getCacheKey("branch", null);
+    if (!cacheRef.current.has(key)) {
+      cacheRef.current.set(key, {
+        diff: initialData.diff,
+        comments: [],
+        notes: [],
+        timestamp: Date.now(),
+      });
+    }
+  }
+
   const buildUrl = useCallback(
     (path: string, params: Record<string, string> = {}) => {
       const url = new URL(path, window.location.origin);
@@ -112,6 +137,9 @@ export function useDiff(repoId?: string | null): UseDiffResult {
 
       try {
         const diffParams: Record<string, string> = { mode: currentMode };
+        if (groupRenames) {
+          diffParams.group_renames = "true";
+        }
         if (currentCompareBranch) {
           diffParams.compare = currentCompareBranch;
         }
@@ -127,7 +155,7 @@ export function useDiff(repoId?: string | null): UseDiffResult {
         const notesRes = results[2];
 
         if (!diffRes?.ok) {
-          throw new Error(diffRes ? await diffRes.text() : "Failed to fetch diff");
+          throw new Error(diffRes ? await readApiError(diffRes, "Failed to fetch diff") : "Failed to fetch diff");
         }
 
         const diffData = (await diffRes.json()) as DiffResponse;
@@ -176,7 +204,7 @@ export function useDiff(repoId?: string | null): UseDiffResult {
         setLoading(false);
       }
     },
-    [repoId, buildUrl],
+    [repoId, buildUrl, groupRenames],
   );
 
   // Clear cache when repo changes
@@ -202,10 +230,13 @@ export function useDiff(repoId?: string | null): UseDiffResult {
     };
   }, [mode, compareBranch, repoId, fetchData]);
 
-  const refresh = useCallback(
-    () => fetchData(mode, compareBranch),
-    [mode, compareBranch, fetchData],
-  );
+  // Explicit refresh re-reads git state server-side first (the 3s poll skips this)
+  const refresh = useCallback(async () => {
+    if (repoId) {
+      await fetch(buildUrl("/api/refresh"), { method: "POST" }).catch(() => undefined);
+    }
+    await fetchData(mode, compareBranch);
+  }, [repoId, buildUrl, mode, compareBranch, fetchData]);
 
   const loadFileDiff = useCallback(
     async (filePath: string): Promise<FileDiff | null> => {
@@ -239,12 +270,12 @@ export function useDiff(repoId?: string | null): UseDiffResult {
   );
 
   const toggleViewed = useCallback(
-    async (filePath: string, currentlyViewed: boolean) => {
+    async (filePath: string, currentlyViewed: boolean, scope: "commit" | "branch" = "commit") => {
       const endpoint = currentlyViewed ? "/api/unmark-viewed" : "/api/mark-viewed";
       const res = await fetch(buildUrl(endpoint), {
         method: "POST",
         headers: { "Content-Type": "application/json" },
-        body: JSON.stringify({ file_path: filePath }),
+        body: JSON.stringify({ file_path: filePath, ...(!currentlyViewed && { scope }) }),
       });
       if (!res.ok) {
         throw new Error("Failed to update");
@@ -254,7 +285,13 @@ export function useDiff(repoId?: string | null): UseDiffResult {
           ? {
               ...prev,
               files: prev.files.map((f) =>
-                f.path === filePath ? { ...f, viewed: !currentlyViewed } : f,
+                f.path === filePath
+                  ? {
+                      ...f,
+                      viewed: !currentlyViewed,
+                      viewed_scope: !currentlyViewed && scope === "branch" ? "branch" : undefined,
+                    }
+                  : f,
               ),
             }
           : null,
@@ -263,6 +300,18 @@ export function useDiff(repoId?: string | null): UseDiffResult {
     [buildUrl],
   );
 
+  // Best effort: a lost position only means the next visit starts at the top
+  const savePosition = useCallback(
+    async (filePath: string) => {
+      await fetch(buildUrl("/api/position"), {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ file_path: filePath }),
+      }).catch(() => undefined);
+    },
+    [buildUrl],
+  );
+
   const addComment = useCallback(
     async (filePath: string, lineNumber: number, text: string, lineContent?: string) => {
       const commentText = lineContent ? `[context: \`${lineContent}\`]\n${text}` : text;
@@ -384,6 +433,7 @@ export function useDiff(repoId?: string | null): UseDiffResult {
     refresh,
     loadFileDiff,
     toggleViewed,
+    savePosition,
     addComment,
     resolveComment,
     dismissNote,
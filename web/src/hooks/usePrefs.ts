@@ -0,0 +1,30 @@
+import { useCallback, useEffect, useState } from "react";
+import type { PrefsResponse } from "../api/types";
+
+interface UsePrefsResult {
+  prefs: Record<string, unknown> | null; // null until loaded
+  setPref: (key: string, value: unknown) => void;
+}
+
+// Preferences stored by the server so they follow the user across browsers
+export function usePrefs(): UsePrefsResult {
+  const [prefs, setPrefs] = useState<Record<string, unknown> | null>(null);
+
+  useEffect(() => {
+    fetch("/api/prefs")
+      .then((res) => (res.ok ? (res.json() as Promise<PrefsResponse>) : { prefs: {} }))
+      .then((data) => setPrefs(data.prefs))
+      .catch(() => setPrefs({}));
+  }, []);
+
+  const setPref = useCallback((key: string, value: unknown) => {
+    setPrefs((prev) => ({ ...prev, [key]: value }));
+    void fetch("/api/prefs", {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ prefs: { [key]: value } }),
+    });
+  }, []);
+
+  return { prefs, setPref };
+}
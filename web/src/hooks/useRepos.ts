@@ -1,4 +1,5 @@
 import { useCallback, useEffect, useState } from "react";
+import { readApiError } from "../api/errors";
 import type { Repository, ReposResponse } from "../api/types";
 
 interface UseReposResult {
@@ -22,7 +23,7 @@ export function useRepos(): UseReposResult {
     try {
       const res = await fetch("/api/repos");
       if (!res.ok) {
-        throw new Error(await res.text());
+        throw new Error(await readApiError(res, "Failed to load repos"));
       }
       const data = (await res.json()) as ReposResponse;
       setRepos(data.repos);
@@ -59,8 +60,7 @@ export function useRepos(): UseReposResult {
         body: JSON.stringify({ path }),
       });
       if (!res.ok) {
-        const text = await res.text();
-        throw new Error(text || "Failed to add repo");
+        throw new Error(await readApiError(res, "Failed to add repo"));
       }
       const repo = (await res.json()) as Repository;
       await fetchRepos(); // Refresh list
@@ -0,0 +1,37 @@
+import { useCallback, useEffect, useMemo, useState } from "react";
+import type { ShortcutsResponse } from "../api/types";
+
+// Maps a pressed key to the built-in key for the same action, or null if the
+// key's default action was rebound elsewhere. Unknown keys pass through unchanged.
+export type TranslateKey = (key: string) => string | null;
+
+export function useShortcuts(): TranslateKey {
+  const [config, setConfig] = useState<ShortcutsResponse | null>(null);
+
+  useEffect(() => {
+    fetch("/api/config/shortcuts")
+      .then((res) => (res.ok ? (res.json() as Promise<ShortcutsResponse>) : null))
+      .then(setConfig)
+      .catch(() => {
+        // Keep the built-in bindings
+      });
+  }, []);
+
+  const { remapped, displaced } = useMemo(() => {
+    const remapped = new Map<string, string>();
+    const displaced = new Set<string>();
+    for (const [action, key] of Object.entries(config?.shortcuts ?? {})) {
+      const original = config?.defaults[action];
+      if (original && original !== key) {
+        remapped.set(key, original);
+        displaced.add(original);
+      }
+    }
+    return { remapped, displaced };
+  }, [config]);
+
+  return useCallback(
+    (key: string) => remapped.get(key) ?? (displaced.has(key) ? null : key),
+    [remapped, displaced],
+  );
+}